@@ -1,3 +1,25 @@
+use crate::locale::{self, Locale};
+use crate::theme::{paint, Stream, Style};
+use std::io::IsTerminal;
+
+/// Environment variable that overrides when the banner is shown: `always`
+/// shows it even when stdout is piped, `never` hides it everywhere.
+pub const BANNER_ENV: &str = "SANSKRITI_BANNER";
+
+/// Whether the banner should be printed. `--no-banner` always wins; otherwise
+/// [`BANNER_ENV`] decides if set, and by default the banner is only shown when
+/// stdout is a terminal so piped output stays clean.
+pub fn banner_enabled(no_banner: bool) -> bool {
+    if no_banner {
+        return false;
+    }
+    match std::env::var(BANNER_ENV).as_deref() {
+        Ok("always") => true,
+        Ok("never") => false,
+        _ => std::io::stdout().is_terminal(),
+    }
+}
+
 pub fn display_banner() {
     let banner_text = r#"
    _____                  __        _ __  _ 
@@ -6,9 +28,13 @@ pub fn display_banner() {
  ___/ / /_/ / / / (__  ) ,< / /  / / /_/ /  
 /____/\__,_/_/ /_/____/_/|_/_/  /_/\__/_/   
 "#;
-   let author_tag ="\x1b[32mVersion 1.1.0 For The Lord!\x1b[0m";
-   let version_tag = "\x1b[1;31mCrafted by megaketu555\x1b[0m";
-    println!("{}",banner_text);
-    println!("{}",author_tag);
-    println!("{}",version_tag)
+    let (version, author) = match locale::current() {
+        Locale::En => ("Version 1.1.0 For The Lord!", "Crafted by megaketu555"),
+        Locale::Sa => ("संस्करणम् १.१.० प्रभवे समर्पितम्!", "megaketu555 द्वारा रचितम्"),
+    };
+    let version_tag = paint(Stream::Stdout, Style::Version, version);
+    let author_tag = paint(Stream::Stdout, Style::Author, author);
+    println!("{}", banner_text);
+    println!("{}", version_tag);
+    println!("{}", author_tag)
 }
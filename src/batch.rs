@@ -0,0 +1,329 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use miette::{IntoDiagnostic, WrapErr};
+
+use crate::diagnostics::Original;
+use crate::lex::Terminators;
+use crate::limits::STACK_SIZE;
+use crate::manifest::Manifest;
+use crate::parse::Arena;
+use crate::source::read_source;
+use crate::{Interpreter, Parser};
+
+/// File extensions that `run-all` picks up as programs.
+pub const SOURCE_EXTENSIONS: &[&str] = &["sk", "skt"];
+
+/// How a single program in a batch ended.
+#[derive(Debug)]
+pub enum Status {
+    Passed,
+    /// The file or its manifest could not be read, or the file could not be
+    /// translated.
+    Unreadable(miette::Report),
+    /// The program had syntax errors and was not run.
+    CompileError(miette::Report),
+    /// The program failed while running.
+    RuntimeError(miette::Report),
+}
+
+impl Status {
+    pub fn passed(&self) -> bool {
+        matches!(self, Status::Passed)
+    }
+}
+
+/// The result of running one program of a batch.
+#[derive(Debug)]
+pub struct Outcome {
+    pub path: PathBuf,
+    /// Everything the program printed, up to where it stopped.
+    pub output: String,
+    pub status: Status,
+    pub elapsed: Duration,
+}
+
+/// Lists the programs directly inside `dir`, sorted by path so that reports
+/// come out in the same order on every machine.
+pub fn collect_sources(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_source = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext));
+        if is_source && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Lists the programs inside `dir` and the directories below it, sorted by
+/// path. Like glob patterns, this skips names that start with `.`.
+pub fn collect_sources_recursively(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for ext in SOURCE_EXTENSIONS {
+        glob(dir, &["**", &format!("*.{ext}")], &mut paths)?;
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// The files that `args` name, in order: each is a file, a directory, which
+/// stands for the programs [`collect_sources`] lists in it, or a glob pattern
+/// like `course/**/*.sk`, for shells that don't expand patterns themselves.
+/// In a pattern, `*` matches any part of a name and `?` any one character,
+/// neither matching names that start with `.` unless the pattern does, and a
+/// `**` component matches any number of directories. A pattern that matches
+/// no files is an error.
+pub fn expand_paths(args: &[PathBuf]) -> miette::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for arg in args {
+        let text = arg.to_string_lossy();
+        if is_pattern(&text) {
+            let mut matched = Vec::new();
+            glob(Path::new(""), &pattern_components(&text), &mut matched)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("expanding '{text}' failed"))?;
+            if matched.is_empty() {
+                return Err(miette::miette!("no files match '{text}'"));
+            }
+            matched.sort();
+            matched.dedup();
+            paths.extend(matched);
+        } else if arg.is_dir() {
+            let sources = collect_sources(arg)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("listing '{}' failed", arg.display()))?;
+            paths.extend(sources);
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+    Ok(paths)
+}
+
+fn is_pattern(text: &str) -> bool {
+    text.contains(['*', '?'])
+}
+
+/// The components of `pattern`, with a leading `/` kept as the first.
+fn pattern_components(pattern: &str) -> Vec<&str> {
+    let root = pattern.starts_with('/').then_some("/");
+    root.into_iter()
+        .chain(pattern.split('/').filter(|component| !component.is_empty()))
+        .collect()
+}
+
+/// Adds the files under `dir` that `components` of a pattern match to
+/// `found`.
+fn glob(dir: &Path, components: &[&str], found: &mut Vec<PathBuf>) -> io::Result<()> {
+    let Some((&first, rest)) = components.split_first() else {
+        if dir.is_file() {
+            found.push(dir.to_path_buf());
+        }
+        return Ok(());
+    };
+    if !is_pattern(first) {
+        let path = dir.join(first);
+        if rest.is_empty() || path.is_dir() {
+            glob(&path, rest, found)?;
+        }
+        return Ok(());
+    }
+
+    let listed = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    if first == "**" {
+        glob(dir, rest, found)?;
+    }
+    for entry in std::fs::read_dir(listed)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if name.starts_with('.') && !first.starts_with('.') {
+            continue;
+        }
+        if first == "**" {
+            // not following links to directories, which could loop
+            if entry.file_type()?.is_dir() {
+                glob(&dir.join(name), components, found)?;
+            }
+        } else if wildcard_matches(first, name) {
+            glob(&dir.join(name), rest, found)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters
+/// and `?` any one.
+fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // where to resume after the last `*`, and how much of `name` it takes
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, taken)) => {
+                    star = Some((after, taken + 1));
+                    p = after;
+                    n = taken + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Calls `f` on every item of `items` on up to `jobs` threads, each with a
+/// stack of [`STACK_SIZE`], and returns the results in the order of `items`.
+pub fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    jobs: NonZeroUsize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.get().min(items.len()) {
+            let worker = std::thread::Builder::new().stack_size(STACK_SIZE);
+            worker
+                .spawn_scoped(scope, || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(i) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results
+                        .lock()
+                        .expect("no worker panics while holding the lock")
+                        .push((i, result));
+                })
+                .expect("spawning a worker thread failed");
+        }
+    });
+
+    let mut results = results.into_inner().expect("all workers have finished");
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Runs every program in `paths` on up to `jobs` threads and returns their
+/// outcomes in the order of `paths`.
+///
+/// Each program gets its own interpreter, built by `configure` from a fresh
+/// one and the program's manifest, so programs cannot observe each other.
+/// `configure` should apply the manifest with [`Manifest::configure`]. Their
+/// output is captured rather than written to stdout. `terminators`, if given,
+/// overrides what each program's manifest says ends a statement.
+pub fn run_all(
+    paths: &[PathBuf],
+    jobs: NonZeroUsize,
+    lossy: bool,
+    terminators: Option<Terminators>,
+    configure: impl Fn(Interpreter, &Manifest) -> Interpreter + Sync,
+) -> Vec<Outcome> {
+    parallel_map(paths, jobs, |path| {
+        run_one(path, lossy, terminators, &configure)
+    })
+}
+
+fn run_one(
+    path: &Path,
+    lossy: bool,
+    terminators: Option<Terminators>,
+    configure: &impl Fn(Interpreter, &Manifest) -> Interpreter,
+) -> Outcome {
+    let started = Instant::now();
+    let output = Captured::default();
+
+    let read = Manifest::for_program(path).and_then(|manifest| {
+        let contents = read_source(path, lossy)?;
+        let (translated, map) = manifest.language_pack().translate_with_map(&contents);
+        Ok((manifest, contents, translated, map))
+    });
+    let status = match read {
+        Err(e) => Status::Unreadable(e),
+        Ok((manifest, contents, translated, map)) => {
+            let original = Original {
+                source: &contents,
+                map: &map,
+            };
+            let terminators = manifest.terminators(terminators);
+            let arena = Arena::new();
+            match Parser::new(&translated, &arena)
+                .terminators(terminators)
+                .parse_program()
+            {
+                Err(e) => Status::CompileError(original.report(e.as_ref())),
+                Ok(program) => {
+                    // programs run side by side, so none of them gets the input
+                    let mut interpreter = configure(Interpreter::new(), &manifest)
+                        .output(output.clone())
+                        .input(io::empty());
+                    match interpreter.eval_program(&program) {
+                        Ok(_) => Status::Passed,
+                        Err(e) => Status::RuntimeError(original.report(&e)),
+                    }
+                }
+            }
+        }
+    };
+
+    Outcome {
+        path: path.to_path_buf(),
+        output: output.contents(),
+        status,
+        elapsed: started.elapsed(),
+    }
+}
+
+/// An in-memory sink for a program's output that stays readable after the
+/// interpreter writing to it is gone.
+#[derive(Clone, Default)]
+pub(crate) struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Captured {
+    /// What has been written so far, with invalid UTF-8 replaced.
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use std::io;
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+use crate::interpreter::RuntimeError;
+use crate::parse::TokenTree;
+use crate::Interpreter;
+
+/// The wall times of the runs of a program by `bench`.
+#[derive(Debug, Clone)]
+pub struct Timings {
+    /// One time per run, from fastest to slowest.
+    samples: Vec<Duration>,
+}
+
+impl Timings {
+    pub fn iterations(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.samples[0]
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    /// The middle time, or the mean of the two middle times for an even
+    /// number of runs.
+    pub fn median(&self) -> Duration {
+        let middle = self.samples.len() / 2;
+        if self.samples.len().is_multiple_of(2) {
+            (self.samples[middle - 1] + self.samples[middle]) / 2
+        } else {
+            self.samples[middle]
+        }
+    }
+}
+
+/// Runs `program` `iterations` times, each time in a fresh interpreter built
+/// by `configure` with its output thrown away, and times each run. Parsing
+/// happens once, before this, so only running is timed. The first run that
+/// fails stops the benchmark with its error.
+pub fn run(
+    program: &[TokenTree<'_>],
+    iterations: NonZeroU32,
+    configure: impl Fn(Interpreter) -> Interpreter,
+) -> Result<Timings, RuntimeError> {
+    let mut samples = Vec::with_capacity(iterations.get() as usize);
+    for _ in 0..iterations.get() {
+        let mut interpreter = configure(Interpreter::new())
+            .output(io::sink())
+            .input(io::empty());
+        let started = Instant::now();
+        interpreter.eval_program(program)?;
+        samples.push(started.elapsed());
+    }
+    samples.sort();
+    Ok(Timings { samples })
+}
@@ -0,0 +1,431 @@
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::gc::Gc;
+use crate::interpreter::{runtime_error, Interpreter, NativeFn, RuntimeError, Value};
+use crate::lex::normalize_digits;
+use crate::locale;
+
+/// The longest string, in bytes, that repeating a string may produce, so that
+/// a stray `"अ" * 1e12` fails cleanly instead of exhausting memory.
+pub const MAX_REPEAT_LEN: usize = 16 * 1024 * 1024;
+
+/// Repeats `s` `count` times, as `"अ" * 3` does. Counts that are not whole,
+/// non-negative numbers give `None`.
+pub(crate) fn repeat(s: &str, count: f64) -> Result<Option<String>, RuntimeError> {
+    if count < 0.0 || count.fract() != 0.0 {
+        return Ok(None);
+    }
+    let len = s.len() as f64 * count;
+    if len > MAX_REPEAT_LEN as f64 {
+        return Err(runtime_error!(
+            "E1018/repeat",
+            size = s.len(),
+            count = count,
+            limit = MAX_REPEAT_LEN
+        ));
+    }
+    Ok(Some(s.repeat(count as usize)))
+}
+
+/// `repeat(s, n)` returns `s` repeated `n` times, like `s * n`.
+pub(crate) const REPEAT: NativeFn = NativeFn {
+    name: "repeat",
+    arity: 2..=2,
+    fun: |_, args| match (&args[0], &args[1]) {
+        (Value::String(s), Value::Number(count)) => repeat(s, *count)?
+            .map(|s| Value::String(s.into()))
+            .ok_or_else(|| runtime_error!("E1018/count", count = count)),
+        (s, count) => Err(runtime_error!(
+            "E1017/repeat",
+            string = locale::current().type_name(s.type_name()),
+            count = locale::current().type_name(count.type_name())
+        )),
+    },
+};
+
+/// `args()` returns the arguments given to the program after `--` on the
+/// command line, as a list of strings.
+pub(crate) const ARGS: NativeFn = NativeFn {
+    name: "args",
+    arity: 0..=0,
+    fun: |interpreter, _| {
+        let args = interpreter
+            .args
+            .iter()
+            .map(|arg| Value::String(arg.as_str().into()))
+            .collect();
+        Ok(Value::List(Gc::new(args)))
+    },
+};
+
+/// `तर्क()`, the Sanskrit name of [`ARGS`].
+pub(crate) const TARKA: NativeFn = NativeFn {
+    name: "तर्क",
+    ..ARGS
+};
+
+/// `assert(condition, message)` fails with the runtime error `assertion
+/// failed: message` unless `condition` is truthy. Like any error a builtin
+/// raises, it points at the call. `message` may be left out.
+pub(crate) const ASSERT: NativeFn = NativeFn {
+    name: "assert",
+    arity: 1..=2,
+    fun: |interpreter, args| {
+        if args[0].is_truthy() {
+            return Ok(Value::Nil);
+        }
+        Err(match args.get(1) {
+            Some(message) => runtime_error!(
+                "E1016/message",
+                message = message.format(interpreter.concat_numbers)
+            ),
+            None => runtime_error!("E1016"),
+        })
+    },
+};
+
+/// `पुष्टि(condition, message)`, the Sanskrit name of [`ASSERT`].
+pub(crate) const PUSHTI: NativeFn = NativeFn {
+    name: "पुष्टि",
+    ..ASSERT
+};
+
+/// `write(x)` prints `x` as `print` would, but without a newline after it,
+/// and flushes the output at once, so that a prompt like `"नाम: "` is seen
+/// on the line the answer is typed on.
+pub(crate) const WRITE: NativeFn = NativeFn {
+    name: "write",
+    arity: 1..=1,
+    fun: |interpreter, args| {
+        let text = interpreter.printed(&args[0]).to_string();
+        interpreter.print_partial(text)?;
+        Ok(Value::Nil)
+    },
+};
+
+/// `कथय्(x)`, the Sanskrit name of [`WRITE`].
+pub(crate) const KATHAY: NativeFn = NativeFn {
+    name: "कथय्",
+    ..WRITE
+};
+
+/// `पृच्छ(prompt)` prints `prompt` as [`KATHAY`] does, then reads a line of
+/// the program's input and returns it without its line ending, or `nil` once
+/// the input has ended. `prompt` may be left out.
+pub(crate) const PRCCHA: NativeFn = NativeFn {
+    name: "पृच्छ",
+    arity: 0..=1,
+    fun: |interpreter, args| {
+        let line = ask(interpreter, args.first())?;
+        Ok(line.map_or(Value::Nil, |line| Value::String(line.into())))
+    },
+};
+
+/// `पृच्छसंख्या(prompt)` reads a line as [`PRCCHA`] does and converts it to
+/// a number as [`SANKHYA`] does, so a line that isn't a number, or the end
+/// of the input, gives `nil`.
+pub(crate) const PRCCHA_SANKHYA: NativeFn = NativeFn {
+    name: "पृच्छसंख्या",
+    arity: 0..=1,
+    fun: |interpreter, args| {
+        let line = ask(interpreter, args.first())?;
+        Ok(line
+            .and_then(|line| parse_number(&line))
+            .map_or(Value::Nil, Value::Number))
+    },
+};
+
+/// `input(prompt)`, the Lox name of [`PRCCHA`].
+pub(crate) const INPUT: NativeFn = NativeFn {
+    name: "input",
+    ..PRCCHA
+};
+
+/// `input_number(prompt)`, the Lox name of [`PRCCHA_SANKHYA`].
+pub(crate) const INPUT_NUMBER: NativeFn = NativeFn {
+    name: "input_number",
+    ..PRCCHA_SANKHYA
+};
+
+/// Prints `prompt`, if there is one, and reads a line of input.
+fn ask(
+    interpreter: &mut Interpreter,
+    prompt: Option<&Value>,
+) -> Result<Option<String>, RuntimeError> {
+    if let Some(prompt) = prompt {
+        let text = interpreter.printed(prompt).to_string();
+        interpreter.print_partial(text)?;
+    }
+    interpreter.read_line()
+}
+
+/// `type(x)` returns the name of the type of `x`, as runtime errors name it:
+/// `"nil"`, `"number"`, `"boolean"`, `"string"`, `"bytes"`, `"list"`,
+/// `"range"`, or `"function"`. The names are in English whatever `--lang`
+/// is, so that programs can compare them.
+pub(crate) const TYPE: NativeFn = NativeFn {
+    name: "type",
+    arity: 1..=1,
+    fun: |_, args| Ok(Value::String(args[0].type_name().into())),
+};
+
+/// `प्रकार(x)`, the Sanskrit name of [`TYPE`].
+pub(crate) const PRAKARA: NativeFn = NativeFn {
+    name: "प्रकार",
+    ..TYPE
+};
+
+/// `random()` returns a random number from 0 (inclusive) to 1 (exclusive).
+pub(crate) const RANDOM: NativeFn = NativeFn {
+    name: "random",
+    arity: 0..=0,
+    fun: |interpreter, _| Ok(Value::Number(interpreter.rng.f64())),
+};
+
+/// `यादृच्छिक()`, the Sanskrit name of [`RANDOM`].
+pub(crate) const YADRCCHIKA: NativeFn = NativeFn {
+    name: "यादृच्छिक",
+    ..RANDOM
+};
+
+/// `randint(a, b)` returns a random whole number from `a` to `b`, both
+/// included.
+pub(crate) const RANDINT: NativeFn = NativeFn {
+    name: "randint",
+    arity: 2..=2,
+    fun: |interpreter, args| {
+        let low = whole_argument("randint", &args[0])?;
+        let high = whole_argument("randint", &args[1])?;
+        if low > high {
+            return Err(runtime_error!("E1018/randint", low = low, high = high));
+        }
+        Ok(Value::Number(interpreter.rng.i64(low..=high) as f64))
+    },
+};
+
+/// `यादृच्छिकपूर्णांक(a, b)`, the Sanskrit name of [`RANDINT`].
+pub(crate) const YADRCCHIKA_PURNANKA: NativeFn = NativeFn {
+    name: "यादृच्छिकपूर्णांक",
+    ..RANDINT
+};
+
+/// `clock()` returns the number of seconds since the interpreter started, for
+/// timing parts of a program. In deterministic mode it instead advances by a
+/// microsecond for every statement executed.
+pub(crate) const CLOCK: NativeFn = NativeFn {
+    name: "clock",
+    arity: 0..=0,
+    fun: |interpreter, _| {
+        let seconds = if interpreter.is_deterministic() {
+            interpreter.statements_executed() as f64 / 1e6
+        } else {
+            interpreter.started.elapsed().as_secs_f64()
+        };
+        Ok(Value::Number(seconds))
+    },
+};
+
+/// `घटिका()`, the Sanskrit name of [`CLOCK`].
+pub(crate) const GHATIKA: NativeFn = NativeFn {
+    name: "घटिका",
+    ..CLOCK
+};
+
+/// `time()` returns the current time as seconds since the Unix epoch. In
+/// deterministic mode it is always 0.
+pub(crate) const TIME: NativeFn = NativeFn {
+    name: "time",
+    arity: 0..=0,
+    fun: |interpreter, _| {
+        if interpreter.is_deterministic() {
+            return Ok(Value::Number(0.0));
+        }
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Value::Number(since_epoch.as_secs_f64()))
+    },
+};
+
+/// `समय()`, the Sanskrit name of [`TIME`].
+pub(crate) const SAMAYA: NativeFn = NativeFn {
+    name: "समय",
+    ..TIME
+};
+
+/// The random number and time builtins, which every interpreter has.
+pub(crate) const CHANCE_AND_TIME: [NativeFn; 8] = [
+    RANDOM,
+    YADRCCHIKA,
+    RANDINT,
+    YADRCCHIKA_PURNANKA,
+    CLOCK,
+    GHATIKA,
+    TIME,
+    SAMAYA,
+];
+
+/// `संख्या(x)` converts a string to a number, reading ASCII or Devanagari
+/// digits with an optional sign, fraction, and exponent around them, and
+/// ignoring surrounding whitespace. A string that isn't a number gives `nil`,
+/// so programs can check input; a number is returned as it is.
+pub(crate) const SANKHYA: NativeFn = NativeFn {
+    name: "संख्या",
+    arity: 1..=1,
+    fun: |_, args| match &args[0] {
+        Value::Number(n) => Ok(Value::Number(*n)),
+        Value::String(s) => Ok(parse_number(s).map_or(Value::Nil, Value::Number)),
+        other => Err(runtime_error!(
+            "E1017/number",
+            kind = locale::current().type_name(other.type_name())
+        )),
+    },
+};
+
+/// `शब्द(x)` converts any value to the string `कथय` would print for it,
+/// writing numbers the way `+` does when it joins them to strings.
+pub(crate) const SHABDA: NativeFn = NativeFn {
+    name: "शब्द",
+    arity: 1..=1,
+    fun: |interpreter, args| match &args[0] {
+        Value::String(s) => Ok(Value::String(s.clone())),
+        other => Ok(Value::String(
+            other.format(interpreter.concat_numbers).to_string().into(),
+        )),
+    },
+};
+
+/// `पूर्णांक(x)` drops the fractional part of a number, rounding toward zero,
+/// so `पूर्णांक(-2.7)` is -2. Strings are converted as by [`SANKHYA`] first.
+/// Anything without a finite whole part (`nil`, infinity, NaN, or a string
+/// that isn't a number) gives `nil`.
+pub(crate) const PURNANKA: NativeFn = NativeFn {
+    name: "पूर्णांक",
+    arity: 1..=1,
+    fun: |_, args| {
+        let n = match &args[0] {
+            Value::Number(n) => Some(*n),
+            Value::String(s) => parse_number(s),
+            Value::Nil => None,
+            other => {
+                return Err(runtime_error!(
+                    "E1017/truncate",
+                    kind = locale::current().type_name(other.type_name())
+                ))
+            }
+        };
+        Ok(n.filter(|n| n.is_finite())
+            .map_or(Value::Nil, |n| Value::Number(n.trunc())))
+    },
+};
+
+/// `to_number(x)`, the Lox name of [`SANKHYA`].
+pub(crate) const TO_NUMBER: NativeFn = NativeFn {
+    name: "to_number",
+    ..SANKHYA
+};
+
+/// `to_string(x)`, the Lox name of [`SHABDA`].
+pub(crate) const TO_STRING: NativeFn = NativeFn {
+    name: "to_string",
+    ..SHABDA
+};
+
+/// `truncate(x)`, the Lox name of [`PURNANKA`].
+pub(crate) const TRUNCATE: NativeFn = NativeFn {
+    name: "truncate",
+    ..PURNANKA
+};
+
+/// The conversion builtins, which every interpreter has.
+pub(crate) const CONVERSIONS: [NativeFn; 6] =
+    [SANKHYA, SHABDA, PURNANKA, TO_NUMBER, TO_STRING, TRUNCATE];
+
+/// Reads `s` as a decimal number, as [`SANKHYA`] describes.
+fn parse_number(s: &str) -> Option<f64> {
+    let s = normalize_digits(s.trim());
+    // rules out the `inf` and `NaN` that Rust would also read
+    if !s.contains(|c: char| c.is_ascii_digit())
+        || !s
+            .chars()
+            .all(|c| matches!(c, '0'..='9' | '.' | '+' | '-' | 'e' | 'E'))
+    {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// `पठ(path)` returns the contents of the text file at `path`. Only available
+/// with [`Interpreter::allow_fs`](crate::Interpreter::allow_fs).
+pub(crate) const PATHA: NativeFn = NativeFn {
+    name: "पठ",
+    arity: 1..=1,
+    fun: |_, args| {
+        let path = path_argument("पठ", &args[0])?;
+        fs::read_to_string(path)
+            .map(|text| Value::String(text.into()))
+            .map_err(|e| runtime_error!("E1019/read", path = path, error = e))
+    },
+};
+
+/// `लिख(path, text)` writes `text` to the file at `path`, replacing what was
+/// there. Only available with [`Interpreter::allow_fs`](crate::Interpreter::allow_fs).
+pub(crate) const LIKHA: NativeFn = NativeFn {
+    name: "लिख",
+    arity: 2..=2,
+    fun: |_, args| {
+        let path = path_argument("लिख", &args[0])?;
+        let Value::String(text) = &args[1] else {
+            return Err(runtime_error!(
+                "E1017/write",
+                kind = locale::current().type_name(args[1].type_name())
+            ));
+        };
+        fs::write(path, text.as_bytes())
+            .map(|()| Value::Nil)
+            .map_err(|e| runtime_error!("E1019/write", path = path, error = e))
+    },
+};
+
+/// `read_file(path)`, the Lox name of [`PATHA`].
+pub(crate) const READ_FILE: NativeFn = NativeFn {
+    name: "read_file",
+    ..PATHA
+};
+
+/// `write_file(path, text)`, the Lox name of [`LIKHA`].
+pub(crate) const WRITE_FILE: NativeFn = NativeFn {
+    name: "write_file",
+    ..LIKHA
+};
+
+/// The builtins that [`Interpreter::allow_fs`](crate::Interpreter::allow_fs)
+/// exposes.
+pub(crate) const FS: [NativeFn; 4] = [PATHA, LIKHA, READ_FILE, WRITE_FILE];
+
+fn path_argument<'a>(name: &str, value: &'a Value) -> Result<&'a str, RuntimeError> {
+    match value {
+        Value::String(path) => Ok(path),
+        other => Err(runtime_error!(
+            "E1017/path",
+            name = name,
+            kind = locale::current().type_name(other.type_name())
+        )),
+    }
+}
+
+/// The largest magnitude below which every whole number is exactly a float.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+fn whole_argument(name: &str, value: &Value) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER => Ok(*n as i64),
+        Value::Number(n) => Err(runtime_error!("E1018/whole", name = name, n = n)),
+        other => Err(runtime_error!(
+            "E1017/numbers",
+            name = name,
+            kind = locale::current().type_name(other.type_name())
+        )),
+    }
+}
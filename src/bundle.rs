@@ -0,0 +1,168 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use miette::{IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{self, Original};
+use crate::manifest::Manifest;
+use crate::pack;
+use crate::parse::Arena;
+use crate::{Interpreter, Parser};
+
+/// The last bytes of an executable made by `bundle`, after the length of the
+/// payload before them.
+const MAGIC: &[u8; 8] = b"SKBUNDLE";
+
+/// The size of the trailer at the end of a bundled executable: the payload
+/// length as a little-endian `u64`, then [`MAGIC`].
+const TRAILER: u64 = 8 + MAGIC.len() as u64;
+
+/// A program packed into a copy of the sanskriti executable by `bundle`,
+/// which the copy runs instead of reading its command line.
+///
+/// The payload is the program's source rather than a compiled form, since
+/// the interpreter has none; the copy translates and parses it at startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    /// The file name of the program, which errors are reported against.
+    pub name: String,
+    /// The program as it was written.
+    pub source: String,
+    /// The text of the `sanskriti.toml` that applied to the program, if any.
+    pub manifest: Option<String>,
+    /// The text of the language pack file the program's keywords come from,
+    /// if they don't come from a built-in pack.
+    #[serde(default)]
+    pub dialect: Option<String>,
+}
+
+impl Bundle {
+    /// Writes a copy of the running executable to `output` with the bundle
+    /// appended, and makes it executable.
+    pub fn write(&self, output: &Path) -> miette::Result<()> {
+        let exe = std::env::current_exe()
+            .into_diagnostic()
+            .wrap_err("finding the sanskriti executable failed")?;
+        let mut bytes = fs::read(&exe)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("reading '{}' failed", exe.display()))?;
+        // bundling from a bundled executable would stack a second payload
+        // on the first; keep only the runtime
+        bytes.truncate(runtime_len(&bytes));
+
+        let payload = serde_json::to_vec(self).expect("bundles serialize");
+        bytes.extend_from_slice(&payload);
+        bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(MAGIC);
+
+        fs::write(output, bytes)
+            .and_then(|()| make_executable(output))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("writing '{}' failed", output.display()))
+    }
+
+    /// The bundle appended to the running executable, if it has one.
+    pub fn embedded() -> Option<Self> {
+        let mut exe = File::open(std::env::current_exe().ok()?).ok()?;
+        let end = exe.seek(SeekFrom::End(0)).ok()?;
+        let trailer_start = end.checked_sub(TRAILER)?;
+
+        let mut trailer = [0; TRAILER as usize];
+        exe.seek(SeekFrom::Start(trailer_start)).ok()?;
+        exe.read_exact(&mut trailer).ok()?;
+        let (len, magic) = trailer.split_at(8);
+        if magic != MAGIC {
+            return None;
+        }
+        let len = u64::from_le_bytes(len.try_into().expect("trailer has 8 length bytes"));
+
+        let mut payload = vec![0; usize::try_from(len).ok()?];
+        exe.seek(SeekFrom::Start(trailer_start.checked_sub(len)?))
+            .ok()?;
+        exe.read_exact(&mut payload).ok()?;
+        serde_json::from_slice(&payload).ok()
+    }
+
+    /// Runs the bundled program with `args` as its arguments, reporting any
+    /// error, and returns the exit code: 0, or 65 for a syntax error and 70
+    /// for a runtime error, as with `run`.
+    pub fn run(&self, args: Vec<String>) -> i32 {
+        let name = Path::new(&self.name);
+        let manifest = match &self.manifest {
+            Some(text) => Manifest::parse(text, manifest_name(name).as_str()),
+            None => Ok(Manifest::default()),
+        };
+        let prepared = manifest.and_then(|mut manifest| {
+            if let Some(text) = &self.dialect {
+                manifest.pack = Some(pack::parse(text, "the bundled language pack")?);
+            }
+            let (translated, map) = manifest.language_pack().translate_with_map(&self.source);
+            Ok((manifest, translated, map))
+        });
+        let (manifest, translated, map) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                diagnostics::emit(&e, Some(name));
+                return 1;
+            }
+        };
+
+        let original = Original {
+            source: &self.source,
+            map: &map,
+        };
+        let arena = Arena::new();
+        let parser = Parser::new(&translated, &arena).terminators(manifest.terminators(None));
+        let program = match parser.parse_program() {
+            Ok(program) => program,
+            Err(e) => {
+                diagnostics::emit(&original.report(e.as_ref()), Some(name));
+                return 65;
+            }
+        };
+        let mut interpreter = manifest.configure(Interpreter::new().args(args));
+        if let Err(e) = interpreter.eval_program(&program) {
+            diagnostics::emit(&original.report(&e), Some(name));
+            return 70;
+        }
+        0
+    }
+}
+
+/// The name errors in a bundled manifest are reported against: the manifest
+/// file as it sat next to the program.
+fn manifest_name(program: &Path) -> String {
+    program
+        .with_file_name(crate::manifest::FILE_NAME)
+        .display()
+        .to_string()
+}
+
+/// The length of `exe` without any bundle appended to it.
+fn runtime_len(exe: &[u8]) -> usize {
+    let Some(trailer_start) = exe.len().checked_sub(TRAILER as usize) else {
+        return exe.len();
+    };
+    let (len, magic) = exe[trailer_start..].split_at(8);
+    if magic != MAGIC {
+        return exe.len();
+    }
+    let len = u64::from_le_bytes(len.try_into().expect("trailer has 8 length bytes"));
+    usize::try_from(len)
+        .ok()
+        .and_then(|len| trailer_start.checked_sub(len))
+        .unwrap_or(exe.len())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where users should report crashes.
+const ISSUES: &str = concat!(env!("CARGO_PKG_REPOSITORY"), "/issues");
+
+/// Replaces Rust's panic message with one that asks the user to report the
+/// bug, after writing a bug-report bundle to the temp directory: the command
+/// line, the version, the panic with a backtrace, and a copy of every file
+/// named on the command line (normally the program being run).
+///
+/// With `RUST_BACKTRACE` set, the usual panic message is printed as well.
+pub fn install_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = payload(info);
+        // a closed stdout, as in `sanskriti keywords | head`, is not a bug
+        if message.starts_with("failed printing to stdout") {
+            std::process::exit(1);
+        }
+
+        if std::env::var_os("RUST_BACKTRACE").is_some() {
+            default(info);
+        }
+        eprintln!("error: sanskriti crashed; this is a bug in sanskriti, not in your program");
+        match write_bundle(info, &message) {
+            Ok(dir) => eprintln!(
+                "A bug report was written to {}\nPlease file an issue at {ISSUES} and attach \
+                 the files in it (check first that they hold nothing private).",
+                dir.display()
+            ),
+            Err(e) => eprintln!(
+                "Writing a bug report failed ({e}).\nPlease file an issue at {ISSUES} with the \
+                 command you ran and this message: {message}"
+            ),
+        }
+    }));
+}
+
+fn payload(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "<non-string panic payload>".to_string())
+}
+
+/// Writes `report.txt`, and copies of the files named on the command line to
+/// `files/`, in a new directory, returning it.
+fn write_bundle(info: &PanicHookInfo<'_>, message: &str) -> std::io::Result<PathBuf> {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let dir = std::env::temp_dir().join(format!(
+        "sanskriti-crash-{since_epoch}-{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir)?;
+
+    let args: Vec<String> = std::env::args().collect();
+    let mut report = String::new();
+    let _ = writeln!(report, "version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "platform: {}-{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "command: {args:?}");
+    let _ = writeln!(report, "panic: {message}");
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "at: {location}");
+    }
+    let _ = writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture());
+    fs::write(dir.join("report.txt"), report)?;
+
+    for arg in args
+        .iter()
+        .skip(1)
+        .map(Path::new)
+        .filter(|path| path.is_file())
+    {
+        if let Some(name) = arg.file_name() {
+            fs::create_dir_all(dir.join("files"))?;
+            fs::copy(arg, dir.join("files").join(name))?;
+        }
+    }
+    Ok(dir)
+}
@@ -0,0 +1,411 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::io::{self, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use miette::{IntoDiagnostic, WrapErr};
+use serde_json::{json, Value as Json};
+
+use crate::debugger::{Mode, Pause, Stepper};
+use crate::interpreter::{Debugger, Interpreter, Location};
+use crate::manifest::Manifest;
+use crate::parse::{Arena, TokenTree};
+use crate::protocol::{read_message, write_message};
+use crate::source::read_source;
+use crate::{note, Parser};
+
+/// The only thread a program has.
+const THREAD_ID: i64 = 1;
+/// The `variablesReference` of the one scope, the globals.
+const GLOBALS_REFERENCE: i64 = 1;
+
+/// Listens on `port` on localhost for one Debug Adapter Protocol client, such
+/// as VS Code, and debugs the program it launches until it disconnects.
+pub fn serve(port: u16, lossy: bool) -> miette::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .into_diagnostic()
+        .wrap_err_with(|| format!("listening on port {port} failed"))?;
+    note!("waiting for a debug adapter client on 127.0.0.1:{port}");
+    let (stream, peer) = listener
+        .accept()
+        .into_diagnostic()
+        .wrap_err("accepting a debug adapter client failed")?;
+    note!("debug adapter client connected from {peer}");
+
+    let connection = Rc::new(RefCell::new(
+        Connection::new(stream)
+            .into_diagnostic()
+            .wrap_err("setting up the connection failed")?,
+    ));
+    Session::default().run(&connection, lossy).into_diagnostic()
+}
+
+/// A DAP connection: messages framed by a `Content-Length` header, each a
+/// JSON object.
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    seq: i64,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            seq: 0,
+        })
+    }
+
+    /// Reads the next message, or `None` once the client has hung up.
+    fn recv(&mut self) -> io::Result<Option<Json>> {
+        read_message(&mut self.reader)
+    }
+
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    fn respond_error(&mut self, request: &Json, message: impl Into<String>) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message.into(),
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// What the client has asked for before the program starts.
+#[derive(Default)]
+struct Session {
+    program: Option<PathBuf>,
+    stop_on_entry: bool,
+    allow_eval: bool,
+    breakpoints: BTreeSet<usize>,
+    configured: bool,
+}
+
+impl Session {
+    fn run(mut self, connection: &Rc<RefCell<Connection>>, lossy: bool) -> io::Result<()> {
+        // the client initializes, launches, and sets breakpoints before it
+        // says it is done configuring, after which the program starts
+        while self.program.is_none() || !self.configured {
+            let Some(request) = connection.borrow_mut().recv()? else {
+                return Ok(());
+            };
+            let mut connection = connection.borrow_mut();
+            match request["command"].as_str().unwrap_or_default() {
+                "initialize" => {
+                    connection.respond(
+                        &request,
+                        json!({
+                            "supportsConfigurationDoneRequest": true,
+                            "supportsTerminateRequest": true,
+                        }),
+                    )?;
+                    connection.event("initialized", json!({}))?;
+                }
+                "launch" => {
+                    let arguments = &request["arguments"];
+                    let Some(program) = arguments["program"].as_str() else {
+                        connection.respond_error(&request, "launch needs a `program` path")?;
+                        continue;
+                    };
+                    self.program = Some(PathBuf::from(program));
+                    self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                    self.allow_eval = arguments["allowEval"].as_bool().unwrap_or(false);
+                    connection.respond(&request, json!({}))?;
+                }
+                "setBreakpoints" => {
+                    self.breakpoints = requested_lines(&request);
+                    connection.respond(&request, breakpoints_body(&self.breakpoints))?;
+                }
+                "configurationDone" => {
+                    self.configured = true;
+                    connection.respond(&request, json!({}))?;
+                }
+                "threads" => connection.respond(&request, threads_body())?,
+                "disconnect" | "terminate" => {
+                    connection.respond(&request, json!({}))?;
+                    return Ok(());
+                }
+                command => {
+                    connection.respond_error(&request, format!("{command} is not supported"))?
+                }
+            }
+        }
+
+        let exit_code = self.launch(connection, lossy)?;
+
+        let mut connection = connection.borrow_mut();
+        connection.event("exited", json!({ "exitCode": exit_code }))?;
+        connection.event("terminated", json!({}))?;
+        while let Some(request) = connection.recv()? {
+            match request["command"].as_str().unwrap_or_default() {
+                "disconnect" => return connection.respond(&request, json!({})),
+                "threads" => connection.respond(&request, threads_body())?,
+                _ => connection.respond_error(&request, "the program has finished")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the program, with its output sent to the client, and returns the
+    /// exit code `run` would have exited with.
+    fn launch(self, connection: &Rc<RefCell<Connection>>, lossy: bool) -> io::Result<i32> {
+        let path = self.program.expect("launched before running");
+        let report = |connection: &Rc<RefCell<Connection>>, e: miette::Report| {
+            connection.borrow_mut().event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{e:?}\n") }),
+            )
+        };
+
+        let read = Manifest::for_program(&path).and_then(|manifest| {
+            let translated = manifest
+                .language_pack()
+                .translate(&read_source(&path, lossy)?);
+            Ok((manifest.terminators(None), translated))
+        });
+        let (terminators, translated) = match read {
+            Ok(read) => read,
+            Err(e) => {
+                report(connection, e)?;
+                return Ok(1);
+            }
+        };
+        let arena = Arena::new();
+        let program = match Parser::new(&translated, &arena)
+            .terminators(terminators)
+            .parse_program()
+        {
+            Ok(program) => program,
+            Err(e) => {
+                report(connection, e)?;
+                return Ok(65);
+            }
+        };
+
+        let mode = if self.stop_on_entry {
+            Mode::Step
+        } else {
+            Mode::Continue
+        };
+        let adapter = Adapter {
+            connection: Rc::clone(connection),
+            path: path.clone(),
+            stepper: Stepper::new(translated.clone(), self.breakpoints, mode),
+            entry: self.stop_on_entry,
+        };
+        let mut interpreter = Interpreter::new()
+            .allow_eval(self.allow_eval)
+            .output(ClientOutput(Rc::clone(connection)))
+            .debugger(adapter);
+
+        match interpreter.eval_program(&program) {
+            Ok(_) => Ok(0),
+            Err(e) => {
+                report(
+                    connection,
+                    miette::Report::new(e).with_source_code(translated.clone()),
+                )?;
+                Ok(70)
+            }
+        }
+    }
+}
+
+/// The debugger hook that pauses the program and serves the client's requests
+/// while it is paused.
+struct Adapter {
+    connection: Rc<RefCell<Connection>>,
+    path: PathBuf,
+    stepper: Stepper,
+    /// Whether the next pause is the one at the very first statement.
+    entry: bool,
+}
+
+impl Adapter {
+    fn pause(
+        &mut self,
+        interpreter: &Interpreter,
+        at: Location,
+        line: usize,
+        reason: &str,
+    ) -> io::Result<()> {
+        let mut connection = self.connection.borrow_mut();
+        connection.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+
+        loop {
+            let Some(request) = connection.recv()? else {
+                // the client is gone, so nobody is left to resume the program
+                std::process::exit(0);
+            };
+            let arguments = &request["arguments"];
+            match request["command"].as_str().unwrap_or_default() {
+                "threads" => connection.respond(&request, threads_body())?,
+                "stackTrace" => connection.respond(
+                    &request,
+                    json!({
+                        "stackFrames": [{
+                            "id": 1,
+                            "name": "<program>",
+                            "source": { "path": self.path },
+                            "line": line,
+                            "column": 1,
+                        }],
+                        "totalFrames": 1,
+                    }),
+                )?,
+                "scopes" => connection.respond(
+                    &request,
+                    json!({
+                        "scopes": [{
+                            "name": "Globals",
+                            "variablesReference": GLOBALS_REFERENCE,
+                            "expensive": false,
+                        }],
+                    }),
+                )?,
+                "variables" => {
+                    let variables: Vec<Json> = interpreter
+                        .globals()
+                        .map(|(name, value)| {
+                            json!({
+                                "name": name,
+                                "value": value.repr(),
+                                "type": value.type_name(),
+                                "variablesReference": 0,
+                            })
+                        })
+                        .collect();
+                    connection.respond(&request, json!({ "variables": variables }))?;
+                }
+                "evaluate" => {
+                    let wanted = arguments["expression"].as_str().unwrap_or_default().trim();
+                    match interpreter.globals().find(|(name, _)| *name == wanted) {
+                        Some((_, value)) => connection.respond(
+                            &request,
+                            json!({ "result": value.repr(), "variablesReference": 0 }),
+                        )?,
+                        None => connection
+                            .respond_error(&request, format!("{wanted} is not defined"))?,
+                    }
+                }
+                "setBreakpoints" => {
+                    self.stepper.breakpoints = requested_lines(&request);
+                    connection.respond(&request, breakpoints_body(&self.stepper.breakpoints))?;
+                }
+                command @ ("continue" | "next" | "stepIn" | "stepOut") => {
+                    let mode = match command {
+                        "continue" => Mode::Continue,
+                        "next" => Mode::Next(at.nesting),
+                        "stepIn" => Mode::Step,
+                        _ => match at.nesting.checked_sub(1) {
+                            Some(outer) => Mode::Next(outer),
+                            None => Mode::Continue,
+                        },
+                    };
+                    self.stepper.resume(mode, line);
+                    let body = if command == "continue" {
+                        json!({ "allThreadsContinued": true })
+                    } else {
+                        json!({})
+                    };
+                    return connection.respond(&request, body);
+                }
+                "disconnect" | "terminate" => {
+                    connection.respond(&request, json!({}))?;
+                    std::process::exit(0);
+                }
+                command => {
+                    connection.respond_error(&request, format!("{command} is not supported"))?
+                }
+            }
+        }
+    }
+}
+
+impl Debugger for Adapter {
+    fn before_statement(&mut self, interpreter: &Interpreter, at: Location, _: &TokenTree<'_>) {
+        if at.calls > 0 {
+            // code run by eval() has no lines in the program's file
+            return;
+        }
+        let line = self.stepper.line_of(at.offset);
+        let Some(pause) = self.stepper.should_pause(at, line) else {
+            return;
+        };
+        let reason = match pause {
+            _ if std::mem::take(&mut self.entry) => "entry",
+            Pause::Step => "step",
+            Pause::Breakpoint => "breakpoint",
+        };
+        if self.pause(interpreter, at, line, reason).is_err() {
+            // the connection broke, and with it the session
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Sends what the program prints to the client as `output` events.
+struct ClientOutput(Rc<RefCell<Connection>>);
+
+impl Write for ClientOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let output = String::from_utf8_lossy(buf);
+        self.0
+            .borrow_mut()
+            .event("output", json!({ "category": "stdout", "output": output }))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn requested_lines(request: &Json) -> BTreeSet<usize> {
+    request["arguments"]["breakpoints"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|breakpoint| breakpoint["line"].as_u64())
+        .map(|line| line as usize)
+        .collect()
+}
+
+fn breakpoints_body(lines: &BTreeSet<usize>) -> Json {
+    let breakpoints: Vec<Json> = lines
+        .iter()
+        .map(|line| json!({ "verified": true, "line": line }))
+        .collect();
+    json!({ "breakpoints": breakpoints })
+}
+
+fn threads_body() -> Json {
+    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })
+}
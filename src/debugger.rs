@@ -0,0 +1,203 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use crate::interpreter::{Debugger, Interpreter, Location};
+use crate::parse::TokenTree;
+use crate::theme::{paint, Stream, Style};
+
+const HELP: &str = "\
+commands:
+  s, step          run to the next statement, entering loops and blocks
+  n, next          run to the next statement at this level or above
+  c, continue      run to the next breakpoint
+  b, break LINE    pause whenever a statement on LINE is about to run
+  d, delete LINE   remove the breakpoint on LINE
+  p, print [NAME]  show the variable NAME, or every variable
+  l, list          show the statement about to run
+  q, quit          stop the program
+  h, help          show this message
+an empty line steps";
+
+/// How far to let the program run before pausing again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Step,
+    /// Pause at the next statement nested at most this deep.
+    Next(usize),
+    Continue,
+}
+
+/// Why a [`Stepper`] paused the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Pause {
+    Step,
+    Breakpoint,
+}
+
+/// The breakpoints and stepping state that decide where a debugger pauses,
+/// whatever it is the debugger talks to.
+pub(crate) struct Stepper {
+    /// The translated program the statements were parsed from. Translation
+    /// keeps lines intact, so offsets into it give lines of the original.
+    translated: String,
+    pub(crate) breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    /// The line of the breakpoint that was continued from, so that the other
+    /// statements on it don't pause again.
+    resumed_from: Option<usize>,
+}
+
+impl Stepper {
+    pub(crate) fn new(translated: String, breakpoints: BTreeSet<usize>, mode: Mode) -> Self {
+        Self {
+            translated,
+            breakpoints,
+            mode,
+            resumed_from: None,
+        }
+    }
+
+    pub(crate) fn line_of(&self, offset: usize) -> usize {
+        self.translated[..offset.min(self.translated.len())]
+            .matches('\n')
+            .count()
+            + 1
+    }
+
+    /// Whether to pause at the statement on `line` at `at`.
+    pub(crate) fn should_pause(&mut self, at: Location, line: usize) -> Option<Pause> {
+        match self.mode {
+            Mode::Step => Some(Pause::Step),
+            Mode::Next(nesting) => (at.nesting <= nesting).then_some(Pause::Step),
+            Mode::Continue => {
+                let resumed_here = self.resumed_from == Some(line);
+                if !resumed_here {
+                    self.resumed_from = None;
+                }
+                (self.breakpoints.contains(&line) && !resumed_here).then_some(Pause::Breakpoint)
+            }
+        }
+    }
+
+    /// Resumes the program paused at `line` until `mode` says to pause again.
+    pub(crate) fn resume(&mut self, mode: Mode, line: usize) {
+        self.mode = mode;
+        if mode == Mode::Continue {
+            self.resumed_from = Some(line);
+        }
+    }
+}
+
+/// An interactive debugger that talks to the user over stdin and stderr,
+/// leaving stdout to the program.
+pub struct Terminal {
+    path: PathBuf,
+    /// The program as the user wrote it, for showing lines.
+    source: String,
+    stepper: Stepper,
+}
+
+impl Terminal {
+    /// Creates a debugger for the program at `path`. It pauses at the first
+    /// statement unless there are `breakpoints`, in which case the program
+    /// runs until it reaches one.
+    pub fn new(
+        path: PathBuf,
+        source: String,
+        translated: String,
+        breakpoints: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let breakpoints: BTreeSet<usize> = breakpoints.into_iter().collect();
+        let mode = if breakpoints.is_empty() {
+            Mode::Step
+        } else {
+            Mode::Continue
+        };
+        Self {
+            path,
+            source,
+            stepper: Stepper::new(translated, breakpoints, mode),
+        }
+    }
+
+    fn show(&self, line: usize, statement: &TokenTree<'_>) {
+        let text = self.source.lines().nth(line - 1).unwrap_or_default();
+        eprintln!(
+            "{} {}:{line}",
+            paint(Stream::Stderr, Style::Note, "paused at"),
+            self.path.display()
+        );
+        eprintln!("{line:>5} | {}", text.trim_end());
+        eprintln!("      = {statement}");
+    }
+}
+
+impl Debugger for Terminal {
+    fn before_statement(
+        &mut self,
+        interpreter: &Interpreter,
+        at: Location,
+        statement: &TokenTree<'_>,
+    ) {
+        if at.calls > 0 {
+            // code run by eval() has no lines in this file
+            return;
+        }
+        let line = self.stepper.line_of(at.offset);
+        if self.stepper.should_pause(at, line).is_none() {
+            return;
+        }
+        self.show(line, statement);
+
+        let mut input = io::stdin().lock();
+        loop {
+            eprint!("(debug) ");
+            let _ = io::stderr().flush();
+
+            let mut command = String::new();
+            if input.read_line(&mut command).unwrap_or(0) == 0 {
+                // stdin is closed, so nobody is left to resume the program
+                eprintln!();
+                std::process::exit(0);
+            }
+            let mut words = command.split_whitespace();
+            let (name, argument) = (words.next().unwrap_or("step"), words.next());
+
+            match name {
+                "s" | "step" => return self.stepper.resume(Mode::Step, line),
+                "n" | "next" => return self.stepper.resume(Mode::Next(at.nesting), line),
+                "c" | "continue" => return self.stepper.resume(Mode::Continue, line),
+                "b" | "break" | "d" | "delete" => {
+                    let Some(target) = argument.and_then(|arg| arg.parse::<usize>().ok()) else {
+                        eprintln!("{name} needs a line number");
+                        continue;
+                    };
+                    if name.starts_with('b') {
+                        self.stepper.breakpoints.insert(target);
+                        eprintln!("breakpoint set on line {target}");
+                    } else if self.stepper.breakpoints.remove(&target) {
+                        eprintln!("breakpoint on line {target} removed");
+                    } else {
+                        eprintln!("no breakpoint on line {target}");
+                    }
+                }
+                "p" | "print" => match argument {
+                    Some(wanted) => match interpreter.globals().find(|(name, _)| *name == wanted) {
+                        Some((name, value)) => eprintln!("{name} = {}", value.repr()),
+                        None => eprintln!("{wanted} is not defined"),
+                    },
+                    None => {
+                        for (name, value) in interpreter.globals() {
+                            eprintln!("{name} = {}", value.repr());
+                        }
+                    }
+                },
+                "l" | "list" => self.show(line, statement),
+                "q" | "quit" => std::process::exit(0),
+                "h" | "help" => eprintln!("{HELP}"),
+                other => eprintln!("unknown command '{other}'; type 'help' for a list"),
+            }
+        }
+    }
+}
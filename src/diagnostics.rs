@@ -0,0 +1,531 @@
+use crate::theme::{self, Stream};
+use crate::translator::SourceMap;
+use miette::{
+    Diagnostic, LabeledSpan, MietteHandler, MietteHandlerOpts, ReportHandler, Severity, SourceCode,
+    SourceSpan,
+};
+use serde_json::json;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::{error::Error, fmt};
+
+/// How diagnostics are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum DiagnosticFormat {
+    /// miette's graphical reports
+    #[default]
+    Human,
+    /// one JSON object per line, for editors and CI
+    Json,
+}
+
+static FORMAT: OnceLock<DiagnosticFormat> = OnceLock::new();
+
+/// Sets the diagnostic format for the rest of the process. Only the first call
+/// has any effect.
+pub fn set_format(format: DiagnosticFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> DiagnosticFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Installs the miette report handler used for every diagnostic the CLI prints.
+pub fn install_hook() {
+    // only fails if a hook was already installed, in which case we keep that one
+    let _ = miette::set_hook(Box::new(|_| Box::new(AksharaHandler::new())));
+}
+
+/// Writes `report` to stderr in the configured [`DiagnosticFormat`]. `file` is
+/// the source file the diagnostic is about, if any.
+pub fn emit(report: &miette::Report, file: Option<&Path>) {
+    match format() {
+        DiagnosticFormat::Human => eprintln!("{report:?}"),
+        DiagnosticFormat::Json => {
+            for line in json_lines(report.as_ref(), None, file) {
+                eprintln!("{line}");
+            }
+        }
+    }
+}
+
+/// Flattens a diagnostic into JSON objects, one per line. A diagnostic that
+/// only groups related ones (such as "found 2 syntax error(s)") is replaced by
+/// them.
+fn json_lines(
+    diagnostic: &dyn Diagnostic,
+    parent_source: Option<&dyn SourceCode>,
+    file: Option<&Path>,
+) -> Vec<serde_json::Value> {
+    let source = diagnostic.source_code().or(parent_source);
+
+    if let Some(related) = diagnostic.related() {
+        let lines: Vec<_> = related
+            .flat_map(|related| json_lines(related, source, file))
+            .collect();
+        if !lines.is_empty() {
+            return lines;
+        }
+    }
+
+    let severity = match diagnostic.severity().unwrap_or(Severity::Error) {
+        Severity::Advice => "advice",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+
+    let mut causes = Vec::new();
+    let mut cause = diagnostic.source();
+    while let Some(e) = cause {
+        causes.push(e.to_string());
+        cause = e.source();
+    }
+
+    let mut labels = diagnostic.labels().into_iter().flatten();
+    let label = labels.next();
+    let location = label
+        .as_ref()
+        .and_then(|label| source.and_then(|source| line_column(source, label.inner())));
+    // the other labels, such as the calls that led to a runtime error
+    let related: Vec<_> = labels
+        .map(|label| {
+            let location = source.and_then(|source| line_column(source, label.inner()));
+            json!({
+                "line": location.map(|(line, _)| line),
+                "column": location.map(|(_, column)| column),
+                "label": label.label(),
+            })
+        })
+        .collect();
+
+    vec![json!({
+        "severity": severity,
+        "message": diagnostic.to_string(),
+        "causes": causes,
+        "code": diagnostic.code().map(|code| code.to_string()),
+        "help": diagnostic.help().map(|help| help.to_string()),
+        "file": file.map(|file| file.display().to_string()),
+        "line": location.map(|(line, _)| line),
+        "column": location.map(|(_, column)| column),
+        "span": label.as_ref().map(|label| json!({
+            "offset": label.offset(),
+            "length": label.len(),
+        })),
+        "label": label.as_ref().and_then(|label| label.label().map(String::from)),
+        "related": related,
+    })]
+}
+
+/// The 1-based line and column (in characters) at which `span` starts.
+fn line_column(source: &dyn SourceCode, span: &SourceSpan) -> Option<(usize, usize)> {
+    let contents = source.read_span(span, 1, 0).ok()?;
+    let text = std::str::from_utf8(contents.data()).ok()?;
+    let before = text.get(..span.offset().checked_sub(contents.span().offset())?)?;
+
+    let line = contents.line() + before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |nl| nl + 1);
+    let column = before[line_start..].chars().count() + 1;
+    Some((line, column))
+}
+
+/// The source of a program as it was written, with the map back to it from
+/// its translation, which is what gets parsed and run. Diagnostics found in
+/// the translation are reported through it, so that their lines, columns,
+/// and snippets are those of the file.
+#[derive(Debug, Clone, Copy)]
+pub struct Original<'a> {
+    pub source: &'a str,
+    pub map: &'a SourceMap,
+}
+
+impl Original<'_> {
+    /// `diagnostic`, whose spans are into the translation, as a report whose
+    /// spans are into the source.
+    pub fn report(&self, diagnostic: &dyn Diagnostic) -> miette::Report {
+        miette::Report::new(self.mapped(diagnostic)).with_source_code(self.source.to_string())
+    }
+
+    fn mapped(&self, diagnostic: &dyn Diagnostic) -> Mapped {
+        let labels = diagnostic.labels().into_iter().flatten().map(|label| {
+            let start = self.map.original_offset(label.offset());
+            let end = self
+                .map
+                .original_offset(label.offset() + label.len())
+                .max(start);
+            let text = label.label().map(String::from);
+            if label.primary() {
+                LabeledSpan::new_primary_with_span(text, start..end)
+            } else {
+                LabeledSpan::new_with_span(text, start..end)
+            }
+        });
+        let mut causes = Vec::new();
+        let mut cause = diagnostic.source();
+        while let Some(e) = cause {
+            causes.push(e.to_string());
+            cause = e.source();
+        }
+        Mapped {
+            message: diagnostic.to_string(),
+            code: diagnostic.code().map(|code| code.to_string()),
+            severity: diagnostic.severity(),
+            help: diagnostic.help().map(|help| help.to_string()),
+            url: diagnostic.url().map(|url| url.to_string()),
+            labels: labels.collect(),
+            related: diagnostic
+                .related()
+                .into_iter()
+                .flatten()
+                .map(|related| self.mapped(related))
+                .collect(),
+            cause: causes.into_iter().rev().fold(None, |source, message| {
+                Some(Box::new(Cause { message, source }))
+            }),
+        }
+    }
+}
+
+/// A diagnostic copied by [`Original::report`], with its spans moved.
+#[derive(Debug)]
+struct Mapped {
+    message: String,
+    code: Option<String>,
+    severity: Option<Severity>,
+    help: Option<String>,
+    url: Option<String>,
+    labels: Vec<LabeledSpan>,
+    related: Vec<Mapped>,
+    cause: Option<Box<Cause>>,
+}
+
+/// One error of the chain of causes of a [`Mapped`] diagnostic.
+#[derive(Debug)]
+struct Cause {
+    message: String,
+    source: Option<Box<Cause>>,
+}
+
+impl fmt::Display for Mapped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Mapped {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause.as_deref().map(|cause| cause as &dyn Error)
+    }
+}
+
+impl Diagnostic for Mapped {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.code
+            .as_ref()
+            .map(|code| Box::new(code) as Box<dyn fmt::Display>)
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.url
+            .as_ref()
+            .map(|url| Box::new(url) as Box<dyn fmt::Display>)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        if self.labels.is_empty() {
+            return None;
+        }
+        Some(Box::new(self.labels.iter().cloned()))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        if self.related.is_empty() {
+            return None;
+        }
+        Some(Box::new(
+            self.related
+                .iter()
+                .map(|related| related as &dyn Diagnostic),
+        ))
+    }
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Cause {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|cause| cause as &dyn Error)
+    }
+}
+
+/// Renders diagnostics through miette's default handler, but first widens each
+/// label so that it starts and ends on an akshara (orthographic syllable)
+/// boundary.
+///
+/// Miette measures columns one code point at a time, so a span that begins or
+/// ends on a matra, virama, or nukta puts the caret in the middle of a
+/// conjunct. Snapping to akshara boundaries keeps the underline under the
+/// whole syllable the user actually sees.
+pub struct AksharaHandler {
+    inner: MietteHandler,
+}
+
+impl AksharaHandler {
+    /// Creates a handler that colors its output according to the theme
+    /// settings for stderr.
+    pub fn new() -> Self {
+        Self {
+            inner: MietteHandlerOpts::new()
+                .color(theme::colors_enabled(Stream::Stderr))
+                .build(),
+        }
+    }
+}
+
+impl Default for AksharaHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReportHandler for AksharaHandler {
+    fn debug(&self, diagnostic: &dyn Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.debug(&AksharaAligned::new(diagnostic, None), f)
+    }
+}
+
+struct AksharaAligned<'a> {
+    inner: &'a dyn Diagnostic,
+    source_code: Option<&'a dyn SourceCode>,
+    related: Vec<AksharaAligned<'a>>,
+}
+
+impl<'a> AksharaAligned<'a> {
+    fn new(inner: &'a dyn Diagnostic, parent_source: Option<&'a dyn SourceCode>) -> Self {
+        let source_code = inner.source_code().or(parent_source);
+        let related = inner
+            .related()
+            .map(|related| {
+                related
+                    .map(|diagnostic| AksharaAligned::new(diagnostic, source_code))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            inner,
+            source_code,
+            related,
+        }
+    }
+
+    fn snap(&self, label: LabeledSpan) -> LabeledSpan {
+        let Some(source) = self.source_code else {
+            return label;
+        };
+        let Ok(contents) = source.read_span(label.inner(), 1, 1) else {
+            return label;
+        };
+        let Ok(text) = std::str::from_utf8(contents.data()) else {
+            return label;
+        };
+
+        let base = contents.span().offset();
+        let start = label.offset().saturating_sub(base);
+        let end = start + label.len();
+        if end > text.len() {
+            return label;
+        }
+
+        let (start, end) = snap_to_aksharas(text, start, end);
+        let span = SourceSpan::from(base + start..base + end);
+        if label.primary() {
+            LabeledSpan::new_primary_with_span(label.label().map(String::from), span)
+        } else {
+            LabeledSpan::new_with_span(label.label().map(String::from), span)
+        }
+    }
+}
+
+impl fmt::Debug for AksharaAligned<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.inner, f)
+    }
+}
+
+impl fmt::Display for AksharaAligned<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.inner, f)
+    }
+}
+
+impl Error for AksharaAligned<'_> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl Diagnostic for AksharaAligned<'_> {
+    fn code<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        self.inner.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.inner.severity()
+    }
+
+    fn help<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        self.inner.help()
+    }
+
+    fn url<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        self.inner.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source_code
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let labels = self.inner.labels()?;
+        Some(Box::new(labels.map(|label| self.snap(label))))
+    }
+
+    fn related<'b>(&'b self) -> Option<Box<dyn Iterator<Item = &'b dyn Diagnostic> + 'b>> {
+        if self.related.is_empty() {
+            return None;
+        }
+        Some(Box::new(
+            self.related
+                .iter()
+                .map(|related| related as &dyn Diagnostic),
+        ))
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.inner.diagnostic_source()
+    }
+}
+
+/// Dependent signs that attach to the preceding letter rather than starting a
+/// new akshara: candrabindu, anusvara, visarga, nukta, matras, virama, stress
+/// marks, vocalic vowel signs, and the ZWJ/ZWNJ joiner controls.
+fn is_dependent(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0900}'..='\u{0903}'
+            | '\u{093A}'..='\u{093C}'
+            | '\u{093E}'..='\u{094F}'
+            | '\u{0951}'..='\u{0957}'
+            | '\u{0962}'..='\u{0963}'
+            | '\u{200C}'
+            | '\u{200D}'
+    )
+}
+
+fn is_consonant(c: char) -> bool {
+    matches!(c, '\u{0915}'..='\u{0939}' | '\u{0958}'..='\u{095F}' | '\u{0978}'..='\u{097F}')
+}
+
+/// Byte offsets at which a new akshara starts, plus `text.len()`.
+fn akshara_boundaries(text: &str) -> Vec<usize> {
+    let mut boundaries = Vec::new();
+    let mut after_virama = false;
+
+    for (i, c) in text.char_indices() {
+        let joins = is_dependent(c) || (after_virama && is_consonant(c));
+        if !joins {
+            boundaries.push(i);
+        }
+        after_virama = match c {
+            '\u{094D}' => true,
+            // a joiner between virama and consonant keeps the conjunct open
+            '\u{200C}' | '\u{200D}' => after_virama,
+            _ => false,
+        };
+    }
+
+    boundaries.push(text.len());
+    boundaries
+}
+
+/// Widens `start..end` within `text` so both ends fall on akshara boundaries.
+pub fn snap_to_aksharas(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let boundaries = akshara_boundaries(text);
+
+    let snapped_start = boundaries
+        .iter()
+        .copied()
+        .take_while(|&b| b <= start)
+        .last()
+        .unwrap_or(0);
+    let snapped_end = boundaries
+        .iter()
+        .copied()
+        .find(|&b| b >= end)
+        .unwrap_or(text.len());
+
+    (snapped_start, snapped_end.max(snapped_start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `क्षेत्र`: the aksharas `क्षे` (a conjunct with a matra) and `त्र`.
+    const WORD: &str = "चर क्षेत्र = 1;";
+    const KSHE: usize = 7;
+    const TRA: usize = 19;
+    const END: usize = 28;
+
+    #[test]
+    fn spans_on_akshara_boundaries_are_kept() {
+        assert_eq!(snap_to_aksharas(WORD, KSHE, END), (KSHE, END));
+        assert_eq!(snap_to_aksharas(WORD, TRA, END), (TRA, END));
+        assert_eq!(snap_to_aksharas("a + b", 2, 3), (2, 3));
+    }
+
+    #[test]
+    fn a_span_starting_on_a_virama_starts_at_its_consonant() {
+        assert_eq!(&WORD[KSHE + 3..KSHE + 6], "\u{094D}");
+        assert_eq!(snap_to_aksharas(WORD, KSHE + 3, END), (KSHE, END));
+    }
+
+    #[test]
+    fn a_span_ending_before_a_matra_takes_it_in() {
+        assert_eq!(&WORD[KSHE + 9..TRA], "\u{0947}");
+        assert_eq!(snap_to_aksharas(WORD, KSHE, KSHE + 9), (KSHE, TRA));
+        assert_eq!(snap_to_aksharas("कि", 3, 6), (0, 6));
+    }
+
+    #[test]
+    fn a_span_ending_after_a_virama_takes_in_the_whole_conjunct() {
+        assert_eq!(snap_to_aksharas(WORD, TRA, TRA + 6), (TRA, END));
+    }
+
+    #[test]
+    fn a_joiner_after_a_virama_keeps_the_conjunct_together() {
+        let text = "क\u{094D}\u{200D}ष";
+        assert_eq!(snap_to_aksharas(text, 9, 12), (0, 12));
+        assert_eq!(snap_to_aksharas(text, 0, 6), (0, 12));
+    }
+
+    #[test]
+    fn a_span_inside_one_akshara_covers_all_of_it() {
+        assert_eq!(snap_to_aksharas(WORD, KSHE + 3, KSHE + 6), (KSHE, TRA));
+    }
+}
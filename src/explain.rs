@@ -0,0 +1,451 @@
+use std::fmt::Write;
+
+use crate::locale::{self, Locale};
+
+/// The long form of an error or warning code, as `explain` prints it.
+#[derive(Debug, Clone, Copy)]
+pub struct Explanation {
+    pub code: &'static str,
+    /// A few words saying what the code is for.
+    pub title: &'static str,
+    /// What the diagnostic means and how to fix it, in English.
+    pub en: &'static str,
+    /// The same, in Sanskrit.
+    pub sa: &'static str,
+    /// A program that gets the diagnostic. A first line starting with `//`
+    /// gives the options it needs. Empty for diagnostics that a short program
+    /// can't show.
+    pub example: &'static str,
+}
+
+/// Every code a diagnostic can have: `E0…` for syntax errors, `E1…` for
+/// runtime errors, and `W…` for the warnings of `check`.
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "unexpected character",
+        en: "The program contains a character that is not part of any token: not a letter, \
+             digit, operator, or bracket. It is often a symbol pasted from elsewhere, or a \
+             quotation mark of the wrong kind. Remove it, or put it inside a string.",
+        sa: "कार्यक्रमे किञ्चित् अक्षरं वर्तते यत् कस्यापि चिह्नस्य भागः न भवति। तद् अक्षरं \
+             निष्कासयतु, अथवा शब्दस्य अन्तः स्थापयतु।",
+        example: "कथय 1 @ 2;",
+    },
+    Explanation {
+        code: "E0002",
+        title: "unterminated string",
+        en: "A string literal was opened with `\"` but never closed, so the rest of the file \
+             was read as part of it. Add the closing `\"`.",
+        sa: "शब्दः `\"` इत्यनेन आरब्धः किन्तु न समापितः। अन्ते `\"` योजयतु।",
+        example: "कथय \"नमस्ते;",
+    },
+    Explanation {
+        code: "E0003",
+        title: "unexpected end of file",
+        en: "The file ended in the middle of a statement or expression, often because a \
+             bracket was opened and not closed. Finish the statement.",
+        sa: "वाक्यस्य मध्ये एव सञ्चिका समाप्ता, प्रायः कोष्ठकस्य असमापनात्। वाक्यं पूरयतु।",
+        example: "कथय (1 + 2",
+    },
+    Explanation {
+        code: "E0004",
+        title: "invalid number literal",
+        en: "A number literal could not be read as a number. Number literals are digits, in \
+             Latin or Devanagari, with at most one decimal point.",
+        sa: "संख्या पठितुं न शक्यते। संख्यायां केवलम् अङ्काः एकः दशमलवबिन्दुः च भवन्ति।",
+        example: "",
+    },
+    Explanation {
+        code: "E0100",
+        title: "syntax errors",
+        en: "The program has syntax errors, which are listed under this one, each with its \
+             own code. A program with syntax errors is not run at all.",
+        sa: "कार्यक्रमे वाक्यरचना-दोषाः सन्ति, ये अधः स्वसङ्केतैः सह सूचिताः। तादृशः कार्यक्रमः \
+             सर्वथा न चाल्यते।",
+        example: "कथय (;\nकथय 1 @ 2;",
+    },
+    Explanation {
+        code: "E0101",
+        title: "expected an expression",
+        en: "Where a value was needed, such as after an operator or inside brackets, the \
+             parser found something that can't start one. Write the missing value.",
+        sa: "यत्र मूल्यम् अपेक्षितम्, तत्र अन्यत् किमपि प्राप्तम्। लुप्तं मूल्यं लिखतु।",
+        example: "कथय (;",
+    },
+    Explanation {
+        code: "E0102",
+        title: "expected the end of the expression",
+        en: "Text that should hold a single expression, such as one an embedding program \
+             reads with `Parser::parse_complete_expression`, goes on after the expression.",
+        sa: "एकम् एव व्यञ्जकम् अपेक्षितम्, किन्तु तस्य अनन्तरम् अपि किमपि लिखितम्।",
+        example: "1 + 2 3",
+    },
+    Explanation {
+        code: "E0103",
+        title: "expected an operator",
+        en: "A value is followed by another value with no operator between them. Usually the \
+             statement before was complete and is missing its `;`, which the error points at.",
+        sa: "मूल्यद्वयस्य मध्ये संक्रियकः नास्ति। प्रायः पूर्ववाक्यस्य अन्ते `;` लुप्तम्।",
+        example: "चर क = 1 कथय क;",
+    },
+    Explanation {
+        code: "E0104",
+        title: "unexpected token",
+        en: "The parser needed a particular token, such as the `(` after `यदि` or the `=` of \
+             an assignment, and found another. The message says which one was missing.",
+        sa: "विशिष्टं चिह्नम् अपेक्षितम्, यथा `यदि` अनन्तरं `(`, किन्तु अन्यत् प्राप्तम्।",
+        example: "यदि क > 0 { कथय क; }",
+    },
+    Explanation {
+        code: "E0105",
+        title: "unterminated block",
+        en: "A block or `मेल` was opened with `{` and the file ended before its `}`. Add the \
+             closing brace.",
+        sa: "`{` इत्यनेन आरब्धः खण्डः `}` विना समाप्तः। अन्ते `}` योजयतु।",
+        example: "यदि (सत्य) { कथय 1;",
+    },
+    Explanation {
+        code: "E0106",
+        title: "arm after the default arm",
+        en: "The `अन्यथा` arm of a `मेल` applies when no other arm does, so it has to come \
+             last. Move it to the end.",
+        sa: "`अन्यथा` इति शाखा अन्ते एव भवेत्, यतः सा तदा प्रवर्तते यदा अन्या न प्रवर्तते।",
+        example: "मेल (1) { अन्यथा: { कथय 0; } 1: { कथय 1; } }",
+    },
+    Explanation {
+        code: "E0107",
+        title: "invalid match arm",
+        en: "The arms of a `मेल` compare against literals, such as `1`, `\"क\"`, or `सत्य`. \
+             For other conditions, use `यदि`.",
+        sa: "`मेल` इत्यस्य शाखाः केवलं स्थिरमूल्यैः सह तुलनां कुर्वन्ति। अन्यासु स्थितिषु `यदि` \
+             प्रयुनक्तु।",
+        example: "मेल (1) { 1 + 1: { कथय 2; } }",
+    },
+    Explanation {
+        code: "E0108",
+        title: "invalid assignment target",
+        en: "Only variables can be assigned to, incremented, or decremented, not numbers or \
+             the results of other expressions.",
+        sa: "केवलं चराणां मूल्यं परिवर्तयितुं शक्यते, न तु संख्यानाम् अन्यव्यञ्जकानां वा।",
+        example: "5++;",
+    },
+    Explanation {
+        code: "E0109",
+        title: "wrong number of values in an assignment",
+        en: "An assignment to several variables at once, as in `क, ख = ख, क`, needs exactly \
+             one value for each variable.",
+        sa: "अनेकेषु चरेषु युगपत् नियोजने प्रत्येकस्य चरस्य एकम् एव मूल्यम् अपेक्षितम्।",
+        example: "चर क = 0;\nचर ख = 0;\nक, ख = 1;",
+    },
+    Explanation {
+        code: "E0110",
+        title: "invalid loop variable",
+        en: "A `पुरा (… मध्ये …)` loop needs a variable name before `मध्ये`, which takes each \
+             item in turn.",
+        sa: "`पुरा (… मध्ये …)` इत्यस्मिन् `मध्ये` इत्यस्मात् पूर्वं चरस्य नाम अपेक्षितम्।",
+        example: "पुरा (1 मध्ये [1, 2]) { कथय 1; }",
+    },
+    Explanation {
+        code: "E0111",
+        title: "nested too deeply",
+        en: "Statements and expressions nest more deeply than the parser allows, which is \
+             usually a sign of generated code. Split the program into smaller pieces.",
+        sa: "वाक्यानि व्यञ्जकानि च अत्यधिकं निहितानि। कार्यक्रमं लघुभागेषु विभजतु।",
+        example: "",
+    },
+    Explanation {
+        code: "E0112",
+        title: "invalid for loop declaration",
+        en: "A `पुरा (चर क = 0; क < 3; क++)` loop can declare its variable with `चर`, but not \
+             with `स्थिर`, since the loop changes it. A `पुरा (क मध्ये …)` loop declares its \
+             variable itself, so it takes a bare name without `चर`.",
+        sa: "`पुरा` इत्यस्य चरः `चर` इत्यनेन घोषणीयः न तु `स्थिर` इत्यनेन। `मध्ये` इत्यस्य \
+             पाशे चरस्य नाम `चर` विना लिखतु।",
+        example: "पुरा (चर फल मध्ये [1, 2]) { कथय फल; }",
+    },
+    Explanation {
+        code: "E1001",
+        title: "division by zero",
+        en: "A number was divided by zero with `/` or `%`. This is only an error with \
+             `--division-by-zero error`; by default the result is `नेति`.",
+        sa: "संख्या शून्येन विभक्ता। एषः दोषः केवलं `--division-by-zero error` सह भवति; \
+             अन्यथा फलं `नेति` भवति।",
+        example: "// sanskriti run --division-by-zero error\nकथय 1 / 0;",
+    },
+    Explanation {
+        code: "E1002",
+        title: "value cannot be iterated over",
+        en: "A `पुरा (… मध्ये …)` loop was given a value that has no items. Only lists, \
+             strings, and ranges such as `1..10` can be looped over.",
+        sa: "आवर्तनाय दत्तस्य मूल्यस्य अवयवाः न सन्ति। केवलं सूचीनां, शब्दानां, परिधीनां च \
+             आवर्तनं भवति।",
+        example: "पुरा (क मध्ये 5) { कथय क; }",
+    },
+    Explanation {
+        code: "E1003",
+        title: "undefined variable",
+        en: "A name was read that no `चर` declares and that isn't a builtin. It is often a \
+             misspelling, which is easy with Devanagari names that differ only in a matra; the \
+             error suggests a defined name that is close to it. Declare the variable before \
+             reading it, or fix the spelling.",
+        sa: "यस्य नाम्नः पठनं कृतं, सः चरः `चर` इत्यनेन न घोषितः। प्रायः वर्तनीदोषः भवति; दोषः \
+             समीपस्थं घोषितं नाम सूचयति। पठनात् पूर्वं चरं घोषयतु, अथवा वर्तनीं शोधयतु।",
+        example: "चर फलम् = 10;\nकथय फलं;",
+    },
+    Explanation {
+        code: "E1004",
+        title: "wrong number of arguments",
+        en: "A function was called with more or fewer arguments than it takes. The message \
+             says how many it expects.",
+        sa: "कार्यम् अपेक्षितात् अधिकैः न्यूनैः वा तर्कैः आहूतम्।",
+        example: "कथय length(\"क\", \"ख\");",
+    },
+    Explanation {
+        code: "E1005",
+        title: "value is not a function",
+        en: "Something other than a function was called with `(…)`, such as a number or a \
+             string.",
+        sa: "कार्यात् भिन्नं किमपि, यथा संख्या, `(…)` इत्यनेन आहूतम्।",
+        example: "चर क = 5;\nक();",
+    },
+    Explanation {
+        code: "E1006",
+        title: "builtin is not enabled",
+        en: "The builtin exists but is off unless the program is run with the option the \
+             message names, such as `--allow-fs` for files or `--allow-eval` for `eval`.",
+        sa: "एतत् अन्तर्निहितं कार्यं केवलं सन्देशे उक्तेन विकल्पेन सह उपलभ्यते।",
+        example: "कथय पठ(\"पत्रम्.txt\");",
+    },
+    Explanation {
+        code: "E1007",
+        title: "uncaught exception",
+        en: "A value was thrown with `फेंक` and no `प्रयत्न … पकड़` caught it, so the program \
+             stopped.",
+        sa: "`फेंक` इत्यनेन क्षिप्तं मूल्यं केनापि `पकड़` इत्यनेन न गृहीतम्।",
+        example: "फेंक \"अमान्य संख्या\";",
+    },
+    Explanation {
+        code: "E1008",
+        title: "stack overflow",
+        en: "Execution nested more deeply than the interpreter allows, usually through a \
+             function, or `eval`, calling itself without end. Recursion through tail calls, \
+             where the call is the value of a `देयम`, doesn't nest, so it can go as deep as it \
+             needs to.",
+        sa: "निष्पादनम् अत्यधिकं निहितम्, प्रायः कस्यचित् कार्यस्य `eval` इत्यस्य वा अनन्तात् \
+             स्व-आह्वानात्। यत्र आह्वानं `देयम` इत्यस्य मूल्यं भवति, तादृशं पुनरावर्तनं न निहितं \
+             भवति।",
+        example: "विनियोग अनन्त(न) { देयम 1 + अनन्त(न + 1); }\nअनन्त(0);",
+    },
+    Explanation {
+        code: "E1009",
+        title: "range ends are not whole numbers",
+        en: "Both ends of a range such as `1..10` have to be whole numbers.",
+        sa: "परिधेः उभौ अन्तौ पूर्णसंख्ये भवेताम्।",
+        example: "पुरा (क मध्ये 1..2.5) { कथय क; }",
+    },
+    Explanation {
+        code: "E1010",
+        title: "list added to a non-list",
+        en: "`+` joins a list only to another list. To add one item, put it in a list of its \
+             own: `[1, 2] + [3]`.",
+        sa: "`+` सूचीं केवलं सूच्या सह योजयति। एकम् अवयवं योजयितुं तं सूच्यां स्थापयतु।",
+        example: "कथय [1, 2] + 3;",
+    },
+    Explanation {
+        code: "E1011",
+        title: "operand of the wrong type",
+        en: "In strict mode, an operator was given operands of types it doesn't take, such \
+             as `-` on a string. Without `--strict` the result would be `नेति`.",
+        sa: "कठोरविधौ संक्रियकाय अयोग्यप्रकारस्य मूल्यं दत्तम्।",
+        example: "// sanskriti run --strict\nकथय -\"क\";",
+    },
+    Explanation {
+        code: "E1012",
+        title: "output could not be written",
+        en: "Writing what `कथय` printed failed, for example because the program's output was \
+             piped into a command that has exited.",
+        sa: "`कथय` इत्यस्य निर्गमः लेखितुं न शक्तः।",
+        example: "",
+    },
+    Explanation {
+        code: "E1013",
+        title: "step limit exceeded",
+        en: "The program ran more steps than `--max-steps`, or `max-steps` in \
+             `sanskriti.toml`, allows. Look for a loop that never ends.",
+        sa: "कार्यक्रमः `--max-steps` इति सीमायाः अधिकानि पदानि अचलत्। अनन्तं चक्रम् अन्विष्यतु।",
+        example: "// sanskriti run --max-steps 1000\nयावद (सत्य) { }",
+    },
+    Explanation {
+        code: "E1014",
+        title: "time limit exceeded",
+        en: "The program ran for longer than `--time-limit`, or `time-limit-secs` in \
+             `sanskriti.toml`, allows.",
+        sa: "कार्यक्रमः `--time-limit` इति सीमायाः अधिकं कालम् अचलत्।",
+        example: "// sanskriti run --time-limit 1\nयावद (सत्य) { }",
+    },
+    Explanation {
+        code: "E1015",
+        title: "memory limit exceeded",
+        en: "The program's values took more memory than `--max-memory`, or `max-memory-mb` \
+             in `sanskriti.toml`, allows.",
+        sa: "कार्यक्रमस्य मूल्यानि `--max-memory` इति सीमायाः अधिकां स्मृतिम् अगृह्णन्।",
+        example: "// sanskriti run --max-memory 1\nचर क = repeat(\"क\", 1000000);",
+    },
+    Explanation {
+        code: "E1016",
+        title: "assertion failed",
+        en: "`assert` or `पुष्टि` was given a condition that does not hold. The message \
+             includes the second argument, if one was given.",
+        sa: "`पुष्टि` इत्यस्मै दत्ता स्थितिः असत्या।",
+        example: "assert(1 > 2, \"गणना\");",
+    },
+    Explanation {
+        code: "E1017",
+        title: "argument of the wrong type",
+        en: "A builtin was given an argument of a type it doesn't take, such as a number \
+             where it needs a string. The message names the type it expects.",
+        sa: "अन्तर्निहिताय कार्याय अयोग्यप्रकारस्य तर्कः दत्तः।",
+        example: "कथय length(5);",
+    },
+    Explanation {
+        code: "E1018",
+        title: "argument out of range",
+        en: "A builtin was given an argument of the right type but with a value it can't \
+             use, such as a start after the end or a fractional count.",
+        sa: "अन्तर्निहिताय कार्याय योग्यप्रकारस्य किन्तु अनुपयोगिमूल्यस्य तर्कः दत्तः।",
+        example: "कथय substring(\"नमस्ते\", 3, 1);",
+    },
+    Explanation {
+        code: "E1019",
+        title: "file could not be read or written",
+        en: "`पठ` or `लिख` failed, usually because the file does not exist or the program \
+             isn't allowed to write there.",
+        sa: "`पठ` `लिख` वा विफलम्, प्रायः सञ्चिकायाः अभावात्।",
+        example: "// sanskriti run --allow-fs\nकथय पठ(\"नास्ति.txt\");",
+    },
+    Explanation {
+        code: "E1020",
+        title: "value could not be serialized",
+        en: "`serialize` was given a value it can't store, such as a function or lists \
+             nested more than 512 deep, or `deserialize` was given bytes that `serialize` \
+             didn't make.",
+        sa: "`serialize` इत्यनेन मूल्यं सङ्ग्रहीतुं न शक्यते, यथा कार्यम्।",
+        example: "कथय serialize(length);",
+    },
+    Explanation {
+        code: "E1021",
+        title: "assignment to an undeclared variable",
+        en: "A value was assigned to a variable that no `चर` declares. Assignment only changes \
+             variables that exist, so that a misspelled name can't quietly make a new one. \
+             Declare the variable with `चर` first, or run with `--implicit-declarations` to let \
+             the assignment declare it.",
+        sa: "यः चरः `चर` इत्यनेन न घोषितः, तस्मिन् मूल्यं नियोजितम्। प्रथमं `चर` इत्यनेन तं \
+             घोषयतु, अथवा `--implicit-declarations` सह चालयतु।",
+        example: "चर योगः = 0;\nयोग = 5;",
+    },
+    Explanation {
+        code: "E1022",
+        title: "assignment to a constant",
+        en: "A value was assigned to a name declared with `स्थिर`. A constant keeps the value it \
+             was declared with; declare it with `चर` instead if it needs to change.",
+        sa: "`स्थिर` इत्यनेन घोषिते नाम्नि मूल्यं नियोजितम्। स्थिरं स्वस्य प्रथमं मूल्यम् एव \
+             धारयति; यदि परिवर्तनम् आवश्यकं तर्हि `चर` इत्यनेन घोषयतु।",
+        example: "स्थिर पाई = 3.14;\nपाई = 3;",
+    },
+    Explanation {
+        code: "E1023",
+        title: "unpacking a value that isn't a list",
+        en: "A declaration like `चर [क, ख] = मूल्यम्;` unpacks the items of a list into \
+             variables, but the value was not a list.",
+        sa: "`चर [क, ख] = मूल्यम्;` इति घोषणा सूच्याः वस्तूनि चरेषु विभजति, किन्तु मूल्यं \
+             सूची नासीत्।",
+        example: "चर [क, ख] = 5;",
+    },
+    Explanation {
+        code: "E1024",
+        title: "unpacking the wrong number of items",
+        en: "A declaration like `चर [क, ख] = युग्मम्;` needs the list to have exactly as many \
+             items as there are variables in the brackets.",
+        sa: "`चर [क, ख] = युग्मम्;` इति घोषणायां सूच्यां यावन्तः चराः तावन्ति एव वस्तूनि \
+             भवेयुः।",
+        example: "चर [क, ख] = [1, 2, 3];",
+    },
+    Explanation {
+        code: "E1025",
+        title: "input could not be read",
+        en: "Reading a line for `पृच्छ` failed, for example because the input was not valid \
+             UTF-8 text. The end of the input is not an error: `पृच्छ` returns `नेति` then.",
+        sa: "`पृच्छ` इत्यस्य कृते पङ्क्तिः पठितुं न शक्ता। निवेशस्य अन्ते `पृच्छ` `नेति` \
+             प्रत्यर्पयति।",
+        example: "",
+    },
+    Explanation {
+        code: "E1026",
+        title: "return outside a function",
+        en: "A `देयम` (`return`) ran outside the body of any function, where there is \
+             nothing to return from. To stop a program early, `फेंक` a value instead.",
+        sa: "`देयम` कस्यापि कार्यस्य शरीरात् बहिः प्रयुक्तम्, यत्र प्रत्यागन्तुं किमपि नास्ति। \
+             कार्यक्रमं पूर्वमेव स्थगयितुं `फेंक` प्रयुङ्क्ताम्।",
+        example: "देयम 1;",
+    },
+    Explanation {
+        code: "E1027",
+        title: "eval code that doesn't parse",
+        en: "The string given to `eval` is not a valid expression or program. The error \
+             says where in the string parsing stopped.",
+        sa: "`eval` इत्यस्मै दत्तः शब्दः न वैधं व्यञ्जकं न च कार्यक्रमः। दोषः शब्दे विश्लेषणं \
+             कुत्र स्थगितम् इति वदति।",
+        example: "// sanskriti run --allow-eval\nकथय eval(\"1 +\");",
+    },
+    Explanation {
+        code: "W0001",
+        title: "unused variable",
+        en: "A variable is declared but never read. Remove it, or start its name with `_` if \
+             it is there on purpose.",
+        sa: "चरः घोषितः किन्तु कदापि न पठितः। तं निष्कासयतु, अथवा तस्य नाम `_` इत्यनेन आरभतु।",
+        example: "चर क = 1;",
+    },
+    Explanation {
+        code: "W0002",
+        title: "value never read",
+        en: "A value is stored in a variable and then overwritten before anything reads it, \
+             so storing it had no effect.",
+        sa: "चरे स्थापितं मूल्यं पठनात् पूर्वम् एव पुनः लिखितम्।",
+        example: "चर क = 1;\nक = 2;\nकथय क;",
+    },
+    Explanation {
+        code: "W0003",
+        title: "unreachable code",
+        en: "Statements come after a `देयम` or `फेंक` that always leaves the block, so they \
+             never run.",
+        sa: "`देयम` `फेंक` वा अनन्तरं स्थितानि वाक्यानि कदापि न चलन्ति।",
+        example: "फेंक \"त्रुटि\";\nकथय 1;",
+    },
+];
+
+/// The explanation of `code`, which can be written in either case.
+pub fn explanation(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+/// `explanation` as `explain` prints it: the code and title, what it means
+/// in both languages with the current locale's first, and the example.
+pub fn render(explanation: &Explanation) -> String {
+    let (first, second, example) = match locale::current() {
+        Locale::En => (explanation.en, explanation.sa, "Example"),
+        Locale::Sa => (explanation.sa, explanation.en, "उदाहरणम्"),
+    };
+    let mut out = format!(
+        "{}: {}\n\n{first}\n\n{second}\n",
+        explanation.code, explanation.title
+    );
+    if !explanation.example.is_empty() {
+        let _ = write!(out, "\n{example}:\n\n");
+        for line in explanation.example.lines() {
+            let _ = writeln!(out, "    {line}");
+        }
+    }
+    out
+}
@@ -0,0 +1,400 @@
+use unicode_width::UnicodeWidthStr;
+
+use crate::builtins::MAX_REPEAT_LEN;
+use crate::interpreter::{runtime_error, Interpreter, NativeFn, RuntimeError, Value};
+use crate::{locale, message};
+
+/// `कथयf(format, values…)` prints `format` with each placeholder in it
+/// replaced by the next of `values`, for lining up tables:
+/// `कथयf("{:<8} {:>6.2}", नाम, मूल्य)`. A placeholder is `{}`, which writes
+/// the value as `कथय` would, or `{:spec}`, where `spec` is, in order and
+/// each optional:
+///
+/// - a fill character and an alignment, `<` (left), `^` (centered), or `>`
+///   (right); numbers are aligned right and everything else left otherwise
+/// - `0`, to pad a number with zeros after its sign
+/// - a minimum width, in terminal columns
+/// - `.` and a precision: the digits after the point of a number, or the
+///   most characters of anything else to write
+///
+/// `{{` and `}}` write a brace. Giving more or fewer values than there are
+/// placeholders is a runtime error, as is a malformed placeholder.
+pub(crate) const KATHAYAF: NativeFn = NativeFn {
+    name: "कथयf",
+    arity: 1..=usize::MAX,
+    fun: |interpreter, args| {
+        let text = format(interpreter, &args[0], &args[1..])?;
+        interpreter.print_line(text)?;
+        Ok(Value::Nil)
+    },
+};
+
+/// `printf(format, values…)`, the Lox name of [`KATHAYAF`].
+pub(crate) const PRINTF: NativeFn = NativeFn {
+    name: "printf",
+    ..KATHAYAF
+};
+
+/// `template` with its placeholders replaced by `values`, as [`KATHAYAF`]
+/// describes.
+fn format(
+    interpreter: &Interpreter,
+    template: &Value,
+    values: &[Value],
+) -> Result<String, RuntimeError> {
+    let Value::String(template) = template else {
+        return Err(runtime_error!(
+            "E1017/format",
+            kind = locale::current().type_name(template.type_name())
+        ));
+    };
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = &**template;
+    let mut placeholders = 0;
+    while let Some(at) = rest.find(['{', '}']) {
+        out.push_str(&rest[..at]);
+        let brace = &rest[at..at + 1];
+        rest = &rest[at + 1..];
+        if let Some(after) = rest.strip_prefix(brace) {
+            out.push_str(brace);
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            return Err(format_error(message!("E1018/format-brace")));
+        }
+
+        let Some(end) = rest.find('}') else {
+            return Err(format_error(message!("E1018/format-unclosed")));
+        };
+        let spec = Spec::parse(&rest[..end])?;
+        rest = &rest[end + 1..];
+        if let Some(value) = values.get(placeholders) {
+            spec.write(&mut out, interpreter, value);
+        }
+        placeholders += 1;
+    }
+    out.push_str(rest);
+
+    if placeholders != values.len() {
+        return Err(format_error(message!(
+            "E1018/format-count",
+            placeholders = placeholders,
+            values = values.len()
+        ))
+        .with_help(message!("E1018/format-count-help")));
+    }
+    Ok(out)
+}
+
+fn format_error(message: String) -> RuntimeError {
+    runtime_error!("E1018/format", message = message)
+}
+
+#[derive(Clone, Copy)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// What goes between the braces of a placeholder.
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(text: &str) -> Result<Self, RuntimeError> {
+        let mut spec = Spec {
+            fill: ' ',
+            align: None,
+            zero: false,
+            width: 0,
+            precision: None,
+        };
+        if text.is_empty() {
+            return Ok(spec);
+        }
+        let Some(text) = text.strip_prefix(':') else {
+            return Err(format_error(message!(
+                "E1018/format-placeholder",
+                text = text
+            )));
+        };
+
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        };
+        let mut chars = text.chars();
+        let mut rest = text;
+        match (chars.next(), chars.next().and_then(align)) {
+            (Some(fill), Some(alignment)) => {
+                spec.fill = fill;
+                spec.align = Some(alignment);
+                rest = chars.as_str();
+            }
+            (Some(first), _) if align(first).is_some() => {
+                spec.align = align(first);
+                rest = &text[1..];
+            }
+            _ => {}
+        }
+
+        if let Some(after) = rest.strip_prefix('0') {
+            spec.zero = true;
+            rest = after;
+        }
+        let (width, after) = number(rest);
+        spec.width = width.unwrap_or(0);
+        rest = after;
+        if let Some(after) = rest.strip_prefix('.') {
+            let (precision, after) = number(after);
+            if precision.is_none() {
+                return Err(format_error(message!("E1018/format-precision")));
+            }
+            spec.precision = precision;
+            rest = after;
+        }
+        let largest = spec.width.max(spec.precision.unwrap_or(0));
+        if largest > MAX_REPEAT_LEN {
+            return Err(format_error(message!(
+                "E1018/format-limit",
+                largest = largest,
+                limit = MAX_REPEAT_LEN
+            )));
+        }
+
+        if !rest.is_empty() {
+            return Err(format_error(message!(
+                "E1018/format-spec",
+                rest = rest,
+                text = text
+            )));
+        }
+        Ok(spec)
+    }
+
+    /// Writes `value` to `out` as this spec says.
+    fn write(&self, out: &mut String, interpreter: &Interpreter, value: &Value) {
+        let number = matches!(value, Value::Number(_));
+        let text = match (value, self.precision) {
+            (Value::Number(n), Some(precision)) => format!("{n:.precision$}"),
+            (_, Some(precision)) => interpreter
+                .printed(value)
+                .to_string()
+                .chars()
+                .take(precision)
+                .collect(),
+            (_, None) => interpreter.printed(value).to_string(),
+        };
+
+        let padding = self.width.saturating_sub(text.width());
+        if self.zero && number && self.align.is_none() {
+            let (sign, digits) = match text.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", &*text),
+            };
+            out.push_str(sign);
+            out.extend(std::iter::repeat_n('0', padding));
+            out.push_str(digits);
+            return;
+        }
+
+        let fill = if self.zero && self.align.is_none() {
+            '0'
+        } else {
+            self.fill
+        };
+        let align = self
+            .align
+            .unwrap_or(if number { Align::Right } else { Align::Left });
+        let before = match align {
+            Align::Left => 0,
+            Align::Center => padding / 2,
+            Align::Right => padding,
+        };
+        out.extend(std::iter::repeat_n(fill, before));
+        out.push_str(&text);
+        out.extend(std::iter::repeat_n(fill, padding - before));
+    }
+}
+
+/// The whole number at the start of `text`, if there is one, and the rest.
+fn number(text: &str) -> (Option<usize>, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    (text[..end].parse().ok(), &text[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A number value.
+    fn n(number: f64) -> Value {
+        Value::Number(number)
+    }
+
+    /// A string value.
+    fn s(text: &str) -> Value {
+        Value::String(text.into())
+    }
+
+    /// `template` formatted with `values`, or the message of the error it
+    /// fails with.
+    fn formatted(template: &str, values: &[Value]) -> Result<String, String> {
+        format(&Interpreter::new(), &s(template), values).map_err(|e| e.message)
+    }
+
+    /// The message of the `E1018` error with `message`.
+    fn error(message: String) -> Result<String, String> {
+        Err(format_error(message).message)
+    }
+
+    #[test]
+    fn placeholders_write_values_as_print_does() {
+        assert_eq!(
+            formatted("{} + {} = {}", &[n(1.5), n(2.5), s("चत्वारि")]),
+            Ok("1.5 + 2.5 = चत्वारि".to_string())
+        );
+        assert_eq!(formatted("नमः", &[]), Ok("नमः".to_string()));
+    }
+
+    #[test]
+    fn fill_and_alignment_pad_to_the_width() {
+        assert_eq!(
+            formatted("{:*<6}|{:*^7}|{:*>6}", &[s("ab"), s("ab"), s("ab")]),
+            Ok("ab****|**ab***|****ab".to_string())
+        );
+        assert_eq!(
+            formatted("{:<6}|{:^6}|{:>6}", &[n(1.5), n(1.5), n(1.5)]),
+            Ok("1.5   | 1.5  |   1.5".to_string())
+        );
+        assert_eq!(formatted("{:ॐ>5}", &[s("ab")]), Ok("ॐॐॐab".to_string()));
+    }
+
+    #[test]
+    fn numbers_align_right_and_everything_else_left() {
+        assert_eq!(
+            formatted("[{:6}] [{:6}]", &[n(-2.5), s("ab")]),
+            Ok("[  -2.5] [ab    ]".to_string())
+        );
+    }
+
+    #[test]
+    fn the_width_is_counted_in_terminal_columns() {
+        assert_eq!(formatted("[{:>5}]", &[s("कथय")]), Ok("[  कथय]".to_string()));
+    }
+
+    #[test]
+    fn zero_padding_goes_after_the_sign_of_a_number() {
+        assert_eq!(
+            formatted("{:06} {:06}", &[n(-3.5), n(3.5)]),
+            Ok("-003.5 0003.5".to_string())
+        );
+        assert_eq!(
+            formatted("{:07.2}", &[n(-1.23456)]),
+            Ok("-001.23".to_string())
+        );
+        assert_eq!(formatted("{:05}", &[s("ab")]), Ok("ab000".to_string()));
+        assert_eq!(formatted("{:*>05}", &[n(1.5)]), Ok("**1.5".to_string()));
+    }
+
+    #[test]
+    fn precision_is_digits_of_numbers_and_characters_of_anything_else() {
+        assert_eq!(
+            formatted("{:.2}|{:.2}|{:.0}", &[n(2.0 / 3.0), s("abcdef"), n(2.5)]),
+            Ok("0.67|ab|2".to_string())
+        );
+        assert_eq!(formatted("{:.2}", &[s("संस्कृत")]), Ok("सं".to_string()));
+        assert_eq!(
+            formatted("{:>6.3}", &[s("abcdef")]),
+            Ok("   abc".to_string())
+        );
+    }
+
+    #[test]
+    fn doubled_braces_write_one_brace() {
+        assert_eq!(
+            formatted("{{}} {{{}}}", &[n(1.5)]),
+            Ok("{} {1.5}".to_string())
+        );
+    }
+
+    #[test]
+    fn the_values_must_match_the_placeholders() {
+        let failure = format(&Interpreter::new(), &s("{} {}"), &[n(1.0)]).unwrap_err();
+        assert_eq!(failure.code, Some("E1018"));
+        assert_eq!(
+            Err(failure.message),
+            error(message!("E1018/format-count", placeholders = 2, values = 1))
+        );
+        assert_eq!(failure.help, Some(message!("E1018/format-count-help")));
+
+        assert_eq!(
+            formatted("{}", &[n(1.0), n(2.0), n(3.0)]),
+            error(message!("E1018/format-count", placeholders = 1, values = 3))
+        );
+        assert_eq!(
+            formatted("{{}}", &[n(1.0)]),
+            error(message!("E1018/format-count", placeholders = 0, values = 1))
+        );
+    }
+
+    #[test]
+    fn malformed_placeholders_are_errors() {
+        assert_eq!(
+            formatted("a } b", &[]),
+            error(message!("E1018/format-brace"))
+        );
+        assert_eq!(
+            formatted("{:<5", &[n(1.0)]),
+            error(message!("E1018/format-unclosed"))
+        );
+        assert_eq!(
+            formatted("{x}", &[n(1.0)]),
+            error(message!("E1018/format-placeholder", text = "x"))
+        );
+        assert_eq!(
+            formatted("{:5.}", &[n(1.0)]),
+            error(message!("E1018/format-precision"))
+        );
+        assert_eq!(
+            formatted("{:5x}", &[n(1.0)]),
+            error(message!("E1018/format-spec", rest = "x", text = "5x"))
+        );
+        assert_eq!(
+            formatted("{:<<<}", &[n(1.0)]),
+            error(message!("E1018/format-spec", rest = "<", text = "<<<"))
+        );
+    }
+
+    #[test]
+    fn widths_and_precisions_are_limited() {
+        let largest = MAX_REPEAT_LEN + 1;
+        assert_eq!(
+            formatted(&std::format!("{{:.{largest}}}"), &[n(1.0)]),
+            error(message!(
+                "E1018/format-limit",
+                largest = largest,
+                limit = MAX_REPEAT_LEN
+            ))
+        );
+        assert!(formatted(&std::format!("{{:{MAX_REPEAT_LEN}}}"), &[n(1.0)]).is_ok());
+    }
+
+    #[test]
+    fn the_format_must_be_a_string() {
+        let error = format(&Interpreter::new(), &n(1.0), &[]).unwrap_err();
+        assert_eq!(error.code, Some("E1017"));
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+
+use self_cell::self_cell;
+
+use crate::gc::{self, Gc};
+use crate::interpreter::{Environment, Str};
+use crate::lex::Token;
+use crate::parse::{Arena, Atom, TokenTree};
+
+type Tree<'a> = TokenTree<'a>;
+
+self_cell!(
+    /// A function's body, in an arena of its own so that it outlives the
+    /// program or snippet it was parsed from.
+    struct Body {
+        owner: Arena,
+
+        #[covariant]
+        dependent: Tree,
+    }
+);
+
+/// A function written in the program, by a `विनियोग` declaration or
+/// expression.
+pub struct Function {
+    /// The name it was declared with; function expressions have none.
+    pub name: Option<Str>,
+    pub parameters: Vec<Str>,
+    /// The scope of the call of the function it was made in, if any, which
+    /// it shares with that call, so that it can still read and assign its
+    /// variables after that function has returned.
+    pub(crate) captured: Option<Environment>,
+    body: Body,
+}
+
+impl Function {
+    pub(crate) fn new(
+        name: Option<Str>,
+        parameters: &[Token<'_>],
+        body: &TokenTree<'_>,
+        captured: Option<Environment>,
+    ) -> Self {
+        Function {
+            name,
+            parameters: parameters
+                .iter()
+                .map(|parameter| parameter.origin.into())
+                .collect(),
+            captured,
+            body: Body::new(Arena::new(), |arena| copy(body, arena)),
+        }
+    }
+
+    /// What the function runs when called.
+    pub fn body(&self) -> &TokenTree<'_> {
+        self.body.borrow_dependent()
+    }
+
+    /// How errors and traces refer to the function.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("<anonymous>")
+    }
+}
+
+impl gc::Trace for Function {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        if let Some(captured) = &self.captured {
+            visit(Gc::addr(captured));
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "<fn {name}>"),
+            None => write!(f, "<fn>"),
+        }
+    }
+}
+
+/// A copy of `node`, with everything it refers to, in `arena`. Spans and
+/// offsets are kept, so errors in the copy point into the original source.
+fn copy<'a>(node: &TokenTree<'_>, arena: &'a Arena) -> TokenTree<'a> {
+    match node {
+        TokenTree::Atom(atom) => TokenTree::Atom(copy_atom(atom, arena)),
+        TokenTree::Cons(op, children) => TokenTree::Cons(*op, all(children, arena)),
+        TokenTree::Fun {
+            name,
+            parameters,
+            body,
+        } => TokenTree::Fun {
+            name: name.as_ref().map(|name| copy_atom(name, arena)),
+            parameters: arena.alloc_slice_fill_iter(parameters.iter().map(|parameter| Token {
+                origin: arena.alloc_str(parameter.origin),
+                offset: parameter.offset,
+                kind: parameter.kind,
+            })),
+            body: one(body, arena),
+        },
+        TokenTree::Call { callee, arguments } => TokenTree::Call {
+            callee: one(callee, arena),
+            arguments: all(arguments, arena),
+        },
+        TokenTree::If { condition, yes, no } => TokenTree::If {
+            condition: one(condition, arena),
+            yes: one(yes, arena),
+            no: no.map(|no| one(no, arena)),
+        },
+        TokenTree::Block { statements, value } => TokenTree::Block {
+            statements: all(statements, arena),
+            value: value.map(|value| one(value, arena)),
+        },
+        TokenTree::Statement { offset, node } => TokenTree::Statement {
+            offset: *offset,
+            node: one(node, arena),
+        },
+        TokenTree::Spanned { span, node } => TokenTree::Spanned {
+            span: span.clone(),
+            node: one(node, arena),
+        },
+        TokenTree::Assignments { targets, values } => TokenTree::Assignments {
+            targets: all(targets, arena),
+            values: all(values, arena),
+        },
+        TokenTree::Chain {
+            operands,
+            operators,
+            spans,
+        } => TokenTree::Chain {
+            operands: all(operands, arena),
+            operators: arena.alloc_slice_copy(operators),
+            spans: arena.alloc_slice_clone(spans),
+        },
+        TokenTree::Match {
+            subject,
+            arms,
+            default,
+        } => TokenTree::Match {
+            subject: one(subject, arena),
+            arms: arena.alloc_slice_fill_iter(arms.iter().map(|(literals, body)| {
+                let literals = arena.alloc_slice_fill_iter(
+                    literals.iter().map(|literal| copy_atom(literal, arena)),
+                );
+                (&*literals, copy(body, arena))
+            })),
+            default: default.map(|default| one(default, arena)),
+        },
+        TokenTree::ForIn {
+            variable,
+            iterable,
+            body,
+        } => TokenTree::ForIn {
+            variable: copy_atom(variable, arena),
+            iterable: one(iterable, arena),
+            body: one(body, arena),
+        },
+        TokenTree::Try {
+            body,
+            variable,
+            handler,
+        } => TokenTree::Try {
+            body: one(body, arena),
+            variable: copy_atom(variable, arena),
+            handler: one(handler, arena),
+        },
+    }
+}
+
+fn one<'a>(node: &TokenTree<'_>, arena: &'a Arena) -> &'a TokenTree<'a> {
+    arena.alloc(copy(node, arena))
+}
+
+fn all<'a>(nodes: &[TokenTree<'_>], arena: &'a Arena) -> &'a [TokenTree<'a>] {
+    arena.alloc_slice_fill_iter(nodes.iter().map(|node| copy(node, arena)))
+}
+
+fn copy_atom<'a>(atom: &Atom<'_>, arena: &'a Arena) -> Atom<'a> {
+    match *atom {
+        Atom::String(s) => Atom::String(arena.alloc_str(s)),
+        Atom::Ident(name, offset) => Atom::Ident(arena.alloc_str(name), offset),
+        Atom::Number(n) => Atom::Number(n),
+        Atom::Nil => Atom::Nil,
+        Atom::Bool(b) => Atom::Bool(b),
+        Atom::Super => Atom::Super,
+        Atom::This => Atom::This,
+    }
+}
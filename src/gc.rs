@@ -0,0 +1,214 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::rc::{Rc, Weak};
+
+/// A shared, immutable reference to a value on the object heap: a value that
+/// can refer to other heap values, such as a list, a function, or the scope
+/// a function captured, and so could end up in a reference cycle. It is
+/// counted like an `Rc`, which frees objects as soon as the last reference
+/// goes, and [`collect`] finds and frees the cycles that counting can't.
+///
+/// Dropping an object that holds the last reference to another drops that
+/// one after it instead of inside it, so that a long chain of objects, such
+/// as a list nested a million deep, can't overflow the stack.
+pub struct Gc<T: Trace + 'static>(Option<Rc<T>>);
+
+/// A value that can live on the object heap.
+pub trait Trace {
+    /// Passes the [`Gc::addr`] of each heap object this one refers to
+    /// directly to `visit`, once per reference.
+    fn trace(&self, visit: &mut dyn FnMut(usize));
+
+    /// Lets go of the objects this one refers to, which [`collect`] calls on
+    /// every object of a cycle that nothing outside of it can reach, so that
+    /// counting can free them. Objects that can't change once built can't be
+    /// part of a cycle, and need not do anything.
+    fn clear(&self) {}
+}
+
+impl<T: Trace + 'static> Gc<T> {
+    /// Moves `value` onto the heap.
+    pub fn new(value: T) -> Self {
+        let object = Rc::new(value);
+        HEAP.with_borrow_mut(|heap| {
+            let weak: Weak<dyn Trace> = Rc::downgrade(&object) as Weak<dyn Trace>;
+            heap.objects.push(weak);
+        });
+        Gc(Some(object))
+    }
+
+    /// The reference, which only [`Drop`] takes.
+    fn rc(&self) -> &Rc<T> {
+        self.0
+            .as_ref()
+            .expect("the reference is only taken when it is dropped")
+    }
+
+    /// Where the object is, which identifies it for [`Trace::trace`].
+    pub fn addr(this: &Self) -> usize {
+        Rc::as_ptr(this.rc()).cast::<()>() as usize
+    }
+
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(this.rc(), other.rc())
+    }
+}
+
+impl<T: Trace + 'static> Clone for Gc<T> {
+    fn clone(&self) -> Self {
+        Gc(Some(Rc::clone(self.rc())))
+    }
+}
+
+impl<T: Trace + 'static> Deref for Gc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.rc()
+    }
+}
+
+impl<T: Trace + fmt::Debug + 'static> fmt::Debug for Gc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.rc().fmt(f)
+    }
+}
+
+impl<T: Trace + PartialEq + 'static> PartialEq for Gc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rc() == other.rc()
+    }
+}
+
+impl<T: Trace + 'static> Drop for Gc<T> {
+    fn drop(&mut self) {
+        let Some(object) = self.0.take() else {
+            return;
+        };
+        let object: Rc<dyn Trace> = object;
+        // while another object is being dropped, this one waits its turn
+        // instead of being dropped inside it
+        let dropping = DROPPING.try_with(|dropping| dropping.replace(true));
+        match dropping {
+            Ok(true) => {
+                let _ = PENDING.try_with(move |pending| pending.borrow_mut().push(object));
+            }
+            Ok(false) => {
+                drop(object);
+                while let Ok(Some(next)) = PENDING.try_with(|pending| pending.borrow_mut().pop()) {
+                    drop(next);
+                }
+                DROPPING.set(false);
+            }
+            // the thread is exiting
+            Err(_) => drop(object),
+        }
+    }
+}
+
+/// Every object made on this thread, held weakly so that it is freed as
+/// usual when its count drops to zero.
+#[derive(Default)]
+struct Heap {
+    objects: Vec<Weak<dyn Trace>>,
+    /// How many objects there may be before [`collect_if_due`] collects.
+    threshold: usize,
+}
+
+/// The fewest objects that [`collect_if_due`] bothers collecting.
+const MIN_THRESHOLD: usize = 1024;
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        objects: Vec::new(),
+        threshold: MIN_THRESHOLD,
+    });
+    /// Whether a [`Gc`] is being dropped on this thread.
+    static DROPPING: Cell<bool> = const { Cell::new(false) };
+    /// References that went while another [`Gc`] was being dropped, which
+    /// are dropped once it has been.
+    static PENDING: RefCell<Vec<Rc<dyn Trace>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How many objects on this thread's heap are still alive.
+pub fn live_objects() -> usize {
+    HEAP.with_borrow(|heap| {
+        heap.objects
+            .iter()
+            .filter(|object| object.strong_count() > 0)
+            .count()
+    })
+}
+
+/// Collects if the heap has grown to twice its size after the last
+/// collection, so that the time spent collecting stays proportional to the
+/// objects made. Returns how many objects were freed.
+pub fn collect_if_due() -> usize {
+    let due = HEAP.with_borrow(|heap| heap.objects.len() >= heap.threshold);
+    if due {
+        collect()
+    } else {
+        0
+    }
+}
+
+/// Frees every object on this thread's heap that is only reachable from
+/// other objects in a cycle, and returns how many it freed.
+///
+/// The roots aren't needed: an object that is referred to more times than
+/// the objects of the heap account for must be held from somewhere else,
+/// like a variable or a value being computed, so it and everything it
+/// reaches are alive. Everything else is garbage.
+pub fn collect() -> usize {
+    let objects: Vec<Rc<dyn Trace>> = HEAP.with_borrow_mut(|heap| {
+        heap.objects.retain(|object| object.strong_count() > 0);
+        heap.objects.iter().filter_map(Weak::upgrade).collect()
+    });
+    let index: HashMap<usize, usize> = objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| (Rc::as_ptr(object).cast::<()>() as usize, i))
+        .collect();
+
+    // references from outside the heap, less the one `objects` holds
+    let mut outside: Vec<usize> = objects
+        .iter()
+        .map(|object| Rc::strong_count(object) - 1)
+        .collect();
+    for object in &objects {
+        object.trace(&mut |addr| {
+            if let Some(&i) = index.get(&addr) {
+                outside[i] = outside[i].saturating_sub(1);
+            }
+        });
+    }
+
+    let mut reachable = vec![false; objects.len()];
+    let mut pending: Vec<usize> = (0..objects.len()).filter(|&i| outside[i] > 0).collect();
+    while let Some(i) = pending.pop() {
+        if std::mem::replace(&mut reachable[i], true) {
+            continue;
+        }
+        objects[i].trace(&mut |addr| {
+            if let Some(&child) = index.get(&addr) {
+                pending.push(child);
+            }
+        });
+    }
+
+    for (object, reachable) in objects.iter().zip(&reachable) {
+        if !reachable {
+            object.clear();
+        }
+    }
+    let before = objects.len();
+    drop(objects);
+
+    HEAP.with_borrow_mut(|heap| {
+        heap.objects.retain(|object| object.strong_count() > 0);
+        heap.threshold = (heap.objects.len() * 2).max(MIN_THRESHOLD);
+        before - heap.objects.len()
+    })
+}
@@ -0,0 +1,147 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::batch::{Outcome, Status};
+
+/// The first line of an expected-output file for a program that should exit
+/// with a code other than 0, as in `# exit code: 70`.
+const EXIT_CODE_HEADER: &str = "# exit code: ";
+
+/// Where the expected output of `program` is kept: next to it, with
+/// `.expected` in place of its extension.
+pub fn expected_path(program: &Path) -> PathBuf {
+    program.with_extension("expected")
+}
+
+/// What a program of `test-dir` is expected to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    /// Everything the program prints.
+    pub stdout: String,
+    /// The code the program exits with, as `run` would: 0, or 65 for a syntax
+    /// error and 70 for a runtime error.
+    pub exit_code: i32,
+}
+
+impl Expected {
+    /// Reads an expected-output file: the program's output, after an
+    /// optional `# exit code: N` line.
+    pub fn parse(text: &str) -> Self {
+        if let Some(rest) = text.strip_prefix(EXIT_CODE_HEADER) {
+            let (code, stdout) = rest.split_once('\n').unwrap_or((rest, ""));
+            if let Ok(exit_code) = code.trim().parse() {
+                return Self {
+                    stdout: stdout.to_string(),
+                    exit_code,
+                };
+            }
+        }
+        Self {
+            stdout: text.to_string(),
+            exit_code: 0,
+        }
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        fs::read_to_string(path).map(|text| Self::parse(&text))
+    }
+}
+
+/// How a program compared with its expected output.
+#[derive(Debug)]
+pub enum Verdict {
+    Passed,
+    /// There is no expected-output file next to the program, or it can't be
+    /// read.
+    NoExpected(io::Error),
+    Failed {
+        expected: Expected,
+        /// The exit code the program ended with.
+        exit_code: i32,
+    },
+}
+
+impl Verdict {
+    pub fn passed(&self) -> bool {
+        matches!(self, Verdict::Passed)
+    }
+}
+
+/// Compares a program's run with the expected-output file next to it.
+pub fn compare(outcome: &Outcome) -> Verdict {
+    let expected = match Expected::read(&expected_path(&outcome.path)) {
+        Ok(expected) => expected,
+        Err(e) => return Verdict::NoExpected(e),
+    };
+    let exit_code = exit_code(&outcome.status);
+    if expected.stdout == outcome.output && expected.exit_code == exit_code {
+        Verdict::Passed
+    } else {
+        Verdict::Failed {
+            expected,
+            exit_code,
+        }
+    }
+}
+
+/// The exit code `run` would give a program that ended with `status`.
+fn exit_code(status: &Status) -> i32 {
+    match status {
+        Status::Passed => 0,
+        Status::Unreadable(_) => 1,
+        Status::CompileError(_) => 65,
+        Status::RuntimeError(_) => 70,
+    }
+}
+
+/// One line of a [`diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    /// A line of both texts.
+    Same(&'a str),
+    /// A line only of the expected text.
+    Missing(&'a str),
+    /// A line only of the actual text.
+    Extra(&'a str),
+}
+
+/// The lines of `expected` and `actual`, in order, marked by which of the two
+/// they belong to; the lines marked as in both are a longest common
+/// subsequence of the two.
+pub fn diff<'a>(expected: &'a str, actual: &'a str) -> Vec<DiffLine<'a>> {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // common[i][j] is the length of the longest common subsequence of
+    // expected[i..] and actual[j..]
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() && j < actual.len() {
+        if expected[i] == actual[j] {
+            lines.push(DiffLine::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(DiffLine::Missing(expected[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Extra(actual[j]));
+            j += 1;
+        }
+    }
+    lines.extend(expected[i..].iter().map(|line| DiffLine::Missing(line)));
+    lines.extend(actual[j..].iter().map(|line| DiffLine::Extra(line)));
+    lines
+}
@@ -0,0 +1,191 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::lex::{Token, TokenKind};
+use crate::theme::{paint, Stream, Style};
+use crate::translator::LanguagePack;
+use crate::Lexer;
+
+/// What a stretch of source code is, for coloring it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Sanskrit or Lox keywords, including the literals `सत्य`, `असत्य`, and `नेति`
+    Keyword,
+    String,
+    Number,
+    Identifier,
+    /// Operators and punctuation
+    Operator,
+    Comment,
+}
+
+impl Category {
+    /// The name used for the category in HTML class names.
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Keyword => "keyword",
+            Category::String => "string",
+            Category::Number => "number",
+            Category::Identifier => "identifier",
+            Category::Operator => "operator",
+            Category::Comment => "comment",
+        }
+    }
+
+    /// The style the category is painted in on a terminal, if any.
+    fn style(self) -> Option<Style> {
+        match self {
+            Category::Keyword => Some(Style::Keyword),
+            Category::String => Some(Style::String),
+            Category::Number => Some(Style::Number),
+            Category::Identifier => None,
+            Category::Operator => Some(Style::Operator),
+            Category::Comment => Some(Style::Comment),
+        }
+    }
+}
+
+/// A classified stretch of source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub category: Category,
+    /// Byte range in the source.
+    pub range: Range<usize>,
+}
+
+/// How `highlight` renders a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum HighlightFormat {
+    /// ANSI escape codes, for terminals
+    #[default]
+    Ansi,
+    /// a `<pre>` block with a `<span class="sk-…">` per token, for web pages
+    Html,
+}
+
+/// Classifies the tokens and comments of untranslated `source`, written with
+/// the keywords of `pack`, in order. Whitespace is left out, and so is
+/// everything from the first lexer error on, so that a half-typed program
+/// still highlights up to the mistake.
+pub fn classify(source: &str, pack: &LanguagePack) -> Vec<Highlight> {
+    let mut highlights = Vec::new();
+    let mut end = 0;
+
+    for token in Lexer::new(source) {
+        let Ok(token) = token else {
+            return highlights;
+        };
+        comments(source, end..token.offset, &mut highlights);
+        end = token.offset + token.origin.len();
+        highlights.push(Highlight {
+            category: category(&token, pack),
+            range: token.offset..end,
+        });
+    }
+
+    comments(source, end..source.len(), &mut highlights);
+    highlights
+}
+
+/// Finds the `//` comments in a stretch of source between two tokens.
+fn comments(source: &str, gap: Range<usize>, highlights: &mut Vec<Highlight>) {
+    let mut at = gap.start;
+    while let Some(start) = source[at..gap.end].find("//") {
+        let start = at + start;
+        let end = source[start..gap.end]
+            .find('\n')
+            .map_or(gap.end, |nl| start + nl);
+        highlights.push(Highlight {
+            category: Category::Comment,
+            range: start..end,
+        });
+        at = end;
+    }
+}
+
+fn category(token: &Token<'_>, pack: &LanguagePack) -> Category {
+    match token.kind {
+        TokenKind::String
+        | TokenKind::StringHead
+        | TokenKind::StringMiddle
+        | TokenKind::StringTail => Category::String,
+        TokenKind::Number(_) => Category::Number,
+        TokenKind::Ident if pack.keyword(token.origin).is_some() => Category::Keyword,
+        TokenKind::Ident => Category::Identifier,
+        TokenKind::And
+        | TokenKind::Catch
+        | TokenKind::Class
+        | TokenKind::Default
+        | TokenKind::Else
+        | TokenKind::False
+        | TokenKind::For
+        | TokenKind::Fun
+        | TokenKind::If
+        | TokenKind::In
+        | TokenKind::Match
+        | TokenKind::Nil
+        | TokenKind::Or
+        | TokenKind::Print
+        | TokenKind::Return
+        | TokenKind::Super
+        | TokenKind::This
+        | TokenKind::Throw
+        | TokenKind::True
+        | TokenKind::Try
+        | TokenKind::Var
+        | TokenKind::Const
+        | TokenKind::While => Category::Keyword,
+        _ => Category::Operator,
+    }
+}
+
+/// Renders `source` in `format`, with the highlights from [`classify`]. ANSI
+/// colors are only written if they are enabled on stdout.
+pub fn render(source: &str, pack: &LanguagePack, format: HighlightFormat) -> String {
+    let highlights = classify(source, pack);
+    let mut out = String::new();
+    if format == HighlightFormat::Html {
+        out.push_str("<pre class=\"sanskriti\"><code>");
+    }
+
+    let mut end = 0;
+    for highlight in &highlights {
+        push_plain(&mut out, &source[end..highlight.range.start], format);
+        let text = &source[highlight.range.clone()];
+        match format {
+            HighlightFormat::Ansi => match highlight.category.style() {
+                Some(style) => out.push_str(&paint(Stream::Stdout, style, text)),
+                None => out.push_str(text),
+            },
+            HighlightFormat::Html => {
+                let _ = write!(
+                    out,
+                    "<span class=\"sk-{}\">{}</span>",
+                    highlight.category.name(),
+                    escape_html(text)
+                );
+            }
+        }
+        end = highlight.range.end;
+    }
+    push_plain(&mut out, &source[end..], format);
+
+    if format == HighlightFormat::Html {
+        out.push_str("</code></pre>\n");
+    }
+    out
+}
+
+fn push_plain(out: &mut String, text: &str, format: HighlightFormat) {
+    match format {
+        HighlightFormat::Ansi => out.push_str(text),
+        HighlightFormat::Html => out.push_str(&escape_html(text)),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,37 @@
+use std::collections::HashSet;
+
+use crate::interpreter::Str;
+
+/// Keeps one shared copy of each distinct text it is given, so that text that
+/// comes up again and again, such as a string literal evaluated on every run
+/// of a loop or the name of a variable, is only allocated once and is then
+/// handed out as a cheap copy of a [`Str`].
+///
+/// The texts are kept for as long as the interner is, so only text from a
+/// program's source, of which there is a bounded amount, should be interned,
+/// and not text built while it runs.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Str>,
+}
+
+impl Interner {
+    /// The shared copy of `text`, made the first time `text` is seen.
+    pub fn intern(&mut self, text: &str) -> Str {
+        if let Some(interned) = self.strings.get(text) {
+            return interned.clone();
+        }
+        let interned = Str::from(text);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// How many distinct texts have been interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
@@ -1,17 +1,523 @@
-use std::collections::HashMap;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use miette::{Diagnostic, LabeledSpan, SourceSpan};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt,
+    io::{self, BufRead, Write},
+    ops::{Range, RangeInclusive},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
 
-use crate::parse::{Atom, Op, TokenTree};
+use crate::builtins::{
+    self, ARGS, ASSERT, INPUT, INPUT_NUMBER, KATHAY, PRAKARA, PRCCHA, PRCCHA_SANKHYA, PUSHTI,
+    REPEAT, TARKA, TYPE, WRITE,
+};
+use crate::diagnostics::Original;
+use crate::format::{KATHAYAF, PRINTF};
+use crate::function::Function;
+use crate::gc::{self, Gc};
+use crate::intern::Interner;
+use crate::lex::{is_ident_continue, is_ident_start};
+use crate::limits::{Budget, Limits, DEFAULT_MAX_DEPTH};
+use crate::lists::LISTS;
+use crate::locale::{self, Locale};
+use crate::parse::{Arena, Atom, Op, Parser, TokenTree};
+use crate::profile::Profile;
+use crate::serialize::{DESERIALIZE, SERIALIZE};
+use crate::strings::STRINGS;
+use crate::theme::{paint, Stream, Style};
+use crate::translator::{
+    translate_file_contents, translate_file_contents_with_map, LANGUAGE_PACKS, LOX_KEYWORDS,
+};
 
 #[derive(Clone, Debug)]
 pub enum Value {
     Nil,
     Number(f64),
     Bool(bool),
-    String(String),
+    String(Str),
+    /// An opaque byte blob, such as the output of `serialize`.
+    Bytes(Bytes),
+    /// An immutable list on the object heap; clones share the items.
+    List(Gc<Vec<Value>>),
+    /// The whole numbers from one to another, as made by `1..10`.
+    Range(NumberRange),
+    Native(NativeFn),
+    /// A function the embedding program registered with
+    /// [`Interpreter::register_fn`].
+    Host(HostFn),
+    /// A function declared or written as an expression in the program.
+    Function(Gc<Function>),
+}
+
+/// An error raised while a program runs, as opposed to a syntax error found
+/// before it starts.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct RuntimeError {
+    pub message: String,
+    /// Where in the program's source the error happened, if known.
+    pub span: Option<SourceSpan>,
+    /// The error's code, which `explain` describes; errors raised by host
+    /// functions have none unless they give one.
+    pub code: Option<&'static str>,
+    /// A suggestion for fixing the error.
+    pub help: Option<String>,
+    /// The calls of the program's functions that were under way when the
+    /// error happened, outermost first; empty if it happened outside them.
+    pub stack: Vec<StackFrame>,
+}
+
+/// A call of one of the program's functions, as a [`RuntimeError`] records
+/// it. A function that a tail call left isn't under way any more, so the
+/// one it called takes its place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// The name of the function called; function expressions have none.
+    pub function: Option<String>,
+    /// Where the call is in the program's source, if it is in it: calls
+    /// made by builtins such as `map` or by the host are not.
+    pub call: Option<SourceSpan>,
+}
+
+impl StackFrame {
+    /// How labels refer to the function called.
+    fn describe(&self) -> String {
+        match &self.function {
+            Some(name) => format!("`{name}`"),
+            None => "an anonymous function".to_string(),
+        }
+    }
+}
+
+impl Diagnostic for RuntimeError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.code
+            .map(|code| Box::new(code) as Box<dyn fmt::Display>)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    /// The error's own span, then each call that led to it: a label at each
+    /// call site names the function called there, so that the line numbers
+    /// of the snippet trace the error back to the top of the program.
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.span?;
+        let here = match self.stack.last() {
+            Some(frame) => format!("here, in {}", frame.describe()),
+            None => "here".to_string(),
+        };
+
+        // innermost first; a call that recursion makes over and over gets one
+        // label
+        let mut calls: Vec<(&StackFrame, usize)> = Vec::new();
+        for frame in self.stack.iter().rev() {
+            if frame.call.is_none() || frame.call == Some(span) {
+                continue;
+            }
+            match calls.iter_mut().find(|(seen, _)| *seen == frame) {
+                Some((_, times)) => *times += 1,
+                None => calls.push((frame, 1)),
+            }
+        }
+        let calls = calls.into_iter().filter_map(|(frame, times)| {
+            let label = match times {
+                1 => format!("calling {}", frame.describe()),
+                _ => format!("calling {}, {times} times", frame.describe()),
+            };
+            Some(LabeledSpan::new_with_span(Some(label), frame.call?))
+        });
+        Some(Box::new(
+            std::iter::once(LabeledSpan::new_with_span(Some(here), span)).chain(calls),
+        ))
+    }
+}
+
+/// A [`RuntimeError`] with the message for `key` from the
+/// [catalog](crate::locale::CATALOG), in the current locale, and the code
+/// the key starts with.
+macro_rules! runtime_error {
+    ($key:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::interpreter::RuntimeError::new($crate::message!($key $(, $name = $value)*))
+            .with_code($crate::locale::code($key))
+    };
+}
+pub(crate) use runtime_error;
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
+            code: None,
+            help: None,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Points the error at `span` of the program's source.
+    pub fn at(mut self, span: Range<usize>) -> Self {
+        self.span = Some(span.into());
+        self
+    }
+
+    /// Gives the error a code, one of those `explain` knows.
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Adds a suggestion for fixing the error.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    fn arity(name: &str, arity: &RangeInclusive<usize>, got: usize) -> Self {
+        let expected = if arity.start() == arity.end() {
+            arity.start().to_string()
+        } else if *arity.end() == usize::MAX {
+            match locale::current() {
+                Locale::En => format!("at least {}", arity.start()),
+                Locale::Sa => format!("न्यूनातिन्यूनं {}", arity.start()),
+            }
+        } else {
+            format!("{} to {}", arity.start(), arity.end())
+        };
+        runtime_error!("E1004", name = name, expected = expected, got = got)
+    }
+}
+
+/// The whole numbers from `start` up to `end`, which is included only if
+/// `inclusive` is set: `1..4` is 1, 2, 3 and `1..=4` is 1, 2, 3, 4. The
+/// numbers aren't stored, so a range of any length takes the same space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberRange {
+    pub start: f64,
+    pub end: f64,
+    pub inclusive: bool,
+}
+
+impl NumberRange {
+    /// How many numbers the range holds, which is 0 if it ends before it
+    /// starts.
+    pub fn len(&self) -> usize {
+        let len = self.end - self.start + if self.inclusive { 1.0 } else { 0.0 };
+        len.max(0.0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for NumberRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dots = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{dots}{}", self.start, self.end)
+    }
+}
+
+/// The text of a string value. Copies share it, so reading a string from a
+/// variable or passing it to a function doesn't copy the text, and it is only
+/// copied when one of them is changed. Appending to text that nothing else
+/// shares extends it in place, so building a string up with `+=` in a loop
+/// takes time in proportion to its length rather than to its length squared.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Str(Rc<String>);
+
+impl Str {
+    /// Appends `s`, copying the text first if it is shared.
+    pub fn push_str(&mut self, s: &str) {
+        Rc::make_mut(&mut self.0).push_str(s);
+    }
+
+    /// Whether `self` and `other` share their text.
+    fn ptr_eq(&self, other: &Str) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl std::ops::Deref for Str {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Str {
+    fn from(s: String) -> Self {
+        Str(Rc::new(s))
+    }
+}
+
+impl From<&str> for Str {
+    fn from(s: &str) -> Self {
+        Str(Rc::new(s.to_string()))
+    }
+}
+
+impl From<Str> for String {
+    fn from(s: Str) -> Self {
+        Rc::try_unwrap(s.0).unwrap_or_else(|shared| String::clone(&shared))
+    }
+}
+
+impl std::borrow::Borrow<str> for Str {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Display for Str {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl fmt::Debug for Str {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A function implemented in Rust and callable from programs.
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: &'static str,
+    /// How many arguments the function accepts; calling it with any other
+    /// number is a runtime error.
+    pub arity: RangeInclusive<usize>,
+    pub fun: fn(&mut Interpreter, &[Value]) -> Result<Value, RuntimeError>,
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// A Rust closure registered by the program embedding the interpreter, and
+/// callable from programs like a builtin.
+#[derive(Clone)]
+pub struct HostFn {
+    pub name: Rc<str>,
+    /// How many arguments the function accepts, as for [`NativeFn::arity`].
+    pub arity: RangeInclusive<usize>,
+    fun: Rc<HostCallback>,
+}
+
+type HostCallback = dyn Fn(&[Value]) -> Result<Value, RuntimeError>;
+
+impl fmt::Debug for HostFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// What a function registered with [`Interpreter::register_fn`] may return: a
+/// [`Value`], or a `Result` whose error becomes a runtime error in the program.
+pub trait IntoCallResult {
+    fn into_call_result(self) -> Result<Value, RuntimeError>;
+}
+
+impl IntoCallResult for Value {
+    fn into_call_result(self) -> Result<Value, RuntimeError> {
+        Ok(self)
+    }
+}
+
+impl IntoCallResult for Result<Value, RuntimeError> {
+    fn into_call_result(self) -> Result<Value, RuntimeError> {
+        self
+    }
+}
+
+/// `eval(code)` runs `code` in the caller's environment and returns its value:
+/// `code` may be a single expression, or a program whose value is that of its
+/// last statement if that is an expression. `eval(code, true)` runs it in a
+/// fresh environment instead, whose variables still count towards the memory
+/// limit. Code that fails to parse is a runtime error, and so are runtime
+/// errors in it, which propagate to the caller.
+const EVAL: NativeFn = NativeFn {
+    name: "eval",
+    arity: 1..=2,
+    fun: |interpreter, args| {
+        let Value::String(code) = &args[0] else {
+            return Err(runtime_error!(
+                "E1017/string",
+                name = "eval",
+                kind = locale::current().type_name(args[0].type_name())
+            ));
+        };
+        let fresh = args.get(1).is_some_and(Value::is_truthy);
+
+        let unparsable = |e: miette::Report| {
+            let error = match e.related() {
+                Some(related) => related.map(describe).collect::<Vec<_>>().join("; "),
+                None => describe(e.as_ref()),
+            };
+            runtime_error!("E1027", error = error)
+        };
+        let translated = translate_file_contents(code).map_err(unparsable)?;
+        let arena = Arena::new();
+        let expr = Parser::new(&translated, &arena)
+            .parse_complete_expression()
+            .ok();
+        let program = match expr {
+            Some(_) => Vec::new(),
+            None => Parser::new(&translated, &arena)
+                .parse_program()
+                .map_err(unparsable)?,
+        };
+
+        let outer = fresh.then(|| interpreter.env.fresh());
+        let value = match &expr {
+            Some(expr) => interpreter.eval_expr(expr),
+            None => interpreter.eval_program(&program),
+        };
+        if let Some(outer) = outer {
+            interpreter.env.restore(outer);
+        }
+        // spans in `code` would be taken for spans in the program
+        value.map_err(|e| RuntimeError {
+            span: None,
+            stack: Vec::new(),
+            ..e
+        })
+    },
+};
+
+/// The message of `diagnostic` with those of its causes, on one line.
+fn describe(diagnostic: &dyn Diagnostic) -> String {
+    let mut messages = vec![diagnostic.to_string()];
+    let mut cause = diagnostic.source();
+    while let Some(error) = cause {
+        messages.push(error.to_string());
+        cause = error.source();
+    }
+    messages.join(": ")
+}
+
+/// Whether a program could call a function named `name`: it has to lex as an
+/// identifier, and not be a keyword in Lox or in a language pack.
+fn is_callable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_ident_start)
+        && chars.all(is_ident_continue)
+        && !LOX_KEYWORDS.contains(&name)
+        && LANGUAGE_PACKS
+            .iter()
+            .all(|pack| pack.keyword(name).is_none())
+}
+
+/// The command-line flag that turns on the builtin `name`, if it is one that
+/// is off by default.
+fn capability_flag(name: &str) -> Option<&'static str> {
+    if name == EVAL.name {
+        Some("--allow-eval")
+    } else if builtins::FS.iter().any(|fs| fs.name == name) {
+        Some("--allow-fs")
+    } else {
+        None
+    }
+}
+
+/// How numbers are written when printed or converted to strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum NumberFormat {
+    /// always with a fractional part, so `2` is written `2.0`
+    #[default]
+    Decimal,
+    /// as reference Lox writes them: `2`, `2.5`, `Infinity`, `NaN`
+    Lox,
+}
+
+/// A value written out as text; see [`Value::format`].
+pub struct Formatted<'a> {
+    value: &'a Value,
+    numbers: NumberFormat,
+    /// Whether strings are quoted, as they are inside lists.
+    quoted: bool,
+    /// The locale `nil`, `true`, and `false` are written in.
+    locale: Locale,
+}
+
+impl Formatted<'_> {
+    /// Writes `nil`, `true`, and `false` as they are written in `locale`.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Nil => write!(f, "{}", self.locale.keyword("nil")),
+            Value::Number(n) => match self.numbers {
+                NumberFormat::Decimal if n.is_finite() && *n == n.trunc() => write!(f, "{n}.0"),
+                NumberFormat::Decimal => write!(f, "{n}"),
+                NumberFormat::Lox if n.is_infinite() => {
+                    write!(f, "{}Infinity", if *n < 0.0 { "-" } else { "" })
+                }
+                NumberFormat::Lox => write!(f, "{n}"),
+            },
+            Value::Bool(b) => {
+                write!(
+                    f,
+                    "{}",
+                    self.locale.keyword(if *b { "true" } else { "false" })
+                )
+            }
+            Value::String(s) if self.quoted => write!(f, "\"{s}\""),
+            Value::String(s) => write!(f, "{s}"),
+            Value::Bytes(b) => write!(f, "<{} bytes>", b.len()),
+            Value::Range(range) => write!(f, "{range}"),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let item = Formatted {
+                        value: item,
+                        numbers: self.numbers,
+                        quoted: true,
+                        locale: self.locale,
+                    };
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Native(native) => write!(f, "{native:?}"),
+            Value::Host(host) => write!(f, "{host:?}"),
+            Value::Function(function) => write!(f, "{function:?}"),
+        }
+    }
+}
+
+/// How `कथय` prints a value by default, with numbers in
+/// [`NumberFormat::Decimal`].
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(NumberFormat::Decimal))
+    }
 }
 
 impl Value {
-    fn is_truthy(&self) -> bool {
+    /// Whether the value counts as true in a condition: everything but `nil`
+    /// and `असत्य` does.
+    pub fn is_truthy(&self) -> bool {
         match self {
             Value::Nil => false,
             Value::Bool(b) => *b,
@@ -19,221 +525,2284 @@ impl Value {
         }
     }
 
-    fn to_display(&self) -> String {
-        match self {
-            Value::Nil => "nil".to_string(),
-            Value::Number(n) => {
-                if *n == n.trunc() {
-                    format!("{n}.0")
-                } else {
-                    n.to_string()
-                }
+    /// The name of this value's type, as used in runtime error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Number(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::String(_) => "string",
+            Value::Bytes(_) => "bytes",
+            Value::List(_) => "list",
+            Value::Range(_) => "range",
+            Value::Native(_) | Value::Host(_) | Value::Function(_) => "function",
+        }
+    }
+
+    /// Roughly how many bytes the value takes up beyond its own size: the text
+    /// of a string or blob, and the items of a list. Items shared between
+    /// lists are counted once per list.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Value::String(s) => s.len(),
+            Value::Bytes(b) => b.len(),
+            Value::List(items) => {
+                // lists can nest deeper than the stack would let this recurse
+                let mut pending = vec![items];
+                let mut size = 0;
+                while let Some(items) = pending.pop() {
+                    size += items.len() * std::mem::size_of::<Value>();
+                    for item in items.iter() {
+                        match item {
+                            Value::List(items) => pending.push(items),
+                            item => size += item.heap_size(),
+                        }
+                    }
+                }
+                size
+            }
+            _ => 0,
+        }
+    }
+
+    /// Starts going through the values that `for (x in value)` visits: the
+    /// items of a list, the characters (code points) of a string, or the
+    /// numbers of a range. Other values can't be iterated over.
+    pub fn iterate(&self) -> Result<ValueIter, RuntimeError> {
+        match self {
+            Value::List(items) => Ok(ValueIter::List {
+                items: Gc::clone(items),
+                next: 0,
+            }),
+            Value::String(s) => Ok(ValueIter::Chars {
+                string: s.clone(),
+                next: 0,
+            }),
+            Value::Range(range) => Ok(ValueIter::Range {
+                range: *range,
+                next: 0,
+            }),
+            other => Err(runtime_error!(
+                "E1002",
+                kind = locale::current().type_name(other.type_name())
+            )),
+        }
+    }
+
+    /// Whether `==` holds between two values. Values of different types are
+    /// never equal, and lists are equal when their items are, pairwise.
+    fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(x), Value::Bool(y)) => x == y,
+            (Value::Number(x), Value::Number(y)) => x == y,
+            (Value::String(x), Value::String(y)) => x == y,
+            (Value::List(x), Value::List(y)) => {
+                x.len() == y.len() && x.iter().zip(y.iter()).all(|(x, y)| x.equals(y))
+            }
+            (Value::Range(x), Value::Range(y)) => x == y,
+            (Value::Function(x), Value::Function(y)) => Gc::ptr_eq(x, y),
+            _ => false,
+        }
+    }
+
+    /// The value as `कथय` prints it, and as `+` and string interpolation
+    /// convert it to a string, with numbers written in `numbers` format.
+    pub fn format(&self, numbers: NumberFormat) -> Formatted<'_> {
+        Formatted {
+            value: self,
+            numbers,
+            quoted: false,
+            locale: Locale::En,
+        }
+    }
+
+    /// Like the [`Display`](fmt::Display) form, but with strings quoted so
+    /// that they can be told apart from other values.
+    pub(crate) fn repr(&self) -> String {
+        Formatted {
+            value: self,
+            numbers: NumberFormat::Decimal,
+            quoted: true,
+            locale: Locale::En,
+        }
+        .to_string()
+    }
+}
+
+/// The values of a collection, one at a time, as returned by
+/// [`Value::iterate`].
+pub enum ValueIter {
+    List {
+        items: Gc<Vec<Value>>,
+        next: usize,
+    },
+    /// `next` is a byte offset into `string`.
+    Chars {
+        string: Str,
+        next: usize,
+    },
+    /// `next` counts the numbers already visited.
+    Range {
+        range: NumberRange,
+        next: usize,
+    },
+}
+
+impl Iterator for ValueIter {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        match self {
+            ValueIter::List { items, next } => {
+                let item = items.get(*next)?.clone();
+                *next += 1;
+                Some(item)
+            }
+            ValueIter::Chars { string, next } => {
+                let c = string[*next..].chars().next()?;
+                *next += c.len_utf8();
+                Some(Value::String(c.to_string().into()))
+            }
+            ValueIter::Range { range, next } => {
+                if *next >= range.len() {
+                    return None;
+                }
+                let n = range.start + *next as f64;
+                *next += 1;
+                Some(Value::Number(n))
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Passes the heap object the value refers to, if any, to `visit`, for
+    /// [`gc::Trace::trace`].
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        match self {
+            Value::List(items) => visit(Gc::addr(items)),
+            Value::Function(function) => visit(Gc::addr(function)),
+            _ => {}
+        }
+    }
+}
+
+impl gc::Trace for Vec<Value> {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        for item in self {
+            item.trace(visit);
+        }
+    }
+}
+
+/// Variable bindings, kept in definition order so that anything listing them
+/// (and the maps and instance fields that will share this storage) is
+/// deterministic across runs.
+#[derive(Default)]
+pub struct Env {
+    vars: IndexMap<Str, Value>,
+    /// The names of the variables and the string literals of the program,
+    /// so that each is allocated only once.
+    strings: Interner,
+    /// The total [`Value::heap_size`] of the variables, if it is being kept
+    /// track of for a memory limit.
+    bytes: Option<usize>,
+    /// The variables declared with `स्थिर`, which can't be assigned to.
+    constants: HashSet<Str>,
+    /// The scopes of the functions being called, innermost last. Only the
+    /// innermost one is visible, with the scopes it encloses in, along with
+    /// the global variables.
+    locals: Vec<Environment>,
+}
+
+/// A [`Scope`], shared by the call it belongs to and the functions made in
+/// it, so that they all see each other's assignments. A function stored in
+/// the scope it captured makes a cycle, which [`gc::collect`] frees.
+pub(crate) type Environment = Gc<RefCell<Scope>>;
+
+/// The variables of one call of a function, its parameters and those it
+/// declares, and the scope of the call the function was made in, whose
+/// variables it can see too.
+#[derive(Default)]
+pub(crate) struct Scope {
+    vars: IndexMap<Str, Value>,
+    constants: HashSet<Str>,
+    enclosing: Option<Environment>,
+    /// The total [`Value::heap_size`] of the variables, counted in
+    /// [`Env::bytes`] while the call is being made, if that is kept track of.
+    bytes: Option<usize>,
+}
+
+impl gc::Trace for RefCell<Scope> {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        let scope = self.borrow();
+        for value in scope.vars.values() {
+            value.trace(visit);
+        }
+        if let Some(enclosing) = &scope.enclosing {
+            visit(Gc::addr(enclosing));
+        }
+    }
+
+    fn clear(&self) {
+        let mut scope = self.borrow_mut();
+        let vars = std::mem::take(&mut scope.vars);
+        let enclosing = scope.enclosing.take();
+        // dropping them can drop other scopes of the cycle, which borrow
+        // their own cells
+        drop(scope);
+        drop((vars, enclosing));
+    }
+}
+
+impl Env {
+    fn define(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.locals.last().cloned() {
+            let mut scope = scope.borrow_mut();
+            scope.constants.remove(name);
+            let added = scope.bytes.map(|_| value.heap_size());
+            let old = scope.vars.insert(self.strings.intern(name), value);
+            self.account_local(&mut scope, old.as_ref(), added);
+            return;
+        }
+        if !self.constants.is_empty() {
+            self.constants.remove(name);
+        }
+        self.store(name, value);
+    }
+
+    fn define_constant(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.locals.last().cloned() {
+            let mut scope = scope.borrow_mut();
+            let name = self.strings.intern(name);
+            let added = scope.bytes.map(|_| value.heap_size());
+            let old = scope.vars.insert(name.clone(), value);
+            self.account_local(&mut scope, old.as_ref(), added);
+            scope.constants.insert(name);
+            return;
+        }
+        self.store(name, value);
+        self.constants.insert(self.strings.intern(name));
+    }
+
+    fn store(&mut self, name: &str, value: Value) {
+        let added = self.bytes.map(|_| value.heap_size());
+        let old = match self.vars.get_mut(name) {
+            Some(slot) => Some(std::mem::replace(slot, value)),
+            None => {
+                let name = self.strings.intern(name);
+                self.vars.insert(name, value)
+            }
+        };
+        self.account(old.as_ref(), added);
+    }
+
+    fn assign(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.resolve(name) {
+            let mut scope = scope.borrow_mut();
+            let added = scope.bytes.map(|_| value.heap_size());
+            if let Some(slot) = scope.vars.get_mut(name) {
+                let old = std::mem::replace(slot, value);
+                self.account_local(&mut scope, Some(&old), added);
+            }
+        } else if let Some(slot) = self.vars.get_mut(name) {
+            let added = self.bytes.map(|_| value.heap_size());
+            let old = std::mem::replace(slot, value);
+            self.account(Some(&old), added);
+        } else {
+            self.store(name, value);
+        }
+    }
+
+    /// Updates the byte count for a variable going from `old` to a value of
+    /// `added` bytes.
+    fn account(&mut self, old: Option<&Value>, added: Option<usize>) {
+        if let (Some(bytes), Some(added)) = (&mut self.bytes, added) {
+            *bytes = *bytes - old.map_or(0, Value::heap_size) + added;
+        }
+    }
+
+    /// Updates the byte counts of `scope`, and of the program, for a local
+    /// variable going from `old` to a value of `added` bytes.
+    fn account_local(&mut self, scope: &mut Scope, old: Option<&Value>, added: Option<usize>) {
+        if let (Some(bytes), Some(added)) = (&mut scope.bytes, added) {
+            let removed = old.map_or(0, Value::heap_size);
+            *bytes = *bytes - removed + added;
+            if let Some(total) = &mut self.bytes {
+                *total = *total - removed + added;
+            }
+        }
+    }
+
+    /// Makes `scope` that of the function being called, counting its
+    /// variables towards the program's if a memory limit is kept track of.
+    fn push_scope(&mut self, mut scope: Scope) {
+        if let Some(total) = &mut self.bytes {
+            let bytes = scope.vars.values().map(Value::heap_size).sum();
+            *total += bytes;
+            scope.bytes = Some(bytes);
+        }
+        self.locals.push(Gc::new(RefCell::new(scope)));
+    }
+
+    /// Leaves the scope of the function that was being called, which stops
+    /// counting towards the program's variables even if a function made in
+    /// it keeps it.
+    fn pop_scope(&mut self) {
+        let Some(scope) = self.locals.pop() else {
+            return;
+        };
+        let bytes = scope.borrow_mut().bytes.take();
+        if let (Some(total), Some(bytes)) = (&mut self.bytes, bytes) {
+            *total -= bytes;
+        }
+    }
+
+    /// Replaces this environment with an empty one, for `eval(code, true)`,
+    /// and returns it. The empty one keeps the interned strings, and counts
+    /// its variables towards the memory limit along with the ones replaced,
+    /// which are still alive.
+    fn fresh(&mut self) -> Env {
+        let fresh = Env {
+            strings: std::mem::take(&mut self.strings),
+            bytes: self.bytes,
+            ..Env::default()
+        };
+        std::mem::replace(self, fresh)
+    }
+
+    /// Goes back to `outer`, the environment that [`Env::fresh`] replaced,
+    /// keeping the strings interned since.
+    fn restore(&mut self, outer: Env) {
+        let strings = std::mem::take(&mut self.strings);
+        *self = outer;
+        self.strings = strings;
+    }
+
+    /// Starts keeping track of the size of the variables.
+    fn track_bytes(&mut self) {
+        self.bytes = Some(self.vars.values().map(Value::heap_size).sum());
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        match self.resolve(name) {
+            Some(scope) => scope.borrow().vars.get(name).cloned(),
+            None => self.vars.get(name).cloned(),
+        }
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.resolve(name).is_some() || self.vars.contains_key(name)
+    }
+
+    /// The scope of the function being called, or of one it was made in,
+    /// that the local variable `name` is in, if it is a local variable.
+    fn resolve(&self, name: &str) -> Option<Environment> {
+        let mut scope = self.locals.last().cloned();
+        while let Some(at) = scope {
+            if at.borrow().vars.contains_key(name) {
+                return Some(at);
+            }
+            scope = at.borrow().enclosing.clone();
+        }
+        None
+    }
+
+    /// Whether the variable `name` refers to was declared with `स्थिर`.
+    fn is_constant(&self, name: &str) -> bool {
+        match self.resolve(name) {
+            Some(scope) => scope.borrow().constants.contains(name),
+            None => self.constants.contains(name),
+        }
+    }
+
+    /// The names of the local variables visible now, innermost first.
+    fn local_names(&self) -> Vec<Str> {
+        let mut names = Vec::new();
+        let mut scope = self.locals.last().cloned();
+        while let Some(at) = scope {
+            names.extend(at.borrow().vars.keys().cloned());
+            scope = at.borrow().enclosing.clone();
+        }
+        names
+    }
+
+    /// What a function made now captures: the scope of the function call
+    /// it is made in, if any, which it shares with that call.
+    fn capture(&self) -> Option<Environment> {
+        self.locals.last().cloned()
+    }
+}
+
+pub struct Interpreter {
+    env: Env,
+    /// Builtins, visible from every environment unless shadowed by a variable.
+    natives: Vec<NativeFn>,
+    /// Functions registered by the host, which shadow builtins of the same
+    /// name the way variables do.
+    hosts: Vec<HostFn>,
+    /// Where `print` writes to.
+    out: Box<dyn Write>,
+    /// Where `पृच्छ` reads lines from.
+    input: Box<dyn BufRead>,
+    deterministic: bool,
+    trace: Trace,
+    /// How many native calls deep execution currently is, for indenting traces.
+    depth: usize,
+    /// How many statements enclose the one being executed.
+    nesting: usize,
+    /// How many statements and expressions are being evaluated, one inside
+    /// the next, which is how deep the Rust stack has grown.
+    frames: usize,
+    /// How large `frames` may get before the program is stopped.
+    max_depth: usize,
+    debugger: Option<Box<dyn Debugger>>,
+    strict: bool,
+    /// Whether assigning to an undeclared variable declares it.
+    implicit_declarations: bool,
+    division_by_zero: DivisionByZero,
+    /// How `print` writes numbers.
+    print_numbers: NumberFormat,
+    /// What `print` writes `nil`, `true`, and `false` as.
+    locale: Locale,
+    /// How `+` and string interpolation write numbers into strings.
+    pub(crate) concat_numbers: NumberFormat,
+    /// The span of the operator about to be applied, from the
+    /// [`TokenTree::Spanned`] around it.
+    operator: Option<Range<usize>>,
+    budget: Budget,
+    /// The program's command-line arguments, for `args()`.
+    pub(crate) args: Vec<String>,
+    /// How many times each builtin and host function has been called.
+    native_calls: IndexMap<String, u64>,
+    /// Where `random()` and `randint()` get their numbers.
+    pub(crate) rng: fastrand::Rng,
+    /// When the interpreter was created, which `clock()` counts from.
+    pub(crate) started: Instant,
+    /// The value a `throw` is unwinding with, until a `catch` takes it.
+    thrown: Option<Value>,
+    /// What a `देयम` is unwinding with, until the function call it returns
+    /// from takes it.
+    returning: Option<Returning>,
+    /// How many `प्रयत्न` bodies the statement being executed is in, within
+    /// the function being called, which can't make tail calls from them.
+    catching: usize,
+    /// The name of each function of the program being called, outermost
+    /// first, and where it was called from, for [`RuntimeError::stack`].
+    calls: Vec<(Option<Str>, Option<Range<usize>>)>,
+    /// What `run --profile` has measured so far, if it is profiling.
+    profile: Option<Profile>,
+}
+
+/// What a `देयम` returns.
+enum Returning {
+    Value(Value),
+    /// A tail call of a function with arguments, at a span, which the call
+    /// being returned from makes in its place.
+    Call(Gc<Function>, Vec<Value>, Option<Range<usize>>),
+}
+
+/// The callee and arguments of a call, evaluated, and the builtin it names
+/// if that is one that is off.
+struct CallOperands<'de> {
+    callee: Value,
+    arguments: Vec<Value>,
+    disabled: Option<(&'de str, &'static str)>,
+}
+
+/// Hooks that let a debugger follow a program, and pause it, as it runs.
+pub trait Debugger {
+    /// Called just before each statement of a program or block runs. The
+    /// program stays paused until this returns.
+    fn before_statement(
+        &mut self,
+        interpreter: &Interpreter,
+        at: Location,
+        statement: &TokenTree<'_>,
+    );
+}
+
+/// Where a statement that is about to run sits in the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset of the statement in the source it was parsed from.
+    pub offset: usize,
+    /// How many statements enclose this one, so that a debugger can step over
+    /// the bodies of loops and blocks.
+    pub nesting: usize,
+    /// How many native calls deep the statement runs. Statements run by
+    /// builtins such as `eval` come from sources other than the program's.
+    pub calls: usize,
+}
+
+/// What `/` and `%` do when the divisor is zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DivisionByZero {
+    /// a runtime error pointing at the operator
+    Error,
+    /// the IEEE 754 result, as in reference Lox: ±infinity, or NaN for `0 / 0`
+    /// and any `%` by zero
+    Infinity,
+    /// `nil`
+    #[default]
+    Nil,
+}
+
+/// What `run --trace` reports on stderr as the program executes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, clap::ValueEnum)]
+pub enum Trace {
+    #[default]
+    Off,
+    /// each statement before it runs
+    Statements,
+    /// also each expression evaluated, with its value
+    Expressions,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What seeds the random numbers of a deterministic interpreter.
+const DETERMINISTIC_SEED: u64 = 108;
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            env: Env::default(),
+            natives: [
+                SERIALIZE,
+                DESERIALIZE,
+                REPEAT,
+                ARGS,
+                TARKA,
+                ASSERT,
+                PUSHTI,
+                TYPE,
+                PRAKARA,
+                KATHAYAF,
+                PRINTF,
+                WRITE,
+                KATHAY,
+                PRCCHA,
+                PRCCHA_SANKHYA,
+                INPUT,
+                INPUT_NUMBER,
+            ]
+            .into_iter()
+            .chain(builtins::CHANCE_AND_TIME)
+            .chain(STRINGS)
+            .chain(LISTS)
+            .chain(builtins::CONVERSIONS)
+            .collect(),
+            hosts: Vec::new(),
+            out: Box::new(io::stdout()),
+            input: Box::new(io::BufReader::new(io::stdin())),
+            deterministic: false,
+            trace: Trace::Off,
+            depth: 0,
+            nesting: 0,
+            frames: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            debugger: None,
+            strict: false,
+            implicit_declarations: false,
+            division_by_zero: DivisionByZero::default(),
+            print_numbers: NumberFormat::default(),
+            locale: locale::current(),
+            concat_numbers: NumberFormat::default(),
+            operator: None,
+            budget: Budget::default(),
+            args: Vec::new(),
+            native_calls: IndexMap::new(),
+            rng: fastrand::Rng::new(),
+            started: Instant::now(),
+            thrown: None,
+            returning: None,
+            catching: 0,
+            calls: Vec::new(),
+            profile: None,
+        }
+    }
+
+    /// Sends the program's `print` output to `out` instead of stdout.
+    pub fn output(mut self, out: impl Write + 'static) -> Self {
+        self.out = Box::new(out);
+        self
+    }
+
+    /// Makes `पृच्छ` read the program's input from `input` instead of stdin.
+    pub fn input(mut self, input: impl BufRead + 'static) -> Self {
+        self.input = Box::new(input);
+        self
+    }
+
+    /// In deterministic mode every source of nondeterminism the interpreter
+    /// exposes to programs (random numbers, the clock, iteration order) is
+    /// pinned, so a program produces the same output on every machine.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        if deterministic {
+            self.rng = fastrand::Rng::with_seed(DETERMINISTIC_SEED);
+        }
+        self
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// In strict mode, operators written in the program fail with a runtime
+    /// error when given operands of the wrong types, as in reference Lox:
+    /// `-सत्य`, `"क" * 2`, `"क" + 1`, and `1 < "क"` are errors rather than
+    /// `nil` (or, for strings, repetition and concatenation).
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Lets assignment to a variable that no `चर` declared declare it, as in
+    /// `क = 1;` on its own, rather than fail.
+    pub fn implicit_declarations(mut self, implicit: bool) -> Self {
+        self.implicit_declarations = implicit;
+        self
+    }
+
+    /// Sets what dividing by zero, with `/` or `%`, results in.
+    pub fn division_by_zero(mut self, policy: DivisionByZero) -> Self {
+        self.division_by_zero = policy;
+        self
+    }
+
+    /// Sets how `कथय` prints numbers.
+    pub fn print_numbers(mut self, format: NumberFormat) -> Self {
+        self.print_numbers = format;
+        self
+    }
+
+    /// Sets the locale `कथय` writes `nil`, `true`, and `false` in, which is
+    /// the one set with `--lang` unless this is called. Strings built with `+`
+    /// and interpolation always use the Lox words, so that what a program
+    /// computes doesn't depend on how its output is shown.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Sets how `+` and string interpolation write numbers into strings, as in
+    /// `"क = " + 2`. This is separate from [`Interpreter::print_numbers`] so
+    /// that a program's printed output and the strings it builds can be moved
+    /// to the Lox format one at a time.
+    pub fn concat_numbers(mut self, format: NumberFormat) -> Self {
+        self.concat_numbers = format;
+        self
+    }
+
+    /// Exposes `पठ` (`read_file`) and `लिख` (`write_file`), which read and
+    /// write text files. They are off by default so that a program can't
+    /// touch the file system unless whoever runs it allows that.
+    pub fn allow_fs(mut self, allow: bool) -> Self {
+        self.natives
+            .retain(|native| !builtins::FS.iter().any(|fs| fs.name == native.name));
+        if allow {
+            self.natives.extend(builtins::FS);
+        }
+        self
+    }
+
+    /// Sets the arguments that `args()` (or `तर्क()`) returns to the program.
+    pub fn args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Stops the program with a runtime error once it exceeds one of `limits`.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        if limits.max_memory.is_some() {
+            self.env.track_bytes();
+        }
+        self.budget.limits = limits;
+        self
+    }
+
+    /// Stops the program with a "stack overflow" runtime error once its
+    /// statements and expressions nest more than `max_depth` deep, such as
+    /// `((…))` written a thousand times, before it could overflow the stack of
+    /// the thread it runs on. Defaults to [`DEFAULT_MAX_DEPTH`], which fits in
+    /// the 8 MiB of a main thread in an optimized build; a thread with a
+    /// smaller stack needs a lower limit, and a larger one can take a higher
+    /// one.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Stops the program with a runtime error once it has executed more than
+    /// `max_steps` statements or run for longer than `max_millis`
+    /// milliseconds, whichever comes first, so that a program like
+    /// `यावद (सत्य) {}` can't run forever. Any memory limit is kept.
+    pub fn with_limits(self, max_steps: u64, max_millis: u64) -> Self {
+        let limits = Limits {
+            max_steps: Some(max_steps),
+            time: Some(Duration::from_millis(max_millis)),
+            ..self.budget.limits
+        };
+        self.limits(limits)
+    }
+
+    /// Applies each of `limits` that isn't set yet, for limits that ones
+    /// already given take precedence over, such as a manifest's.
+    pub(crate) fn fallback_limits(self, limits: Limits) -> Self {
+        let limits = self.budget.limits.or(limits);
+        self.limits(limits)
+    }
+
+    /// Reports statements, and optionally expressions, on stderr as they run.
+    pub fn trace(mut self, trace: Trace) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Measures how often each line and function runs, and for how long,
+    /// for [`Interpreter::take_profile`].
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile.then(Profile::new);
+        self
+    }
+
+    /// Lets `debugger` watch, and pause, every statement as it runs.
+    pub fn debugger(mut self, debugger: impl Debugger + 'static) -> Self {
+        self.debugger = Some(Box::new(debugger));
+        self
+    }
+
+    /// The variables defined so far, in definition order.
+    pub fn globals(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.env.vars.iter().map(|(name, value)| (&**name, value))
+    }
+
+    /// The value of the variable `name`, or `None` if the program (or the
+    /// host, through [`Interpreter::set`]) hasn't defined it. Builtins aren't
+    /// variables, so `get("repeat")` is `None` unless a variable shadows it.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.env.get(name)
+    }
+
+    /// Sets the variable `name` to `value`, defining it if it isn't yet, so
+    /// that a host can hand a program its inputs before running it or change
+    /// them between runs.
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.env.assign(name, value);
+    }
+
+    /// How many statements have run so far, counting every run of a loop
+    /// body, as limited by [`Limits::max_steps`].
+    pub fn statements_executed(&self) -> u64 {
+        self.budget.steps()
+    }
+
+    /// What has been measured since the interpreter was made with
+    /// [`Interpreter::profile`], which stops it being measured.
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        let mut profile = self.profile.take()?;
+        profile.finish();
+        Some(profile)
+    }
+
+    /// Whether the program was stopped for exceeding one of its [`Limits`],
+    /// rather than failing by itself, so that a grader can tell a program
+    /// that ran out of time from one that is wrong.
+    pub fn limit_exceeded(&self) -> bool {
+        self.budget.exceeded()
+    }
+
+    /// How many times each builtin and host function has been called, in the
+    /// order of their first calls.
+    pub fn native_calls(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.native_calls
+            .iter()
+            .map(|(name, calls)| (name.as_str(), *calls))
+    }
+
+    fn count_call(&mut self, name: &str) {
+        match self.native_calls.get_mut(name) {
+            Some(calls) => *calls += 1,
+            None => {
+                self.native_calls.insert(name.to_string(), 1);
             }
-            Value::Bool(b) => b.to_string(),
-            Value::String(s) => s.clone(),
         }
     }
-}
 
-#[derive(Default)]
-pub struct Env {
-    vars: HashMap<String, Value>,
-}
+    /// `value` written as `print` writes it.
+    pub(crate) fn printed<'v>(&self, value: &'v Value) -> Formatted<'v> {
+        value.format(self.print_numbers).locale(self.locale)
+    }
 
-impl Env {
-    fn define(&mut self, name: &str, value: Value) {
-        self.vars.insert(name.to_string(), value);
+    /// Writes `text` and a newline to the program's output, as `print` does.
+    pub(crate) fn print_line(&mut self, text: impl fmt::Display) -> Result<(), RuntimeError> {
+        writeln!(self.out, "{text}").map_err(|e| runtime_error!("E1012", error = e))
     }
 
-    fn assign(&mut self, name: &str, value: Value) {
-        if let Some(slot) = self.vars.get_mut(name) {
-            *slot = value;
-        } else {
-            self.define(name, value);
+    /// Writes `text` to the program's output without a newline, and flushes
+    /// it, so that a prompt shows before the program waits for input.
+    pub(crate) fn print_partial(&mut self, text: impl fmt::Display) -> Result<(), RuntimeError> {
+        write!(self.out, "{text}")
+            .and_then(|()| self.out.flush())
+            .map_err(|e| runtime_error!("E1012", error = e))
+    }
+
+    /// The next line of the program's input, without its line ending, or
+    /// `None` at the end of the input.
+    pub(crate) fn read_line(&mut self) -> Result<Option<String>, RuntimeError> {
+        let mut line = String::new();
+        let read = self
+            .input
+            .read_line(&mut line)
+            .map_err(|e| runtime_error!("E1025", error = e))?;
+        if read == 0 {
+            return Ok(None);
         }
+        let end = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(end);
+        Ok(Some(line))
     }
 
-    fn get(&self, name: &str) -> Value {
-        self.vars
-            .get(name)
-            .cloned()
-            .unwrap_or(Value::Nil)
+    fn trace_line(&self, line: fmt::Arguments<'_>) {
+        eprintln!(
+            "{}: {:indent$}{line}",
+            paint(Stream::Stderr, Style::Note, "trace"),
+            "",
+            indent = self.depth * 2
+        );
     }
-}
 
-pub struct Interpreter {
-    env: Env,
-}
+    /// Makes the Rust closure `fun` callable from programs as `name`, with any
+    /// number of arguments. `fun` returns either a [`Value`] or a
+    /// `Result<Value, RuntimeError>`, whose error the program sees as a runtime
+    /// error. A function registered under the name of a builtin replaces it.
+    ///
+    /// # Panics
+    ///
+    /// If `name` is not an identifier, or is a keyword in Lox or in a
+    /// language pack, since programs could never call the function.
+    pub fn register_fn<R: IntoCallResult>(
+        self,
+        name: &str,
+        fun: impl Fn(&[Value]) -> R + 'static,
+    ) -> Self {
+        self.register_fn_with_arity(name, 0..=usize::MAX, fun)
+    }
 
-impl Interpreter {
-    pub fn new() -> Self {
-        Self { env: Env::default() }
+    /// Like [`Interpreter::register_fn`], but calls with a number of arguments
+    /// outside `arity` fail with a runtime error before `fun` is called, so
+    /// `fun` can index into its arguments without checking their count.
+    pub fn register_fn_with_arity<R: IntoCallResult>(
+        mut self,
+        name: &str,
+        arity: RangeInclusive<usize>,
+        fun: impl Fn(&[Value]) -> R + 'static,
+    ) -> Self {
+        assert!(
+            is_callable_name(name),
+            "can't register a function as '{name}', which programs can't call"
+        );
+        self.hosts.retain(|host| &*host.name != name);
+        self.hosts.push(HostFn {
+            name: name.into(),
+            arity,
+            fun: Rc::new(move |args| fun(args).into_call_result()),
+        });
+        self
     }
 
-    pub fn eval_program<'de>(&mut self, stmts: &[TokenTree<'de>]) {
+    /// Exposes the `eval` builtin, which lets a program run code built at
+    /// runtime. It is off by default since such code can do anything the
+    /// program itself can.
+    pub fn allow_eval(mut self, allow: bool) -> Self {
+        self.natives.retain(|native| native.name != EVAL.name);
+        if allow {
+            self.natives.push(EVAL);
+        }
+        self
+    }
+
+    /// Runs `stmts` and returns the value of the last one if it is an
+    /// expression statement, or `nil` otherwise, so that a whole program can be
+    /// used as an expression: `चर क = 4; क * क` evaluates to `16`. Blocks and
+    /// `यदि` count as expressions, with the value of their own last statement
+    /// or the branch taken.
+    pub fn eval_program<'de>(&mut self, stmts: &[TokenTree<'de>]) -> Result<Value, RuntimeError> {
+        // left over from a throw that no catch took
+        self.thrown = None;
+        self.returning = None;
+        let mut last = Value::Nil;
         for stmt in stmts {
-            self.exec(stmt);
+            last = self.exec(stmt)?;
+        }
+        Ok(last)
+    }
+
+    /// Translates, parses, and runs the Sanskrit (or Lox) snippet `source`,
+    /// returning its value as [`Interpreter::eval_program`] does. Variables it
+    /// defines stay defined for later calls, so a REPL or notebook can feed an
+    /// interpreter one snippet at a time:
+    ///
+    /// ```text
+    /// interpreter.eval_str("चर क = 2;")?;
+    /// interpreter.eval_str("क * 3")?; // 6
+    /// ```
+    ///
+    /// Errors point into the snippet as written. A snippet with a syntax
+    /// error runs none of its statements.
+    pub fn eval_str(&mut self, source: &str) -> miette::Result<Value> {
+        let (translated, map) = translate_file_contents_with_map(source)?;
+        let original = Original { source, map: &map };
+        let arena = Arena::new();
+        let program = Parser::new(&translated, &arena)
+            .parse_program()
+            .map_err(|e| original.report(e.as_ref()))?;
+        self.eval_program(&program).map_err(|e| original.report(&e))
+    }
+
+    /// The value of the variable, host function, or builtin called `name`,
+    /// or an error at `offset`, where the name is written, suggesting a
+    /// defined name close to it.
+    fn lookup(&self, name: &str, offset: usize) -> Result<Value, RuntimeError> {
+        self.find(name).ok_or_else(|| {
+            let error = runtime_error!("E1003", name = name).at(offset..offset + name.len());
+            match self.similar_name(name) {
+                Some(similar) => error.with_help(crate::message!("did-you-mean", name = similar)),
+                None => error,
+            }
+        })
+    }
+
+    /// Declares the variable `target` names with `value`, or, when `target`
+    /// is a list of names as in `चर [क, ख] = युग्मम्;`, each of them with the
+    /// item of `value` at the same position.
+    fn declare(
+        &mut self,
+        target: &TokenTree<'_>,
+        value: Value,
+        constant: bool,
+    ) -> Result<(), RuntimeError> {
+        match target {
+            TokenTree::Atom(Atom::Ident(name, _)) if constant => {
+                self.env.define_constant(name, value)
+            }
+            TokenTree::Atom(Atom::Ident(name, _)) => self.env.define(name, value),
+            TokenTree::Cons(Op::List, names) => {
+                let Value::List(items) = &value else {
+                    return Err(runtime_error!(
+                        "E1023",
+                        kind = locale::current().type_name(value.type_name())
+                    ));
+                };
+                if items.len() != names.len() {
+                    return Err(
+                        runtime_error!("E1024", expected = names.len(), got = items.len())
+                            .with_help(crate::message!("E1024-help")),
+                    );
+                }
+                for (name, item) in names.iter().zip(items.iter()) {
+                    self.declare(name, item.clone(), constant)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Fails unless `name` can be assigned to: it is a variable declared with
+    /// `चर`, or undeclared variables are declared by assigning to them.
+    fn assignable(&self, name: &str) -> Result<(), RuntimeError> {
+        if self.env.is_constant(name) {
+            return Err(
+                runtime_error!("E1022", name = name).with_help(crate::message!("E1022-help"))
+            );
         }
+        if self.implicit_declarations || self.env.is_defined(name) {
+            return Ok(());
+        }
+        let error = runtime_error!("E1021", name = name);
+        Err(match self.similar_name(name) {
+            Some(similar) => error.with_help(crate::message!("did-you-mean", name = similar)),
+            None => error.with_help(crate::message!("E1021-help")),
+        })
+    }
+
+    fn find(&self, name: &str) -> Option<Value> {
+        self.env
+            .get(name)
+            .or_else(|| {
+                self.hosts
+                    .iter()
+                    .find(|host| &*host.name == name)
+                    .cloned()
+                    .map(Value::Host)
+            })
+            .or_else(|| {
+                self.natives
+                    .iter()
+                    .find(|native| native.name == name)
+                    .cloned()
+                    .map(Value::Native)
+            })
+    }
+
+    /// The defined name closest to `name`, if one is close enough to be a
+    /// misspelling of it: at most one edit away for every two characters,
+    /// since a matra or virama counts as a character of its own.
+    fn similar_name(&self, name: &str) -> Option<String> {
+        let most = name.chars().count().div_ceil(2);
+        let hosts = self.hosts.iter().map(|host| &*host.name);
+        let natives = self.natives.iter().map(|native| native.name);
+        let locals = self.env.local_names();
+        self.env
+            .vars
+            .keys()
+            .chain(&locals)
+            .map(|name| &**name)
+            .chain(hosts)
+            .chain(natives)
+            .map(|defined| (edit_distance(name, defined), defined))
+            .filter(|&(distance, _)| distance <= most)
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, defined)| defined.to_string())
     }
 
-    fn exec<'de>(&mut self, node: &TokenTree<'de>) {
+    /// Executes a statement, returning its value if it is an expression
+    /// statement (including blocks and `if`) and `nil` otherwise.
+    fn exec<'de>(&mut self, node: &TokenTree<'de>) -> Result<Value, RuntimeError> {
+        self.budget.step(self.env.bytes.unwrap_or(0))?;
+        gc::collect_if_due();
+        if self.trace >= Trace::Statements {
+            // compound statements are shown by their head only, since their
+            // bodies are traced statement by statement as they run
+            match node {
+                TokenTree::Block { .. } | TokenTree::Statement { .. } => {}
+                TokenTree::If { condition, .. } => {
+                    self.trace_line(format_args!("(if {condition})"))
+                }
+                TokenTree::Cons(Op::While, children) => {
+                    self.trace_line(format_args!("(while {})", children[0]))
+                }
+                TokenTree::Cons(Op::For, children) => self.trace_line(format_args!(
+                    "(for {}; {}; {})",
+                    children[0], children[1], children[2]
+                )),
+                TokenTree::ForIn {
+                    variable, iterable, ..
+                } => self.trace_line(format_args!("(for {variable} in {iterable})")),
+                TokenTree::Match { subject, .. } => {
+                    self.trace_line(format_args!("(match {subject})"))
+                }
+                TokenTree::Try { .. } => self.trace_line(format_args!("(try)")),
+                _ => self.trace_line(format_args!("{node}")),
+            }
+        }
+
         match node {
-            TokenTree::Cons(Op::Group, children) => {
-                for stmt in children {
-                    self.exec(stmt);
+            TokenTree::Statement { offset, node } => {
+                if let Some(mut debugger) = self.debugger.take() {
+                    let at = Location {
+                        offset: *offset,
+                        nesting: self.nesting,
+                        calls: self.depth,
+                    };
+                    debugger.before_statement(self, at, node);
+                    self.debugger = Some(debugger);
+                }
+                self.enter()?;
+                if let Some(profile) = &mut self.profile {
+                    profile.enter_statement(*offset);
+                }
+                self.nesting += 1;
+                let result = self.exec(node);
+                self.nesting -= 1;
+                self.frames -= 1;
+                if let Some(profile) = &mut self.profile {
+                    profile.leave_statement();
+                }
+                // errors that don't say where they happened point at the
+                // statement they stopped
+                return match result {
+                    Err(e) if e.span.is_none() => Err(e.at(*offset..*offset)),
+                    result => result,
+                };
+            }
+            TokenTree::Block { statements, value } => {
+                for stmt in statements.iter() {
+                    self.exec(stmt)?;
+                }
+                if let Some(value) = value {
+                    return self.eval_expr(value);
                 }
             }
             TokenTree::If { condition, yes, no } => {
-                if self.eval_expr(condition).is_truthy() {
-                    self.exec(yes);
+                if self.eval_expr(condition)?.is_truthy() {
+                    return self.exec(yes);
                 } else if let Some(no_branch) = no {
-                    self.exec(no_branch);
+                    return self.exec(no_branch);
                 }
             }
-            TokenTree::Cons(Op::Var, children) => {
-                if let [TokenTree::Atom(Atom::Ident(name)), expr] = &children[..] {
-                    let value = self.eval_expr(expr);
-                    self.env.define(name, value);
+            TokenTree::Cons(op @ (Op::Var | Op::Const), children) => {
+                for declaration in children.chunks(2) {
+                    if let [target, expr] = declaration {
+                        let value = self.eval_expr(expr)?;
+                        self.declare(target, value, *op == Op::Const)?;
+                    }
+                }
+            }
+            TokenTree::Fun {
+                name: Some(Atom::Ident(name, _)),
+                ..
+            } => {
+                let function = self.function(node);
+                self.env.define(name, function);
+            }
+            TokenTree::Cons(Op::Return, children) => {
+                if self.env.locals.is_empty() {
+                    return Err(runtime_error!("E1026").with_help(crate::message!("E1026-help")));
                 }
+                let tail = self.catching == 0;
+                let returning = match &children[..] {
+                    [expr] => match tail_call(expr) {
+                        Some((span, TokenTree::Call { callee, arguments })) if tail => {
+                            let operands = self.call_operands(callee, arguments)?;
+                            match operands.callee {
+                                Value::Function(function) => {
+                                    Returning::Call(function, operands.arguments, span)
+                                }
+                                _ => Returning::Value(self.finish_call(span, operands)?),
+                            }
+                        }
+                        _ => Returning::Value(self.eval_expr(expr)?),
+                    },
+                    _ => Returning::Value(Value::Nil),
+                };
+                self.returning = Some(returning);
+                // taken by the call being returned from, which ignores the error
+                return Err(RuntimeError::new("returning"));
             }
             TokenTree::Cons(Op::Print, children) => {
                 if let [expr] = &children[..] {
-                    let value = self.eval_expr(expr);
-                    println!("{}", value.to_display());
+                    let value = self.eval_expr(expr)?;
+                    let text = self.printed(&value).to_string();
+                    self.print_line(text)?;
+                }
+            }
+            TokenTree::Assignments { targets, values } => {
+                let values = values
+                    .iter()
+                    .map(|value| self.eval_expr(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for target in targets.iter() {
+                    if let TokenTree::Atom(Atom::Ident(name, _)) = target {
+                        self.assignable(name)?;
+                    }
+                }
+                for (target, value) in targets.iter().zip(values) {
+                    if let TokenTree::Atom(Atom::Ident(name, _)) = target {
+                        self.env.assign(name, value);
+                    }
                 }
             }
             TokenTree::Cons(Op::While, children) => {
                 if let [cond, body] = &children[..] {
-                    while self.eval_expr(cond).is_truthy() {
-                        self.exec(body);
+                    while self.eval_expr(cond)?.is_truthy() {
+                        self.exec(body)?;
+                    }
+                }
+            }
+            TokenTree::Cons(Op::For, children) => {
+                if let [init, cond, increment, body] = &children[..] {
+                    self.exec(init)?;
+                    while self.eval_expr(cond)?.is_truthy() {
+                        self.exec(body)?;
+                        self.eval_expr(increment)?;
                     }
                 }
             }
-            other => {
-                let _ = self.eval_expr(other);
+            TokenTree::Match {
+                subject,
+                arms,
+                default,
+            } => {
+                let value = self.eval_expr(subject)?;
+                let arm = arms
+                    .iter()
+                    .find(|(literals, _)| {
+                        literals.iter().any(|literal| is_literal(&value, literal))
+                    })
+                    .map(|(_, body)| body)
+                    .or(default.as_deref());
+                if let Some(body) = arm {
+                    return self.exec(body);
+                }
+            }
+            TokenTree::ForIn {
+                variable,
+                iterable,
+                body,
+            } => {
+                if let Atom::Ident(name, _) = variable {
+                    for item in self.eval_expr(iterable)?.iterate()? {
+                        self.env.define(name, item);
+                        self.exec(body)?;
+                    }
+                }
             }
+            TokenTree::Try {
+                body,
+                variable,
+                handler,
+            } => match self.catch(body) {
+                Ok(_) => {}
+                // a program that runs out of its limits stops, whatever it
+                // catches, and a `देयम` returns through a `प्रयत्न`
+                Err(e) if self.budget.exceeded() || self.returning.is_some() => return Err(e),
+                Err(e) => {
+                    // a runtime error is caught as its message
+                    let caught = self
+                        .thrown
+                        .take()
+                        .unwrap_or_else(|| Value::String(e.message.into()));
+                    if let Atom::Ident(name, _) = variable {
+                        self.env.define(name, caught);
+                    }
+                    self.exec(handler)?;
+                }
+            },
+            other => return self.eval_expr(other),
         }
+        Ok(Value::Nil)
     }
 
-    fn eval_expr<'de>(&mut self, node: &TokenTree<'de>) -> Value {
-        match node {
+    /// Executes the body of a `प्रयत्न`.
+    fn catch(&mut self, body: &TokenTree<'_>) -> Result<Value, RuntimeError> {
+        self.catching += 1;
+        let result = self.exec(body);
+        self.catching -= 1;
+        result
+    }
+
+    fn eval_expr<'de>(&mut self, node: &TokenTree<'de>) -> Result<Value, RuntimeError> {
+        self.enter()?;
+        let value = self.eval_node(node);
+        self.frames -= 1;
+        let value = value?;
+        if self.trace >= Trace::Expressions && !matches!(node, TokenTree::Atom(_)) {
+            self.trace_line(format_args!("{node} => {}", value.repr()));
+        }
+        Ok(value)
+    }
+
+    /// Counts one more level of nesting, failing if there are too many.
+    fn enter(&mut self) -> Result<(), RuntimeError> {
+        if self.frames >= self.max_depth {
+            return Err(runtime_error!("E1008", depth = self.max_depth));
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn eval_node<'de>(&mut self, node: &TokenTree<'de>) -> Result<Value, RuntimeError> {
+        Ok(match node {
             TokenTree::Atom(atom) => match atom {
                 Atom::Number(n) => Value::Number(*n),
                 Atom::Bool(b) => Value::Bool(*b),
                 Atom::Nil => Value::Nil,
-                Atom::String(s) => Value::String(s.to_string()),
-                Atom::Ident(name) => self.env.get(name),
+                // code that eval() runs is built while the program runs, so
+                // its literals aren't kept
+                Atom::String(s) if self.depth == 0 => Value::String(self.env.strings.intern(s)),
+                Atom::String(s) => Value::String((*s).into()),
+                Atom::Ident(name, offset) => self.lookup(name, *offset)?,
                 Atom::Super | Atom::This => Value::Nil,
             },
             TokenTree::Cons(op, children) => match (op, &children[..]) {
+                (Op::List, items) => {
+                    let items = items
+                        .iter()
+                        .map(|item| self.eval_expr(item))
+                        .collect::<Result<_, _>>()?;
+                    self.allocated(None, Value::List(Gc::new(items)))?
+                }
                 (Op::Group, children) => {
                     if let Some(first) = children.first() {
-                        self.eval_expr(first)
+                        self.eval_expr(first)?
                     } else {
                         Value::Nil
                     }
                 }
                 (Op::Minus, [expr]) => {
-                    if let Value::Number(n) = self.eval_expr(expr) {
-                        Value::Number(-n)
-                    } else {
-                        Value::Nil
+                    let span = self.operator.take();
+                    match self.eval_expr(expr)? {
+                        Value::Number(n) => Value::Number(-n),
+                        v => self.mismatched(span, "E1011/negate", Op::Minus, &[&v])?,
                     }
                 }
                 (Op::Bang, [expr]) => {
-                    let v = self.eval_expr(expr);
+                    let v = self.eval_expr(expr)?;
                     Value::Bool(!v.is_truthy())
                 }
-                (Op::Assign, [TokenTree::Atom(Atom::Ident(name)), expr]) => {
-                    let value = self.eval_expr(expr);
+                (Op::Throw, [expr]) => {
+                    let span = self.operator.take();
+                    let value = self.eval_expr(expr)?;
+                    let error = runtime_error!(
+                        "E1007",
+                        value = value.format(self.print_numbers).locale(self.locale)
+                    );
+                    self.thrown = Some(value);
+                    return Err(match span {
+                        Some(span) => error.at(span),
+                        None => error,
+                    });
+                }
+                (op @ (Op::Range | Op::RangeInclusive), [start, end]) => {
+                    let span = self.operator.take();
+                    let start = self.eval_expr(start)?;
+                    let end = self.eval_expr(end)?;
+                    match (start, end) {
+                        (Value::Number(start), Value::Number(end))
+                            if is_whole(start) && is_whole(end) =>
+                        {
+                            Value::Range(NumberRange {
+                                start,
+                                end,
+                                inclusive: *op == Op::RangeInclusive,
+                            })
+                        }
+                        (start, end) => {
+                            let error =
+                                runtime_error!("E1009", start = start.repr(), end = end.repr());
+                            return Err(match span {
+                                Some(span) => error.at(span),
+                                None => error,
+                            });
+                        }
+                    }
+                }
+                (Op::Assign, [TokenTree::Atom(Atom::Ident(name, _)), expr]) => {
+                    let value = match self.appended(name, expr) {
+                        Some((span, offset, rhs)) => self.append(name, offset, span, rhs)?,
+                        None => self.eval_expr(expr)?,
+                    };
+                    self.assignable(name)?;
                     self.env.assign(name, value.clone());
                     value
                 }
-                (Op::Plus, [lhs, rhs]) => match (self.eval_expr(lhs), self.eval_expr(rhs)) {
-                    (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
-                    (Value::String(a), Value::String(b)) => Value::String(format!("{a}{b}")),
-                    (Value::String(a), b) => Value::String(format!("{a}{}", b.to_display())),
-                    (a, Value::String(b)) => Value::String(format!("{}{}", a.to_display(), b)),
-                    _ => Value::Nil,
-                },
-                (Op::Minus, [lhs, rhs]) => match (self.eval_expr(lhs), self.eval_expr(rhs)) {
-                    (Value::Number(a), Value::Number(b)) => Value::Number(a - b),
-                    _ => Value::Nil,
-                },
-                (Op::Star, [lhs, rhs]) => match (self.eval_expr(lhs), self.eval_expr(rhs)) {
-                    (Value::Number(a), Value::Number(b)) => Value::Number(a * b),
-                    _ => Value::Nil,
-                },
-                (Op::Slash, [lhs, rhs]) => match (self.eval_expr(lhs), self.eval_expr(rhs)) {
-                    (Value::Number(_), Value::Number(0.0)) => Value::Nil,
-                    (Value::Number(a), Value::Number(b)) => Value::Number(a / b),
-                    _ => Value::Nil,
-                },
-                (Op::Less, [lhs, rhs]) => Value::Bool(
-                    matches!(
-                        (self.eval_expr(lhs), self.eval_expr(rhs)),
-                        (Value::Number(a), Value::Number(b)) if a < b
-                    ),
-                ),
-                (Op::LessEqual, [lhs, rhs]) => Value::Bool(
-                    matches!(
-                        (self.eval_expr(lhs), self.eval_expr(rhs)),
-                        (Value::Number(a), Value::Number(b)) if a <= b
-                    ),
-                ),
-                (Op::Greater, [lhs, rhs]) => Value::Bool(
-                    matches!(
-                        (self.eval_expr(lhs), self.eval_expr(rhs)),
-                        (Value::Number(a), Value::Number(b)) if a > b
-                    ),
-                ),
-                (Op::GreaterEqual, [lhs, rhs]) => Value::Bool(
-                    matches!(
-                        (self.eval_expr(lhs), self.eval_expr(rhs)),
-                        (Value::Number(a), Value::Number(b)) if a >= b
-                    ),
-                ),
+                (Op::Plus, [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    let a = self.eval_expr(lhs)?;
+                    let b = self.eval_expr(rhs)?;
+                    self.add(span, a, b)?
+                }
+                (Op::Minus, [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    match (self.eval_expr(lhs)?, self.eval_expr(rhs)?) {
+                        (Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+                        (a, b) => {
+                            self.mismatched(span, "E1011/arithmetic", Op::Minus, &[&a, &b])?
+                        }
+                    }
+                }
+                (Op::Star, [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    let lenient = !self.strict || span.is_none();
+                    match (self.eval_expr(lhs)?, self.eval_expr(rhs)?) {
+                        (Value::Number(a), Value::Number(b)) => Value::Number(a * b),
+                        (Value::String(s), Value::Number(n))
+                        | (Value::Number(n), Value::String(s))
+                            if lenient =>
+                        {
+                            let repeated = builtins::repeat(&s, n)?;
+                            let repeated = repeated.map_or(Value::Nil, |s| Value::String(s.into()));
+                            self.allocated(span, repeated)?
+                        }
+                        (a, b) => self.mismatched(span, "E1011/arithmetic", Op::Star, &[&a, &b])?,
+                    }
+                }
+                (Op::Slash, [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    match (self.eval_expr(lhs)?, self.eval_expr(rhs)?) {
+                        (Value::Number(_), Value::Number(0.0))
+                            if self.division_by_zero != DivisionByZero::Infinity =>
+                        {
+                            self.divided_by_zero(span)?
+                        }
+                        (Value::Number(a), Value::Number(b)) => Value::Number(a / b),
+                        (a, b) => {
+                            self.mismatched(span, "E1011/arithmetic", Op::Slash, &[&a, &b])?
+                        }
+                    }
+                }
+                (Op::Percent, [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    match (self.eval_expr(lhs)?, self.eval_expr(rhs)?) {
+                        (Value::Number(_), Value::Number(0.0))
+                            if self.division_by_zero != DivisionByZero::Infinity =>
+                        {
+                            self.divided_by_zero(span)?
+                        }
+                        (Value::Number(a), Value::Number(b)) => Value::Number(a % b),
+                        (a, b) => {
+                            self.mismatched(span, "E1011/arithmetic", Op::Percent, &[&a, &b])?
+                        }
+                    }
+                }
+                (Op::StarStar, [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    match (self.eval_expr(lhs)?, self.eval_expr(rhs)?) {
+                        (Value::Number(a), Value::Number(b)) => Value::Number(a.powf(b)),
+                        (a, b) => {
+                            self.mismatched(span, "E1011/arithmetic", Op::StarStar, &[&a, &b])?
+                        }
+                    }
+                }
+                (op @ (Op::Less | Op::LessEqual | Op::Greater | Op::GreaterEqual), [lhs, rhs]) => {
+                    let span = self.operator.take();
+                    let a = self.eval_expr(lhs)?;
+                    let b = self.eval_expr(rhs)?;
+                    self.check_comparable(span, *op, &a, &b)?;
+                    Value::Bool(compare(*op, &a, &b))
+                }
                 (Op::EqualEqual, [lhs, rhs]) => {
-                    let a = self.eval_expr(lhs);
-                    let b = self.eval_expr(rhs);
-                    let equal = match (&a, &b) {
-                        (Value::Nil, Value::Nil) => true,
-                        (Value::Bool(x), Value::Bool(y)) => x == y,
-                        (Value::Number(x), Value::Number(y)) => x == y,
-                        (Value::String(x), Value::String(y)) => x == y,
-                        _ => false,
-                    };
-                    Value::Bool(equal)
+                    let a = self.eval_expr(lhs)?;
+                    let b = self.eval_expr(rhs)?;
+                    Value::Bool(a.equals(&b))
                 }
                 (Op::BangEqual, [lhs, rhs]) => {
-                    if let Value::Bool(eq) =
-                        self.eval_expr(&TokenTree::Cons(Op::EqualEqual, vec![lhs.clone(), rhs.clone()]))
-                    {
-                        Value::Bool(!eq)
+                    let a = self.eval_expr(lhs)?;
+                    let b = self.eval_expr(rhs)?;
+                    Value::Bool(!a.equals(&b))
+                }
+                (
+                    op @ (Op::PreIncrement
+                    | Op::PreDecrement
+                    | Op::PostIncrement
+                    | Op::PostDecrement),
+                    [TokenTree::Atom(Atom::Ident(name, offset))],
+                ) => {
+                    let span = self.operator.take();
+                    let old = self.lookup(name, *offset)?;
+                    self.assignable(name)?;
+                    let Value::Number(old) = old else {
+                        let written = match op {
+                            Op::PreIncrement | Op::PostIncrement => Op::PreIncrement,
+                            _ => Op::PreDecrement,
+                        };
+                        let span = span.unwrap_or(*offset..*offset + name.len());
+                        return self.mismatched(Some(span), "E1011/step", written, &[&old]);
+                    };
+                    let new = match op {
+                        Op::PreIncrement | Op::PostIncrement => old + 1.0,
+                        _ => old - 1.0,
+                    };
+                    self.env.assign(name, Value::Number(new));
+                    match op {
+                        Op::PreIncrement | Op::PreDecrement => Value::Number(new),
+                        _ => Value::Number(old),
+                    }
+                }
+                (Op::Ternary, [cond, yes, no]) => {
+                    if self.eval_expr(cond)?.is_truthy() {
+                        self.eval_expr(yes)?
                     } else {
-                        Value::Bool(false)
+                        self.eval_expr(no)?
                     }
                 }
                 (Op::And, [lhs, rhs]) => {
-                    let left = self.eval_expr(lhs);
+                    let left = self.eval_expr(lhs)?;
                     if !left.is_truthy() {
                         left
                     } else {
-                        self.eval_expr(rhs)
+                        self.eval_expr(rhs)?
                     }
                 }
                 (Op::Or, [lhs, rhs]) => {
-                    let left = self.eval_expr(lhs);
+                    let left = self.eval_expr(lhs)?;
                     if left.is_truthy() {
                         left
                     } else {
-                        self.eval_expr(rhs)
+                        self.eval_expr(rhs)?
                     }
                 }
                 _ => Value::Nil,
             },
-            TokenTree::Fun { .. } | TokenTree::Call { .. } | TokenTree::If { .. } => Value::Nil,
+            TokenTree::Call { callee, arguments } => {
+                let span = self.operator.take();
+                let operands = self.call_operands(callee, arguments)?;
+                self.finish_call(span, operands)?
+            }
+            TokenTree::Block { .. }
+            | TokenTree::If { .. }
+            | TokenTree::Statement { .. }
+            | TokenTree::Assignments { .. }
+            | TokenTree::ForIn { .. }
+            | TokenTree::Match { .. }
+            | TokenTree::Try { .. } => self.exec(node)?,
+            TokenTree::Chain {
+                operands,
+                operators,
+                spans,
+            } => {
+                // stop at the first pair that does not hold, like `और` does
+                let mut left = self.eval_expr(&operands[0])?;
+                for ((op, span), operand) in operators.iter().zip(spans.iter()).zip(&operands[1..])
+                {
+                    let right = self.eval_expr(operand)?;
+                    self.check_comparable(Some(span.clone()), *op, &left, &right)?;
+                    if !compare(*op, &left, &right) {
+                        return Ok(Value::Bool(false));
+                    }
+                    left = right;
+                }
+                Value::Bool(true)
+            }
+            TokenTree::Spanned { span, node } => {
+                self.operator = Some(span.clone());
+                self.eval_node(node)?
+            }
+            TokenTree::Fun { .. } => self.function(node),
+        })
+    }
+
+    /// Evaluates the callee and the arguments of a call.
+    fn call_operands<'de>(
+        &mut self,
+        callee: &TokenTree<'de>,
+        arguments: &[TokenTree<'de>],
+    ) -> Result<CallOperands<'de>, RuntimeError> {
+        // a builtin that is off is not defined, but calling it is an error
+        // that says how to turn it on
+        let disabled = match callee {
+            TokenTree::Atom(Atom::Ident(name, _)) => {
+                capability_flag(name).map(|flag| (*name, flag))
+            }
+            _ => None,
+        };
+        let callee = match disabled {
+            Some((name, _)) => self.find(name).unwrap_or(Value::Nil),
+            None => self.eval_expr(callee)?,
+        };
+        let arguments = arguments
+            .iter()
+            .map(|arg| self.eval_expr(arg))
+            .collect::<Result<_, _>>()?;
+        Ok(CallOperands {
+            callee,
+            arguments,
+            disabled,
+        })
+    }
+
+    /// Makes the call `operands` were evaluated for, at `span`.
+    fn finish_call(
+        &mut self,
+        span: Option<Range<usize>>,
+        operands: CallOperands<'_>,
+    ) -> Result<Value, RuntimeError> {
+        let result = match (operands.callee, operands.disabled) {
+            (Value::Nil, Some((name, flag))) => {
+                Err(runtime_error!("E1006", name = name, flag = flag))
+            }
+            (Value::Function(function), _) => {
+                self.call_function(function, &operands.arguments, span.clone())
+            }
+            (callee, _) => self.call(callee, &operands.arguments),
+        };
+        // errors that don't say where they happened point at the call
+        match (result, span) {
+            (Err(e), Some(span)) if e.span.is_none() => Err(e.at(span)),
+            (result, span) => self.allocated(span, result?),
+        }
+    }
+
+    /// Calls `callee` with `arguments`, as a call in the program does, and
+    /// returns what it returns: `callee` can be a builtin, a host function,
+    /// or a function of the program, and anything else is an error.
+    pub fn call(&mut self, callee: Value, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::Native(native) => {
+                if !native.arity.contains(&arguments.len()) {
+                    return Err(RuntimeError::arity(
+                        native.name,
+                        &native.arity,
+                        arguments.len(),
+                    ));
+                }
+                self.count_call(native.name);
+                self.enter_profiled(native.name);
+                self.depth += 1;
+                let result = (native.fun)(self, arguments);
+                self.depth -= 1;
+                self.leave_profiled();
+                result
+            }
+            Value::Host(host) => {
+                if !host.arity.contains(&arguments.len()) {
+                    return Err(RuntimeError::arity(
+                        &host.name,
+                        &host.arity,
+                        arguments.len(),
+                    ));
+                }
+                self.count_call(&host.name);
+                self.enter_profiled(&host.name);
+                let result = (host.fun)(arguments);
+                self.leave_profiled();
+                result
+            }
+            Value::Function(function) => self.call_function(function, arguments, None),
+            other => Err(runtime_error!(
+                "E1005",
+                kind = self.locale.type_name(other.type_name())
+            )),
+        }
+    }
+
+    /// Runs the body of `function` in a scope of its own, with its
+    /// parameters set to `arguments`. It returns what a `देयम` in it returns,
+    /// or else the value of its body, as a block has.
+    ///
+    /// A `देयम` whose value is a call to a function of the program is a tail
+    /// call: it returns to here first, and the function it calls runs in
+    /// place of the one that returned, so recursion through tail calls
+    /// doesn't nest however deep it goes.
+    ///
+    /// `span` is where the call is in the program's source, if it is there.
+    fn call_function(
+        &mut self,
+        function: Gc<Function>,
+        arguments: &[Value],
+        span: Option<Range<usize>>,
+    ) -> Result<Value, RuntimeError> {
+        // a `प्रयत्न` around the call doesn't keep tail calls in it from
+        // leaving the function
+        let catching = std::mem::take(&mut self.catching);
+        let mut call = (function, arguments.to_vec(), span);
+        let result = loop {
+            let (function, arguments, span) = call;
+            let result = self.run_function(&function, arguments, span.clone());
+            call = match (result, self.returning.take()) {
+                (_, Some(Returning::Value(value))) => break Ok(value),
+                (_, Some(Returning::Call(function, arguments, span))) => {
+                    (function, arguments, span)
+                }
+                // errors of a tail call point at it, as those of a call do
+                (Err(e), None) => match span {
+                    Some(span) if e.span.is_none() => break Err(e.at(span)),
+                    _ => break Err(e),
+                },
+                (Ok(value), None) => break Ok(value),
+            };
+        };
+        self.catching = catching;
+        result
+    }
+
+    /// Runs the body of `function` once, for [`Interpreter::call_function`].
+    fn run_function(
+        &mut self,
+        function: &Gc<Function>,
+        arguments: Vec<Value>,
+        span: Option<Range<usize>>,
+    ) -> Result<Value, RuntimeError> {
+        let arity = function.parameters.len();
+        if arguments.len() != arity {
+            return Err(RuntimeError::arity(
+                function.display_name(),
+                &(arity..=arity),
+                arguments.len(),
+            ));
+        }
+
+        let mut scope = Scope {
+            enclosing: function.captured.clone(),
+            ..Scope::default()
+        };
+        if let Some(name) = &function.name {
+            // so that a function declared inside another can call itself
+            scope.constants.remove(name);
+            scope
+                .vars
+                .insert(name.clone(), Value::Function(Gc::clone(function)));
+        }
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            scope.constants.remove(parameter);
+            scope.vars.insert(parameter.clone(), argument);
+        }
+        self.env.push_scope(scope);
+        self.calls.push((function.name.clone(), span));
+        self.enter_profiled(function.display_name());
+        let mut result = self.exec(function.body());
+        if let Err(e) = &mut result {
+            // the innermost call an error leaves records every call, and
+            // the ones around it keep that; a `देयम` unwinds as an error too
+            if e.stack.is_empty() && self.returning.is_none() {
+                e.stack = self.stack();
+            }
+        }
+        self.leave_profiled();
+        self.calls.pop();
+        self.env.pop_scope();
+        result
+    }
+
+    /// Tells the profile, if there is one, that the function `name` has been
+    /// called.
+    fn enter_profiled(&mut self, name: &str) {
+        if let Some(profile) = &mut self.profile {
+            profile.enter_function(name);
+        }
+    }
+
+    /// Tells the profile, if there is one, that the function entered last has
+    /// returned.
+    fn leave_profiled(&mut self) {
+        if let Some(profile) = &mut self.profile {
+            profile.leave_function();
+        }
+    }
+
+    /// The calls under way, as [`RuntimeError::stack`] records them.
+    fn stack(&self) -> Vec<StackFrame> {
+        self.calls
+            .iter()
+            .map(|(function, call)| StackFrame {
+                function: function.as_deref().map(String::from),
+                call: call.clone().map(SourceSpan::from),
+            })
+            .collect()
+    }
+
+    /// The function that the [`TokenTree::Fun`] `node` makes.
+    fn function(&mut self, node: &TokenTree<'_>) -> Value {
+        let TokenTree::Fun {
+            name,
+            parameters,
+            body,
+        } = node
+        else {
+            unreachable!("only called on functions");
+        };
+        let name = match name {
+            Some(Atom::Ident(name, _)) => Some(self.env.strings.intern(name)),
+            _ => None,
+        };
+        Value::Function(Gc::new(Function::new(
+            name,
+            parameters,
+            body,
+            self.env.capture(),
+        )))
+    }
+
+    /// `a + b`, where `span` is that of the `+`.
+    fn add(
+        &mut self,
+        span: Option<Range<usize>>,
+        a: Value,
+        b: Value,
+    ) -> Result<Value, RuntimeError> {
+        // the `+` that string interpolation builds has no span and always
+        // concatenates
+        let lenient = !self.strict || span.is_none();
+        let sum = match (a, b) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (Value::String(mut a), Value::String(b)) => {
+                a.push_str(&b);
+                Value::String(a)
+            }
+            (Value::String(mut a), b) if lenient => {
+                a.push_str(&b.format(self.concat_numbers).to_string());
+                Value::String(a)
+            }
+            (a, Value::String(b)) if lenient => {
+                Value::String(format!("{}{b}", a.format(self.concat_numbers)).into())
+            }
+            (Value::List(a), Value::List(b)) => {
+                Value::List(Gc::new(a.iter().chain(b.iter()).cloned().collect()))
+            }
+            (a @ Value::List(_), b) | (a, b @ Value::List(_)) => {
+                let error = runtime_error!(
+                    "E1010",
+                    left = self.locale.type_name(a.type_name()),
+                    right = self.locale.type_name(b.type_name())
+                );
+                return Err(match span {
+                    Some(span) => error.at(span),
+                    None => error,
+                });
+            }
+            (a, b) => self.mismatched(span.clone(), "E1011/add", Op::Plus, &[&a, &b])?,
+        };
+        self.allocated(span, sum)
+    }
+
+    /// Checks that `value`, which was just built, fits in the memory limit
+    /// along with the program's variables, before the program gets it.
+    fn allocated(
+        &mut self,
+        span: Option<Range<usize>>,
+        value: Value,
+    ) -> Result<Value, RuntimeError> {
+        let Some(memory) = self.env.bytes else {
+            return Ok(value);
+        };
+        match self.budget.allocate(value.heap_size(), memory) {
+            Ok(()) => Ok(value),
+            Err(error) => Err(match span {
+                Some(span) => error.at(span),
+                None => error,
+            }),
+        }
+    }
+
+    /// If `expr`, the value assigned to `name`, is `name + rhs` (as `name +=
+    /// rhs` also becomes), the span of the `+`, the offset of the `name` in
+    /// it, and `rhs`. Not while tracing
+    /// expressions, since [`Interpreter::append`] skips the trace of the `+`.
+    fn appended<'a, 'de>(
+        &self,
+        name: &str,
+        expr: &'a TokenTree<'de>,
+    ) -> Option<(&'a Range<usize>, usize, &'a TokenTree<'de>)> {
+        let TokenTree::Spanned { span, node } = expr else {
+            return None;
+        };
+        match &**node {
+            TokenTree::Cons(Op::Plus, children) if self.trace < Trace::Expressions => {
+                match &children[..] {
+                    [TokenTree::Atom(Atom::Ident(lhs, offset)), rhs] if *lhs == name => {
+                        Some((span, *offset, rhs))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates `name + rhs` for `name = name + rhs`. The variable lets go
+    /// of its string before the `+`, so that, when nothing else shares the
+    /// string, the `+` appends to it in place instead of copying it.
+    fn append(
+        &mut self,
+        name: &str,
+        offset: usize,
+        span: &Range<usize>,
+        rhs: &TokenTree<'_>,
+    ) -> Result<Value, RuntimeError> {
+        let a = self.lookup(name, offset)?;
+        let b = self.eval_expr(rhs)?;
+        if let Value::String(a) = &a {
+            let held = matches!(self.env.get(name), Some(Value::String(s)) if s.ptr_eq(a));
+            // only when the + can't fail, since the variable is nil until
+            // it is assigned the result
+            if held && (!self.strict || matches!(b, Value::String(_))) {
+                self.env.assign(name, Value::Nil);
+            }
+        }
+        self.add(Some(span.clone()), a, b)
+    }
+
+    /// The value of `op` applied to `operands` of types it does not take:
+    /// `nil`, or in strict mode the error with `key`, which says what it
+    /// expected.
+    fn mismatched(
+        &self,
+        span: Option<Range<usize>>,
+        key: &'static str,
+        op: Op,
+        operands: &[&Value],
+    ) -> Result<Value, RuntimeError> {
+        if !self.strict {
+            return Ok(Value::Nil);
+        }
+        let types = match operands {
+            [a, b] => crate::message!(
+                "E1011/types",
+                left = self.locale.type_name(a.type_name()),
+                right = self.locale.type_name(b.type_name())
+            ),
+            _ => operands
+                .iter()
+                .map(|v| self.locale.type_name(v.type_name()))
+                .collect(),
+        };
+        let message = crate::locale::message(key, &[("op", &op), ("types", &types)]);
+        let error = RuntimeError::new(message).with_code(crate::locale::code(key));
+        Err(match span {
+            Some(span) => error.at(span),
+            None => error,
+        })
+    }
+
+    /// The value of dividing by zero under a policy other than
+    /// [`DivisionByZero::Infinity`], which needs no special case.
+    fn divided_by_zero(&self, span: Option<Range<usize>>) -> Result<Value, RuntimeError> {
+        match self.division_by_zero {
+            DivisionByZero::Nil => Ok(Value::Nil),
+            _ => {
+                let error = runtime_error!("E1001");
+                Err(match span {
+                    Some(span) => error.at(span),
+                    None => error,
+                })
+            }
+        }
+    }
+
+    /// In strict mode, fails unless `a` and `b` are numbers or strings that
+    /// `op` can order.
+    fn check_comparable(
+        &self,
+        span: Option<Range<usize>>,
+        op: Op,
+        a: &Value,
+        b: &Value,
+    ) -> Result<(), RuntimeError> {
+        match (a, b) {
+            (Value::Number(_), Value::Number(_)) | (Value::String(_), Value::String(_)) => Ok(()),
+            _ => self
+                .mismatched(span, "E1011/compare", op, &[a, b])
+                .map(drop),
+        }
+    }
+}
+
+/// Applies an ordering operator. Numbers are ordered by value and strings by
+/// their code points, so `"क" < "ख"`; any other operands compare false.
+fn compare(op: Op, a: &Value, b: &Value) -> bool {
+    let ordering = match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::String(a), Value::String(b)) => Some((**a).cmp(&**b)),
+        _ => None,
+    };
+    let Some(ordering) = ordering else {
+        return false;
+    };
+    match op {
+        Op::Less => ordering.is_lt(),
+        Op::LessEqual => ordering.is_le(),
+        Op::Greater => ordering.is_gt(),
+        Op::GreaterEqual => ordering.is_ge(),
+        _ => unreachable!("{op} is not an ordering operator"),
+    }
+}
+
+/// The span and the [`TokenTree::Call`] of `expr` if it is a call, perhaps
+/// in parentheses.
+fn tail_call<'a, 'de>(
+    expr: &'a TokenTree<'de>,
+) -> Option<(Option<Range<usize>>, &'a TokenTree<'de>)> {
+    match expr {
+        TokenTree::Spanned { span, node } => match tail_call(node)? {
+            (None, call) => Some((Some(span.clone()), call)),
+            call => Some(call),
+        },
+        TokenTree::Cons(Op::Group, [inner]) => tail_call(inner),
+        TokenTree::Call { .. } => Some((None, expr)),
+        _ => None,
+    }
+}
+
+/// Whether `value` equals `literal`, as `==` would find.
+fn is_literal(value: &Value, literal: &Atom<'_>) -> bool {
+    match (value, literal) {
+        (Value::Number(x), Atom::Number(y)) => x == y,
+        (Value::String(x), Atom::String(y)) => **x == **y,
+        (Value::Bool(x), Atom::Bool(y)) => x == y,
+        (Value::Nil, Atom::Nil) => true,
+        _ => false,
+    }
+}
+
+fn is_whole(n: f64) -> bool {
+    n.is_finite() && n.fract() == 0.0
+}
+
+/// How many characters have to be inserted, removed, or replaced to turn `a`
+/// into `b` (the Levenshtein distance).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
         }
     }
+    row[b.len()]
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::Captured;
+    use crate::limits::STACK_SIZE;
+
+    /// What `source`, in Lox keywords, prints, or the code of the runtime
+    /// error it stops with, with `eval` on. It runs on a stack as big as the
+    /// CLI's, as deep recursion needs.
+    fn run(source: &'static str) -> Result<String, Option<&'static str>> {
+        run_limited(source, Limits::default())
+    }
+
+    /// What `source` prints under `limits`, as with [`run`].
+    fn run_limited(source: &'static str, limits: Limits) -> Result<String, Option<&'static str>> {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || {
+                let arena = Arena::new();
+                let program = Parser::new(source, &arena)
+                    .parse_program()
+                    .expect("the program parses");
+                let output = Captured::default();
+                let mut interpreter = Interpreter::new()
+                    .output(output.clone())
+                    .limits(limits)
+                    .allow_eval(true);
+                match interpreter.eval_program(&program) {
+                    Ok(_) => Ok(output.contents()),
+                    Err(e) => Err(e.code),
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn tail_calls_run_in_constant_depth() {
+        let source = "
+            fun count(n, total) {
+                if (n == 0) { return total; }
+                return count(n - 1, total + 1);
+            }
+            print count(1000000, 0);
+        ";
+        assert_eq!(run(source), Ok("1000000.0\n".to_string()));
+    }
+
+    #[test]
+    fn mutual_tail_calls_run_in_constant_depth() {
+        let source = "
+            fun even(n) {
+                if (n == 0) { return true; }
+                return odd(n - 1);
+            }
+            fun odd(n) {
+                if (n == 0) { return false; }
+                return even(n - 1);
+            }
+            print even(100001);
+            print odd(100001);
+        ";
+        assert_eq!(run(source), Ok("false\ntrue\n".to_string()));
+    }
+
+    #[test]
+    fn calls_not_in_tail_position_still_hit_the_depth_limit() {
+        let source = "
+            fun sum(n) {
+                if (n == 0) { return 0; }
+                return 1 + sum(n - 1);
+            }
+            print sum(1000000);
+        ";
+        assert_eq!(run(source), Err(Some("E1008")));
+    }
+
+    #[test]
+    fn local_variables_count_towards_the_memory_limit() {
+        let limits = Limits {
+            max_memory: Some(1024 * 1024),
+            ..Limits::default()
+        };
+        // 64 KiB, doubled in each of a hundred calls at once
+        let deep = "
+            var s = \"x\";
+            for (var i = 0; i < 16; i++) { s = s + s; }
+            fun hold(s, n) {
+                var doubled = s + s;
+                if (n == 0) { return 0; }
+                return 1 + hold(s, n - 1);
+            }
+            print hold(s, 100);
+        ";
+        assert_eq!(run_limited(deep, limits), Err(Some("E1015")));
+        // the same hundred calls one after another, each freeing its variables
+        let shallow = "
+            var s = \"x\";
+            for (var i = 0; i < 16; i++) { s = s + s; }
+            fun hold(s) {
+                var doubled = s + s;
+                return 1;
+            }
+            var calls = 0;
+            for (var i = 0; i < 100; i++) { calls = calls + hold(s); }
+            print calls;
+        ";
+        assert_eq!(run_limited(shallow, limits), Ok("100.0\n".to_string()));
+    }
+
+    #[test]
+    fn deeply_nested_lists_are_measured_and_dropped() {
+        // on the test thread's small stack, which recursing once per level
+        // would overflow
+        let depth = 1_000_000;
+        let nested = (0..depth).fold(Value::Nil, |inner, _| Value::List(Gc::new(vec![inner])));
+        assert_eq!(nested.heap_size(), depth * std::mem::size_of::<Value>());
+        drop(nested);
+    }
+
+    #[test]
+    fn eval_rejects_code_that_does_not_parse_and_values_that_are_not_code() {
+        assert_eq!(run("print eval(\"1 + 2\");"), Ok("3.0\n".to_string()));
+        assert_eq!(run("print eval(\"print 1 +;\");"), Err(Some("E1027")));
+        assert_eq!(run("print eval(\"(\", true);"), Err(Some("E1027")));
+        assert_eq!(run("print eval(5);"), Err(Some("E1017")));
+    }
+
+    #[test]
+    fn fresh_eval_environments_count_towards_the_memory_limit() {
+        let limits = Limits {
+            max_memory: Some(1024 * 1024),
+            ..Limits::default()
+        };
+        // 2 MiB, built where the caller's variables can't be seen
+        let source = r#"
+            var s = "x";
+            print eval("""var t = "x"; for (var i = 0; i < 21; i++) { t = t + t; } t""", true);
+        "#;
+        assert_eq!(run_limited(source, limits), Err(Some("E1015")));
+        assert_eq!(
+            run("var s = 1; print eval(\"s\", true);"),
+            Err(Some("E1003"))
+        );
+        assert_eq!(
+            run("var s = 1; print eval(\"s\");"),
+            Ok("1.0\n".to_string())
+        );
+    }
+
+    #[test]
+    fn stepping_a_non_number_is_a_type_error_in_strict_mode() {
+        let arena = Arena::new();
+        let program = Parser::new("var x = \"a\";\nx++;", &arena)
+            .parse_program()
+            .expect("the program parses");
+        let mut interpreter = Interpreter::new().output(io::sink()).strict(true);
+        let error = interpreter.eval_program(&program).unwrap_err();
+        assert_eq!(error.code, Some("E1011"));
+        assert_eq!(error.span, Some(SourceSpan::from(13..14)));
+
+        assert_eq!(run("var x = \"a\"; x--; print x;"), Ok("a\n".to_string()));
+    }
+
+    #[test]
+    fn undefined_variables_point_at_their_name() {
+        let arena = Arena::new();
+        let program = Parser::new("var a = 1;\nprint a / b;", &arena)
+            .parse_program()
+            .expect("the program parses");
+        let mut interpreter = Interpreter::new().output(io::sink());
+        let error = interpreter.eval_program(&program).unwrap_err();
+        assert_eq!(error.code, Some("E1003"));
+        assert_eq!(error.span, Some(SourceSpan::from(21..22)));
+    }
+
+    #[test]
+    fn c_style_for_loops_run() {
+        let source = "
+            for (var i = 0; i < 3; i++) { print i; }
+            var j = 10;
+            for (j = 0; j < 2; j = j + 1) { print j; }
+            print j;
+        ";
+        assert_eq!(
+            run(source),
+            Ok("0.0\n1.0\n2.0\n0.0\n1.0\n2.0\n".to_string())
+        );
+    }
+
+    #[test]
+    fn closures_share_the_variables_they_capture() {
+        let source = "
+            fun counter() {
+                var count = 0;
+                fun increment() {
+                    count = count + 1;
+                    return count;
+                }
+                return increment;
+            }
+            var a = counter();
+            var b = counter();
+            print a();
+            print a();
+            print b();
+        ";
+        assert_eq!(run(source), Ok("1.0\n2.0\n1.0\n".to_string()));
+    }
+
+    #[test]
+    fn cycles_through_scopes_are_collected() {
+        let source = "
+            fun counter() {
+                var count = 0;
+                fun increment() {
+                    count = count + 1;
+                    return count;
+                }
+                return increment;
+            }
+            var next = counter();
+            next();
+            for (var i = 0; i < 5000; i++) { counter(); }
+            print next();
+        ";
+        let arena = Arena::new();
+        let program = Parser::new(source, &arena)
+            .parse_program()
+            .expect("the program parses");
+        let output = Captured::default();
+        let mut interpreter = Interpreter::new().output(output.clone());
+        interpreter
+            .eval_program(&program)
+            .expect("the program runs");
+        // each call of `counter` left its scope and `increment` holding each
+        // other, and the collections while the loop ran freed them, but not
+        // the scope `next` still needs
+        assert_eq!(output.contents(), "2.0\n");
+        gc::collect();
+        // `counter`, `next`, and the scope `next` captured
+        assert_eq!(gc::live_objects(), 3);
+    }
+
+    #[test]
+    fn keyword_examples_run() {
+        let pack = &crate::translator::SANSKRIT;
+        for keyword in pack.keywords.iter().filter(|k| !k.example.is_empty()) {
+            let source = pack.translate(keyword.example);
+            let arena = Arena::new();
+            let program = Parser::new(&source, &arena)
+                .parse_program()
+                .unwrap_or_else(|e| panic!("the example of {} doesn't parse: {e}", keyword.native));
+            let mut interpreter = Interpreter::new().output(Captured::default());
+            if let Err(e) = interpreter.eval_program(&program) {
+                panic!("the example of {} fails: {}", keyword.native, e.message);
+            }
+        }
+    }
+}
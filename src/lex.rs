@@ -1,16 +1,15 @@
 use miette::{Diagnostic, Error, LabeledSpan, SourceSpan};
 use std::{borrow::Cow, fmt};
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 #[derive(Diagnostic, Debug, Error)]
-#[error("Unexpected EOF")]
+#[error("{}", crate::message!("E0003"))]
+#[diagnostic(code(E0003))]
 pub struct Eof;
 
 #[derive(Diagnostic, Debug, Error)]
-#[error("Unexpected token '{token}'")]
+#[error("{}", crate::message!("E0001", token = token))]
+#[diagnostic(code(E0001))]
 pub struct SingleTokenError {
     #[source_code]
     src: String,
@@ -23,13 +22,14 @@ pub struct SingleTokenError {
 
 impl SingleTokenError {
     pub fn line(&self) -> usize {
-        let until_unrecongized = &self.src[..=self.err_span.offset()];
-        until_unrecongized.lines().count()
+        let until_unrecongized = &self.src[..self.err_span.offset()];
+        until_unrecongized.matches('\n').count() + 1
     }
 }
 
 #[derive(Diagnostic, Debug, Error)]
-#[error("Unterminated string")]
+#[error("{}", crate::message!("E0002"))]
+#[diagnostic(code(E0002))]
 pub struct StringTerminationError {
     #[source_code]
     src: String,
@@ -40,32 +40,44 @@ pub struct StringTerminationError {
 
 impl StringTerminationError {
     pub fn line(&self) -> usize {
-        let until_unrecongized = &self.src[..=self.err_span.offset()];
-        until_unrecongized.lines().count()
+        let until_unrecongized = &self.src[..self.err_span.offset()];
+        until_unrecongized.matches('\n').count() + 1
     }
 }
 
-fn is_devanagari(c: char) -> bool {
-    ('\u{0900}'..='\u{097F}').contains(&c)
+/// Identifiers follow the Unicode XID rules (UAX #31). XID_Continue covers
+/// Devanagari matras, nukta, and virama, and since Unicode 15.1 also ZWJ/ZWNJ,
+/// which select the half-form rendering of a conjunct (e.g. क्‍ष).
+pub(crate) fn is_ident_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
 }
 
-fn agent_log_lex(run_id: &str, hypothesis_id: &str, location: &str, message: &str, data: &str) {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    let timestamp = now.as_millis();
-    let log_path = "debug-14ec27.log";
-
-    if let Ok(mut file) = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_path)
-    {
-        let line = format!(
-            "{{\"sessionId\":\"14ec27\",\"id\":\"log_lex_{timestamp}\",\"timestamp\":{timestamp},\"location\":\"{location}\",\"message\":\"{message}\",\"data\":{data},\"runId\":\"{run_id}\",\"hypothesisId\":\"{hypothesis_id}\"}}\n"
-        );
-        let _ = file.write_all(line.as_bytes());
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
+
+/// Whether the lexer reads `text` as exactly one identifier.
+pub(crate) fn is_word(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(is_ident_start) && chars.all(is_ident_continue)
+}
+
+/// Rewrites Devanagari digits (०-९) into their ASCII counterparts so that
+/// numeric literals written in either script parse the same way.
+pub(crate) fn normalize_digits(literal: &str) -> Cow<'_, str> {
+    if literal.is_ascii() {
+        return Cow::Borrowed(literal);
     }
+
+    Cow::Owned(
+        literal
+            .chars()
+            .map(|c| match c {
+                '०'..='९' => char::from(b'0' + (c as u32 - '०' as u32) as u8),
+                c => c,
+            })
+            .collect(),
+    )
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -81,12 +93,28 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
+    /// `..`, between the ends of a range
+    DotDot,
+    /// `..=`, between the ends of a range that includes its end
+    DotDotEqual,
     Minus,
     Plus,
     Semicolon,
     Star,
+    StarStar,
+    PlusEqual,
+    MinusEqual,
+    PlusPlus,
+    MinusMinus,
+    StarEqual,
+    SlashEqual,
+    Percent,
+    Question,
+    Colon,
     BangEqual,
     EqualEqual,
     LessEqual,
@@ -97,92 +125,258 @@ pub enum TokenKind {
     Bang,
     Equal,
     String,
+    /// `"text ${` — the start of an interpolated string
+    StringHead,
+    /// `} text ${` — literal text between two interpolations
+    StringMiddle,
+    /// `} text"` — the end of an interpolated string
+    StringTail,
     Ident,
     Number(f64),
     And,
+    Catch,
     Class,
+    Const,
+    Default,
     Else,
     False,
     For,
     Fun,
     If,
+    In,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
 }
 
+impl TokenKind {
+    /// The name of the kind as `tokenize` prints it, such as `LEFT_PAREN`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::LeftParen => "LEFT_PAREN",
+            TokenKind::RightParen => "RIGHT_PAREN",
+            TokenKind::LeftBrace => "LEFT_BRACE",
+            TokenKind::RightBrace => "RIGHT_BRACE",
+            TokenKind::LeftBracket => "LEFT_BRACKET",
+            TokenKind::RightBracket => "RIGHT_BRACKET",
+            TokenKind::Comma => "COMMA",
+            TokenKind::Dot => "DOT",
+            TokenKind::DotDot => "DOT_DOT",
+            TokenKind::DotDotEqual => "DOT_DOT_EQUAL",
+            TokenKind::Minus => "MINUS",
+            TokenKind::Plus => "PLUS",
+            TokenKind::Semicolon => "SEMICOLON",
+            TokenKind::Star => "STAR",
+            TokenKind::StarStar => "STAR_STAR",
+            TokenKind::Percent => "PERCENT",
+            TokenKind::PlusEqual => "PLUS_EQUAL",
+            TokenKind::MinusEqual => "MINUS_EQUAL",
+            TokenKind::PlusPlus => "PLUS_PLUS",
+            TokenKind::MinusMinus => "MINUS_MINUS",
+            TokenKind::StarEqual => "STAR_EQUAL",
+            TokenKind::SlashEqual => "SLASH_EQUAL",
+            TokenKind::Question => "QUESTION",
+            TokenKind::Colon => "COLON",
+            TokenKind::BangEqual => "BANG_EQUAL",
+            TokenKind::EqualEqual => "EQUAL_EQUAL",
+            TokenKind::LessEqual => "LESS_EQUAL",
+            TokenKind::GreaterEqual => "GREATER_EQUAL",
+            TokenKind::Less => "LESS",
+            TokenKind::Greater => "GREATER",
+            TokenKind::Slash => "SLASH",
+            TokenKind::Bang => "BANG",
+            TokenKind::Equal => "EQUAL",
+            TokenKind::String => "STRING",
+            TokenKind::StringHead => "STRING_HEAD",
+            TokenKind::StringMiddle => "STRING_MIDDLE",
+            TokenKind::StringTail => "STRING_TAIL",
+            TokenKind::Ident => "IDENTIFIER",
+            TokenKind::Number(_) => "NUMBER",
+            TokenKind::And => "AND",
+            TokenKind::Catch => "CATCH",
+            TokenKind::Class => "CLASS",
+            TokenKind::Const => "CONST",
+            TokenKind::Default => "DEFAULT",
+            TokenKind::Else => "ELSE",
+            TokenKind::False => "FALSE",
+            TokenKind::For => "FOR",
+            TokenKind::Fun => "FUN",
+            TokenKind::If => "IF",
+            TokenKind::In => "IN",
+            TokenKind::Match => "MATCH",
+            TokenKind::Nil => "NIL",
+            TokenKind::Or => "OR",
+            TokenKind::Print => "PRINT",
+            TokenKind::Return => "RETURN",
+            TokenKind::Super => "SUPER",
+            TokenKind::This => "THIS",
+            TokenKind::Throw => "THROW",
+            TokenKind::True => "TRUE",
+            TokenKind::Try => "TRY",
+            TokenKind::Var => "VAR",
+            TokenKind::While => "WHILE",
+        }
+    }
+
+    /// The kind named `name`, the reverse of [`TokenKind::name`]. `NUMBER`
+    /// gives `None`, since a number token also needs its value.
+    pub fn from_name(name: &str) -> Option<Self> {
+        const KINDS: &[TokenKind] = &[
+            TokenKind::LeftParen,
+            TokenKind::RightParen,
+            TokenKind::LeftBrace,
+            TokenKind::RightBrace,
+            TokenKind::LeftBracket,
+            TokenKind::RightBracket,
+            TokenKind::Comma,
+            TokenKind::Dot,
+            TokenKind::DotDot,
+            TokenKind::DotDotEqual,
+            TokenKind::Minus,
+            TokenKind::Plus,
+            TokenKind::Semicolon,
+            TokenKind::Star,
+            TokenKind::StarStar,
+            TokenKind::Percent,
+            TokenKind::PlusEqual,
+            TokenKind::MinusEqual,
+            TokenKind::PlusPlus,
+            TokenKind::MinusMinus,
+            TokenKind::StarEqual,
+            TokenKind::SlashEqual,
+            TokenKind::Question,
+            TokenKind::Colon,
+            TokenKind::BangEqual,
+            TokenKind::EqualEqual,
+            TokenKind::LessEqual,
+            TokenKind::GreaterEqual,
+            TokenKind::Less,
+            TokenKind::Greater,
+            TokenKind::Slash,
+            TokenKind::Bang,
+            TokenKind::Equal,
+            TokenKind::String,
+            TokenKind::StringHead,
+            TokenKind::StringMiddle,
+            TokenKind::StringTail,
+            TokenKind::Ident,
+            TokenKind::And,
+            TokenKind::Catch,
+            TokenKind::Class,
+            TokenKind::Const,
+            TokenKind::Default,
+            TokenKind::Else,
+            TokenKind::False,
+            TokenKind::For,
+            TokenKind::Fun,
+            TokenKind::If,
+            TokenKind::In,
+            TokenKind::Match,
+            TokenKind::Nil,
+            TokenKind::Or,
+            TokenKind::Print,
+            TokenKind::Return,
+            TokenKind::Super,
+            TokenKind::This,
+            TokenKind::Throw,
+            TokenKind::True,
+            TokenKind::Try,
+            TokenKind::Var,
+            TokenKind::While,
+        ];
+        KINDS.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
 impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let origin = self.origin;
+        let (name, origin) = (self.kind.name(), self.origin);
         match self.kind {
-            TokenKind::LeftParen => write!(f, "LEFT_PAREN {origin} null"),
-            TokenKind::RightParen => write!(f, "RIGHT_PAREN {origin} null"),
-            TokenKind::LeftBrace => write!(f, "LEFT_BRACE {origin} null"),
-            TokenKind::RightBrace => write!(f, "RIGHT_BRACE {origin} null"),
-            TokenKind::Comma => write!(f, "COMMA {origin} null"),
-            TokenKind::Dot => write!(f, "DOT {origin} null"),
-            TokenKind::Minus => write!(f, "MINUS {origin} null"),
-            TokenKind::Plus => write!(f, "PLUS {origin} null"),
-            TokenKind::Semicolon => write!(f, "SEMICOLON {origin} null"),
-            TokenKind::Star => write!(f, "STAR {origin} null"),
-            TokenKind::BangEqual => write!(f, "BANG_EQUAL {origin} null"),
-            TokenKind::EqualEqual => write!(f, "EQUAL_EQUAL {origin} null"),
-            TokenKind::LessEqual => write!(f, "LESS_EQUAL {origin} null"),
-            TokenKind::GreaterEqual => write!(f, "GREATER_EQUAL {origin} null"),
-            TokenKind::Less => write!(f, "LESS {origin} null"),
-            TokenKind::Greater => write!(f, "GREATER {origin} null"),
-            TokenKind::Slash => write!(f, "SLASH {origin} null"),
-            TokenKind::Bang => write!(f, "BANG {origin} null"),
-            TokenKind::Equal => write!(f, "EQUAL {origin} null"),
-            TokenKind::String => write!(f, "STRING {origin} {}", Token::unescape(origin)),
-            TokenKind::Ident => write!(f, "IDENTIFIER {origin} null"),
+            TokenKind::String
+            | TokenKind::StringHead
+            | TokenKind::StringMiddle
+            | TokenKind::StringTail => write!(f, "{name} {origin} {}", Token::unescape(origin)),
             TokenKind::Number(n) => {
                 if n == n.trunc() {
                     // tests require that integers are printed as N.0
-                    write!(f, "NUMBER {origin} {n}.0")
+                    write!(f, "{name} {origin} {n}.0")
                 } else {
-                    write!(f, "NUMBER {origin} {n}")
+                    write!(f, "{name} {origin} {n}")
                 }
             }
-            TokenKind::And => write!(f, "AND {origin} null"),
-            TokenKind::Class => write!(f, "CLASS {origin} null"),
-            TokenKind::Else => write!(f, "ELSE {origin} null"),
-            TokenKind::False => write!(f, "FALSE {origin} null"),
-            TokenKind::For => write!(f, "FOR {origin} null"),
-            TokenKind::Fun => write!(f, "FUN {origin} null"),
-            TokenKind::If => write!(f, "IF {origin} null"),
-            TokenKind::Nil => write!(f, "NIL {origin} null"),
-            TokenKind::Or => write!(f, "OR {origin} null"),
-            TokenKind::Print => write!(f, "PRINT {origin} null"),
-            TokenKind::Return => write!(f, "RETURN {origin} null"),
-            TokenKind::Super => write!(f, "SUPER {origin} null"),
-            TokenKind::This => write!(f, "THIS {origin} null"),
-            TokenKind::True => write!(f, "TRUE {origin} null"),
-            TokenKind::Var => write!(f, "VAR {origin} null"),
-            TokenKind::While => write!(f, "WHILE {origin} null"),
+            _ => write!(f, "{name} {origin} null"),
         }
     }
 }
 
 impl Token<'_> {
     pub fn unescape<'de>(s: &'de str) -> Cow<'de, str> {
-        // Lox has no escaping, so just remove the "
-        // Since it has no escaping, strings can't contain ", so trim won't trim multiple
-        Cow::Borrowed(s.trim_matches('"'))
+        // a raw string keeps everything between its """, except for a line
+        // break right after the opening ones
+        if let Some(raw) = s
+            .strip_prefix(RAW_QUOTES)
+            .and_then(|s| s.strip_suffix(RAW_QUOTES))
+        {
+            let raw = raw
+                .strip_prefix('\n')
+                .or_else(|| raw.strip_prefix("\r\n"))
+                .unwrap_or(raw);
+            return Cow::Borrowed(raw);
+        }
+        // Lox has no escaping, so just remove the delimiters: " on either end
+        // of a plain string, and the } and ${ around interpolated parts
+        let s = s.strip_prefix(['"', '}']).unwrap_or(s);
+        let s = s
+            .strip_suffix('"')
+            .or_else(|| s.strip_suffix("${"))
+            .unwrap_or(s);
+        Cow::Borrowed(s)
     }
 }
 
+/// What opens and closes a raw string, which has no interpolation and can
+/// hold `"`: `"""वदति "नमः" इति"""`.
+pub const RAW_QUOTES: &str = "\"\"\"";
+
+/// What ends a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Terminators {
+    /// only `;`, as in Lox
+    #[default]
+    Semicolons,
+    /// `;`, or a line break after something that can end a statement
+    Newlines,
+}
+
 pub struct Lexer<'de> {
     whole: &'de str,
     rest: &'de str,
     byte: usize,
     peeked: Option<Result<Token<'de>, miette::Error>>,
+    /// For each `${` we are inside of, the number of `{` opened within it
+    /// that are not yet closed, so we know which `}` resumes the string.
+    interpolations: Vec<usize>,
+    terminators: Terminators,
+    /// The token scanned after a line break that got a `;` inserted before it.
+    pending: Option<Token<'de>>,
+    /// The kind and end offset of the last token scanned.
+    last: Option<(TokenKind, usize)>,
+    /// The `(`, `[`, and `{` that are open, innermost last.
+    open: Vec<TokenKind>,
+    /// The end offset of the last token `next` returned, as opposed to peeked.
+    consumed_end: usize,
+    /// Tokens to yield instead of scanning the source, for [`Lexer::from_tokens`].
+    replay: Option<std::vec::IntoIter<Token<'de>>>,
 }
 
 impl<'de> Lexer<'de> {
@@ -192,11 +386,60 @@ impl<'de> Lexer<'de> {
             rest: input,
             byte: 0,
             peeked: None,
+            interpolations: Vec::new(),
+            terminators: Terminators::default(),
+            pending: None,
+            last: None,
+            open: Vec::new(),
+            consumed_end: 0,
+            replay: None,
+        }
+    }
+
+    /// A lexer that yields `tokens` rather than scanning `source`, which the
+    /// tokens' offsets point into. Statement terminators are still inserted
+    /// at line breaks in `source` as set with [`Lexer::terminators`].
+    pub fn from_tokens(source: &'de str, tokens: Vec<Token<'de>>) -> Self {
+        Self {
+            replay: Some(tokens.into_iter()),
+            ..Self::new(source)
         }
     }
+
+    /// Sets what ends a statement. With [`Terminators::Newlines`], the lexer
+    /// yields a `;` token with an empty origin at a line break when
+    ///
+    /// - the last token on the line is an identifier, a literal, `this`,
+    ///   `super`, `return`, `++`, `--`, or a closing `)`, `]`, or `}`,
+    /// - the line break is not inside `(…)` or `[…]`, and
+    /// - the next line does not start with `{`, `}`, `.`, or `else`,
+    ///
+    /// so that `print 1` and `चर क = 2` on lines of their own need no `;`,
+    /// while a call's arguments or a condition can still span lines.
+    pub fn terminators(mut self, terminators: Terminators) -> Self {
+        self.terminators = terminators;
+        self
+    }
+
+    /// Whether the next token is inside `(…)` or `[…]`.
+    pub(crate) fn in_brackets(&self) -> bool {
+        matches!(
+            self.open.last(),
+            Some(TokenKind::LeftParen | TokenKind::LeftBracket)
+        )
+    }
+
+    /// The offset just past the last token taken with `next`, where a missing
+    /// token after it would go.
+    pub(crate) fn consumed_end(&self) -> usize {
+        self.consumed_end
+    }
 }
 
 impl<'de> Lexer<'de> {
+    /// The next token, if it is of the `expected` kind, or else the error
+    /// with the message whose key is `unexpected`, from the
+    /// [catalog](crate::locale::CATALOG).
     pub fn expect(
         &mut self,
         expected: TokenKind,
@@ -216,8 +459,10 @@ impl<'de> Lexer<'de> {
                 labels = vec![
                     LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here"),
                 ],
-                help = format!("Expected {token:?}"),
-                "{unexpected}",
+                code = "E0104",
+                help = crate::message!("expected-token", token = format!("{token:?}")),
+                "{}",
+                crate::locale::message(unexpected, &[]),
             }
             .with_source_code(self.whole.to_string())),
             Some(Err(e)) => Err(e),
@@ -230,9 +475,83 @@ impl<'de> Lexer<'de> {
             return self.peeked.as_ref();
         }
 
-        self.peeked = self.next();
+        self.peeked = self.advance();
         self.peeked.as_ref()
     }
+
+    /// Scans the next token, first inserting a `;` if it starts a new line
+    /// that ends the statement before it.
+    fn advance(&mut self) -> Option<Result<Token<'de>, Error>> {
+        if let Some(token) = self.pending.take() {
+            self.record(&token);
+            return Some(Ok(token));
+        }
+
+        let next = self.scan();
+        let Some(Ok(token)) = next else {
+            return next;
+        };
+        if let Some((_, end)) = self.last.filter(|_| self.ends_statement_before(&token)) {
+            self.pending = Some(token);
+            let semicolon = Token {
+                origin: &self.whole[end..end],
+                offset: end,
+                kind: TokenKind::Semicolon,
+            };
+            self.record(&semicolon);
+            return Some(Ok(semicolon));
+        }
+        self.record(&token);
+        Some(Ok(token))
+    }
+
+    fn ends_statement_before(&self, next: &Token<'de>) -> bool {
+        let Some((last, end)) = self.last else {
+            return false;
+        };
+        self.terminators == Terminators::Newlines
+            && self.whole[end..next.offset].contains('\n')
+            && matches!(
+                last,
+                TokenKind::Ident
+                    | TokenKind::Number(_)
+                    | TokenKind::String
+                    | TokenKind::StringTail
+                    | TokenKind::True
+                    | TokenKind::False
+                    | TokenKind::Nil
+                    | TokenKind::This
+                    | TokenKind::Super
+                    | TokenKind::Return
+                    | TokenKind::PlusPlus
+                    | TokenKind::MinusMinus
+                    | TokenKind::RightParen
+                    | TokenKind::RightBracket
+                    | TokenKind::RightBrace
+            )
+            && !self.in_brackets()
+            && !matches!(
+                next.kind,
+                TokenKind::LeftBrace
+                    | TokenKind::RightBrace
+                    | TokenKind::Dot
+                    | TokenKind::Else
+                    | TokenKind::Catch
+            )
+    }
+
+    fn record(&mut self, token: &Token<'de>) {
+        self.last = Some((token.kind, token.offset + token.origin.len()));
+        match token.kind {
+            TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => {
+                self.open.push(token.kind)
+            }
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                self.open.pop();
+            }
+            _ => {}
+        }
+    }
 }
 
 impl<'de> Iterator for Lexer<'de> {
@@ -240,8 +559,22 @@ impl<'de> Iterator for Lexer<'de> {
 
     /// Once the iterator returns `Err`, it will only return `None`.
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(next) = self.peeked.take() {
-            return Some(next);
+        let next = match self.peeked.take() {
+            Some(next) => Some(next),
+            None => self.advance(),
+        };
+        if let Some(Ok(token)) = &next {
+            self.consumed_end = token.offset + token.origin.len();
+        }
+        next
+    }
+}
+
+impl<'de> Lexer<'de> {
+    /// Scans the next token of the source as written.
+    fn scan(&mut self) -> Option<Result<Token<'de>, Error>> {
+        if let Some(tokens) = &mut self.replay {
+            return tokens.next().map(Ok);
         }
 
         loop {
@@ -257,9 +590,14 @@ impl<'de> Iterator for Lexer<'de> {
             enum Started {
                 Slash,
                 String,
+                /// a `}` that closes an interpolation and resumes its string
+                StringContinued,
                 Number,
                 Ident,
                 IfEqualElse(TokenKind, TokenKind),
+                /// an operator that may be immediately followed by one of the
+                /// listed suffixes to form a longer operator
+                Suffixed(&'static [(&'static str, TokenKind)], TokenKind),
             }
 
             let just = move |kind: TokenKind| {
@@ -273,44 +611,68 @@ impl<'de> Iterator for Lexer<'de> {
             let started = match c {
                 '(' => return just(TokenKind::LeftParen),
                 ')' => return just(TokenKind::RightParen),
-                '{' => return just(TokenKind::LeftBrace),
-                '}' => return just(TokenKind::RightBrace),
+                '{' => {
+                    if let Some(depth) = self.interpolations.last_mut() {
+                        *depth += 1;
+                    }
+                    return just(TokenKind::LeftBrace);
+                }
+                '}' => match self.interpolations.last_mut() {
+                    Some(0) => Started::StringContinued,
+                    Some(depth) => {
+                        *depth -= 1;
+                        return just(TokenKind::RightBrace);
+                    }
+                    None => return just(TokenKind::RightBrace),
+                },
+                '[' => return just(TokenKind::LeftBracket),
+                ']' => return just(TokenKind::RightBracket),
                 ',' => return just(TokenKind::Comma),
-                '.' => return just(TokenKind::Dot),
-                '-' => return just(TokenKind::Minus),
-                '+' => return just(TokenKind::Plus),
-                ';' => return just(TokenKind::Semicolon),
-                '*' => return just(TokenKind::Star),
+                '.' => Started::Suffixed(
+                    &[(".=", TokenKind::DotDotEqual), (".", TokenKind::DotDot)],
+                    TokenKind::Dot,
+                ),
+                '-' => Started::Suffixed(
+                    &[("-", TokenKind::MinusMinus), ("=", TokenKind::MinusEqual)],
+                    TokenKind::Minus,
+                ),
+                '+' => Started::Suffixed(
+                    &[("+", TokenKind::PlusPlus), ("=", TokenKind::PlusEqual)],
+                    TokenKind::Plus,
+                ),
+                // the danda and double danda end statements as in Sanskrit
+                // prose, the double one conventionally closing a section
+                ';' | '।' | '॥' => return just(TokenKind::Semicolon),
+                '*' => Started::Suffixed(
+                    &[("*", TokenKind::StarStar), ("=", TokenKind::StarEqual)],
+                    TokenKind::Star,
+                ),
+                '%' => return just(TokenKind::Percent),
+                '?' => return just(TokenKind::Question),
+                ':' => return just(TokenKind::Colon),
                 '/' => Started::Slash,
                 '<' => Started::IfEqualElse(TokenKind::LessEqual, TokenKind::Less),
                 '>' => Started::IfEqualElse(TokenKind::GreaterEqual, TokenKind::Greater),
                 '!' => Started::IfEqualElse(TokenKind::BangEqual, TokenKind::Bang),
                 '=' => Started::IfEqualElse(TokenKind::EqualEqual, TokenKind::Equal),
-                '"' => Started::String,
-                '0'..='9' => Started::Number,
-                c if c == '_' || c.is_alphabetic() || is_devanagari(c) => {
-                    // #region agent log
-                    agent_log_lex(
-                        "pre-fix-unicode",
-                        "LEX-H2",
-                        "src/lex.rs:265",
-                        "start_ident",
-                        &format!("{{\"char\":\"{c}\",\"codepoint\":{}}}", c as u32),
-                    );
-                    // #endregion
-                    Started::Ident
+                '&' | '|' if self.rest.starts_with(c) => {
+                    self.rest = &self.rest[1..];
+                    self.byte += 1;
+                    return Some(Ok(Token {
+                        origin: &c_onwards[..2],
+                        offset: c_at,
+                        kind: if c == '&' {
+                            TokenKind::And
+                        } else {
+                            TokenKind::Or
+                        },
+                    }));
                 }
+                '"' => Started::String,
+                '0'..='9' | '०'..='९' => Started::Number,
+                c if is_ident_start(c) => Started::Ident,
                 c if c.is_whitespace() => continue,
                 c => {
-                    // #region agent log
-                    agent_log_lex(
-                        "pre-fix-unicode",
-                        "LEX-H1",
-                        "src/lex.rs:268",
-                        "unexpected_char",
-                        &format!("{{\"char\":\"{c}\",\"codepoint\":{}}}", c as u32),
-                    );
-                    // #endregion
                     return Some(Err(SingleTokenError {
                         src: self.whole.to_string(),
                         token: c,
@@ -321,15 +683,69 @@ impl<'de> Iterator for Lexer<'de> {
             };
 
             break match started {
-                Started::String => {
-                    if let Some(end) = self.rest.find('"') {
+                Started::String if self.rest.starts_with("\"\"") => {
+                    // `"""`, a raw string, which ends at the next `"""`. Any
+                    // more quotes right before those belong to the string
+                    let Some(close) = self.rest[2..].find(RAW_QUOTES) else {
+                        let err = StringTerminationError {
+                            src: self.whole.to_string(),
+                            err_span: SourceSpan::from(c_at..self.whole.len()),
+                        };
+                        self.byte += self.rest.len();
+                        self.rest = &self.rest[self.rest.len()..];
+                        return Some(Err(err.into()));
+                    };
+                    let after = &self.rest[2 + close..];
+                    let quotes = after.len() - after.trim_start_matches('"').len();
+                    let end = 2 + close + quotes;
+                    let literal = &c_onwards[..1 + end];
+                    self.byte += end;
+                    self.rest = &self.rest[end..];
+                    Some(Ok(Token {
+                        origin: literal,
+                        offset: c_at,
+                        kind: TokenKind::String,
+                    }))
+                }
+                Started::String | Started::StringContinued => {
+                    let continued = matches!(started, Started::StringContinued);
+                    let quote = self.rest.find('"');
+                    let open = self
+                        .rest
+                        .find("${")
+                        .filter(|&open| quote.is_none_or(|q| open < q));
+
+                    if let Some(open) = open {
+                        let literal = &c_onwards[..c.len_utf8() + open + 2];
+                        self.byte += open + 2;
+                        self.rest = &self.rest[open + 2..];
+                        if !continued {
+                            self.interpolations.push(0);
+                        }
+                        Some(Ok(Token {
+                            origin: literal,
+                            offset: c_at,
+                            kind: if continued {
+                                TokenKind::StringMiddle
+                            } else {
+                                TokenKind::StringHead
+                            },
+                        }))
+                    } else if let Some(end) = quote {
                         let literal = &c_onwards[..end + 1 + 1];
                         self.byte += end + 1;
                         self.rest = &self.rest[end + 1..];
+                        if continued {
+                            self.interpolations.pop();
+                        }
                         Some(Ok(Token {
                             origin: literal,
                             offset: c_at,
-                            kind: TokenKind::String,
+                            kind: if continued {
+                                TokenKind::StringTail
+                            } else {
+                                TokenKind::String
+                            },
                         }))
                     } else {
                         let err = StringTerminationError {
@@ -340,6 +756,7 @@ impl<'de> Iterator for Lexer<'de> {
                         // swallow the remainder of input as being a string
                         self.byte += self.rest.len();
                         self.rest = &self.rest[self.rest.len()..];
+                        self.interpolations.clear();
 
                         return Some(Err(err.into()));
                     }
@@ -347,10 +764,18 @@ impl<'de> Iterator for Lexer<'de> {
                 Started::Slash => {
                     if self.rest.starts_with('/') {
                         // this is a comment!
-                        let line_end = self.rest.find('\n').unwrap_or_else(|| self.rest.len());
+                        let line_end = self.rest.find('\n').unwrap_or(self.rest.len());
                         self.byte += line_end;
                         self.rest = &self.rest[line_end..];
                         continue;
+                    } else if self.rest.starts_with('=') {
+                        self.rest = &self.rest[1..];
+                        self.byte += 1;
+                        Some(Ok(Token {
+                            origin: &c_onwards[..2],
+                            offset: c_at,
+                            kind: TokenKind::SlashEqual,
+                        }))
                     } else {
                         Some(Ok(Token {
                             origin: c_str,
@@ -361,39 +786,36 @@ impl<'de> Iterator for Lexer<'de> {
                 }
                 Started::Ident => {
                     let first_non_ident = c_onwards
-                        .find(|c: char| !(c == '_' || c.is_alphanumeric() || is_devanagari(c)))
-                        .unwrap_or_else(|| c_onwards.len());
+                        .find(|c: char| !is_ident_continue(c))
+                        .unwrap_or(c_onwards.len());
 
                     let literal = &c_onwards[..first_non_ident];
                     let extra_bytes = literal.len() - c.len_utf8();
                     self.byte += extra_bytes;
                     self.rest = &self.rest[extra_bytes..];
 
-                    // #region agent log
-                    agent_log_lex(
-                        "pre-fix-unicode",
-                        "LEX-H2",
-                        "src/lex.rs:326",
-                        "ident_literal",
-                        &format!("{{\"literal\":\"{literal}\"}}"),
-                    );
-                    // #endregion
-
                     let kind = match literal {
                         "and" => TokenKind::And,
+                        "catch" => TokenKind::Catch,
                         "class" => TokenKind::Class,
+                        "const" => TokenKind::Const,
+                        "default" => TokenKind::Default,
                         "else" => TokenKind::Else,
                         "false" => TokenKind::False,
                         "for" => TokenKind::For,
                         "fun" => TokenKind::Fun,
                         "if" => TokenKind::If,
+                        "in" => TokenKind::In,
+                        "match" => TokenKind::Match,
                         "nil" => TokenKind::Nil,
                         "or" => TokenKind::Or,
                         "print" => TokenKind::Print,
                         "return" => TokenKind::Return,
                         "super" => TokenKind::Super,
                         "this" => TokenKind::This,
+                        "throw" => TokenKind::Throw,
                         "true" => TokenKind::True,
+                        "try" => TokenKind::Try,
                         "var" => TokenKind::Var,
                         "while" => TokenKind::While,
                         _ => TokenKind::Ident,
@@ -407,16 +829,20 @@ impl<'de> Iterator for Lexer<'de> {
                 }
                 Started::Number => {
                     let first_non_digit = c_onwards
-                        .find(|c| !matches!(c, '.' | '0'..='9'))
-                        .unwrap_or_else(|| c_onwards.len());
+                        .find(|c| !matches!(c, '.' | '0'..='9' | '०'..='९'))
+                        .unwrap_or(c_onwards.len());
 
                     let mut literal = &c_onwards[..first_non_digit];
+                    // in `1..10` the number ends before the `..`
+                    if let Some(range) = literal.find("..") {
+                        literal = &literal[..range];
+                    }
                     let mut dotted = literal.splitn(3, '.');
                     match (dotted.next(), dotted.next(), dotted.next()) {
                         (Some(one), Some(two), Some(_)) => {
                             literal = &literal[..one.len() + 1 + two.len()];
                         }
-                        (Some(one), Some(two), None) if two.is_empty() => {
+                        (Some(one), Some(""), None) => {
                             literal = &literal[..one.len()];
                         }
                         _ => {
@@ -427,15 +853,22 @@ impl<'de> Iterator for Lexer<'de> {
                     self.byte += extra_bytes;
                     self.rest = &self.rest[extra_bytes..];
 
-                    let n = match literal.parse() {
+                    let n = match normalize_digits(literal).parse::<f64>() {
                         Ok(n) => n,
                         Err(e) => {
                             return Some(Err(miette::miette! {
                                 labels = vec![
-                                    LabeledSpan::at(self.byte - literal.len()..self.byte, "this numeric literal"),
+                                    LabeledSpan::at(
+                                        self.byte - literal.len()..self.byte,
+                                        "this numeric literal",
+                                    ),
                                 ],
-                                "{e}",
-                            }.with_source_code(self.whole.to_string())));
+                                code = "E0004",
+                                help = e.to_string(),
+                                "{}",
+                                crate::message!("E0004"),
+                            }
+                            .with_source_code(self.whole.to_string())));
                         }
                     };
 
@@ -466,7 +899,76 @@ impl<'de> Iterator for Lexer<'de> {
                         }))
                     }
                 }
+                Started::Suffixed(suffixes, otherwise) => {
+                    if let Some(&(suffix, kind)) = suffixes
+                        .iter()
+                        .find(|(suffix, _)| self.rest.starts_with(suffix))
+                    {
+                        let span = &c_onwards[..c.len_utf8() + suffix.len()];
+                        self.rest = &self.rest[suffix.len()..];
+                        self.byte += suffix.len();
+                        Some(Ok(Token {
+                            origin: span,
+                            offset: c_at,
+                            kind,
+                        }))
+                    } else {
+                        Some(Ok(Token {
+                            origin: c_str,
+                            offset: c_at,
+                            kind: otherwise,
+                        }))
+                    }
+                }
             };
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The kind and text of each token the lexer reads from `input`.
+    fn tokens(input: &str) -> Vec<(TokenKind, &str)> {
+        Lexer::new(input)
+            .map(|token| token.expect("the input lexes"))
+            .map(|token| (token.kind, token.origin))
+            .collect()
+    }
+
+    #[test]
+    fn devanagari_names_are_single_identifiers() {
+        for name in [
+            "गणना_संख्या",
+            "संख्या",
+            "क्ष",
+            "ज़रा",
+            "क्\u{200D}ष",
+            "क्\u{200C}ष",
+            "_अस्थायी",
+            "योग२",
+        ] {
+            assert_eq!(tokens(name), [(TokenKind::Ident, name)], "{name:?}");
+        }
+    }
+
+    #[test]
+    fn names_end_where_identifier_characters_do() {
+        assert_eq!(
+            tokens("क+ख"),
+            [
+                (TokenKind::Ident, "क"),
+                (TokenKind::Plus, "+"),
+                (TokenKind::Ident, "ख"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_lone_combining_mark_is_not_an_identifier() {
+        assert!(!is_word("\u{093E}"));
+        assert!(!is_word("\u{094D}क"));
+        assert!(!is_word("१क"));
+        assert!(is_word("क\u{093E}"));
+    }
+}
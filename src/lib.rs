@@ -4,11 +4,81 @@ pub use lex::Lexer;
 pub mod parse;
 pub use parse::Parser;
 
+pub mod unparse;
+
+pub mod optimize;
+
+pub mod lint;
+
 pub mod interpreter;
 pub use interpreter::Interpreter;
 
+pub mod limits;
+
+pub mod gc;
+
+pub mod intern;
+
 pub mod banner;
 pub use banner::display_banner;
 
 pub mod translator;
-pub use translator::translate_file_contents;
\ No newline at end of file
+pub use translator::translate_file_contents;
+
+pub mod diagnostics;
+
+pub mod crash;
+
+pub mod theme;
+
+pub mod logging;
+
+pub mod source;
+
+pub mod serialize;
+
+pub mod builtins;
+
+pub mod strings;
+
+pub mod lists;
+
+pub mod format;
+
+pub mod function;
+
+pub mod batch;
+
+pub mod watch;
+
+pub mod testing;
+
+pub mod golden;
+
+pub mod bench;
+
+pub mod profile;
+
+pub mod report;
+
+pub mod bundle;
+
+pub mod debugger;
+
+pub mod dap;
+
+pub mod lsp;
+
+pub mod highlight;
+
+pub mod manifest;
+
+pub mod pack;
+
+pub mod locale;
+
+pub mod explain;
+
+pub mod token_stream;
+
+mod protocol;
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::interpreter::{runtime_error, RuntimeError};
+
+/// Caps on how much a program may do before it is stopped with a runtime
+/// error, for running code that can't be trusted to finish, such as student
+/// submissions on a grader. `None` means no cap.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Limits {
+    /// How many statements the program may execute, counting every run of a
+    /// loop body.
+    pub max_steps: Option<u64>,
+    /// How long the program may run, measured from its first statement.
+    pub time: Option<Duration>,
+    /// Roughly how many bytes the values in the program's variables may take
+    /// up, counting the text of strings and blobs and the items of lists,
+    /// together with any value being built from them.
+    pub max_memory: Option<usize>,
+}
+
+impl Limits {
+    /// Each limit of `self`, or of `fallback` where `self` has none.
+    pub fn or(self, fallback: Limits) -> Limits {
+        Limits {
+            max_steps: self.max_steps.or(fallback.max_steps),
+            time: self.time.or(fallback.time),
+            max_memory: self.max_memory.or(fallback.max_memory),
+        }
+    }
+}
+
+/// How deeply a program's statements and expressions may nest unless
+/// [`Interpreter::max_depth`](crate::Interpreter::max_depth) says otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// How large a stack the threads that the CLI runs programs on get, so that
+/// [`DEFAULT_MAX_DEPTH`] is reached before the stack runs out even in a debug
+/// build, whose frames are several times larger than an optimized build's.
+pub const STACK_SIZE: usize = 128 * 1024 * 1024;
+
+/// What a program has used of its [`Limits`] so far.
+#[derive(Debug, Default)]
+pub(crate) struct Budget {
+    pub(crate) limits: Limits,
+    steps: u64,
+    started: Option<Instant>,
+    /// Whether a limit has been exceeded, after which the program must stop
+    /// even if it would catch the error.
+    exceeded: bool,
+}
+
+/// How many steps go by between looks at the clock, which is slower to read
+/// than a counter.
+const CLOCK_EVERY: u64 = 1024;
+
+impl Budget {
+    pub(crate) fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub(crate) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    /// Counts a step, failing if that exceeds a limit. `memory` is the current
+    /// size of the program's variables.
+    pub(crate) fn step(&mut self, memory: usize) -> Result<(), RuntimeError> {
+        let result = self.check(memory);
+        self.exceeded |= result.is_err();
+        result
+    }
+
+    /// Checks that a new value of `size` bytes fits next to the `memory`
+    /// that the program's variables hold, before the program can keep it.
+    pub(crate) fn allocate(&mut self, size: usize, memory: usize) -> Result<(), RuntimeError> {
+        if let Some(max) = self
+            .limits
+            .max_memory
+            .filter(|&max| memory.saturating_add(size) > max)
+        {
+            self.exceeded = true;
+            return Err(runtime_error!(
+                "E1015/value",
+                size = bytes(size),
+                max = bytes(max)
+            ));
+        }
+        Ok(())
+    }
+
+    fn check(&mut self, memory: usize) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        if let Some(max) = self.limits.max_steps.filter(|&max| self.steps > max) {
+            return Err(runtime_error!("E1013", max = max));
+        }
+        if let Some(max) = self.limits.max_memory.filter(|&max| memory > max) {
+            return Err(runtime_error!("E1015/variables", max = bytes(max)));
+        }
+        if let Some(time) = self.limits.time {
+            let started = *self.started.get_or_insert_with(Instant::now);
+            if self.steps.is_multiple_of(CLOCK_EVERY) && started.elapsed() > time {
+                return Err(runtime_error!("E1014", time = format!("{time:.2?}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bytes(n: usize) -> String {
+    const MIB: usize = 1024 * 1024;
+    if n >= MIB && n.is_multiple_of(MIB) {
+        format!("{} MiB", n / MIB)
+    } else {
+        format!("{n} byte(s)")
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use miette::{LabeledSpan, Severity};
+
+use crate::parse::{Atom, Op, TokenTree};
+
+/// Looks for code in `program`, which was parsed from `source`, that is
+/// probably a mistake even though it runs: variables that are never read,
+/// values assigned to a variable that are overwritten before they are read,
+/// and statements after a `return` or `throw` that can never run. Returns a
+/// warning per problem, in source order.
+///
+/// Variables live in one environment for the whole program, so a variable
+/// counts as read if its name is read anywhere. A program that calls `eval`
+/// can read any variable, so its variables aren't checked. Names starting
+/// with `_` are never reported as unused.
+pub fn lint(program: &[TokenTree<'_>], source: &str) -> Vec<miette::Report> {
+    let mut lint = Lint {
+        source,
+        reads: HashSet::new(),
+        declarations: Vec::new(),
+        uses_eval: false,
+        warnings: Vec::new(),
+    };
+    lint.sequence(program);
+    for statement in program {
+        lint.node(statement);
+    }
+
+    if !lint.uses_eval {
+        let mut reported = HashSet::new();
+        for (name, span) in std::mem::take(&mut lint.declarations) {
+            if lint.reads.contains(name) || name.starts_with('_') || !reported.insert(name) {
+                continue;
+            }
+            lint.warn(
+                span,
+                "declared here",
+                format!("if this is intentional, name it `_{name}`"),
+                crate::message!("W0001", name = name),
+                "W0001",
+                false,
+            );
+        }
+    } else {
+        lint.warnings.retain(|(_, warning)| !warning.is_dead_store);
+    }
+
+    lint.warnings.sort_by_key(|(start, _)| *start);
+    lint.warnings
+        .into_iter()
+        .map(|(_, warning)| warning.report.with_source_code(source.to_string()))
+        .collect()
+}
+
+struct Warning {
+    report: miette::Report,
+    /// Whether this is an overwritten assignment, which `eval` could read.
+    is_dead_store: bool,
+}
+
+struct Lint<'a, 'de> {
+    source: &'a str,
+    /// Every name read anywhere in the program.
+    reads: HashSet<&'de str>,
+    /// Every `var` declaration, in the order they were found.
+    declarations: Vec<(&'de str, Option<Range<usize>>)>,
+    uses_eval: bool,
+    /// Each warning with where it starts, for sorting.
+    warnings: Vec<(usize, Warning)>,
+}
+
+impl<'de> Lint<'_, 'de> {
+    /// Records the reads and declarations of `node` and checks the statement
+    /// lists inside it.
+    fn node(&mut self, node: &TokenTree<'de>) {
+        match node {
+            TokenTree::Atom(Atom::Ident(name, _)) => {
+                self.reads.insert(name);
+            }
+            TokenTree::Cons(Op::Var | Op::Const, children) => {
+                for declaration in children.chunks(2) {
+                    if let [target, value] = declaration {
+                        for name in declared(target) {
+                            self.declarations.push((name, self.span_of(name)));
+                        }
+                        self.node(value);
+                    }
+                }
+                return;
+            }
+            TokenTree::Cons(Op::Assign, children) => {
+                if let [TokenTree::Atom(Atom::Ident(..)), value] = &children[..] {
+                    self.node(value);
+                    return;
+                }
+            }
+            TokenTree::Assignments { targets, values } => {
+                for target in targets.iter() {
+                    if !matches!(target, TokenTree::Atom(Atom::Ident(..))) {
+                        self.node(target);
+                    }
+                }
+                values.iter().for_each(|value| self.node(value));
+                return;
+            }
+            TokenTree::Call {
+                callee: TokenTree::Atom(Atom::Ident("eval", _)),
+                ..
+            } => self.uses_eval = true,
+            TokenTree::Block { statements, .. } => self.sequence(statements),
+            _ => {}
+        }
+        for child in children(node) {
+            self.node(child);
+        }
+    }
+
+    /// Checks a list of statements that run one after another.
+    fn sequence(&mut self, statements: &[TokenTree<'de>]) {
+        for (i, statement) in statements.iter().enumerate() {
+            if let Some(name) = stored(statement) {
+                self.dead_store(name, &statements[i + 1..]);
+            }
+        }
+
+        let Some(exit) = statements.iter().position(exits) else {
+            return;
+        };
+        let (
+            Some(TokenTree::Statement { offset: exit, .. }),
+            Some(TokenTree::Statement { offset, .. }),
+        ) = (statements.get(exit), statements.get(exit + 1))
+        else {
+            return;
+        };
+        let labels = vec![
+            LabeledSpan::at(self.line_from(*exit), "the code leaves here"),
+            LabeledSpan::at(self.line_from(*offset), "so this never runs"),
+        ];
+        self.push(
+            *offset,
+            false,
+            miette::miette!(
+                severity = Severity::Warning,
+                code = "W0003",
+                labels = labels,
+                help = crate::message!("W0003-help"),
+                "{}",
+                crate::message!("W0003")
+            ),
+        );
+    }
+
+    /// Warns if the value stored in `name` is stored again by one of the
+    /// `rest` of the statements before anything reads it. The search gives up
+    /// at anything that could read it indirectly: a call, or leaving the code
+    /// with `return` or `throw`, after which other code can see the value.
+    fn dead_store(&mut self, name: &'de str, rest: &[TokenTree<'de>]) {
+        for later in rest {
+            if reads(later, name) || calls_or_exits(later) {
+                return;
+            }
+            if stored(later) == Some(name) {
+                let help = match line(later, self.source) {
+                    Some(line) => crate::message!("W0002-help", line = line),
+                    None => crate::message!("W0002-help-no-line"),
+                };
+                self.warn(
+                    self.span_of(name),
+                    "assigned here",
+                    help,
+                    crate::message!("W0002", name = name),
+                    "W0002",
+                    true,
+                );
+                return;
+            }
+        }
+    }
+
+    fn warn(
+        &mut self,
+        span: Option<Range<usize>>,
+        label: &str,
+        help: String,
+        message: String,
+        code: &'static str,
+        is_dead_store: bool,
+    ) {
+        let start = span.as_ref().map_or(0, |span| span.start);
+        let labels: Vec<_> = span
+            .map(|span| LabeledSpan::at(span, label))
+            .into_iter()
+            .collect();
+        self.push(
+            start,
+            is_dead_store,
+            miette::miette!(
+                severity = Severity::Warning,
+                code = code,
+                labels = labels,
+                help = help,
+                "{message}"
+            ),
+        );
+    }
+
+    fn push(&mut self, start: usize, is_dead_store: bool, report: miette::Report) {
+        self.warnings.push((
+            start,
+            Warning {
+                report,
+                is_dead_store,
+            },
+        ));
+    }
+
+    /// The rest of the line of the source from `offset`.
+    fn line_from(&self, offset: usize) -> Range<usize> {
+        let end = self.source[offset..]
+            .find('\n')
+            .map_or(self.source.len(), |end| offset + end);
+        offset..end
+    }
+
+    /// Where `name`, which the lexer sliced out of the source, is in it, or
+    /// `None` for a name that doesn't come from the source.
+    fn span_of(&self, name: &str) -> Option<Range<usize>> {
+        let start = (name.as_ptr() as usize).checked_sub(self.source.as_ptr() as usize)?;
+        (start + name.len() <= self.source.len()).then(|| start..start + name.len())
+    }
+}
+
+/// The variable `statement` assigns a new value to outright, with `var` or
+/// `=`, if any. `var x = nil` declares `x` for later rather than storing a
+/// value in it.
+fn stored<'de>(statement: &TokenTree<'de>) -> Option<&'de str> {
+    let TokenTree::Statement { node, .. } = statement else {
+        return None;
+    };
+    match &**node {
+        TokenTree::Cons(Op::Var | Op::Const, children) => match &children[..] {
+            [_, TokenTree::Atom(Atom::Nil)] => None,
+            [TokenTree::Atom(Atom::Ident(name, _)), _] => Some(name),
+            _ => None,
+        },
+        TokenTree::Cons(Op::Assign, children) => match &children[..] {
+            [TokenTree::Atom(Atom::Ident(name, _)), _] => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The variables a `var` declaration's `target` declares: its name, or the
+/// names in a list like `[x, y]`.
+fn declared<'de>(target: &TokenTree<'de>) -> Vec<&'de str> {
+    match target {
+        TokenTree::Atom(Atom::Ident(name, _)) => vec![name],
+        TokenTree::Cons(Op::List, names) => names.iter().flat_map(declared).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The line of `source` that `statement` starts on.
+fn line(statement: &TokenTree<'_>, source: &str) -> Option<usize> {
+    let TokenTree::Statement { offset, .. } = statement else {
+        return None;
+    };
+    Some(source[..*offset].matches('\n').count() + 1)
+}
+
+/// Whether `node` reads `name` anywhere, including in the value it assigns.
+fn reads(node: &TokenTree<'_>, name: &str) -> bool {
+    match node {
+        TokenTree::Atom(Atom::Ident(ident, _)) => *ident == name,
+        TokenTree::Cons(Op::Var | Op::Const, children) => children
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .any(|value| reads(value, name)),
+        TokenTree::Cons(Op::Assign, children) => match &children[..] {
+            [TokenTree::Atom(Atom::Ident(..)), value] => reads(value, name),
+            _ => children.iter().any(|child| reads(child, name)),
+        },
+        _ => children(node).into_iter().any(|child| reads(child, name)),
+    }
+}
+
+/// Whether running `node` could call into other code or leave the code
+/// around it.
+fn calls_or_exits(node: &TokenTree<'_>) -> bool {
+    matches!(
+        node,
+        TokenTree::Call { .. } | TokenTree::Cons(Op::Return | Op::Throw, _)
+    ) || children(node).into_iter().any(calls_or_exits)
+}
+
+/// Whether `statement` always leaves the code it is in.
+fn exits(statement: &TokenTree<'_>) -> bool {
+    let mut node = statement;
+    while let TokenTree::Statement { node: inner, .. } | TokenTree::Spanned { node: inner, .. } =
+        node
+    {
+        node = inner;
+    }
+    matches!(node, TokenTree::Cons(Op::Return | Op::Throw, _))
+}
+
+/// The nodes directly inside `node`.
+fn children<'a, 'de>(node: &'a TokenTree<'de>) -> Vec<&'a TokenTree<'de>> {
+    match node {
+        TokenTree::Atom(_) => Vec::new(),
+        TokenTree::Cons(_, children) => children.iter().collect(),
+        TokenTree::Fun { body, .. } => vec![body],
+        TokenTree::Call { callee, arguments } => {
+            std::iter::once(&**callee).chain(arguments.iter()).collect()
+        }
+        TokenTree::If { condition, yes, no } => [Some(&**condition), Some(&**yes), no.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect(),
+        TokenTree::Block { statements, value } => {
+            statements.iter().chain(value.as_deref()).collect()
+        }
+        TokenTree::Statement { node, .. } | TokenTree::Spanned { node, .. } => vec![node],
+        TokenTree::Assignments { targets, values } => targets.iter().chain(values.iter()).collect(),
+        TokenTree::Chain { operands, .. } => operands.iter().collect(),
+        TokenTree::Match {
+            subject,
+            arms,
+            default,
+        } => std::iter::once(&**subject)
+            .chain(arms.iter().map(|(_, body)| body))
+            .chain(default.as_deref())
+            .collect(),
+        TokenTree::ForIn { iterable, body, .. } => vec![iterable, body],
+        TokenTree::Try { body, handler, .. } => vec![body, handler],
+    }
+}
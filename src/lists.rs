@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+
+use crate::gc::Gc;
+use crate::interpreter::{runtime_error, NativeFn, RuntimeError, Value, ValueIter};
+use crate::locale;
+
+/// `map(items, f)` returns the list of what `f` returns for each item of a
+/// list, each character of a string, or each number of a range, in order:
+/// `map([1, 2, 3], विनियोग (क) { क * क })` is `[1.0, 4.0, 9.0]`.
+pub(crate) const MAP: NativeFn = NativeFn {
+    name: "map",
+    arity: 2..=2,
+    fun: |interpreter, args| {
+        let f = function_argument("map", &args[1])?;
+        let mapped = items_argument("map", &args[0])?
+            .map(|item| interpreter.call(f.clone(), &[item]))
+            .collect::<Result<_, _>>()?;
+        Ok(Value::List(Gc::new(mapped)))
+    },
+};
+
+/// `filter(items, f)` returns the list of the items for which `f` returns a
+/// truthy value, in order.
+pub(crate) const FILTER: NativeFn = NativeFn {
+    name: "filter",
+    arity: 2..=2,
+    fun: |interpreter, args| {
+        let f = function_argument("filter", &args[1])?;
+        let mut kept = Vec::new();
+        for item in items_argument("filter", &args[0])? {
+            if interpreter
+                .call(f.clone(), std::slice::from_ref(&item))?
+                .is_truthy()
+            {
+                kept.push(item);
+            }
+        }
+        Ok(Value::List(Gc::new(kept)))
+    },
+};
+
+/// `reduce(items, f, initial)` combines the items into one value: it calls
+/// `f` with `initial` and the first item, then with what that returned and
+/// the second item, and so on, and returns the last result. Without
+/// `initial` the first item takes its place, so `reduce(सूची, योग)` adds up
+/// a list; reducing no items without `initial` is an error.
+pub(crate) const REDUCE: NativeFn = NativeFn {
+    name: "reduce",
+    arity: 2..=3,
+    fun: |interpreter, args| {
+        let f = function_argument("reduce", &args[1])?;
+        let mut items = items_argument("reduce", &args[0])?;
+        let Some(mut total) = args.get(2).cloned().or_else(|| items.next()) else {
+            return Err(
+                runtime_error!("E1018/reduce").with_help(crate::message!("E1018/reduce-help"))
+            );
+        };
+        for item in items {
+            total = interpreter.call(f.clone(), &[total, item])?;
+        }
+        Ok(total)
+    },
+};
+
+/// `sort(items)` returns a list of the items in ascending order, which have
+/// to be all numbers or all strings; strings are ordered by code point, as
+/// `<` orders them. `sort(items, compare)` orders them by calling
+/// `compare(a, b)`, which returns a negative number if `a` goes before `b`,
+/// a positive one if it goes after, or 0, so `sort(शब्दाः, collate)` puts
+/// words in dictionary order. Items that compare equal keep their order.
+pub(crate) const SORT: NativeFn = NativeFn {
+    name: "sort",
+    arity: 1..=2,
+    fun: |interpreter, args| {
+        let items: Vec<Value> = items_argument("sort", &args[0])?.collect();
+        let sorted = match args.get(1) {
+            None => merge_sort(items, &mut natural_order)?,
+            Some(compare) => {
+                let compare = function_argument("sort", compare)?;
+                merge_sort(items, &mut |a, b| {
+                    let compared = interpreter.call(compare.clone(), &[a.clone(), b.clone()])?;
+                    ordering(compared)
+                })?
+            }
+        };
+        Ok(Value::List(Gc::new(sorted)))
+    },
+};
+
+/// The list builtins, which every interpreter has.
+pub(crate) const LISTS: [NativeFn; 4] = [MAP, FILTER, REDUCE, SORT];
+
+/// How [`SORT`] orders items without a comparison.
+fn natural_order(a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => Ok(a.total_cmp(b)),
+        (Value::String(a), Value::String(b)) => Ok(a.cmp(b)),
+        (a, b) => Err(runtime_error!(
+            "E1017/sort",
+            left = locale::current().type_name(a.type_name()),
+            right = locale::current().type_name(b.type_name())
+        )
+        .with_help(crate::message!("E1017/sort-help"))),
+    }
+}
+
+/// What a comparison given to [`SORT`] returning `compared` means.
+fn ordering(compared: Value) -> Result<Ordering, RuntimeError> {
+    match compared {
+        Value::Number(n) if n < 0.0 => Ok(Ordering::Less),
+        Value::Number(n) if n > 0.0 => Ok(Ordering::Greater),
+        Value::Number(_) => Ok(Ordering::Equal),
+        other => Err(runtime_error!(
+            "E1017/comparison",
+            kind = locale::current().type_name(other.type_name())
+        )
+        .with_help(crate::message!("E1017/comparison-help"))),
+    }
+}
+
+/// `items` sorted stably by `order`, or the first error `order` gives. The
+/// standard library's sorts may panic when an order isn't consistent, which
+/// one written in the program need not be, so this is a plain merge sort.
+fn merge_sort(
+    mut items: Vec<Value>,
+    order: &mut dyn FnMut(&Value, &Value) -> Result<Ordering, RuntimeError>,
+) -> Result<Vec<Value>, RuntimeError> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, order)?;
+    let right = merge_sort(right, order)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // an item of the right half only goes first if it is smaller, which
+        // keeps equal items in order
+        let right_first = order(b, a)? == Ordering::Less;
+        merged.extend(if right_first {
+            right.next()
+        } else {
+            left.next()
+        });
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn items_argument(name: &str, value: &Value) -> Result<ValueIter, RuntimeError> {
+    match value {
+        Value::List(_) | Value::String(_) | Value::Range(_) => value.iterate(),
+        other => Err(runtime_error!(
+            "E1017/items",
+            name = name,
+            kind = locale::current().type_name(other.type_name())
+        )),
+    }
+}
+
+fn function_argument(name: &str, value: &Value) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Native(_) | Value::Host(_) | Value::Function(_) => Ok(value.clone()),
+        other => Err(runtime_error!(
+            "E1017/function",
+            name = name,
+            kind = locale::current().type_name(other.type_name())
+        )),
+    }
+}
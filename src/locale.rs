@@ -0,0 +1,1070 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::translator::{LanguagePack, SANSKRIT};
+
+/// The language that messages, the banner, and `nil`, `true`, and `false` in
+/// a program's output are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// Sanskrit, in Devanagari
+    Sa,
+}
+
+impl Locale {
+    /// The language pack whose keywords this locale writes, if it has one.
+    pub fn pack(self) -> Option<&'static LanguagePack> {
+        match self {
+            Locale::En => None,
+            Locale::Sa => Some(&SANSKRIT),
+        }
+    }
+
+    /// How the Lox keyword `lox`, such as `nil`, is written in this locale.
+    pub fn keyword(self, lox: &'static str) -> &'static str {
+        self.pack()
+            .and_then(|pack| pack.keywords.iter().find(|keyword| keyword.lox == lox))
+            .map_or(lox, |keyword| keyword.native)
+    }
+
+    /// The name of a value's type, given as [`Value::type_name`] names it.
+    ///
+    /// [`Value::type_name`]: crate::interpreter::Value::type_name
+    pub fn type_name(self, name: &'static str) -> &'static str {
+        if self == Locale::En {
+            return name;
+        }
+        match name {
+            "nil" => self.keyword("nil"),
+            "number" => "संख्या",
+            "boolean" => "तार्किकम्",
+            "string" => "शब्दः",
+            "bytes" => "बाइट्-समूहः",
+            "list" => "सूची",
+            "range" => "परिधिः",
+            "function" => "कार्यम्",
+            other => other,
+        }
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Sets the locale for the rest of the process, as `--lang` does. Only the
+/// first call has an effect.
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// The locale set with [`set_locale`], or English.
+pub fn current() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// One message of the [`CATALOG`], in each language. `{name}` in a message
+/// stands for the argument called `name`, and `{{` and `}}` for a brace.
+#[derive(Debug, Clone, Copy)]
+pub struct Message {
+    /// The stable key the message is looked up by: the code of the
+    /// diagnostic, followed by `/` and a variant when the code has several
+    /// messages, as in `E1017/string`, or a name like `in-block` for the
+    /// context that a syntax error is reported in. The help that goes with a
+    /// message has its key followed by `-help`, as in `E1017/sort-help`, and
+    /// help shared by several codes has a name, like `did-you-mean`.
+    pub key: &'static str,
+    pub en: &'static str,
+    pub sa: &'static str,
+}
+
+/// Every message and help of a diagnostic, and every context a syntax error
+/// is reported in, in each language.
+pub const CATALOG: &[Message] = &[
+    Message {
+        key: "E0001",
+        en: "Unexpected token '{token}'",
+        sa: "अप्रत्याशितं चिह्नम् '{token}'",
+    },
+    Message {
+        key: "E0002",
+        en: "Unterminated string",
+        sa: "असमाप्तः शब्दः",
+    },
+    Message {
+        key: "E0003",
+        en: "Unexpected EOF",
+        sa: "सञ्चिकायाः अप्रत्याशितः अन्तः",
+    },
+    Message {
+        key: "E0004",
+        en: "invalid number literal",
+        sa: "असाधुः संख्या-शब्दः",
+    },
+    Message {
+        key: "E0100",
+        en: "found {count} syntax error(s)",
+        sa: "{count} वाक्यरचना-दोषाः प्राप्ताः",
+    },
+    Message {
+        key: "E0101",
+        en: "Expected an expression",
+        sa: "व्यञ्जकम् अपेक्षितम्",
+    },
+    Message {
+        key: "unexpected-token",
+        en: "Unexpected {token}",
+        sa: "अप्रत्याशितम् {token}",
+    },
+    Message {
+        key: "E0102",
+        en: "Expected the end of the expression",
+        sa: "व्यञ्जकस्य अन्तः अपेक्षितः",
+    },
+    Message {
+        key: "E0103",
+        en: "Expected an infix operator",
+        sa: "मध्यस्थः संक्रियकः अपेक्षितः",
+    },
+    Message {
+        key: "E0103-help-same-line",
+        en: "to start a new statement here, end the one before it with `;`",
+        sa: "अत्र नूतनं वाक्यम् आरब्धुं पूर्ववाक्यं `;` इत्यनेन समापयतु",
+    },
+    Message {
+        key: "E0103-help-same-line-newlines",
+        en: "to start a new statement here, end the one before it with `;` or a line break",
+        sa: "अत्र नूतनं वाक्यम् आरब्धुं पूर्ववाक्यं `;` इत्यनेन पङ्क्ति-विरामेण वा समापयतु",
+    },
+    Message {
+        key: "E0103-help-next-line",
+        en: "if the statement on line {line} is complete, end it with `;`",
+        sa: "यदि {line} पङ्क्तेः वाक्यं सम्पूर्णं तर्हि तत् `;` इत्यनेन समापयतु",
+    },
+    Message {
+        key: "E0103-help-next-line-semicolons",
+        en: "if the statement on line {line} is complete, end it with `;`, or let line \
+             breaks end statements with `--terminators newlines`",
+        sa: "यदि {line} पङ्क्तेः वाक्यं सम्पूर्णं तर्हि तत् `;` इत्यनेन समापयतु, अथवा \
+             `--terminators newlines` इत्यनेन पङ्क्ति-विरामैः वाक्यानि समापयतु",
+    },
+    Message {
+        key: "E0104/identifier",
+        en: "expected a name",
+        sa: "नाम अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-catch",
+        en: "missing catch",
+        sa: "पकड़ अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-colon",
+        en: "missing :",
+        sa: ": अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-comma-or-bracket",
+        en: "missing , or ]",
+        sa: ", ] वा अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-comma-or-paren",
+        en: "missing , or )",
+        sa: ", ) वा अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-equal",
+        en: "missing =",
+        sa: "= अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-interpolation-end",
+        en: "missing }} after interpolated expression",
+        sa: "अन्तर्निविष्टात् व्यञ्जकात् परं }} अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-left-brace",
+        en: "missing {{",
+        sa: "{{ अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-left-paren",
+        en: "missing (",
+        sa: "( अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-right-paren",
+        en: "missing )",
+        sa: ") अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/missing-semicolon",
+        en: "missing ;",
+        sa: "; अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/parameter",
+        en: "expected a parameter name",
+        sa: "प्राचलस्य नाम अपेक्षितम्",
+    },
+    Message {
+        key: "E0104/variable",
+        en: "expected a variable name",
+        sa: "चरस्य नाम अपेक्षितम्",
+    },
+    Message {
+        key: "expected-token",
+        en: "Expected {token}",
+        sa: "अपेक्षितम् {token}",
+    },
+    Message {
+        key: "E0105/block",
+        en: "unterminated block",
+        sa: "असमाप्तः खण्डः",
+    },
+    Message {
+        key: "E0105/match",
+        en: "unterminated match",
+        sa: "असमाप्तं मेल",
+    },
+    Message {
+        key: "E0106",
+        en: "Arm after the default arm of match",
+        sa: "मेल इत्यस्य अन्यथा-शाखायाः परं शाखा",
+    },
+    Message {
+        key: "E0106-help",
+        en: "move the default arm to the end, since it applies only when no other does",
+        sa: "अन्यथा-शाखाम् अन्ते स्थापयतु, यतः सा तदा एव प्रवर्तते यदा अन्या न प्रवर्तते",
+    },
+    Message {
+        key: "E0107",
+        en: "Invalid match arm {arm}",
+        sa: "असाध्वी मेल-शाखा {arm}",
+    },
+    Message {
+        key: "E0107-help",
+        en: "match arms compare against literals, like 1, \"क\", or सत्य; use यदि for other \
+             conditions",
+        sa: "मेल-शाखाः 1, \"क\", सत्य इव मूल्यैः सह तुलयन्ति; अन्याभ्यः स्थितिभ्यः यदि \
+             प्रयुङ्क्ताम्",
+    },
+    Message {
+        key: "E0108/assignment",
+        en: "Invalid assignment target {target}",
+        sa: "असाधु नियोजन-लक्ष्यम् {target}",
+    },
+    Message {
+        key: "E0108/assignment-help",
+        en: "only variables can be assigned to together, as in `क, ख = ख, क`",
+        sa: "केवलं चराः सह नियोक्तुं शक्याः, यथा `क, ख = ख, क`",
+    },
+    Message {
+        key: "E0108/declaration",
+        en: "Invalid declaration target {target}",
+        sa: "असाधु घोषणा-लक्ष्यम् {target}",
+    },
+    Message {
+        key: "E0108/declaration-help",
+        en: "only variable names can be unpacked into, as in `चर [क, ख] = युग्मम्;`",
+        sa: "केवलं चर-नामसु विभजनं शक्यम्, यथा `चर [क, ख] = युग्मम्;`",
+    },
+    Message {
+        key: "E0108/increment",
+        en: "Invalid {op} target",
+        sa: "{op} इत्यस्य असाधु लक्ष्यम्",
+    },
+    Message {
+        key: "E0108/increment-help",
+        en: "only variables can be incremented or decremented, not {target}",
+        sa: "केवलं चराः वर्धयितुं ह्रासयितुं वा शक्याः, न तु {target}",
+    },
+    Message {
+        key: "E0109",
+        en: "Assigning {values} value(s) to {targets} variable(s)",
+        sa: "{values} मूल्यानि {targets} चरेषु नियुज्यन्ते",
+    },
+    Message {
+        key: "E0109-help",
+        en: "give each variable exactly one value",
+        sa: "प्रत्येकस्मै चराय एकम् एव मूल्यं ददातु",
+    },
+    Message {
+        key: "E0110",
+        en: "Invalid for loop variable {variable}",
+        sa: "पुरा-पाशस्य असाधुः चरः {variable}",
+    },
+    Message {
+        key: "E0110-help",
+        en: "a for-in loop needs a variable name, as in `पुरा (फल मध्ये सूची) {{ … }}`",
+        sa: "पुरा-मध्ये-आवृत्त्यै चर-नाम आवश्यकम्, यथा `पुरा (फल मध्ये सूची) {{ … }}`",
+    },
+    Message {
+        key: "E0111",
+        en: "Nested too deeply",
+        sa: "अत्यधिकं निहितम्",
+    },
+    Message {
+        key: "E0111-help",
+        en: "statements and expressions can nest at most {max} deep",
+        sa: "वाक्यानि व्यञ्जकानि च अधिकतमं {max} स्तरान् यावत् निहितानि भवितुम् अर्हन्ति",
+    },
+    Message {
+        key: "E0112/constant",
+        en: "The variable of a for loop can't be a constant",
+        sa: "पुरा-पाशस्य चरः स्थिरः भवितुं न अर्हति",
+    },
+    Message {
+        key: "E0112/constant-help",
+        en: "declare it with `चर`, as in `पुरा (चर क = 0; क < 3; क++) {{ … }}`",
+        sa: "`चर` इत्यनेन घोषयतु, यथा `पुरा (चर क = 0; क < 3; क++) {{ … }}`",
+    },
+    Message {
+        key: "E0112/for-in",
+        en: "A for-in loop declares its variable itself",
+        sa: "पुरा-मध्ये पाशः स्वचरं स्वयं घोषयति",
+    },
+    Message {
+        key: "E0112/for-in-help",
+        en: "leave out `चर`, as in `पुरा (फल मध्ये सूची) {{ … }}`",
+        sa: "`चर` त्यजतु, यथा `पुरा (फल मध्ये सूची) {{ … }}`",
+    },
+    Message {
+        key: "E1001",
+        en: "division by zero",
+        sa: "शून्येन विभाजनम्",
+    },
+    Message {
+        key: "E1002",
+        en: "cannot iterate over a {kind}: only lists, strings, and ranges can be looped over",
+        sa: "{kind} इत्यस्य आवर्तनं न शक्यम्: केवलं सूचीनां, शब्दानां, परिधीनां च आवर्तनं भवति",
+    },
+    Message {
+        key: "E1003",
+        en: "undefined variable '{name}'",
+        sa: "अपरिभाषितः चरः '{name}'",
+    },
+    Message {
+        key: "did-you-mean",
+        en: "did you mean '{name}'?",
+        sa: "किं '{name}' इति अभिप्रेतम्?",
+    },
+    Message {
+        key: "E1004",
+        en: "{name}() takes {expected} argument(s) but was given {got}",
+        sa: "{name}() {expected} तर्कान् स्वीकरोति, किन्तु {got} दत्ताः",
+    },
+    Message {
+        key: "E1005",
+        en: "can only call functions, not {kind}",
+        sa: "केवलं कार्याणाम् आह्वानं भवति, न तु {kind} इत्यस्य",
+    },
+    Message {
+        key: "E1006",
+        en: "{name}() is only available with {flag}",
+        sa: "{name}() केवलं {flag} सह उपलभ्यते",
+    },
+    Message {
+        key: "E1007",
+        en: "uncaught exception: {value}",
+        sa: "अगृहीतः अपवादः: {value}",
+    },
+    Message {
+        key: "E1008",
+        en: "stack overflow: statements and expressions nest more than {depth} deep",
+        sa: "स्तम्भ-अतिप्रवाहः: वाक्यानि व्यञ्जकानि च {depth} स्तरेभ्यः अधिकं निहितानि",
+    },
+    Message {
+        key: "E1009",
+        en: "the ends of a range must be whole numbers, not {start} and {end}",
+        sa: "परिधेः अन्तौ पूर्णसंख्ये भवेताम्, न तु {start} {end} च",
+    },
+    Message {
+        key: "E1010",
+        en: "cannot add {left} and {right}: lists can only be added to lists",
+        sa: "{left} {right} च योजयितुं न शक्यते: सूच्यः केवलं सूचीभिः सह युज्यन्ते",
+    },
+    Message {
+        key: "E1011/add",
+        en: "the operands of + must be two numbers or two strings, not {types}",
+        sa: "+ इत्यस्य कर्मणी द्वे संख्ये द्वौ शब्दौ वा भवेताम्, न तु {types}",
+    },
+    Message {
+        key: "E1011/arithmetic",
+        en: "the operands of {op} must be numbers, not {types}",
+        sa: "{op} इत्यस्य कर्मणी संख्ये भवेताम्, न तु {types}",
+    },
+    Message {
+        key: "E1011/compare",
+        en: "the operands of {op} must both be numbers or both be strings, not {types}",
+        sa: "{op} इत्यस्य कर्मणी उभे संख्ये उभौ शब्दौ वा भवेताम्, न तु {types}",
+    },
+    Message {
+        key: "E1011/negate",
+        en: "the operand of - must be a number, not {types}",
+        sa: "- इत्यस्य कर्म संख्या भवेत्, न तु {types}",
+    },
+    Message {
+        key: "E1011/step",
+        en: "the operand of {op} must be a number, not {types}",
+        sa: "{op} इत्यस्य कर्म संख्या भवेत्, न तु {types}",
+    },
+    Message {
+        key: "E1011/types",
+        en: "{left} and {right}",
+        sa: "{left} {right} च",
+    },
+    Message {
+        key: "E1012",
+        en: "failed to write output: {error}",
+        sa: "निर्गमः लेखितुं न शक्तः: {error}",
+    },
+    Message {
+        key: "E1013",
+        en: "step limit exceeded: the program ran more than {max} step(s)",
+        sa: "पद-सीमा अतिक्रान्ता: कार्यक्रमः {max} पदेभ्यः अधिकं चलितः",
+    },
+    Message {
+        key: "E1014",
+        en: "time limit exceeded: the program ran longer than {time}",
+        sa: "काल-सीमा अतिक्रान्ता: कार्यक्रमः {time} कालात् अधिकं चलितः",
+    },
+    Message {
+        key: "E1015/value",
+        en: "memory limit exceeded: a new value of {size} would take the program past {max}",
+        sa: "स्मृति-सीमा अतिक्रान्ता: {size} परिमाणस्य नूतनं मूल्यं कार्यक्रमं {max} तः परं नयेत्",
+    },
+    Message {
+        key: "E1015/variables",
+        en: "memory limit exceeded: the program's variables hold more than {max}",
+        sa: "स्मृति-सीमा अतिक्रान्ता: कार्यक्रमस्य चराः {max} तः अधिकं धारयन्ति",
+    },
+    Message {
+        key: "E1016",
+        en: "assertion failed",
+        sa: "प्रतिज्ञा विफला",
+    },
+    Message {
+        key: "E1016/message",
+        en: "assertion failed: {message}",
+        sa: "प्रतिज्ञा विफला: {message}",
+    },
+    Message {
+        key: "E1017/bytes",
+        en: "deserialize() expects bytes, not {kind}",
+        sa: "deserialize() बाइट्-समूहम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/comparison",
+        en: "sort() expects the comparison to return a number, not {kind}",
+        sa: "sort() तुलनायाः संख्याम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/comparison-help",
+        en: "return a negative number, 0, or a positive number, as collate() does",
+        sa: "collate() इव ऋणसंख्यां, 0, धनसंख्यां वा प्रत्यर्पयतु",
+    },
+    Message {
+        key: "E1017/format",
+        en: "कथयf() expects a format string, not {kind}",
+        sa: "कथयf() प्रारूप-शब्दम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/function",
+        en: "{name}() expects a function, not {kind}",
+        sa: "{name}() कार्यम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/index",
+        en: "{name}() expects a number for an index, not {kind}",
+        sa: "{name}() अनुक्रमाय संख्याम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/items",
+        en: "{name}() expects a list, a string, or a range, not {kind}",
+        sa: "{name}() सूचीं, शब्दं, परिधिं वा अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/number",
+        en: "संख्या() converts a string to a number, not {kind}",
+        sa: "संख्या() शब्दं संख्यायां परिवर्तयति, न तु {kind}",
+    },
+    Message {
+        key: "E1017/numbers",
+        en: "{name}() expects numbers, not {kind}",
+        sa: "{name}() संख्याः अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/path",
+        en: "{name}() expects a path string, not {kind}",
+        sa: "{name}() मार्ग-शब्दम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/repeat",
+        en: "repeat() expects a string and a number, not {string} and {count}",
+        sa: "repeat() शब्दं संख्यां च अपेक्षते, न तु {string} {count} च",
+    },
+    Message {
+        key: "E1017/sort",
+        en: "sort() can only order numbers among numbers and strings among strings, \
+             not a {left} and a {right}",
+        sa: "sort() संख्याः संख्याभिः सह शब्दान् शब्दैः सह एव क्रमयति, न तु {left} {right} च",
+    },
+    Message {
+        key: "E1017/sort-help",
+        en: "give sort() a comparison function to order other values",
+        sa: "अन्यानि मूल्यानि क्रमयितुं sort() इत्यस्मै तुलना-कार्यं ददातु",
+    },
+    Message {
+        key: "E1017/string",
+        en: "{name}() expects a string, not {kind}",
+        sa: "{name}() शब्दम् अपेक्षते, न तु {kind}",
+    },
+    Message {
+        key: "E1017/truncate",
+        en: "पूर्णांक() truncates a number, not {kind}",
+        sa: "पूर्णांक() संख्यां छिनत्ति, न तु {kind}",
+    },
+    Message {
+        key: "E1017/write",
+        en: "लिख() writes a string, not {kind}",
+        sa: "लिख() शब्दं लिखति, न तु {kind}",
+    },
+    Message {
+        key: "E1018/count",
+        en: "repeat() needs a whole, non-negative count, not {count}",
+        sa: "repeat() अऋणात्मकां पूर्णसंख्याम् अपेक्षते, न तु {count}",
+    },
+    Message {
+        key: "E1018/format",
+        en: "कथयf(): {message}",
+        sa: "कथयf(): {message}",
+    },
+    Message {
+        key: "E1018/format-brace",
+        en: "a `}}` outside a placeholder must be written `}}}}`",
+        sa: "स्थानधारकात् बहिः `}}` `}}}}` इति लेखनीयम्",
+    },
+    Message {
+        key: "E1018/format-count",
+        en: "the format has {placeholders} placeholder(s) but was given {values} value(s)",
+        sa: "प्रारूपे {placeholders} स्थानधारकाः सन्ति, किन्तु {values} मूल्यानि दत्तानि",
+    },
+    Message {
+        key: "E1018/format-count-help",
+        en: "give each `{{}}` exactly one value, and write a literal brace as `{{{{` or `}}}}`",
+        sa: "प्रत्येकस्मै `{{}}` एकम् एव मूल्यं ददातु, कोष्ठकं तु `{{{{` `}}}}` वा इति लिखतु",
+    },
+    Message {
+        key: "E1018/format-limit",
+        en: "a width or precision of {largest} exceeds the limit of {limit}",
+        sa: "{largest} इति विस्तारः परिशुद्धता वा {limit} सीमाम् अतिक्रामति",
+    },
+    Message {
+        key: "E1018/format-placeholder",
+        en: "placeholders are written `{{}}` or `{{:spec}}`, not `{{{text}}}`",
+        sa: "स्थानधारकाः `{{}}` `{{:spec}}` वा इति लिख्यन्ते, न तु `{{{text}}}`",
+    },
+    Message {
+        key: "E1018/format-precision",
+        en: "a `.` in a placeholder must be followed by a precision",
+        sa: "स्थानधारके `.` इत्यस्य अनन्तरं परिशुद्धता भवेत्",
+    },
+    Message {
+        key: "E1018/format-spec",
+        en: "`{rest}` in the placeholder `{{:{text}}}` is not a fill, alignment, width, \
+             or precision",
+        sa: "`{{:{text}}}` स्थानधारके `{rest}` न पूरकं, न संरेखणं, न विस्तारः, न परिशुद्धता",
+    },
+    Message {
+        key: "E1018/format-unclosed",
+        en: "a placeholder is missing its `}}`",
+        sa: "स्थानधारकस्य `}}` नास्ति",
+    },
+    Message {
+        key: "E1018/index",
+        en: "{name}() index {index} is not a whole number from 0 to the string's length, {length}",
+        sa: "{name}() इत्यस्य अनुक्रमः {index} 0 तः शब्दस्य दैर्घ्यं {length} यावत् \
+             पूर्णसंख्या नास्ति",
+    },
+    Message {
+        key: "E1018/randint",
+        en: "randint() needs a lower bound no greater than the upper one, not {low} and {high}",
+        sa: "randint() इत्यस्य अधःसीमा ऊर्ध्वसीमायाः अधिका न भवेत्, न तु {low} {high} च",
+    },
+    Message {
+        key: "E1018/reduce",
+        en: "reduce() of nothing needs an initial value",
+        sa: "रिक्तस्य reduce() आरम्भमूल्यम् अपेक्षते",
+    },
+    Message {
+        key: "E1018/reduce-help",
+        en: "pass the value to start from as the third argument",
+        sa: "आरम्भ-मूल्यं तृतीय-तर्करूपेण ददातु",
+    },
+    Message {
+        key: "E1018/repeat",
+        en: "repeating a string of {size} byte(s) {count} times exceeds the limit of {limit} bytes",
+        sa: "{size} बाइट्-परिमाणस्य शब्दस्य {count} वारं पुनरावृत्तिः {limit} \
+             बाइट्-सीमाम् अतिक्रामति",
+    },
+    Message {
+        key: "E1018/replace",
+        en: "replace() can't replace an empty string",
+        sa: "replace() रिक्तं शब्दं प्रतिस्थापयितुं न शक्नोति",
+    },
+    Message {
+        key: "E1018/substring",
+        en: "substring() needs a start no greater than the end, not {start} and {end}",
+        sa: "substring() इत्यस्य आरम्भः अन्तात् अधिकः न भवेत्, न तु {start} {end} च",
+    },
+    Message {
+        key: "E1018/whole",
+        en: "{name}() needs whole numbers, not {n}",
+        sa: "{name}() पूर्णसंख्याः अपेक्षते, न तु {n}",
+    },
+    Message {
+        key: "E1019/read",
+        en: "पठ() couldn't read '{path}': {error}",
+        sa: "पठ() '{path}' पठितुं न शक्तम्: {error}",
+    },
+    Message {
+        key: "E1019/write",
+        en: "लिख() couldn't write '{path}': {error}",
+        sa: "लिख() '{path}' लेखितुं न शक्तम्: {error}",
+    },
+    Message {
+        key: "E1020/deserialize",
+        en: "deserialize(): {error}",
+        sa: "deserialize(): {error}",
+    },
+    Message {
+        key: "E1020/serialize",
+        en: "serialize(): {error}",
+        sa: "serialize(): {error}",
+    },
+    Message {
+        key: "E1021",
+        en: "assignment to undeclared variable '{name}'",
+        sa: "अघोषिते चरे '{name}' नियोजनम्",
+    },
+    Message {
+        key: "E1021-help",
+        en: "declare the variable before assigning to it",
+        sa: "नियोजनात् पूर्वं चरं घोषयतु",
+    },
+    Message {
+        key: "E1022",
+        en: "cannot assign to constant '{name}'",
+        sa: "स्थिरे '{name}' नियोजनं न शक्यम्",
+    },
+    Message {
+        key: "E1022-help",
+        en: "declare it with `चर` instead of `स्थिर` to let it change",
+        sa: "परिवर्तनाय `स्थिर` इत्यस्य स्थाने `चर` इत्यनेन घोषयतु",
+    },
+    Message {
+        key: "E1023",
+        en: "cannot unpack a {kind} into variables: only lists can be unpacked",
+        sa: "{kind} इत्यस्य चरेषु विभजनं न शक्यम्: केवलं सूच्यः विभज्यन्ते",
+    },
+    Message {
+        key: "E1024",
+        en: "cannot unpack {got} item(s) into {expected} variable(s)",
+        sa: "{got} वस्तूनि {expected} चरेषु विभक्तुं न शक्यन्ते",
+    },
+    Message {
+        key: "E1024-help",
+        en: "give the list exactly one item for each variable",
+        sa: "प्रत्येकस्मै चराय सूच्याम् एकम् एव वस्तु ददातु",
+    },
+    Message {
+        key: "E1025",
+        en: "failed to read input: {error}",
+        sa: "आगमः पठितुं न शक्तः: {error}",
+    },
+    Message {
+        key: "E1026",
+        en: "cannot return from outside a function",
+        sa: "कार्यात् बहिः देयम् न शक्यम्",
+    },
+    Message {
+        key: "E1026-help",
+        en: "`देयम` can only be used in the body of a `विनियोग`",
+        sa: "`देयम` केवलं `विनियोग` इत्यस्य शरीरे प्रयोक्तुं शक्यते",
+    },
+    Message {
+        key: "E1027",
+        en: "eval() couldn't parse its code: {error}",
+        sa: "eval() सङ्केतं विश्लेषितुं न शक्तम्: {error}",
+    },
+    Message {
+        key: "W0001",
+        en: "variable `{name}` is never read",
+        sa: "चरः `{name}` कदापि न पठ्यते",
+    },
+    Message {
+        key: "W0002",
+        en: "value assigned to `{name}` is never read",
+        sa: "`{name}` इत्यस्मिन् नियुक्तं मूल्यं कदापि न पठ्यते",
+    },
+    Message {
+        key: "W0002-help",
+        en: "it is overwritten on line {line} before anything reads it",
+        sa: "केनापि पठनात् पूर्वं {line} पङ्क्तौ तत् पुनः लिख्यते",
+    },
+    Message {
+        key: "W0002-help-no-line",
+        en: "it is overwritten before anything reads it",
+        sa: "केनापि पठनात् पूर्वं तत् पुनः लिख्यते",
+    },
+    Message {
+        key: "W0003",
+        en: "unreachable code",
+        sa: "अप्राप्यः सङ्केतः",
+    },
+    Message {
+        key: "W0003-help",
+        en: "remove the unreachable code, or move it before the exit",
+        sa: "अप्राप्यं सङ्केतं निष्कासयतु, अथवा निर्गमात् पूर्वं स्थापयतु",
+    },
+    Message {
+        key: "in-block",
+        en: "in block",
+        sa: "खण्डे",
+    },
+    Message {
+        key: "in-block-expression",
+        en: "in block expression",
+        sa: "खण्ड-व्यञ्जके",
+    },
+    Message {
+        key: "in-if-condition",
+        en: "in if condition",
+        sa: "यदि इत्यस्य प्रतिबन्धे",
+    },
+    Message {
+        key: "in-if-body",
+        en: "in body of if",
+        sa: "यदि इत्यस्य शरीरे",
+    },
+    Message {
+        key: "in-else-body",
+        en: "in body of else",
+        sa: "अथ्वा इत्यस्य शरीरे",
+    },
+    Message {
+        key: "in-if-expression",
+        en: "in if expression",
+        sa: "यदि-व्यञ्जके",
+    },
+    Message {
+        key: "in-match-value",
+        en: "in match value",
+        sa: "मेल इत्यस्य मूल्ये",
+    },
+    Message {
+        key: "in-match-arms",
+        en: "in match arms",
+        sa: "मेल इत्यस्य शाखासु",
+    },
+    Message {
+        key: "in-match-arm",
+        en: "in match arm",
+        sa: "मेल इत्यस्य शाखायाम्",
+    },
+    Message {
+        key: "in-match-arm-number",
+        en: "in arm #{number} of match",
+        sa: "मेल इत्यस्य {number}-तम्यां शाखायाम्",
+    },
+    Message {
+        key: "in-match-default",
+        en: "in default arm of match",
+        sa: "मेल इत्यस्य अन्यथा-शाखायाम्",
+    },
+    Message {
+        key: "in-match-expression",
+        en: "in match expression",
+        sa: "मेल-व्यञ्जके",
+    },
+    Message {
+        key: "in-try-body",
+        en: "in body of try",
+        sa: "प्रयत्न इत्यस्य शरीरे",
+    },
+    Message {
+        key: "after-try-body",
+        en: "after body of try",
+        sa: "प्रयत्न इत्यस्य शरीरात् परम्",
+    },
+    Message {
+        key: "in-catch-variable",
+        en: "in catch variable",
+        sa: "पकड़ इत्यस्य चरे",
+    },
+    Message {
+        key: "in-catch-body",
+        en: "in body of catch",
+        sa: "पकड़ इत्यस्य शरीरे",
+    },
+    Message {
+        key: "in-throw-value",
+        en: "in value of throw",
+        sa: "फेंक इत्यस्य मूल्ये",
+    },
+    Message {
+        key: "in-while-condition",
+        en: "in while loop condition",
+        sa: "यावद इत्यस्य प्रतिबन्धे",
+    },
+    Message {
+        key: "in-while-body",
+        en: "in body of while loop",
+        sa: "यावद इत्यस्य शरीरे",
+    },
+    Message {
+        key: "in-for-header",
+        en: "in for loop header",
+        sa: "पुरा-पाशस्य शीर्षे",
+    },
+    Message {
+        key: "in-for-init",
+        en: "in init of for loop",
+        sa: "पुरा-पाशस्य आरम्भे",
+    },
+    Message {
+        key: "in-for-condition",
+        en: "in condition of for loop",
+        sa: "पुरा-पाशस्य प्रतिबन्धे",
+    },
+    Message {
+        key: "in-for-increment",
+        en: "in increment of for loop",
+        sa: "पुरा-पाशस्य वर्धने",
+    },
+    Message {
+        key: "in-for-body",
+        en: "in body of for loop",
+        sa: "पुरा-पाशस्य शरीरे",
+    },
+    Message {
+        key: "in-for-in",
+        en: "in for-in loop",
+        sa: "पुरा-मध्ये पाशे",
+    },
+    Message {
+        key: "in-for-in-collection",
+        en: "in the collection of for-in loop",
+        sa: "पुरा-मध्ये पाशस्य समूहे",
+    },
+    Message {
+        key: "in-for-in-body",
+        en: "in body of for-in loop",
+        sa: "पुरा-मध्ये पाशस्य शरीरे",
+    },
+    Message {
+        key: "in-declaration",
+        en: "in variable declaration",
+        sa: "चरस्य घोषणायाम्",
+    },
+    Message {
+        key: "in-declaration-value",
+        en: "in the value of variable declaration",
+        sa: "चरस्य घोषणायाः मूल्ये",
+    },
+    Message {
+        key: "in-assignment-target",
+        en: "in target #{number} of assignment",
+        sa: "नियोजनस्य {number}-तमे लक्ष्ये",
+    },
+    Message {
+        key: "in-assignment-value",
+        en: "in value #{number} of assignment",
+        sa: "नियोजनस्य {number}-तमे मूल्ये",
+    },
+    Message {
+        key: "in-multiple-assignment",
+        en: "in multiple assignment",
+        sa: "बहु-नियोजने",
+    },
+    Message {
+        key: "in-class-name",
+        en: "in class name",
+        sa: "श्रेणी इत्यस्य नाम्नि",
+    },
+    Message {
+        key: "in-class-body",
+        en: "in class definition",
+        sa: "श्रेणी इत्यस्य परिभाषायाम्",
+    },
+    Message {
+        key: "in-function-name",
+        en: "in function name declaration",
+        sa: "कार्यस्य नामघोषणायाम्",
+    },
+    Message {
+        key: "function-named",
+        en: "function {name}",
+        sa: "कार्यम् {name}",
+    },
+    Message {
+        key: "function-expression",
+        en: "function expression",
+        sa: "कार्य-व्यञ्जकम्",
+    },
+    Message {
+        key: "in-parameters",
+        en: "in parameter list of {function}",
+        sa: "{function} इत्यस्य प्राचलसूच्याम्",
+    },
+    Message {
+        key: "in-parameter",
+        en: "in parameter #{number} of {function}",
+        sa: "{function} इत्यस्य {number}-तमे प्राचले",
+    },
+    Message {
+        key: "in-function-body",
+        en: "in body of {function}",
+        sa: "{function} इत्यस्य शरीरे",
+    },
+    Message {
+        key: "in-arguments",
+        en: "in argument list of function call",
+        sa: "कार्याह्वानस्य तर्कसूच्याम्",
+    },
+    Message {
+        key: "in-argument",
+        en: "in argument #{number} of function call",
+        sa: "कार्याह्वानस्य {number}-तमे तर्के",
+    },
+    Message {
+        key: "in-list",
+        en: "in list",
+        sa: "सूच्याम्",
+    },
+    Message {
+        key: "in-list-item",
+        en: "in item #{number}",
+        sa: "{number}-तमे वस्तुनि",
+    },
+    Message {
+        key: "in-interpolated-string",
+        en: "in interpolated string",
+        sa: "अन्तर्निविष्ट-शब्दे",
+    },
+    Message {
+        key: "in-interpolated-expression",
+        en: "in interpolated expression",
+        sa: "अन्तर्निविष्टे व्यञ्जके",
+    },
+    Message {
+        key: "in-group",
+        en: "in bracketed expression",
+        sa: "कोष्ठक-व्यञ्जके",
+    },
+    Message {
+        key: "after-group",
+        en: "after bracketed expression",
+        sa: "कोष्ठक-व्यञ्जकात् परम्",
+    },
+    Message {
+        key: "in-prefix-operand",
+        en: "in operand of prefix {op}",
+        sa: "पूर्वस्थस्य {op} इत्यस्य कर्मणि",
+    },
+    Message {
+        key: "on-left-hand-side",
+        en: "on left-hand side",
+        sa: "वामपक्षे",
+    },
+    Message {
+        key: "on-right-hand-side",
+        en: "on the right-hand side",
+        sa: "दक्षिणपक्षे",
+    },
+    Message {
+        key: "on-right-hand-side-of",
+        en: "on the right-hand side of {op}",
+        sa: "{op} इत्यस्य दक्षिणपक्षे",
+    },
+    Message {
+        key: "instead-of-operator",
+        en: "in place of expected operator",
+        sa: "अपेक्षितस्य संक्रियकस्य स्थाने",
+    },
+    Message {
+        key: "in-true-branch",
+        en: "in the true branch of {condition} ?",
+        sa: "{condition} ? इत्यस्य सत्य-शाखायाम्",
+    },
+    Message {
+        key: "in-conditional",
+        en: "in conditional expression {condition} ? {yes}",
+        sa: "सप्रतिबन्ध-व्यञ्जके {condition} ? {yes}",
+    },
+    Message {
+        key: "in-false-branch",
+        en: "in the false branch of {condition} ? {yes} :",
+        sa: "{condition} ? {yes} : इत्यस्य असत्य-शाखायाम्",
+    },
+];
+
+/// The message with `key` in the current locale, with each `{name}` in it
+/// replaced by the argument called `name`. See [`message!`](crate::message!).
+pub fn message(key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let message = CATALOG
+        .iter()
+        .find(|message| message.key == key)
+        .unwrap_or_else(|| panic!("message {key} is not in the catalog"));
+    let template = match current() {
+        Locale::En => message.en,
+        Locale::Sa => message.sa,
+    };
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(at) = rest.find(['{', '}']) {
+        out.push_str(&rest[..at]);
+        let brace = &rest[at..at + 1];
+        let after = &rest[at + 1..];
+        if let Some(after) = after.strip_prefix(brace) {
+            out.push_str(brace);
+            rest = after;
+            continue;
+        }
+        assert_eq!(brace, "{", "a `}}` in message {key} is not doubled");
+        let close = after
+            .find('}')
+            .expect("placeholders in the catalog are closed");
+        let name = &after[..close];
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => out.push_str(&value.to_string()),
+            None => panic!("message {key} needs an argument called {name}"),
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The code of the diagnostic whose message has `key`: `E1017` for
+/// `E1017/string`.
+pub fn code(key: &'static str) -> &'static str {
+    key.split_once('/').map_or(key, |(code, _)| code)
+}
+
+/// The message with a key from the [`CATALOG`] in the current locale, with
+/// its arguments given by name: `message!("E1004", name = "length", expected
+/// = 1, got = 2)`.
+#[macro_export]
+macro_rules! message {
+    ($key:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::locale::message(
+            $key,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
@@ -0,0 +1,91 @@
+use crate::theme::{paint, Stream, Style};
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much the CLI reports about what it is doing, besides the program's own
+/// output and diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// `-q`: no banner and no notes
+    Quiet,
+    /// the default: banner and short notes
+    Normal,
+    /// `-v`: pipeline stages and their timings
+    Verbose,
+    /// `-vv`: also dumps intermediate artifacts such as the translated source
+    Debug,
+}
+
+impl Level {
+    /// Maps the `-q` flag and the number of `-v` flags to a level.
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Level::Quiet,
+            (false, 0) => Level::Normal,
+            (false, 1) => Level::Verbose,
+            (false, _) => Level::Debug,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> Level {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => Level::Quiet,
+        1 => Level::Normal,
+        2 => Level::Verbose,
+        _ => Level::Debug,
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    self::level() >= level
+}
+
+/// Writes a log line to stderr if `level` is enabled. Use the [`note!`],
+/// [`verbose!`], and [`debug!`] macros rather than calling this directly.
+///
+/// [`note!`]: crate::note
+/// [`verbose!`]: crate::verbose
+/// [`debug!`]: crate::debug
+pub fn log(level: Level, args: fmt::Arguments<'_>) {
+    if !enabled(level) {
+        return;
+    }
+
+    let tag = match level {
+        Level::Quiet | Level::Normal => "note",
+        Level::Verbose => "verbose",
+        Level::Debug => "debug",
+    };
+    eprintln!("{}: {args}", paint(Stream::Stderr, Style::Note, tag));
+}
+
+/// Logs a short, non-essential note. Hidden by `-q`.
+#[macro_export]
+macro_rules! note {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Normal, format_args!($($arg)*))
+    };
+}
+
+/// Logs a pipeline stage or decision. Shown with `-v`.
+#[macro_export]
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Verbose, format_args!($($arg)*))
+    };
+}
+
+/// Logs an intermediate artifact. Shown with `-vv`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
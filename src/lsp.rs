@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+
+use miette::{Diagnostic, IntoDiagnostic};
+use serde_json::{json, Value as Json};
+
+use crate::lex::{Token, TokenKind};
+use crate::manifest::Manifest;
+use crate::parse::Arena;
+use crate::protocol::{read_message, write_message};
+use crate::translator::{Keyword, LanguagePack, SANSKRIT};
+use crate::{Lexer, Parser};
+
+/// JSON-RPC's error code for a method the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Serves the Language Server Protocol over stdin and stdout until the client
+/// exits.
+pub fn serve() -> miette::Result<()> {
+    let mut server = Server {
+        documents: HashMap::new(),
+        input: BufReader::new(io::stdin()),
+        output: io::stdout(),
+        shut_down: false,
+    };
+    server.run().into_diagnostic()
+}
+
+struct Server {
+    /// The text of every open document, by URI.
+    documents: HashMap<String, String>,
+    input: BufReader<io::Stdin>,
+    output: io::Stdout,
+    shut_down: bool,
+}
+
+impl Server {
+    fn run(&mut self) -> io::Result<()> {
+        while let Some(message) = read_message(&mut self.input)? {
+            let method = message["method"].as_str().unwrap_or_default();
+            let params = &message["params"];
+            let id = &message["id"];
+
+            if !id.is_null() {
+                let result = match method {
+                    "initialize" => Some(json!({
+                        "capabilities": {
+                            "textDocumentSync": {
+                                "openClose": true,
+                                "change": 1,
+                                "save": { "includeText": true },
+                            },
+                            "hoverProvider": true,
+                            "definitionProvider": true,
+                            "completionProvider": {},
+                        },
+                        "serverInfo": {
+                            "name": env!("CARGO_PKG_NAME"),
+                            "version": env!("CARGO_PKG_VERSION"),
+                        },
+                    })),
+                    "shutdown" => {
+                        self.shut_down = true;
+                        Some(Json::Null)
+                    }
+                    "textDocument/hover" => Some(self.hover(params)),
+                    "textDocument/definition" => Some(self.definition(params)),
+                    "textDocument/completion" => Some(self.completion(params)),
+                    _ => None,
+                };
+                let response = match result {
+                    Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    None => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": METHOD_NOT_FOUND,
+                            "message": format!("{method} is not supported"),
+                        },
+                    }),
+                };
+                write_message(&mut self.output, &response)?;
+                continue;
+            }
+
+            let uri = params["textDocument"]["uri"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            match method {
+                "exit" => std::process::exit(if self.shut_down { 0 } else { 1 }),
+                "textDocument/didOpen" => {
+                    let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                    self.documents.insert(uri.clone(), text.to_string());
+                    self.publish_diagnostics(&uri)?;
+                }
+                "textDocument/didChange" => {
+                    // full sync: the last change holds the whole new text
+                    let changes = params["contentChanges"].as_array();
+                    if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                        self.documents.insert(uri, text.to_string());
+                    }
+                }
+                "textDocument/didSave" => {
+                    if let Some(text) = params["text"].as_str() {
+                        self.documents.insert(uri.clone(), text.to_string());
+                    }
+                    self.publish_diagnostics(&uri)?;
+                }
+                "textDocument/didClose" => {
+                    self.documents.remove(&uri);
+                    self.notify(
+                        "textDocument/publishDiagnostics",
+                        json!({ "uri": uri, "diagnostics": [] }),
+                    )?;
+                }
+                // other notifications, such as `initialized`, need no reply
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn notify(&mut self, method: &str, params: Json) -> io::Result<()> {
+        write_message(
+            &mut self.output,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+
+    /// Reports the syntax errors in the document at `uri`.
+    fn publish_diagnostics(&mut self, uri: &str) -> io::Result<()> {
+        let Some(text) = self.documents.get(uri) else {
+            return Ok(());
+        };
+        // the manifest next to the file decides the keywords and what ends a
+        // statement
+        let manifest = path_of(uri)
+            .and_then(|path| Manifest::for_program(&path).ok())
+            .unwrap_or_default();
+        let diagnostics = syntax_errors(text, &manifest);
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    /// The document and byte offset that `params` point at.
+    fn locate(&self, params: &Json) -> Option<(&str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+        let position = &params["position"];
+        let offset = offset_at(
+            text,
+            position["line"].as_u64()? as usize,
+            position["character"].as_u64()? as usize,
+        );
+        Some((text, offset))
+    }
+
+    /// Shows the Lox equivalent of the keyword under the cursor.
+    fn hover(&self, params: &Json) -> Json {
+        let Some((text, offset)) = self.locate(params) else {
+            return Json::Null;
+        };
+        let Some(token) = token_at(text, offset) else {
+            return Json::Null;
+        };
+        let Some(keyword) = keyword(token.origin) else {
+            return Json::Null;
+        };
+
+        let synonym = if token.origin == keyword.native {
+            String::new()
+        } else {
+            format!("**{}** is another spelling of ", token.origin)
+        };
+        let mut value = format!(
+            "{synonym}**{}** ({}), which is Lox `{}`",
+            keyword.native, keyword.transliteration, keyword.lox
+        );
+        if !keyword.example.is_empty() {
+            value.push_str(&format!("\n\n```\n{}\n```", keyword.example));
+        }
+        json!({
+            "contents": {
+                "kind": "markdown",
+                "value": value,
+            },
+            "range": range(text, token.offset, token.offset + token.origin.len()),
+        })
+    }
+
+    /// Finds where the variable, function, or class under the cursor is
+    /// first declared. Programs have one flat scope, so that is the only
+    /// declaration that can be meant.
+    fn definition(&self, params: &Json) -> Json {
+        let Some((text, offset)) = self.locate(params) else {
+            return Json::Null;
+        };
+        let Some(name) = token_at(text, offset).filter(|t| t.kind == TokenKind::Ident) else {
+            return Json::Null;
+        };
+
+        let tokens = tokens(text);
+        let declaration = tokens.windows(2).find_map(|pair| {
+            let declares = match pair[0].kind {
+                TokenKind::Var | TokenKind::Const | TokenKind::Fun | TokenKind::Class => true,
+                TokenKind::Ident => keyword(pair[0].origin)
+                    .is_some_and(|k| matches!(k.lox, "var" | "const" | "fun" | "class")),
+                _ => false,
+            };
+            (declares && pair[1].origin == name.origin).then_some(pair[1])
+        });
+
+        match declaration {
+            Some(declaration) => json!({
+                "uri": params["textDocument"]["uri"],
+                "range": range(
+                    text,
+                    declaration.offset,
+                    declaration.offset + declaration.origin.len(),
+                ),
+            }),
+            None => Json::Null,
+        }
+    }
+
+    /// Offers every keyword, and every identifier used in the document.
+    fn completion(&self, params: &Json) -> Json {
+        let mut items: Vec<Json> = SANSKRIT
+            .keywords
+            .iter()
+            .map(|keyword| {
+                json!({
+                    "label": keyword.native,
+                    "kind": 14,
+                    "detail": keyword.lox,
+                    "documentation": keyword.example,
+                })
+            })
+            .collect();
+
+        if let Some(text) = params["textDocument"]["uri"]
+            .as_str()
+            .and_then(|uri| self.documents.get(uri))
+        {
+            let mut seen = Vec::new();
+            for token in tokens(text) {
+                if token.kind == TokenKind::Ident
+                    && keyword(token.origin).is_none()
+                    && !seen.contains(&token.origin)
+                {
+                    seen.push(token.origin);
+                    items.push(json!({ "label": token.origin, "kind": 6 }));
+                }
+            }
+        }
+
+        json!(items)
+    }
+}
+
+fn keyword(word: &str) -> Option<&'static Keyword> {
+    SANSKRIT.keyword(word)
+}
+
+/// The tokens of the untranslated document, in which Sanskrit keywords lex
+/// as identifiers. Lexing stops at the first error.
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    Lexer::new(text).map_while(Result::ok).collect()
+}
+
+fn token_at(text: &str, offset: usize) -> Option<Token<'_>> {
+    tokens(text)
+        .into_iter()
+        .find(|token| (token.offset..=token.offset + token.origin.len()).contains(&offset))
+}
+
+/// The path of a `file://` URI.
+fn path_of(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escaped = (encoded[i] == b'%')
+            .then(|| std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// Parses the document and turns its syntax errors into LSP diagnostics.
+fn syntax_errors(text: &str, manifest: &Manifest) -> Vec<Json> {
+    let pack = manifest.language_pack();
+    let translated = pack.translate(text);
+    let Err(report) = Parser::new(&translated, &Arena::new())
+        .terminators(manifest.terminators(None))
+        .parse_program()
+    else {
+        return Vec::new();
+    };
+
+    let mut errors = Vec::new();
+    flatten(report.as_ref(), &mut errors);
+    errors
+        .into_iter()
+        .map(|(message, span)| {
+            let start = original_offset(pack, text, &translated, span.0);
+            let end = original_offset(pack, text, &translated, span.0 + span.1).max(start);
+            lsp_diagnostic(text, (start, end), message)
+        })
+        .collect()
+}
+
+/// Collects each error a diagnostic groups, with its message and the offset
+/// and length of its first label.
+fn flatten(diagnostic: &dyn Diagnostic, out: &mut Vec<(String, (usize, usize))>) {
+    if let Some(related) = diagnostic.related() {
+        let before = out.len();
+        for related in related {
+            flatten(related, out);
+        }
+        if out.len() > before {
+            return;
+        }
+    }
+
+    let mut message = diagnostic.to_string();
+    let mut cause = diagnostic.source();
+    while let Some(e) = cause {
+        message.push_str(&format!("\n{e}"));
+        cause = e.source();
+    }
+    let span = diagnostic
+        .labels()
+        .and_then(|mut labels| labels.next())
+        .map_or((0, 0), |label| (label.offset(), label.len()));
+    out.push((message, span));
+}
+
+fn lsp_diagnostic(text: &str, (start, end): (usize, usize), message: String) -> Json {
+    json!({
+        "range": range(text, start, end),
+        "severity": 1,
+        "source": "sanskriti",
+        "message": message,
+    })
+}
+
+/// Maps a byte offset in the translated source back to the original. The
+/// translator only rewrites keywords in place, so lines line up, and within
+/// a line the offset is found by translating longer and longer prefixes of
+/// the original line. Offsets inside a keyword map to its start.
+fn original_offset(pack: &LanguagePack, original: &str, translated: &str, offset: usize) -> usize {
+    let offset = offset.min(translated.len());
+    let line = translated[..offset].matches('\n').count();
+    let translated_start = translated[..offset].rfind('\n').map_or(0, |nl| nl + 1);
+    let column = offset - translated_start;
+    let translated_line = translated[translated_start..]
+        .lines()
+        .next()
+        .unwrap_or_default();
+
+    let original_start: usize = original
+        .split_inclusive('\n')
+        .take(line)
+        .map(str::len)
+        .sum();
+    let original_line = original[original_start..]
+        .lines()
+        .next()
+        .unwrap_or_default();
+
+    let mut best = 0;
+    for (end, _) in original_line
+        .char_indices()
+        .skip(1)
+        .chain([(original_line.len(), ' ')])
+    {
+        let prefix = pack.translate(&original_line[..end]);
+        if translated_line.starts_with(&prefix) {
+            if prefix.len() > column {
+                break;
+            }
+            best = end;
+        }
+    }
+    original_start + best
+}
+
+fn range(text: &str, start: usize, end: usize) -> Json {
+    json!({ "start": position_of(text, start), "end": position_of(text, end) })
+}
+
+/// The LSP position of a byte offset: a 0-based line, and a column counted in
+/// UTF-16 code units, as LSP clients count them by default.
+fn position_of(text: &str, offset: usize) -> Json {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |nl| nl + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+/// The byte offset of an LSP position, clamped to the document.
+fn offset_at(text: &str, line: usize, character: usize) -> usize {
+    let line_start: usize = text.split_inclusive('\n').take(line).map(str::len).sum();
+    let mut units = 0;
+    for (i, c) in text[line_start.min(text.len())..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
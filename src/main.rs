@@ -1,91 +1,1484 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand};
+use miette::{IntoDiagnostic, Severity, WrapErr};
 use sanskriti as imp;
-use miette::{IntoDiagnostic, WrapErr};
-use std::fs;
-use std::path::PathBuf;
+use sanskriti::banner::banner_enabled;
+use sanskriti::batch;
+use sanskriti::bundle::Bundle;
+use sanskriti::crash;
+use sanskriti::debugger::Terminal;
+use sanskriti::diagnostics::{self, DiagnosticFormat, Original};
 use sanskriti::display_banner;
-use sanskriti::translate_file_contents;
+use sanskriti::golden;
+use sanskriti::highlight::{self, HighlightFormat};
+use sanskriti::interpreter::{DivisionByZero, NumberFormat, Trace};
+use sanskriti::lex::Terminators;
+use sanskriti::limits::{Limits, STACK_SIZE};
+use sanskriti::locale::{self, Locale};
+use sanskriti::logging::{self, Level};
+use sanskriti::manifest::{LimitsTable, Manifest};
+use sanskriti::parse::Arena;
+use sanskriti::report::RunStatus;
+use sanskriti::source::read_source;
+use sanskriti::testing;
+use sanskriti::theme::{self, paint, ColorChoice, Stream, Style};
+use sanskriti::token_stream::{to_json, TokenFormat, TokenStream};
+use sanskriti::translator::SourceMap;
+use sanskriti::unparse::Keywords;
+use sanskriti::{debug, note, verbose};
+use std::fmt::Write as _;
+use std::io;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// When to use colors in output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Only print program output and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Report pipeline stages (-v) and intermediate artifacts (-vv)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Don't print the banner (also hidden when stdout is not a terminal)
+    #[arg(long, global = true)]
+    no_banner: bool,
+
+    /// Replace invalid UTF-8 in source files with U+FFFD instead of failing
+    #[arg(long, global = true)]
+    lossy: bool,
+
+    /// How to write errors to stderr
+    #[arg(long, global = true, value_enum, default_value_t = DiagnosticFormat::Human)]
+    diagnostics: DiagnosticFormat,
+
+    /// Read keywords from this language pack file [default: from
+    /// sanskriti.toml, else Sanskrit]
+    #[arg(long, global = true, value_name = "FILE")]
+    dialect: Option<PathBuf>,
+
+    /// What ends a statement [default: from sanskriti.toml, else semicolons]
+    #[arg(long, global = true, value_enum)]
+    terminators: Option<Terminators>,
+
+    /// Write messages, the banner, and printed nil, true, and false in this
+    /// language [default: en]
+    #[arg(long, global = true, value_enum)]
+    lang: Option<Locale>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    Tokenize { filename: PathBuf },
-    Parse { filename: PathBuf },
-    Run { filename: PathBuf },
+    Tokenize {
+        /// Files, directories of programs, or glob patterns like 'course/**/*.sk'
+        #[arg(required = true, value_name = "FILES")]
+        filenames: Vec<PathBuf>,
+        /// How to write the tokens
+        #[arg(long, value_enum, default_value_t = TokenFormat::Text)]
+        format: TokenFormat,
+        /// How many files to process at once [default: the number of CPUs]
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+    },
+    Parse {
+        /// Files, directories of programs, or glob patterns like 'course/**/*.sk'
+        #[arg(required_unless_present = "from_tokens", value_name = "FILES")]
+        filenames: Vec<PathBuf>,
+        /// Parse tokens written by `tokenize --format json` instead of a
+        /// source file (`-` reads them from stdin)
+        #[arg(long, value_name = "FILE", conflicts_with = "filenames")]
+        from_tokens: Option<PathBuf>,
+        /// How many files to process at once [default: the number of CPUs]
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+    },
+    Check {
+        /// Files, directories of programs, or glob patterns like 'course/**/*.sk'
+        #[arg(required = true, value_name = "FILES")]
+        filenames: Vec<PathBuf>,
+        /// Fail if the program has any warnings, not only syntax errors
+        #[arg(long)]
+        deny_warnings: bool,
+        /// How many files to process at once [default: the number of CPUs]
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+        /// Check again each time one of the files changes
+        #[arg(short, long)]
+        watch: bool,
+    },
+    Run {
+        /// The program to run [default: the entry of the project the current
+        /// directory is in]
+        filename: Option<PathBuf>,
+        #[command(flatten)]
+        run: RunArgs,
+        /// Print each statement to stderr as it runs (or with `=expressions`,
+        /// also each expression and its value)
+        #[arg(long, value_enum, num_args = 0..=1, require_equals = true,
+              default_value_t = Trace::Off, default_missing_value = "statements")]
+        trace: Trace,
+        #[command(flatten)]
+        limits: LimitArgs,
+        /// Write statistics about the run to a JSON file next to the program
+        #[arg(long)]
+        report: bool,
+        /// Print the functions and lines the program spent the most time in
+        /// to stderr once it ends
+        #[arg(long)]
+        profile: bool,
+        /// Also write the time spent in each chain of calls to FILE, in the
+        /// folded format that flamegraph tools read
+        #[arg(long, value_name = "FILE", requires = "profile")]
+        profile_folded: Option<PathBuf>,
+        /// Fold constant expressions and prune `if`s on literals before running
+        #[arg(short = 'O', long)]
+        optimize: bool,
+        /// Run again each time the program changes
+        #[arg(short, long)]
+        watch: bool,
+        /// Arguments for the program, which it gets from args()
+        #[arg(last = true)]
+        program_args: Vec<String>,
+    },
+    /// Check every program of a project, as listed in its sanskriti.toml
+    Build {
+        /// A directory in the project [default: the current directory]
+        dir: Option<PathBuf>,
+        /// Fail if any program has warnings, not only syntax errors
+        #[arg(long)]
+        deny_warnings: bool,
+        /// How many files to process at once [default: the number of CPUs]
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+    },
+    /// Explain an error or warning code, in English and Sanskrit, with an
+    /// example
+    Explain {
+        /// A code such as E1003 [default: list every code]
+        code: Option<String>,
+    },
+    /// Package a program and the runtime into one executable
+    Bundle {
+        filename: PathBuf,
+        /// Where to write the executable [default: the program's name, in
+        /// the current directory]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Run a program under an interactive step debugger
+    Debug {
+        filename: PathBuf,
+        /// Pause whenever a statement on this line is about to run (repeatable)
+        #[arg(short, long = "break", value_name = "LINE")]
+        breakpoints: Vec<usize>,
+        #[command(flatten)]
+        run: RunArgs,
+        #[command(flatten)]
+        limits: LimitArgs,
+        /// Arguments for the program, which it gets from args()
+        #[arg(last = true)]
+        program_args: Vec<String>,
+    },
+    /// Serve the debugger over the Debug Adapter Protocol, for editors like VS Code
+    Dap {
+        /// The localhost port to listen on for a client
+        #[arg(long)]
+        port: u16,
+    },
+    /// Serve the Language Server Protocol over stdio, for editors
+    Lsp,
+    /// Print a program with syntax highlighting
+    Highlight {
+        filename: PathBuf,
+        #[arg(long, value_enum, default_value_t = HighlightFormat::Ansi)]
+        format: HighlightFormat,
+    },
+    /// Run every program in a directory in parallel and summarize the results
+    RunAll {
+        dir: PathBuf,
+        /// How many programs to run at once [default: the number of CPUs]
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+        #[command(flatten)]
+        run: RunArgs,
+        #[command(flatten)]
+        limits: LimitArgs,
+    },
+    /// Run the test functions of a program and summarize the results
+    Test {
+        filename: PathBuf,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    /// Run every program in a directory and compare its output with the
+    /// `.expected` file next to it, with random numbers, the clock, and
+    /// iteration order pinned
+    TestDir {
+        dir: PathBuf,
+        /// How many programs to run at once [default: the number of CPUs]
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    /// Run a program repeatedly and report how long it takes, with random
+    /// numbers, the clock, and iteration order pinned
+    Bench {
+        filename: PathBuf,
+        /// How many times to run the program
+        #[arg(short = 'n', long, default_value = "10")]
+        iterations: NonZeroU32,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    /// Print a program with every keyword in its canonical spelling
+    Normalize { filename: PathBuf },
+    /// Print a program with its keywords translated to Lox, as it is run
+    Translate {
+        filename: PathBuf,
+        /// Also write a JSON source map from byte offsets in the output back
+        /// to byte offsets in the program
+        #[arg(long, value_name = "FILE")]
+        source_map: Option<PathBuf>,
+    },
+    /// Print every keyword of a language pack with its Lox equivalent
+    Keywords,
+    /// Parse a program and print it back out from its syntax tree, with
+    /// keywords in Lox, or in Sanskrit with `--lang sa`
+    Unparse { filename: PathBuf },
+    /// Work with language pack files
+    Lang {
+        #[command(subcommand)]
+        command: LangCommands,
+    },
+}
+
+/// How to run programs, which takes precedence over the manifest.
+#[derive(clap::Args, Debug, Clone, Copy)]
+#[command(about = None, long_about = None)]
+struct RunArgs {
+    /// Pin random numbers, the clock, and iteration order for reproducible output
+    #[arg(long)]
+    deterministic: bool,
+    /// Let programs run code built at runtime with eval()
+    #[arg(long)]
+    allow_eval: bool,
+    /// Let programs read and write files with पठ() and लिख()
+    #[arg(long)]
+    allow_fs: bool,
+    /// Make operands of the wrong type a runtime error, as in reference Lox
+    #[arg(long)]
+    strict: bool,
+    /// Let assigning to an undeclared variable declare it, instead of
+    /// failing
+    #[arg(long)]
+    implicit_declarations: bool,
+    /// What dividing by zero results in [default: from sanskriti.toml, else nil]
+    #[arg(long, value_enum)]
+    division_by_zero: Option<DivisionByZero>,
+    /// How to write numbers when printing them or adding them to strings
+    #[arg(long, value_enum, default_value_t = NumberFormat::Decimal)]
+    numbers: NumberFormat,
+}
+
+impl RunArgs {
+    /// Applies the flags to `interpreter`, then what `manifest` sets that
+    /// they don't.
+    fn configure(self, interpreter: imp::Interpreter, manifest: &Manifest) -> imp::Interpreter {
+        manifest
+            .configure(
+                interpreter
+                    .deterministic(self.deterministic)
+                    .allow_eval(self.allow_eval)
+                    .allow_fs(self.allow_fs)
+                    .strict(self.strict)
+                    .implicit_declarations(self.implicit_declarations)
+                    .print_numbers(self.numbers)
+                    .concat_numbers(self.numbers),
+            )
+            .division_by_zero(manifest.division_by_zero(self.division_by_zero))
+    }
+}
+
+/// Limits to run programs under, which take precedence over those of the
+/// manifest.
+#[derive(clap::Args, Debug, Clone, Copy)]
+#[command(about = None, long_about = None)]
+struct LimitArgs {
+    /// Stop a program once it has executed this many statements
+    #[arg(long, value_name = "N")]
+    max_steps: Option<u64>,
+    /// Stop a program once it has run for this many seconds
+    #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+    /// Stop a program once its strings and lists take up more than this many
+    /// MiB, roughly
+    #[arg(long, value_name = "MB")]
+    max_memory: Option<u64>,
+}
+
+impl LimitArgs {
+    fn to_limits(self) -> Limits {
+        LimitsTable {
+            max_steps: self.max_steps,
+            max_memory_mb: self.max_memory,
+            time_limit_secs: self.time_limit,
+        }
+        .to_limits()
+    }
+}
+
+fn parse_seconds(secs: &str) -> Result<Duration, String> {
+    let secs: f64 = secs.parse().map_err(|e| format!("{e}"))?;
+    Duration::try_from_secs_f64(secs).map_err(|_| "expected a non-negative number".to_string())
+}
+
+#[derive(Subcommand, Debug)]
+enum LangCommands {
+    /// Check that a language pack file is complete, unambiguous, and normalized
+    Check { file: PathBuf },
+}
+
+fn main() {
+    crash::install_hook();
+    let cli = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(cli)
+        .expect("spawning the main thread failed");
+    if let Err(panic) = cli.join() {
+        // the crash hook has already reported it
+        std::panic::resume_unwind(panic);
+    }
 }
 
-fn main() -> miette::Result<()> {
-    display_banner();
+fn cli() {
+    if let Some(bundle) = Bundle::embedded() {
+        diagnostics::install_hook();
+        let args = std::env::args().skip(1).collect();
+        std::process::exit(bundle.run(args));
+    }
     let args = Args::parse();
+    theme::set_color_choice(args.color);
+    logging::set_level(Level::from_flags(args.quiet, args.verbose));
+    diagnostics::set_format(args.diagnostics);
+    locale::set_locale(args.lang.unwrap_or_default());
+    diagnostics::install_hook();
+    if logging::enabled(Level::Normal) && banner_enabled(args.no_banner) {
+        display_banner();
+    }
+
+    if let Err(e) = run(args) {
+        diagnostics::emit(&e, None);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> miette::Result<()> {
+    if let Some(path) = &args.dialect {
+        imp::manifest::set_dialect(imp::pack::load(path)?);
+        verbose!("using language pack '{}'", path.display());
+    }
+
     match args.command {
-        Commands::Tokenize { filename } => {
-            let mut any_cc_err = false;
+        Commands::Tokenize {
+            filenames,
+            format,
+            jobs,
+        } => {
+            let paths = batch::expand_paths(&filenames)?;
+            if format == TokenFormat::Json && paths.len() > 1 {
+                return Err(miette::miette!(
+                    "--format json writes the tokens of one file, not {}",
+                    paths.len()
+                ));
+            }
+            let lossy = args.lossy;
+            let results = batch::parallel_map(&paths, jobs_or_cpus(jobs), |path| {
+                tokenize(path, lossy, format)
+            });
+            for (path, result) in paths.iter().zip(&results) {
+                result.emit(path, paths.len() > 1);
+            }
 
-            let file_contents = fs::read_to_string(&filename)
-                .into_diagnostic()
-                .wrap_err_with(|| format!("reading '{}' failed", filename.display()))?;
+            if results.iter().any(|result| result.unreadable) {
+                std::process::exit(1);
+            }
+            if results.iter().any(|result| result.syntax_errors) {
+                std::process::exit(65);
+            }
+        }
+        Commands::Parse {
+            filenames,
+            from_tokens,
+            jobs,
+        } => {
+            let Some(path) = from_tokens else {
+                let paths = batch::expand_paths(&filenames)?;
+                let (lossy, terminators) = (args.lossy, args.terminators);
+                let results = batch::parallel_map(&paths, jobs_or_cpus(jobs), |path| {
+                    parse(path, lossy, terminators)
+                });
+                for (path, result) in paths.iter().zip(&results) {
+                    result.emit(path, paths.len() > 1);
+                }
 
-            for token in imp::Lexer::new(&file_contents) {
-                let token = match token {
-                    Ok(t) => t,
+                if results.iter().any(|result| result.unreadable) {
+                    std::process::exit(1);
+                }
+                if results.iter().any(|result| result.syntax_errors) {
+                    std::process::exit(65);
+                }
+                return Ok(());
+            };
+
+            let stream = TokenStream::from_json(&read_tokens(&path)?)?;
+            let arena = Arena::new();
+            let parser = imp::Parser::from_tokens(stream.source(), stream.tokens(), &arena);
+            match parser.parse_program() {
+                Ok(program) => {
+                    for statement in program {
+                        println!("{statement}");
+                    }
+                }
+                Err(e) => {
+                    diagnostics::emit(&e, Some(&path));
+                    std::process::exit(65);
+                }
+            }
+        }
+        Commands::Check {
+            filenames,
+            deny_warnings,
+            jobs,
+            watch,
+        } => {
+            let paths = batch::expand_paths(&filenames)?;
+            let jobs = jobs_or_cpus(jobs);
+            let (lossy, terminators) = (args.lossy, args.terminators);
+            let check = || check_files(&paths, jobs, lossy, terminators, deny_warnings);
+            if watch {
+                imp::watch::watch(&paths, check)?;
+            } else {
+                let code = check();
+                if code != 0 {
+                    std::process::exit(code);
+                }
+            }
+        }
+        Commands::Run {
+            filename,
+            run,
+            trace,
+            limits,
+            report,
+            profile,
+            profile_folded,
+            optimize,
+            watch,
+            program_args,
+        } => {
+            let filename = match filename {
+                Some(filename) => filename,
+                None => project_entry()?,
+            };
+            // the exit code of one run, or an error if the program can't be read
+            let run_once = || -> miette::Result<i32> {
+                let run_started = Instant::now();
+                let write_report = |status, error: Option<&dyn std::fmt::Display>, interpreter| {
+                    if !report {
+                        return;
+                    }
+                    let error = error.map(ToString::to_string);
+                    let elapsed = run_started.elapsed();
+                    let error = error.as_deref();
+                    match imp::report::write(&filename, status, error, elapsed, interpreter) {
+                        Ok(path) => verbose!("wrote report '{}'", path.display()),
+                        Err(e) => warn_report_failed(&filename, &e),
+                    }
+                };
+
+                let file_contents = read_source(&filename, args.lossy)?;
+                let manifest = manifest(&filename)?;
+                let (translated_contents, map) = translate(&manifest, &filename, &file_contents);
+                let original = Original {
+                    source: &file_contents,
+                    map: &map,
+                };
+
+                let started = Instant::now();
+                let arena = Arena::new();
+                let parser = parser(&manifest, &translated_contents, &arena, args.terminators);
+                let mut program = match parser.parse_program() {
+                    Ok(program) => program,
                     Err(e) => {
-                        eprintln!("{e:?}");
-                        if let Some(unrecognized) = e.downcast_ref::<imp::lex::SingleTokenError>() {
-                            any_cc_err = true;
-                            eprintln!(
-                                "[line {}] Error: Unexpected character: {}",
-                                unrecognized.line(),
-                                unrecognized.token
-                            );
-                        } else if let Some(unterminated) =
-                            e.downcast_ref::<imp::lex::StringTerminationError>()
-                        {
-                            any_cc_err = true;
-                            eprintln!("[line {}] Error: Unterminated string.", unterminated.line(),);
-                        }
-                        continue;
+                        write_report(RunStatus::SyntaxError, Some(&e), None);
+                        diagnostics::emit(&original.report(e.as_ref()), Some(&filename));
+                        return Ok(65);
                     }
                 };
-                println!("{token}");
+                verbose!(
+                    "parsed {} statement(s) in {:.2?}",
+                    program.len(),
+                    started.elapsed()
+                );
+                if optimize {
+                    let started = Instant::now();
+                    let rewrites = imp::optimize::optimize(&mut program, &arena);
+                    for rewrite in &rewrites {
+                        verbose!("{rewrite}");
+                    }
+                    verbose!(
+                        "optimized in {:.2?}, with {} rewrite(s)",
+                        started.elapsed(),
+                        rewrites.len()
+                    );
+                }
+
+                let started = Instant::now();
+                let mut interpreter = run.configure(
+                    imp::Interpreter::new()
+                        .limits(limits.to_limits())
+                        .trace(trace)
+                        .profile(profile)
+                        .args(program_args.clone()),
+                    &manifest,
+                );
+                let result = interpreter.eval_program(&program);
+                verbose!("executed in {:.2?}", started.elapsed());
+                if let Some(profile) = interpreter.take_profile() {
+                    print_profile(&profile, &translated_contents, &file_contents);
+                    if let Some(path) = &profile_folded {
+                        let name = filename.file_stem().unwrap_or_default().to_string_lossy();
+                        std::fs::write(path, profile.folded(&name))
+                            .into_diagnostic()
+                            .wrap_err_with(|| format!("writing '{}' failed", path.display()))?;
+                    }
+                }
+                if let Err(e) = result {
+                    write_report(RunStatus::RuntimeError, Some(&e), Some(&interpreter));
+                    let report = original.report(&e);
+                    diagnostics::emit(&report, Some(&filename));
+                    return Ok(70);
+                }
+                write_report(RunStatus::Ok, None, Some(&interpreter));
+                Ok(0)
+            };
+            if watch {
+                imp::watch::watch(std::slice::from_ref(&filename), || {
+                    run_once().unwrap_or_else(|e| {
+                        diagnostics::emit(&e, Some(&filename));
+                        1
+                    })
+                })?;
+            } else {
+                let code = run_once()?;
+                if code != 0 {
+                    std::process::exit(code);
+                }
+            }
+        }
+        Commands::Build {
+            dir,
+            deny_warnings,
+            jobs,
+        } => {
+            let manifest = Manifest::for_project(dir.as_deref().unwrap_or(Path::new(".")))?;
+            let name = manifest.project_name().unwrap_or_default();
+            let sources = manifest.sources()?;
+            let paths: Vec<PathBuf> = sources.iter().map(|path| relative(path)).collect();
+            verbose!(
+                "building project '{name}' from '{}'",
+                manifest.root().display()
+            );
+            let jobs = jobs_or_cpus(jobs);
+            let code = check_files(&paths, jobs, args.lossy, args.terminators, deny_warnings);
+            if code != 0 {
+                std::process::exit(code);
             }
-            println!("EOF  null");
+            note!("built project '{name}'");
+        }
+        Commands::Bundle { filename, output } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let manifest = manifest(&filename)?;
+            let (translated_contents, map) = translate(&manifest, &filename, &file_contents);
+            let original = Original {
+                source: &file_contents,
+                map: &map,
+            };
 
-            if any_cc_err {
+            let arena = Arena::new();
+            let parser = parser(&manifest, &translated_contents, &arena, args.terminators);
+            if let Err(e) = parser.parse_program() {
+                diagnostics::emit(&original.report(e.as_ref()), Some(&filename));
                 std::process::exit(65);
             }
+
+            let manifest_text = match &manifest.path {
+                Some(path) => Some(
+                    std::fs::read_to_string(path)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("reading '{}' failed", path.display()))?,
+                ),
+                None => None,
+            };
+            // a pack file goes along with the program, since the bundle can't
+            // count on finding it where it was
+            let dialect_file = args.dialect.clone().or_else(|| {
+                let dialect = manifest.dialect.as_ref()?;
+                let builtin = imp::translator::language_pack(dialect.get_ref()).is_some();
+                (!builtin).then(|| manifest.root().join(dialect.get_ref()))
+            });
+            let dialect_text = match dialect_file {
+                Some(path) => Some(
+                    std::fs::read_to_string(&path)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("reading '{}' failed", path.display()))?,
+                ),
+                None => None,
+            };
+            let bundle = Bundle {
+                name: filename.display().to_string(),
+                source: file_contents,
+                manifest: manifest_text,
+                dialect: dialect_text,
+            };
+            let output = output.unwrap_or_else(|| {
+                let stem = filename.file_stem().unwrap_or(filename.as_os_str());
+                let mut name = stem.to_os_string();
+                name.push(std::env::consts::EXE_SUFFIX);
+                PathBuf::from(name)
+            });
+            bundle.write(&output)?;
+            note!("wrote '{}'", output.display());
+        }
+        Commands::Debug {
+            filename,
+            breakpoints,
+            run,
+            limits,
+            program_args,
+        } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let manifest = manifest(&filename)?;
+            let (translated_contents, map) = translate(&manifest, &filename, &file_contents);
+            let original = Original {
+                source: &file_contents,
+                map: &map,
+            };
+
+            let arena = Arena::new();
+            let parser = parser(&manifest, &translated_contents, &arena, args.terminators);
+            let program = match parser.parse_program() {
+                Ok(program) => program,
+                Err(e) => {
+                    diagnostics::emit(&original.report(e.as_ref()), Some(&filename));
+                    std::process::exit(65);
+                }
+            };
+
+            let debugger = Terminal::new(
+                filename.clone(),
+                file_contents.clone(),
+                translated_contents.clone(),
+                breakpoints,
+            );
+            let mut interpreter = run.configure(
+                imp::Interpreter::new()
+                    .limits(limits.to_limits())
+                    .args(program_args)
+                    .debugger(debugger),
+                &manifest,
+            );
+            if let Err(e) = interpreter.eval_program(&program) {
+                let report = original.report(&e);
+                diagnostics::emit(&report, Some(&filename));
+                std::process::exit(70);
+            }
+        }
+        Commands::Dap { port } => imp::dap::serve(port, args.lossy)?,
+        Commands::Lsp => imp::lsp::serve()?,
+        Commands::Highlight { filename, format } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let pack = manifest(&filename)?.language_pack();
+            print!("{}", highlight::render(&file_contents, pack, format));
         }
-        Commands::Parse { filename } => {
-            let file_contents = fs::read_to_string(&filename)
+        Commands::RunAll {
+            dir,
+            jobs,
+            run,
+            limits,
+        } => {
+            let paths = batch::collect_sources(&dir)
                 .into_diagnostic()
-                .wrap_err_with(|| format!("reading '{}' failed", filename.display()))?;
+                .wrap_err_with(|| format!("listing '{}' failed", dir.display()))?;
+            let jobs = jobs_or_cpus(jobs);
+            verbose!("running {} program(s) on {jobs} thread(s)", paths.len());
+
+            let started = Instant::now();
+            let outcomes = batch::run_all(
+                &paths,
+                jobs,
+                args.lossy,
+                args.terminators,
+                |interpreter, manifest| {
+                    run.configure(interpreter.limits(limits.to_limits()), manifest)
+                },
+            );
+            verbose!("ran all programs in {:.2?}", started.elapsed());
 
-            let parser = imp::Parser::new(&file_contents);
-            match parser.parse_expression() {
-                Ok(tt) => println!("{tt}"),
+            print_batch_report(&outcomes);
+            if outcomes.iter().any(|outcome| !outcome.status.passed()) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Test { filename, run } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let manifest = manifest(&filename)?;
+            let (translated_contents, map) = translate(&manifest, &filename, &file_contents);
+            let original = Original {
+                source: &file_contents,
+                map: &map,
+            };
+
+            let arena = Arena::new();
+            let parser = parser(&manifest, &translated_contents, &arena, args.terminators);
+            let program = match parser.parse_program() {
+                Ok(program) => program,
                 Err(e) => {
-                    // TODO: match error line format
-                    eprintln!("{e:?}");
+                    diagnostics::emit(&original.report(e.as_ref()), Some(&filename));
                     std::process::exit(65);
                 }
+            };
+
+            let outcomes = testing::run_tests(&program, original, |interpreter| {
+                run.configure(interpreter, &manifest)
+            });
+            if outcomes.is_empty() {
+                note!(
+                    "no test functions in '{}'; their names start with {}",
+                    filename.display(),
+                    testing::TEST_PREFIXES.join(" or ")
+                );
+            }
+            print_test_report(&filename, &outcomes);
+            if outcomes.iter().any(|outcome| !outcome.passed()) {
+                std::process::exit(1);
             }
         }
-        Commands::Run { filename } => {
-            let file_contents = fs::read_to_string(&filename)
+        Commands::TestDir { dir, jobs, run } => {
+            let paths = batch::collect_sources(&dir)
                 .into_diagnostic()
-                .wrap_err_with(|| format!("reading '{}' failed", filename.display()))?;
-            let translated_contents = imp::translate_file_contents(&file_contents)?;
-            let parser = imp::Parser::new(&translated_contents);
-            let program = parser.parse_program().unwrap();
-            let mut interpreter = imp::Interpreter::new();
-            interpreter.eval_program(&program);
+                .wrap_err_with(|| format!("listing '{}' failed", dir.display()))?;
+            let jobs = jobs_or_cpus(jobs);
+
+            // deterministic, so that the output can be compared from run to run
+            let run = RunArgs {
+                deterministic: true,
+                ..run
+            };
+            let outcomes = batch::run_all(
+                &paths,
+                jobs,
+                args.lossy,
+                args.terminators,
+                |interpreter, manifest| run.configure(interpreter, manifest),
+            );
+            let verdicts: Vec<_> = outcomes.iter().map(golden::compare).collect();
+
+            print_golden_report(&outcomes, &verdicts);
+            if verdicts.iter().any(|verdict| !verdict.passed()) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Bench {
+            filename,
+            iterations,
+            run,
+        } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let manifest = manifest(&filename)?;
+            let (translated_contents, map) = translate(&manifest, &filename, &file_contents);
+            let original = Original {
+                source: &file_contents,
+                map: &map,
+            };
+
+            let arena = Arena::new();
+            let parser = parser(&manifest, &translated_contents, &arena, args.terminators);
+            let program = match parser.parse_program() {
+                Ok(program) => program,
+                Err(e) => {
+                    diagnostics::emit(&original.report(e.as_ref()), Some(&filename));
+                    std::process::exit(65);
+                }
+            };
+
+            // deterministic, so that every run does the same work
+            let run = RunArgs {
+                deterministic: true,
+                ..run
+            };
+            let timings = imp::bench::run(&program, iterations, |interpreter| {
+                run.configure(interpreter, &manifest)
+            });
+            match timings {
+                Ok(timings) => {
+                    println!("{}: {} run(s)", filename.display(), timings.iterations());
+                    println!("  min     {:.2?}", timings.min());
+                    println!("  mean    {:.2?}", timings.mean());
+                    println!("  median  {:.2?}", timings.median());
+                }
+                Err(e) => {
+                    let report = original.report(&e);
+                    diagnostics::emit(&report, Some(&filename));
+                    std::process::exit(70);
+                }
+            }
+        }
+        Commands::Normalize { filename } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let pack = manifest(&filename)?.language_pack();
+            print!("{}", pack.normalize(&file_contents));
+        }
+        Commands::Translate {
+            filename,
+            source_map,
+        } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let pack = manifest(&filename)?.language_pack();
+            let (translated, map) = pack.translate_with_map(&file_contents);
+            print!("{translated}");
+            if let Some(path) = source_map {
+                std::fs::write(&path, map.to_json(&filename.to_string_lossy()))
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("writing '{}' failed", path.display()))?;
+            }
+        }
+        Commands::Explain { code: None } => {
+            for explanation in imp::explain::EXPLANATIONS {
+                println!("{}  {}", explanation.code, explanation.title);
+            }
+        }
+        Commands::Explain { code: Some(code) } => {
+            let explanation = imp::explain::explanation(&code).ok_or_else(|| {
+                miette::miette!(
+                    help = "run `sanskriti explain` to list every code",
+                    "unknown code '{code}'"
+                )
+            })?;
+            print!("{}", imp::explain::render(explanation));
+        }
+        Commands::Keywords => {
+            let manifest = Manifest::for_dir(Path::new("."))?;
+            if let Some(path) = &manifest.path {
+                verbose!("using manifest '{}'", path.display());
+            }
+            let pack = match args.lang.and_then(Locale::pack) {
+                Some(pack) if args.dialect.is_none() => pack,
+                _ => manifest.language_pack(),
+            };
+            print_keyword_table(pack);
+        }
+        Commands::Unparse { filename } => {
+            let file_contents = read_source(&filename, args.lossy)?;
+            let manifest = manifest(&filename)?;
+            let (translated_contents, map) = translate(&manifest, &filename, &file_contents);
+            let original = Original {
+                source: &file_contents,
+                map: &map,
+            };
+
+            let arena = Arena::new();
+            let parser = parser(&manifest, &translated_contents, &arena, args.terminators);
+            let program = match parser.parse_program() {
+                Ok(program) => program,
+                Err(e) => {
+                    diagnostics::emit(&original.report(e.as_ref()), Some(&filename));
+                    std::process::exit(65);
+                }
+            };
+            let keywords = match args.lang.and_then(Locale::pack) {
+                Some(pack) => Keywords::Pack(pack),
+                None => Keywords::Lox,
+            };
+            let terminator = imp::unparse::terminator(&translated_contents);
+            print!("{}", imp::unparse::unparse(&program, keywords, terminator));
+        }
+        Commands::Lang {
+            command: LangCommands::Check { file },
+        } => {
+            let problems = imp::pack::check(&file)?;
+            for problem in &problems {
+                diagnostics::emit(problem, Some(&file));
+            }
+            let errors = problems
+                .iter()
+                .filter(|problem| problem.severity().unwrap_or(Severity::Error) == Severity::Error)
+                .count();
+            let warnings = problems.len() - errors;
+            if errors > 0 {
+                return Err(miette::miette!(
+                    "'{}' has {errors} error(s) and {warnings} warning(s)",
+                    file.display()
+                ));
+            }
+            note!(
+                "'{}' is a valid language pack ({warnings} warning(s))",
+                file.display()
+            );
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn warn_report_failed(program: &Path, e: &io::Error) {
+    let warning = miette::miette!(
+        severity = Severity::Warning,
+        "writing report '{}' failed: {e}",
+        imp::report::path_for(program).display()
+    );
+    diagnostics::emit(&warning, Some(program));
+}
+
+/// Checks the programs at `paths` on `jobs` threads for `check`, reporting
+/// what is found, and returns the code to exit with.
+fn check_files(
+    paths: &[PathBuf],
+    jobs: NonZeroUsize,
+    lossy: bool,
+    terminators: Option<Terminators>,
+    deny_warnings: bool,
+) -> i32 {
+    let results = batch::parallel_map(paths, jobs, |path| check(path, lossy, terminators));
+    for (path, result) in paths.iter().zip(&results) {
+        result.emit(path, false);
+        if result.unreadable || result.syntax_errors {
+            continue;
+        }
+        if result.warnings == 0 {
+            note!("no syntax errors or warnings in '{}'", path.display());
+        } else if !deny_warnings {
+            note!(
+                "no syntax errors in '{}', {} warning(s)",
+                path.display(),
+                result.warnings
+            );
+        }
+    }
+
+    let count = |has: fn(&FileResult) -> bool| results.iter().filter(|r| has(r)).count();
+    let unreadable = count(|result| result.unreadable);
+    let with_errors = count(|result| result.syntax_errors);
+    let with_warnings = count(|result| result.warnings > 0);
+    let warnings: usize = results.iter().map(|result| result.warnings).sum();
+    if paths.len() > 1 {
+        note!(
+            "checked {} file(s): {with_errors} with syntax errors, {unreadable} unreadable, \
+             {warnings} warning(s)",
+            paths.len()
+        );
+    }
+
+    if unreadable > 0 {
+        return 1;
+    }
+    if with_errors > 0 {
+        return 65;
+    }
+    if deny_warnings && warnings > 0 {
+        let error = match paths.iter().zip(&results).find(|(_, r)| r.warnings > 0) {
+            Some((path, _)) if with_warnings == 1 => miette::miette!(
+                "'{}' has {warnings} warning(s), which --deny-warnings makes errors",
+                path.display()
+            ),
+            _ => miette::miette!(
+                "{with_warnings} files have {warnings} warning(s), \
+                 which --deny-warnings makes errors"
+            ),
+        };
+        diagnostics::emit(&error, None);
+        return 1;
+    }
+    0
+}
+
+/// What `tokenize`, `parse`, or `check` found in one file, held back so that
+/// files processed in parallel are reported one at a time, in order.
+#[derive(Default)]
+struct FileResult {
+    /// What to print to stdout.
+    output: String,
+    /// The diagnostics about the file, each with the line to print to stderr
+    /// after it, if any.
+    diagnostics: Vec<(miette::Report, Option<String>)>,
+    /// Whether the file or its manifest couldn't be read or translated.
+    unreadable: bool,
+    syntax_errors: bool,
+    warnings: usize,
+}
+
+impl FileResult {
+    fn unreadable(report: miette::Report) -> Self {
+        FileResult {
+            diagnostics: vec![(report, None)],
+            unreadable: true,
+            ..FileResult::default()
+        }
+    }
+
+    /// Prints what was found in the file at `path`, under a header naming it
+    /// if `header` is set.
+    fn emit(&self, path: &Path, header: bool) {
+        if header {
+            println!("==> {} <==", path.display());
+        }
+        print!("{}", self.output);
+        for (report, line) in &self.diagnostics {
+            diagnostics::emit(report, Some(path));
+            if let Some(line) = line {
+                eprintln!("{line}");
+            }
+        }
+    }
+}
+
+/// `jobs`, or else as many threads as there are CPUs.
+fn jobs_or_cpus(jobs: Option<NonZeroUsize>) -> NonZeroUsize {
+    jobs.or_else(|| std::thread::available_parallelism().ok())
+        .unwrap_or(NonZeroUsize::MIN)
+}
+
+/// The tokens of the file at `path`, for `tokenize`.
+fn tokenize(path: &Path, lossy: bool, format: TokenFormat) -> FileResult {
+    let file_contents = match read_source(path, lossy) {
+        Ok(contents) => contents,
+        Err(e) => return FileResult::unreadable(e),
+    };
+
+    let mut result = FileResult::default();
+    for token in imp::Lexer::new(&file_contents) {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => {
+                let mut line = None;
+                if diagnostics::format() == DiagnosticFormat::Human {
+                    if let Some(unrecognized) = e.downcast_ref::<imp::lex::SingleTokenError>() {
+                        result.syntax_errors = true;
+                        line = Some(format!(
+                            "[line {}] Error: Unexpected character: {}",
+                            unrecognized.line(),
+                            unrecognized.token
+                        ));
+                    } else if let Some(unterminated) =
+                        e.downcast_ref::<imp::lex::StringTerminationError>()
+                    {
+                        result.syntax_errors = true;
+                        line = Some(format!(
+                            "[line {}] Error: Unterminated string.",
+                            unterminated.line()
+                        ));
+                    }
+                }
+                result.diagnostics.push((e, line));
+                continue;
+            }
+        };
+        let _ = match format {
+            TokenFormat::Text => writeln!(result.output, "{token}"),
+            TokenFormat::Json => writeln!(result.output, "{}", to_json(&token, &file_contents)),
+        };
+    }
+    if format == TokenFormat::Text {
+        result.output.push_str("EOF  null\n");
+    }
+    result
+}
+
+/// The tree of each statement of the program at `path`, one per line, or
+/// else every syntax error in it, for `parse`.
+fn parse(path: &Path, lossy: bool, terminators: Option<Terminators>) -> FileResult {
+    let read = read_source(path, lossy).and_then(|contents| {
+        let manifest = manifest(path)?;
+        let (translated, map) = translate(&manifest, path, &contents);
+        Ok((manifest, contents, translated, map))
+    });
+    let (manifest, file_contents, translated_contents, map) = match read {
+        Ok(read) => read,
+        Err(e) => return FileResult::unreadable(e),
+    };
+    let original = Original {
+        source: &file_contents,
+        map: &map,
+    };
+
+    let arena = Arena::new();
+    match parser(&manifest, &translated_contents, &arena, terminators).parse_program() {
+        Ok(program) => FileResult {
+            output: program
+                .iter()
+                .map(|statement| format!("{statement}\n"))
+                .collect(),
+            ..FileResult::default()
+        },
+        Err(e) => FileResult {
+            diagnostics: vec![(original.report(e.as_ref()), None)],
+            syntax_errors: true,
+            ..FileResult::default()
+        },
+    }
+}
+
+/// The syntax errors or else the warnings of the program at `path`, for
+/// `check`.
+fn check(path: &Path, lossy: bool, terminators: Option<Terminators>) -> FileResult {
+    let read = read_source(path, lossy).and_then(|contents| {
+        let manifest = manifest(path)?;
+        let (translated, map) = translate(&manifest, path, &contents);
+        Ok((manifest, contents, translated, map))
+    });
+    let (manifest, file_contents, translated_contents, map) = match read {
+        Ok(read) => read,
+        Err(e) => return FileResult::unreadable(e),
+    };
+
+    let original = Original {
+        source: &file_contents,
+        map: &map,
+    };
+    let started = Instant::now();
+    let arena = Arena::new();
+    let parser = parser(&manifest, &translated_contents, &arena, terminators);
+    match parser.parse_program() {
+        Ok(program) => {
+            verbose!(
+                "parsed {} statement(s) in {:.2?}",
+                program.len(),
+                started.elapsed()
+            );
+            let warnings = imp::lint::lint(&program, &translated_contents);
+            FileResult {
+                warnings: warnings.len(),
+                diagnostics: warnings
+                    .iter()
+                    .map(|warning| (original.report(warning.as_ref()), None))
+                    .collect(),
+                ..FileResult::default()
+            }
+        }
+        Err(e) => FileResult {
+            diagnostics: vec![(original.report(e.as_ref()), None)],
+            syntax_errors: true,
+            ..FileResult::default()
+        },
+    }
+}
+
+/// The entry of the project the current directory is in, for `run` without
+/// a program.
+fn project_entry() -> miette::Result<PathBuf> {
+    let manifest = Manifest::for_project(Path::new("."))?;
+    let entry = manifest
+        .entry()
+        .expect("a project manifest has a [project] table");
+    Ok(relative(&entry))
+}
+
+/// `path` relative to the current directory when it is inside it, so that
+/// messages name files the way the user would.
+fn relative(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .filter(|path| !path.as_os_str().is_empty())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// The manifest that applies to `filename`, reporting which one at `-v`.
+fn manifest(filename: &Path) -> miette::Result<Manifest> {
+    let manifest = Manifest::for_program(filename)?;
+    if let Some(path) = &manifest.path {
+        verbose!("using manifest '{}'", path.display());
+    }
+    Ok(manifest)
+}
+
+/// A parser for translated `contents` that builds in `arena`, with the
+/// statement terminators from the command line or else `manifest`.
+fn parser<'de>(
+    manifest: &Manifest,
+    contents: &'de str,
+    arena: &'de Arena,
+    terminators: Option<Terminators>,
+) -> imp::Parser<'de> {
+    imp::Parser::new(contents, arena).terminators(manifest.terminators(terminators))
+}
+
+/// Reads a token stream from `path`, or from stdin if it is `-`.
+fn read_tokens(path: &Path) -> miette::Result<String> {
+    if path == Path::new("-") {
+        io::read_to_string(io::stdin())
+            .into_diagnostic()
+            .wrap_err("reading tokens from stdin failed")
+    } else {
+        std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("reading '{}' failed", path.display()))
+    }
+}
+
+/// Translates the keywords of `manifest`'s language pack in `contents`,
+/// reporting the stage at `-v`.
+fn translate(manifest: &Manifest, filename: &Path, contents: &str) -> (String, SourceMap) {
+    let started = Instant::now();
+    let (translated, map) = manifest.language_pack().translate_with_map(contents);
+    verbose!(
+        "translated '{}' ({} bytes) in {:.2?}",
+        filename.display(),
+        contents.len(),
+        started.elapsed()
+    );
+    debug!("translated source:\n{translated}");
+    (translated, map)
+}
+
+fn print_batch_report(outcomes: &[batch::Outcome]) {
+    for outcome in outcomes {
+        let (verdict, style) = match outcome.status {
+            batch::Status::Passed => ("ok", Style::Success),
+            batch::Status::Unreadable(_) => ("unreadable", Style::Failure),
+            batch::Status::CompileError(_) => ("syntax error", Style::Failure),
+            batch::Status::RuntimeError(_) => ("runtime error", Style::Failure),
+        };
+        println!(
+            "=== {} ({}, {:.2?})",
+            outcome.path.display(),
+            paint(Stream::Stdout, style, verdict),
+            outcome.elapsed
+        );
+        print!("{}", outcome.output);
+        if let batch::Status::Unreadable(e)
+        | batch::Status::CompileError(e)
+        | batch::Status::RuntimeError(e) = &outcome.status
+        {
+            match diagnostics::format() {
+                DiagnosticFormat::Human => println!("{e:?}"),
+                DiagnosticFormat::Json => diagnostics::emit(e, Some(&outcome.path)),
+            }
+        }
+    }
+
+    let passed = outcomes
+        .iter()
+        .filter(|outcome| outcome.status.passed())
+        .count();
+    println!(
+        "{} program(s): {passed} passed, {} failed",
+        outcomes.len(),
+        outcomes.len() - passed
+    );
+}
+
+fn print_golden_report(outcomes: &[batch::Outcome], verdicts: &[golden::Verdict]) {
+    for (outcome, verdict) in outcomes.iter().zip(verdicts) {
+        let (label, style) = match verdict {
+            golden::Verdict::Passed => ("ok", Style::Success),
+            golden::Verdict::NoExpected(_) => ("no expected output", Style::Failure),
+            golden::Verdict::Failed { .. } => ("FAILED", Style::Failure),
+        };
+        println!(
+            "=== {} ({}, {:.2?})",
+            outcome.path.display(),
+            paint(Stream::Stdout, style, label),
+            outcome.elapsed
+        );
+        match verdict {
+            golden::Verdict::Passed => {}
+            golden::Verdict::NoExpected(e) => println!(
+                "reading '{}' failed: {e}",
+                golden::expected_path(&outcome.path).display()
+            ),
+            golden::Verdict::Failed {
+                expected,
+                exit_code,
+            } => {
+                if expected.exit_code != *exit_code {
+                    println!(
+                        "exit code: expected {}, got {exit_code}",
+                        expected.exit_code
+                    );
+                }
+                if expected.stdout != outcome.output {
+                    print_diff(&expected.stdout, &outcome.output);
+                }
+            }
+        }
+    }
+
+    let passed = verdicts.iter().filter(|verdict| verdict.passed()).count();
+    println!(
+        "{} program(s): {passed} passed, {} failed",
+        verdicts.len(),
+        verdicts.len() - passed
+    );
+}
+
+/// Prints the lines of the expected output missing from the actual output
+/// with `-`, and those added to it with `+`.
+fn print_diff(expected: &str, actual: &str) {
+    let lines = golden::diff(expected, actual);
+    if lines
+        .iter()
+        .all(|line| matches!(line, golden::DiffLine::Same(_)))
+    {
+        println!("output differs only in line endings or the final newline");
+        return;
+    }
+    for line in lines {
+        match line {
+            golden::DiffLine::Same(line) => println!("  {line}"),
+            golden::DiffLine::Missing(line) => {
+                println!(
+                    "{}",
+                    paint(Stream::Stdout, Style::Failure, &format!("- {line}"))
+                )
+            }
+            golden::DiffLine::Extra(line) => {
+                println!(
+                    "{}",
+                    paint(Stream::Stdout, Style::Success, &format!("+ {line}"))
+                )
+            }
+        }
+    }
+}
+
+fn print_test_report(filename: &Path, outcomes: &[testing::TestOutcome]) {
+    for outcome in outcomes {
+        let (verdict, style) = match outcome.error {
+            None => ("ok", Style::Success),
+            Some(_) => ("FAILED", Style::Failure),
+        };
+        println!(
+            "test {} ... {} ({:.2?})",
+            outcome.name,
+            paint(Stream::Stdout, style, verdict),
+            outcome.elapsed
+        );
+        if let Some(e) = &outcome.error {
+            print!("{}", outcome.output);
+            match diagnostics::format() {
+                DiagnosticFormat::Human => println!("{e:?}"),
+                DiagnosticFormat::Json => diagnostics::emit(e, Some(filename)),
+            }
+        }
+    }
+
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    println!(
+        "{} test(s): {passed} passed, {} failed",
+        outcomes.len(),
+        outcomes.len() - passed
+    );
+}
+
+/// How many functions and lines `run --profile` lists.
+const PROFILE_ROWS: usize = 10;
+
+/// Prints the functions and lines that took the most time of their own to
+/// stderr, for `run --profile`. Lines are found in `translated`, which the
+/// program was parsed from, and shown as they are in `source`.
+fn print_profile(profile: &imp::profile::Profile, translated: &str, source: &str) {
+    let elapsed = profile.elapsed();
+    let share = |own: Duration| own.as_secs_f64() / elapsed.as_secs_f64().max(f64::EPSILON) * 100.0;
+    eprintln!("\nprofile: ran for {elapsed:.2?}");
+
+    let functions = profile.functions();
+    if !functions.is_empty() {
+        let width = functions
+            .iter()
+            .map(|f| f.name.width())
+            .max()
+            .unwrap_or(0)
+            .max(8);
+        eprintln!(
+            "\n{:width$}  {:>8}  {:>10}  {:>10}  {:>6}",
+            "function", "calls", "total", "own", "own %"
+        );
+        for function in functions.iter().take(PROFILE_ROWS) {
+            let padding = " ".repeat(width - function.name.width());
+            eprintln!(
+                "{}{padding}  {:>8}  {:>10}  {:>10}  {:>5.1}%",
+                function.name,
+                function.calls,
+                format!("{:.2?}", function.total),
+                format!("{:.2?}", function.own),
+                share(function.own)
+            );
+        }
+    }
+
+    let text: Vec<&str> = source.lines().collect();
+    eprintln!(
+        "\n{:>6}  {:>10}  {:>10}  {:>6}  source",
+        "line", "runs", "own", "own %"
+    );
+    for line in profile.lines(translated).iter().take(PROFILE_ROWS) {
+        let code = text.get(line.line - 1).map_or("", |code| code.trim());
+        eprintln!(
+            "{:>6}  {:>10}  {:>10}  {:>5.1}%  {code}",
+            line.line,
+            line.runs,
+            format!("{:.2?}", line.own),
+            share(line.own)
+        );
+    }
+}
+
+fn print_keyword_table(pack: &imp::translator::LanguagePack) {
+    let header = [pack.name, "Lox", "Transliteration", "Example", "Synonyms"];
+    let synonyms: Vec<String> = pack
+        .keywords
+        .iter()
+        .map(|k| k.synonyms.join(", "))
+        .collect();
+    let rows: Vec<[&str; 5]> = pack
+        .keywords
+        .iter()
+        .zip(&synonyms)
+        .map(|(k, synonyms)| [k.native, k.lox, k.transliteration, k.example, synonyms])
+        .collect();
+
+    let mut widths = header.map(UnicodeWidthStr::width);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let print_row = |row: &[&str; 5]| {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths) {
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - cell.width() + 2));
+        }
+        println!("{}", line.trim_end());
+    };
+
+    print_row(&header);
+    let rule = widths.map(|width| "-".repeat(width));
+    print_row(&rule.each_ref().map(String::as_str));
+    for row in &rows {
+        print_row(row);
+    }
+}
@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use miette::{Diagnostic, IntoDiagnostic, NamedSource, SourceSpan, WrapErr};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+use toml::Spanned;
+
+use crate::batch;
+use crate::interpreter::DivisionByZero;
+use crate::lex::Terminators;
+use crate::limits::Limits;
+use crate::pack;
+use crate::translator::{self, LanguagePack, SANSKRIT};
+use crate::Interpreter;
+
+/// The name of the file that configures the programs in its directory and
+/// the directories below it.
+pub const FILE_NAME: &str = "sanskriti.toml";
+
+/// The settings in a `sanskriti.toml`. Settings given on the command line
+/// take precedence over these.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Manifest {
+    /// Where the manifest was read from, if it was read from a file.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// What ends a statement: `"semicolons"` or `"newlines"`.
+    pub terminators: Option<Terminators>,
+    /// The language pack whose keywords the programs are written in: the
+    /// code of a built-in one, such as `"sa"`, or the path of a pack file,
+    /// relative to the manifest.
+    pub dialect: Option<Spanned<String>>,
+    /// The language pack that `dialect` names, once it is loaded. Parsing
+    /// loads built-in packs, and reading a manifest also loads pack files.
+    #[serde(skip)]
+    pub pack: Option<&'static LanguagePack>,
+    /// Make operands of the wrong type a runtime error, as with `--strict`.
+    #[serde(default)]
+    pub strict: bool,
+    /// Let assignment declare undeclared variables, as with
+    /// `--implicit-declarations`.
+    #[serde(default)]
+    pub implicit_declarations: bool,
+    /// What dividing by zero results in: `"error"`, `"infinity"`, or `"nil"`.
+    pub division_by_zero: Option<DivisionByZero>,
+    /// The `[project]` table, which makes the directory of the manifest the
+    /// root of a project.
+    pub project: Option<Project>,
+    /// The `[limits]` table: caps that programs run with.
+    #[serde(default)]
+    pub limits: LimitsTable,
+    /// The `[capabilities]` table: builtins that are off unless turned on.
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+/// The `[project]` of a manifest: the programs that `build` checks and the
+/// one that `run` runs when given none. Paths are relative to the directory
+/// of the manifest.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Project {
+    /// What messages call the project [default: the name of its directory].
+    pub name: Option<String>,
+    /// The program the project starts from.
+    pub entry: PathBuf,
+    /// The directories whose programs, and those of the directories below
+    /// them, make up the project [default: the directory of the manifest].
+    #[serde(default = "root_dir")]
+    pub source_dirs: Vec<PathBuf>,
+}
+
+fn root_dir() -> Vec<PathBuf> {
+    vec![PathBuf::from(".")]
+}
+
+fn builtin_codes() -> String {
+    let codes: Vec<&str> = translator::LANGUAGE_PACKS
+        .iter()
+        .map(|pack| pack.code)
+        .collect();
+    codes.join(", ")
+}
+
+/// The `[limits]` of a manifest, in the units they are written in.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LimitsTable {
+    pub max_steps: Option<u64>,
+    pub max_memory_mb: Option<u64>,
+    #[serde(default, deserialize_with = "seconds")]
+    pub time_limit_secs: Option<Duration>,
+}
+
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let secs = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(secs)
+        .map(Some)
+        .map_err(|_| D::Error::custom("expected a non-negative number of seconds"))
+}
+
+impl LimitsTable {
+    pub fn to_limits(self) -> Limits {
+        Limits {
+            max_steps: self.max_steps,
+            time: self.time_limit_secs,
+            max_memory: self
+                .max_memory_mb
+                .map(|mb| usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)),
+        }
+    }
+}
+
+/// The `[capabilities]` of a manifest. Each one turned on here is on for every
+/// program under the manifest, as if its command-line flag had been given.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Capabilities {
+    /// The `eval` builtin, as with `--allow-eval`.
+    #[serde(default)]
+    pub eval: bool,
+    /// The file builtins `पठ` and `लिख`, as with `--allow-fs`.
+    #[serde(default)]
+    pub fs: bool,
+}
+
+/// The language pack that `--dialect` picked for every program, whatever
+/// their manifests say.
+static DIALECT: OnceLock<&'static LanguagePack> = OnceLock::new();
+
+/// Makes every program use the keywords of `pack`, overriding the `dialect`
+/// of their manifests, as `--dialect` does. Only the first call has an
+/// effect.
+pub fn set_dialect(pack: &'static LanguagePack) {
+    let _ = DIALECT.set(pack);
+}
+
+#[derive(Diagnostic, Debug, Error)]
+#[error("invalid manifest")]
+pub struct ManifestError {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("{message}")]
+    err_span: Option<SourceSpan>,
+
+    message: String,
+}
+
+impl Manifest {
+    /// Reads the manifest that applies to `program`: the nearest
+    /// `sanskriti.toml` in its directory or one of that directory's ancestors.
+    /// Without one, every setting is left at its default.
+    pub fn for_program(program: &Path) -> miette::Result<Self> {
+        let dir = program
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        Self::for_dir(dir)
+    }
+
+    /// Reads the manifest that applies to programs in `dir`: the nearest
+    /// `sanskriti.toml` in `dir` or one of its ancestors. Without one, every
+    /// setting is left at its default.
+    pub fn for_dir(dir: &Path) -> miette::Result<Self> {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+
+        for dir in dir.ancestors() {
+            let path = dir.join(FILE_NAME);
+            if path.is_file() {
+                return Self::read(&path);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Reads the manifest of the project that `dir` is in: the nearest
+    /// `sanskriti.toml` in `dir` or one of its ancestors, which must have a
+    /// `[project]` table.
+    pub fn for_project(dir: &Path) -> miette::Result<Self> {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let Some(path) = dir
+            .ancestors()
+            .map(|dir| dir.join(FILE_NAME))
+            .find(|path| path.is_file())
+        else {
+            return Err(miette::miette!(
+                help = format!("create a {FILE_NAME} with a [project] table naming its entry"),
+                "'{}' is not in a project",
+                dir.display()
+            ));
+        };
+
+        let manifest = Self::read(&path)?;
+        if manifest.project.is_none() {
+            return Err(miette::miette!(
+                help = "add a [project] table naming the program the project starts from",
+                "'{}' has no [project] table",
+                path.display()
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// Reads the manifest at `path`.
+    pub fn read(path: &Path) -> miette::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("reading '{}' failed", path.display()))?;
+
+        let name = path.display().to_string();
+        let mut manifest = Self::parse(&contents, &name)?;
+        manifest.path = Some(path.to_path_buf());
+        if let (Some(dialect), None) = (&manifest.dialect, manifest.pack) {
+            let file = manifest.root().join(dialect.get_ref());
+            if !file.is_file() {
+                return Err(ManifestError {
+                    err_span: Some(dialect.span().into()),
+                    message: format!(
+                        "no built-in language pack or file named '{}'; the built-in packs \
+                         are {}",
+                        dialect.get_ref(),
+                        builtin_codes()
+                    ),
+                    src: NamedSource::new(name, contents),
+                }
+                .into());
+            }
+            manifest.pack = Some(pack::load(&file)?);
+        }
+        Ok(manifest)
+    }
+
+    /// Parses the text of a manifest; errors name it `name`. A `dialect` that
+    /// is not the code of a built-in language pack is left for the caller to
+    /// load.
+    pub fn parse(contents: &str, name: &str) -> miette::Result<Self> {
+        let mut manifest: Self = toml::from_str(contents).map_err(|e| ManifestError {
+            err_span: e.span().map(SourceSpan::from),
+            message: e.message().to_string(),
+            src: NamedSource::new(name, contents.to_string()),
+        })?;
+        manifest.pack = manifest
+            .dialect
+            .as_ref()
+            .and_then(|dialect| translator::language_pack(dialect.get_ref()));
+        Ok(manifest)
+    }
+
+    /// What ends a statement, with `cli` overriding the manifest.
+    pub fn terminators(&self, cli: Option<Terminators>) -> Terminators {
+        cli.or(self.terminators).unwrap_or_default()
+    }
+
+    /// The language pack the programs are written in: the one `--dialect`
+    /// picked, else the one the manifest names, else Sanskrit.
+    pub fn language_pack(&self) -> &'static LanguagePack {
+        DIALECT.get().copied().or(self.pack).unwrap_or(&SANSKRIT)
+    }
+
+    /// What dividing by zero results in, with `cli` overriding the manifest.
+    pub fn division_by_zero(&self, cli: Option<DivisionByZero>) -> DivisionByZero {
+        cli.or(self.division_by_zero).unwrap_or_default()
+    }
+
+    /// Applies the limits of the manifest to `interpreter`, and turns on the
+    /// capabilities and strictness it lists. Limits already set, such as from
+    /// the command line, take precedence, and settings already on are left
+    /// on. A division-by-zero policy in the manifest replaces the one set.
+    pub fn configure(&self, mut interpreter: Interpreter) -> Interpreter {
+        interpreter = interpreter.fallback_limits(self.limits.to_limits());
+        if self.capabilities.eval {
+            interpreter = interpreter.allow_eval(true);
+        }
+        if self.capabilities.fs {
+            interpreter = interpreter.allow_fs(true);
+        }
+        if self.strict {
+            interpreter = interpreter.strict(true);
+        }
+        if self.implicit_declarations {
+            interpreter = interpreter.implicit_declarations(true);
+        }
+        if let Some(policy) = self.division_by_zero {
+            interpreter = interpreter.division_by_zero(policy);
+        }
+        interpreter
+    }
+
+    /// The directory of the manifest, which is the root of its project.
+    pub fn root(&self) -> &Path {
+        self.path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new("."))
+    }
+
+    /// The name of the project, if the manifest has a `[project]` table.
+    pub fn project_name(&self) -> Option<String> {
+        let project = self.project.as_ref()?;
+        let dir_name = || {
+            let root = self.root().file_name().unwrap_or(self.root().as_os_str());
+            root.to_string_lossy().into_owned()
+        };
+        Some(project.name.clone().unwrap_or_else(dir_name))
+    }
+
+    /// The program the project starts from, if the manifest has a
+    /// `[project]` table.
+    pub fn entry(&self) -> Option<PathBuf> {
+        let project = self.project.as_ref()?;
+        Some(self.root().join(&project.entry).components().collect())
+    }
+
+    /// Every program of the project, sorted by path, with the entry among
+    /// them even when it is outside the source directories. Without a
+    /// `[project]` table, there are none.
+    pub fn sources(&self) -> miette::Result<Vec<PathBuf>> {
+        let Some(project) = &self.project else {
+            return Ok(Vec::new());
+        };
+        let mut sources = Vec::new();
+        for dir in &project.source_dirs {
+            let dir: PathBuf = self.root().join(dir).components().collect();
+            if !dir.is_dir() {
+                return Err(miette::miette!(
+                    "source directory '{}' does not exist",
+                    dir.display()
+                ));
+            }
+            let found = batch::collect_sources_recursively(&dir)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("listing '{}' failed", dir.display()))?;
+            sources.extend(found);
+        }
+        let entry: PathBuf = self.root().join(&project.entry).components().collect();
+        if !sources.contains(&entry) {
+            sources.push(entry);
+        }
+        sources.sort();
+        sources.dedup();
+        Ok(sources)
+    }
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+use std::io;
+
+use crate::interpreter::Value;
+use crate::parse::{Arena, Atom, Op, TokenTree};
+use crate::unparse::Keywords;
+use crate::Interpreter;
+
+/// A rewrite of a program's syntax trees that keeps what the program does,
+/// run by [`optimize`] or on its own with [`run_pass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Pass {
+    /// Replaces arithmetic on number literals, and `+` on string literals,
+    /// with the result: `60 * 60 * 24` becomes `86400`
+    FoldConstants,
+    /// Replaces an `if` whose condition is a literal with the branch it
+    /// always takes
+    PruneBranches,
+}
+
+impl Pass {
+    /// Every pass, in the order [`optimize`] runs them; folding first lets
+    /// pruning see conditions that fold to a literal.
+    pub const ALL: [Pass; 2] = [Pass::FoldConstants, Pass::PruneBranches];
+}
+
+/// One change a pass made, as `run -O -v` reports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rewrite {
+    /// An operator on literals, in Lox source, replaced by its value.
+    Folded { expression: String, value: String },
+    /// An `if` on a literal condition replaced by the branch it takes.
+    Pruned {
+        condition: String,
+        /// `"the first branch"`, `"the else branch"`, or `"nothing"`.
+        kept: &'static str,
+    },
+}
+
+impl fmt::Display for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rewrite::Folded { expression, value } => {
+                write!(f, "folded `{expression}` into `{value}`")
+            }
+            Rewrite::Pruned { condition, kept } => {
+                write!(f, "pruned `if ({condition})`, keeping {kept}")
+            }
+        }
+    }
+}
+
+/// Runs every pass over `program`, as `run -O` does, and returns what they
+/// changed. The nodes the passes rewrite are rebuilt in `arena`, which is
+/// usually the one `program` was parsed into.
+pub fn optimize<'de>(program: &mut [TokenTree<'de>], arena: &'de Arena) -> Vec<Rewrite> {
+    Pass::ALL
+        .into_iter()
+        .flat_map(|pass| run_pass(pass, program, arena))
+        .collect()
+}
+
+/// Runs one pass over `program`, rebuilding its nodes in `arena`, and
+/// returns what it changed, innermost first.
+pub fn run_pass<'de>(
+    pass: Pass,
+    program: &mut [TokenTree<'de>],
+    arena: &'de Arena,
+) -> Vec<Rewrite> {
+    let mut rewrites = Vec::new();
+    match pass {
+        Pass::FoldConstants => {
+            // constants are computed by the interpreter itself, so that
+            // folding can't disagree with running
+            let mut interpreter = Interpreter::new().output(io::sink());
+            for statement in program {
+                *statement = rewrite(statement, arena, &mut |node| {
+                    fold(&mut interpreter, arena, node, &mut rewrites)
+                });
+            }
+        }
+        Pass::PruneBranches => {
+            for statement in program {
+                *statement = rewrite(statement, arena, &mut |node| prune(node, &mut rewrites));
+            }
+        }
+    }
+    rewrites
+}
+
+/// A copy of `node` with `f` applied to every node of it, children before
+/// their parents.
+fn rewrite<'de>(
+    node: &TokenTree<'de>,
+    arena: &'de Arena,
+    f: &mut impl FnMut(TokenTree<'de>) -> TokenTree<'de>,
+) -> TokenTree<'de> {
+    let rebuilt = match node {
+        TokenTree::Atom(_) => node.clone(),
+        TokenTree::Cons(op, children) => TokenTree::Cons(*op, all(children, arena, f)),
+        TokenTree::Fun {
+            name,
+            parameters,
+            body,
+        } => TokenTree::Fun {
+            name: *name,
+            parameters,
+            body: one(body, arena, f),
+        },
+        TokenTree::Call { callee, arguments } => TokenTree::Call {
+            callee: one(callee, arena, f),
+            arguments: all(arguments, arena, f),
+        },
+        TokenTree::If { condition, yes, no } => TokenTree::If {
+            condition: one(condition, arena, f),
+            yes: one(yes, arena, f),
+            no: no.map(|no| one(no, arena, f)),
+        },
+        TokenTree::Block { statements, value } => TokenTree::Block {
+            statements: all(statements, arena, f),
+            value: value.map(|value| one(value, arena, f)),
+        },
+        TokenTree::Statement { offset, node } => TokenTree::Statement {
+            offset: *offset,
+            node: one(node, arena, f),
+        },
+        TokenTree::Spanned { span, node } => TokenTree::Spanned {
+            span: span.clone(),
+            node: one(node, arena, f),
+        },
+        TokenTree::Assignments { targets, values } => TokenTree::Assignments {
+            targets: all(targets, arena, f),
+            values: all(values, arena, f),
+        },
+        TokenTree::Chain {
+            operands,
+            operators,
+            spans,
+        } => TokenTree::Chain {
+            operands: all(operands, arena, f),
+            operators,
+            spans,
+        },
+        TokenTree::Match {
+            subject,
+            arms,
+            default,
+        } => TokenTree::Match {
+            subject: one(subject, arena, f),
+            arms: arena.alloc_slice_fill_iter(
+                arms.iter()
+                    .map(|(literals, body)| (*literals, rewrite(body, arena, f))),
+            ),
+            default: default.map(|default| one(default, arena, f)),
+        },
+        TokenTree::ForIn {
+            variable,
+            iterable,
+            body,
+        } => TokenTree::ForIn {
+            variable: *variable,
+            iterable: one(iterable, arena, f),
+            body: one(body, arena, f),
+        },
+        TokenTree::Try {
+            body,
+            variable,
+            handler,
+        } => TokenTree::Try {
+            body: one(body, arena, f),
+            variable: *variable,
+            handler: one(handler, arena, f),
+        },
+    };
+    f(rebuilt)
+}
+
+/// [`rewrite`] applied to `node`, in `arena`.
+fn one<'de>(
+    node: &TokenTree<'de>,
+    arena: &'de Arena,
+    f: &mut impl FnMut(TokenTree<'de>) -> TokenTree<'de>,
+) -> &'de TokenTree<'de> {
+    arena.alloc(rewrite(node, arena, f))
+}
+
+/// [`rewrite`] applied to each of `nodes`, in `arena`.
+fn all<'de>(
+    nodes: &[TokenTree<'de>],
+    arena: &'de Arena,
+    f: &mut impl FnMut(TokenTree<'de>) -> TokenTree<'de>,
+) -> &'de [TokenTree<'de>] {
+    arena.alloc_slice_fill_iter(nodes.iter().map(|node| rewrite(node, arena, f)))
+}
+
+/// `node`, or its value if it is an operator on literals whose result
+/// doesn't depend on how the interpreter is configured. Strings are only
+/// ever joined to strings, since how a number is written into a string is
+/// configurable, and an operation that fails or gives `nil` or a number that
+/// can't be written as a literal (infinity, NaN) is left to fail or give it
+/// at runtime.
+fn fold<'de>(
+    interpreter: &mut Interpreter,
+    arena: &'de Arena,
+    node: TokenTree<'de>,
+    rewrites: &mut Vec<Rewrite>,
+) -> TokenTree<'de> {
+    // a span is only kept for reporting where an operator failed, so a
+    // folded operator doesn't need it
+    if let TokenTree::Spanned { node: inner, .. } = node {
+        if let TokenTree::Atom(Atom::Number(_) | Atom::String(_)) = inner {
+            return inner.clone();
+        }
+        return node;
+    }
+
+    let foldable = match &node {
+        TokenTree::Cons(Op::Plus, operands) => match &operands[..] {
+            [a, b] => matches!(
+                (literal(a), literal(b)),
+                (Some(Atom::Number(_)), Some(Atom::Number(_)))
+                    | (Some(Atom::String(_)), Some(Atom::String(_)))
+            ),
+            _ => false,
+        },
+        TokenTree::Cons(
+            Op::Minus | Op::Star | Op::StarStar | Op::Slash | Op::Percent,
+            operands,
+        ) => operands
+            .iter()
+            .all(|operand| matches!(literal(operand), Some(Atom::Number(_)))),
+        _ => false,
+    };
+    if !foldable {
+        return node;
+    }
+
+    let value = match interpreter.eval_program(std::slice::from_ref(&node)) {
+        Ok(Value::Number(n)) if n.is_finite() => TokenTree::Atom(Atom::Number(n)),
+        Ok(Value::String(s)) => TokenTree::Atom(Atom::String(arena.alloc_str(&s))),
+        _ => return node,
+    };
+    rewrites.push(Rewrite::Folded {
+        expression: node.to_source(Keywords::Lox),
+        value: value.to_source(Keywords::Lox),
+    });
+    value
+}
+
+/// `node`, or the branch it takes if it is an `if` on a literal. An `if` that
+/// takes no branch becomes an empty block, which is `nil` as an expression,
+/// as the `if` was.
+fn prune<'de>(node: TokenTree<'de>, rewrites: &mut Vec<Rewrite>) -> TokenTree<'de> {
+    let TokenTree::If { condition, yes, no } = node else {
+        return node;
+    };
+    let Some(literal) = literal(condition) else {
+        return node;
+    };
+    let (taken, kept) = match (is_truthy(literal), no) {
+        (true, _) => (Some(yes), "the first branch"),
+        (false, Some(no)) => (Some(no), "the else branch"),
+        (false, None) => (None, "nothing"),
+    };
+    rewrites.push(Rewrite::Pruned {
+        condition: condition.to_source(Keywords::Lox),
+        kept,
+    });
+    taken.cloned().unwrap_or(TokenTree::Block {
+        statements: &[],
+        value: None,
+    })
+}
+
+/// The literal `node` is, looking through parentheses.
+fn literal<'a, 'de>(node: &'a TokenTree<'de>) -> Option<&'a Atom<'de>> {
+    match node {
+        TokenTree::Atom(atom @ (Atom::Number(_) | Atom::String(_) | Atom::Bool(_) | Atom::Nil)) => {
+            Some(atom)
+        }
+        TokenTree::Cons(Op::Group, inner) => match &inner[..] {
+            [inner] => literal(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether a literal is truthy, as [`Value::is_truthy`] decides.
+fn is_truthy(literal: &Atom<'_>) -> bool {
+    !matches!(literal, Atom::Nil | Atom::Bool(false))
+}
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use miette::{Diagnostic, IntoDiagnostic, LabeledSpan, NamedSource, Severity, SourceSpan, WrapErr};
+use serde::Deserialize;
+use thiserror::Error;
+use toml::Spanned;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::lex::is_word;
+use crate::translator::{romanizations, Keyword, LanguagePack, LANGUAGE_PACKS, LOX_KEYWORDS};
+
+/// A language pack as written in a TOML file, with one `[[keyword]]` table
+/// per keyword:
+///
+/// ```toml
+/// code = "sa"
+/// name = "संस्कृतम्"
+///
+/// [[keyword]]
+/// native = "अथ्वा"
+/// synonyms = ["अथवा"]
+/// lox = "else"
+/// transliteration = "athvā"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackFile {
+    pub code: Spanned<String>,
+    pub name: Spanned<String>,
+    #[serde(rename = "keyword", default)]
+    pub keywords: Vec<KeywordEntry>,
+}
+
+/// One `[[keyword]]` table of a [`PackFile`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeywordEntry {
+    pub native: Spanned<String>,
+    #[serde(default)]
+    pub synonyms: Vec<Spanned<String>>,
+    pub lox: Spanned<String>,
+    /// The keyword in IAST, which programs can also spell it as.
+    pub transliteration: Option<Spanned<String>>,
+    #[serde(default)]
+    pub example: String,
+}
+
+impl KeywordEntry {
+    /// The canonical spelling followed by the synonyms.
+    pub fn spellings(&self) -> impl Iterator<Item = &Spanned<String>> {
+        std::iter::once(&self.native).chain(&self.synonyms)
+    }
+}
+
+#[derive(Diagnostic, Debug, Error)]
+#[error("invalid language pack")]
+pub struct PackError {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("{message}")]
+    err_span: Option<SourceSpan>,
+
+    message: String,
+}
+
+/// Reads the language pack at `path` and checks that it is safe to load,
+/// returning a report per problem found. Problems that would make the
+/// translator misread programs are errors; the rest are warnings.
+pub fn check(path: &Path) -> miette::Result<Vec<miette::Report>> {
+    let contents = read(path)?;
+    let (_, problems) = parse_and_check(&contents, &path.display().to_string())?;
+    Ok(problems)
+}
+
+/// The packs [`load`] has read, by canonical path, with the text each was
+/// parsed from.
+static LOADED: OnceLock<Mutex<HashMap<PathBuf, (String, &'static LanguagePack)>>> = OnceLock::new();
+
+/// Reads the language pack at `path` for translating programs with, as
+/// `--dialect` does. A pack with any of the errors [`check`] finds is refused;
+/// warnings are left for `lang check` to report.
+///
+/// Loading the same file again, as `run-all` and `--watch` do for each
+/// program's manifest, gives the pack already loaded, unless the file has
+/// changed since.
+pub fn load(path: &Path) -> miette::Result<&'static LanguagePack> {
+    let contents = read(path)?;
+    let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let loaded = LOADED.get_or_init(Default::default);
+    let cached = loaded
+        .lock()
+        .expect("nothing panics while holding the lock")
+        .get(&key)
+        .filter(|(text, _)| *text == contents)
+        .map(|(_, pack)| *pack);
+    if let Some(pack) = cached {
+        return Ok(pack);
+    }
+
+    let pack = parse(&contents, &path.display().to_string())
+        .wrap_err_with(|| format!("loading the language pack '{}' failed", path.display()))?;
+    loaded
+        .lock()
+        .expect("nothing panics while holding the lock")
+        .insert(key, (contents, pack));
+    Ok(pack)
+}
+
+/// Parses the text of a language pack, as [`load`] does; errors name it
+/// `name`. Like the built-in packs, a loaded pack lives until the process
+/// exits.
+pub fn parse(contents: &str, name: &str) -> miette::Result<&'static LanguagePack> {
+    let (pack, problems) = parse_and_check(contents, name)?;
+    let error = problems
+        .into_iter()
+        .find(|problem| problem.severity().unwrap_or(Severity::Error) == Severity::Error);
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    let keywords = pack
+        .keywords
+        .into_iter()
+        .map(|keyword| Keyword {
+            native: leak(keyword.native.into_inner()),
+            synonyms: Box::leak(
+                keyword
+                    .synonyms
+                    .into_iter()
+                    .map(|synonym| leak(synonym.into_inner()))
+                    .collect(),
+            ),
+            lox: leak(keyword.lox.into_inner()),
+            transliteration: leak(
+                keyword
+                    .transliteration
+                    .map(Spanned::into_inner)
+                    .unwrap_or_default(),
+            ),
+            example: leak(keyword.example),
+        })
+        .collect();
+    Ok(Box::leak(Box::new(LanguagePack {
+        code: leak(pack.code.into_inner()),
+        name: leak(pack.name.into_inner()),
+        keywords: Box::leak(keywords),
+    })))
+}
+
+fn leak(text: String) -> &'static str {
+    Box::leak(text.into_boxed_str())
+}
+
+fn read(path: &Path) -> miette::Result<String> {
+    fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("reading '{}' failed", path.display()))
+}
+
+/// Parses the text of a language pack and finds its problems, which carry
+/// the text as their source code.
+fn parse_and_check(contents: &str, name: &str) -> miette::Result<(PackFile, Vec<miette::Report>)> {
+    let src = NamedSource::new(name, contents.to_string());
+
+    let pack: PackFile = toml::from_str(contents).map_err(|e| PackError {
+        err_span: e.span().map(Into::into),
+        message: e.message().to_string(),
+        src: src.clone(),
+    })?;
+
+    let mut problems = Vec::new();
+    check_code(&pack, &mut problems);
+    check_lox(&pack, &mut problems);
+    check_spellings(&pack, &mut problems);
+
+    let problems = problems
+        .into_iter()
+        .map(|problem| problem.with_source_code(src.clone()))
+        .collect();
+    Ok((pack, problems))
+}
+
+fn check_code(pack: &PackFile, problems: &mut Vec<miette::Report>) {
+    let code = pack.code.get_ref();
+    if let Some(builtin) = LANGUAGE_PACKS.iter().find(|builtin| builtin.code == code) {
+        problems.push(miette::miette!(
+            severity = Severity::Warning,
+            labels = vec![LabeledSpan::at(pack.code.span(), "here")],
+            help = "pick a different code unless the pack is meant to replace it",
+            "the code '{code}' is already used by the built-in {} pack",
+            builtin.name
+        ));
+    }
+}
+
+/// Every Lox keyword must be spelled by exactly one keyword of the pack.
+fn check_lox(pack: &PackFile, problems: &mut Vec<miette::Report>) {
+    let mut seen: HashMap<&str, Range<usize>> = HashMap::new();
+    for keyword in &pack.keywords {
+        let lox = keyword.lox.get_ref().as_str();
+        if !LOX_KEYWORDS.contains(&lox) {
+            problems.push(miette::miette!(
+                labels = vec![LabeledSpan::at(keyword.lox.span(), "not a Lox keyword")],
+                help = format!("the Lox keywords are {}", LOX_KEYWORDS.join(", ")),
+                "unknown Lox keyword '{lox}'"
+            ));
+        } else if let Some(first) = seen.get(lox) {
+            problems.push(miette::miette!(
+                labels = vec![
+                    LabeledSpan::at(first.clone(), "first mapped here"),
+                    LabeledSpan::at(keyword.lox.span(), "and again here"),
+                ],
+                help = "keep one keyword and list the other spellings as its synonyms",
+                "'{lox}' is mapped by more than one keyword"
+            ));
+        } else {
+            seen.insert(lox, keyword.lox.span());
+        }
+    }
+
+    let missing: Vec<&str> = LOX_KEYWORDS
+        .iter()
+        .copied()
+        .filter(|lox| !seen.contains_key(lox))
+        .collect();
+    if !missing.is_empty() {
+        problems.push(miette::miette!(
+            labels = vec![LabeledSpan::at(pack.code.span(), "in this pack")],
+            help = "add a [[keyword]] table for each of them",
+            "no spelling for {}",
+            missing.join(", ")
+        ));
+    }
+}
+
+/// Every spelling must be a single identifier in normalization form C, and
+/// no two keywords may share one. The romanizations of a transliteration
+/// count as spellings.
+fn check_spellings(pack: &PackFile, problems: &mut Vec<miette::Report>) {
+    // every spelling, where it is written, and the Lox keyword it spells
+    let mut spellings: Vec<(String, Range<usize>, &str)> = Vec::new();
+    for keyword in &pack.keywords {
+        let lox = keyword.lox.get_ref().as_str();
+        for spelling in keyword.spellings() {
+            spellings.push((spelling.get_ref().clone(), spelling.span(), lox));
+        }
+        let Some(transliteration) = &keyword.transliteration else {
+            continue;
+        };
+        let text = transliteration.get_ref();
+        if !text.is_empty() && !is_word(text) {
+            problems.push(miette::miette!(
+                severity = Severity::Warning,
+                labels = vec![LabeledSpan::at(transliteration.span(), "here")],
+                help = "only a transliteration that is a single word can be used in programs",
+                "'{}' is not a single word, so programs can't spell '{lox}' with it",
+                text.escape_debug()
+            ));
+        }
+        for romanization in romanizations(text) {
+            spellings.push((romanization, transliteration.span(), lox));
+        }
+    }
+
+    let mut seen: HashMap<&str, (Range<usize>, &str)> = HashMap::new();
+    for (text, span, lox) in &spellings {
+        let (text, lox) = (text.as_str(), *lox);
+        let here = || vec![LabeledSpan::at(span.clone(), "here")];
+
+        if !is_nfc(text) {
+            problems.push(miette::miette!(
+                labels = here(),
+                help = format!(
+                    "its code points are {}; in NFC they are {}",
+                    code_points(text.chars()),
+                    code_points(text.nfc())
+                ),
+                "'{text}' is not in Unicode normalization form C"
+            ));
+        } else if !is_word(text) {
+            problems.push(miette::miette!(
+                labels = here(),
+                help = "spellings are matched as whole words, so they can't be empty or \
+                        contain spaces, punctuation, or joiners",
+                "'{}' is not a single word",
+                text.escape_debug()
+            ));
+        } else if LOX_KEYWORDS.contains(&text) {
+            problems.push(miette::miette!(
+                labels = here(),
+                help = "programs can already use the Lox keywords as they are",
+                "'{text}' is a Lox keyword"
+            ));
+        }
+
+        match seen.get(text) {
+            Some((first, other)) => problems.push(miette::miette!(
+                labels = vec![
+                    LabeledSpan::at(first.clone(), "first spelled here"),
+                    LabeledSpan::at(span.clone(), "and again here"),
+                ],
+                "'{text}' spells {}",
+                if *other == lox {
+                    format!("'{lox}' twice")
+                } else {
+                    format!("both '{other}' and '{lox}'")
+                }
+            )),
+            None => {
+                seen.insert(text, (span.clone(), lox));
+            }
+        }
+    }
+
+    // Whole-word matching keeps a spelling apart from a longer one that
+    // starts with it, but when the rest of the longer one is only vowel
+    // signs or a virama the two are easy to mix up when reading or typing.
+    let mut spellings: Vec<_> = seen.into_iter().collect();
+    spellings.sort_by_key(|(_, (span, _))| span.start);
+    for (short, (short_span, short_lox)) in &spellings {
+        for (long, (long_span, long_lox)) in &spellings {
+            let Some(marks) = long.strip_prefix(short) else {
+                continue;
+            };
+            if short_lox != long_lox && !marks.is_empty() && marks.chars().all(is_combining_mark) {
+                problems.push(miette::miette!(
+                    severity = Severity::Warning,
+                    labels = vec![
+                        LabeledSpan::at(short_span.clone(), format!("'{short_lox}'")),
+                        LabeledSpan::at(long_span.clone(), format!("'{long_lox}'")),
+                    ],
+                    help = "consider a spelling that differs by more than a mark",
+                    "'{short}' and '{long}' differ only by combining marks"
+                ));
+            }
+        }
+    }
+}
+
+fn code_points(chars: impl Iterator<Item = char>) -> String {
+    chars
+        .map(|c| format!("U+{:04X}", u32::from(c)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,22 +1,88 @@
 use crate::{
-    lex::{Token, TokenKind},
-    Lexer,
+    lex::{Terminators, Token, TokenKind},
+    message, Lexer,
 };
-use miette::{Error, LabeledSpan, WrapErr};
-use std::{borrow::Cow, fmt};
+use bumpalo::collections::Vec as BumpVec;
+use miette::{Diagnostic, Error, LabeledSpan, WrapErr};
+use std::{borrow::Cow, fmt, ops::Range};
+use thiserror::Error;
+
+/// Where a [`Parser`] puts the nodes of the trees it builds. Parsing a whole
+/// program fills one arena instead of making an allocation per node, and the
+/// trees borrow from it, so it must outlive them; dropping it frees them all
+/// at once.
+pub use bumpalo::Bump as Arena;
 
 pub struct Parser<'de> {
     whole: &'de str,
+    arena: &'de Arena,
     lexer: Lexer<'de>,
+    terminators: Terminators,
+    /// How many statements and expressions are being parsed, one inside the
+    /// next.
+    depth: usize,
 }
 
-pub struct Ast;
+/// How deeply statements and expressions may nest before the parser gives up
+/// rather than overflow its stack.
+const MAX_NESTING: usize = 4000;
+
+#[derive(Diagnostic, Debug, Error)]
+#[error("{}", message!("E0100", count = errors.len()))]
+#[diagnostic(code(E0100))]
+pub struct ParseErrors {
+    #[related]
+    pub errors: Vec<Error>,
+}
 
 impl<'de> Parser<'de> {
-    pub fn new(input: &'de str) -> Self {
+    /// A parser for `input` that builds its trees in `arena`.
+    pub fn new(input: &'de str, arena: &'de Arena) -> Self {
         Self {
             whole: input,
+            arena,
             lexer: Lexer::new(input),
+            terminators: Terminators::default(),
+            depth: 0,
+        }
+    }
+
+    /// A parser for `tokens`, such as ones read back from `tokenize --format
+    /// json`, instead of the tokens of `source`. See [`Lexer::from_tokens`].
+    pub fn from_tokens(source: &'de str, tokens: Vec<Token<'de>>, arena: &'de Arena) -> Self {
+        Self {
+            whole: source,
+            arena,
+            lexer: Lexer::from_tokens(source, tokens),
+            terminators: Terminators::default(),
+            depth: 0,
+        }
+    }
+
+    /// Sets what ends a statement; see [`Lexer::terminators`] for when a line
+    /// break does.
+    pub fn terminators(mut self, terminators: Terminators) -> Self {
+        self.lexer = self.lexer.terminators(terminators);
+        self.terminators = terminators;
+        self
+    }
+
+    /// Moves `node` into the arena.
+    fn alloc(&self, node: TokenTree<'de>) -> &'de TokenTree<'de> {
+        self.arena.alloc(node)
+    }
+
+    /// Moves `items` into the arena, one after another.
+    fn slice<T>(&self, items: impl IntoIterator<Item = T>) -> &'de [T] {
+        slice_in(self.arena, items)
+    }
+
+    /// Keeps the text of a string literal in the arena, if it isn't already
+    /// part of the source.
+    fn string(&self, text: Cow<'de, str>) -> &'de str {
+        match text {
+            Cow::Borrowed(text) => text,
+            Cow::Owned(text) => self.arena.alloc_str(&text),
         }
     }
 
@@ -24,86 +90,798 @@ impl<'de> Parser<'de> {
         self.parse_expression_within(0)
     }
 
+    /// Parses the whole input as one expression, optionally followed by
+    /// semicolons, and fails if anything else is left over.
+    pub fn parse_complete_expression(mut self) -> Result<TokenTree<'de>, Error> {
+        let expr = self.parse_expression_within(0)?;
+        self.skip_semicolons();
+
+        match self.lexer.next() {
+            None => Ok(expr),
+            Some(Err(e)) => Err(e),
+            Some(Ok(token)) => Err(miette::miette! {
+                labels = vec![
+                    LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here"),
+                ],
+                code = "E0102",
+                help = message!("unexpected-token", token = format!("{token:?}")),
+                "{}", message!("E0102"),
+            }
+            .with_source_code(self.whole.to_string())),
+        }
+    }
+
     pub fn parse(mut self) -> Result<TokenTree<'de>, Error> {
         self.parse_statement_within(0)
     }
 
+    /// Parses a whole program, recovering from syntax errors at statement
+    /// boundaries so that every error in the file is reported at once.
     pub fn parse_program(mut self) -> Result<Vec<TokenTree<'de>>, Error> {
         let mut statements = Vec::new();
+        let mut errors = Vec::new();
 
         loop {
             match self.lexer.peek() {
                 None => break,
-                Some(Ok(Token { kind: TokenKind::Semicolon, .. })) => {
+                Some(Ok(Token {
+                    kind: TokenKind::Semicolon,
+                    ..
+                })) => {
                     // skip stray semicolons between statements
                     self.lexer.next();
                 }
-                Some(Ok(_)) => {
-                    let stmt = self.parse_statement_within(0)?;
-                    statements.push(stmt);
+                Some(Ok(_)) => match self.parse_located_statement() {
+                    Ok(stmt) => {
+                        statements.push(stmt);
+                        self.skip_semicolons();
+                    }
+                    Err(e) => {
+                        errors.push(e);
+                        self.synchronize(&mut errors);
+                    }
+                },
+                Some(Err(_)) => {
+                    let e = self
+                        .lexer
+                        .next()
+                        .expect("checked Some above")
+                        .expect_err("checked Err above");
+                    errors.push(e);
+                }
+            }
+        }
 
-                    // consume any trailing semicolons after a statement
-                    loop {
-                        match self.lexer.peek() {
-                            Some(Ok(Token { kind: TokenKind::Semicolon, .. })) => {
-                                self.lexer.next();
-                            }
-                            _ => break,
-                        }
+        if errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(ParseErrors { errors }.into())
+        }
+    }
+
+    /// Consumes any semicolons following a statement.
+    fn skip_semicolons(&mut self) {
+        while let Some(Ok(Token {
+            kind: TokenKind::Semicolon,
+            ..
+        })) = self.lexer.peek()
+        {
+            self.lexer.next();
+        }
+    }
+
+    /// Panic-mode recovery: discards tokens until just past the end of the
+    /// statement that failed to parse, or until the start of the next one.
+    ///
+    /// Lexer errors encountered along the way are collected into `errors`.
+    fn synchronize(&mut self, errors: &mut Vec<Error>) {
+        let mut depth = 0usize;
+
+        while let Some(next) = self.lexer.peek() {
+            let kind = match next {
+                Ok(token) => token.kind,
+                Err(_) => {
+                    let e = self
+                        .lexer
+                        .next()
+                        .expect("checked Some above")
+                        .expect_err("checked Err above");
+                    errors.push(e);
+                    continue;
+                }
+            };
+
+            match kind {
+                TokenKind::Class
+                | TokenKind::Fun
+                | TokenKind::Var
+                | TokenKind::Const
+                | TokenKind::For
+                | TokenKind::If
+                | TokenKind::Match
+                | TokenKind::Try
+                | TokenKind::While
+                | TokenKind::Print
+                | TokenKind::Return
+                | TokenKind::Throw
+                    if depth == 0 =>
+                {
+                    return;
+                }
+                TokenKind::Semicolon if depth == 0 => {
+                    self.lexer.next();
+                    return;
+                }
+                TokenKind::LeftBrace => {
+                    depth += 1;
+                    self.lexer.next();
+                }
+                TokenKind::RightBrace => {
+                    self.lexer.next();
+                    if depth <= 1 {
+                        // either closes the block we failed inside of, or
+                        // the block that the failed statement opened
+                        return;
                     }
+                    depth -= 1;
                 }
-                Some(Err(_)) => {
-                    return Err(miette::miette!("lexer error while parsing program"));
+                _ => {
+                    self.lexer.next();
                 }
             }
         }
+    }
 
-        Ok(statements)
+    /// Parses a statement, recording where in the source it starts so that
+    /// the interpreter can report it, for example to a debugger.
+    fn parse_located_statement(&mut self) -> Result<TokenTree<'de>, Error> {
+        let offset = match self.lexer.peek() {
+            Some(Ok(token)) => token.offset,
+            _ => self.whole.len(),
+        };
+        let node = self.parse_statement_within(0)?;
+        Ok(TokenTree::Statement {
+            offset,
+            node: self.alloc(node),
+        })
     }
 
     pub fn parse_block(&mut self) -> Result<TokenTree<'de>, Error> {
-        self.lexer.expect(TokenKind::LeftBrace, "missing {")?;
+        self.lexer
+            .expect(TokenKind::LeftBrace, "E0104/missing-left-brace")?;
+        self.parse_block_body()
+    }
 
-        let mut statements = Vec::new();
+    /// The error for `token` standing where an operator should. Outside of
+    /// brackets, that usually means the statement before it was complete and
+    /// is missing its `;`, so the error points at where the `;` would go.
+    fn missing_operator(&self, token: Token<'de>) -> Error {
+        let here = LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here");
+        if self.lexer.in_brackets() {
+            return miette::miette! {
+                labels = vec![here],
+                code = "E0103",
+                help = message!("unexpected-token", token = format!("{token:?}")),
+                "{}", message!("E0103"),
+            }
+            .with_source_code(self.whole.to_string());
+        }
+
+        let end = self.lexer.consumed_end();
+        let line = self.whole[..end].matches('\n').count() + 1;
+        let help = if !self.whole[end..token.offset].contains('\n') {
+            if self.terminators == Terminators::Newlines {
+                message!("E0103-help-same-line-newlines")
+            } else {
+                message!("E0103-help-same-line")
+            }
+        } else if self.terminators == Terminators::Semicolons {
+            message!("E0103-help-next-line-semicolons", line = line)
+        } else {
+            message!("E0103-help-next-line", line = line)
+        };
+        miette::miette! {
+            labels = vec![here, LabeledSpan::at_offset(end, "expected `;` here")],
+            code = "E0103",
+            help = help,
+            "{}", message!("E0103"),
+        }
+        .with_source_code(self.whole.to_string())
+    }
+
+    /// Parses the statements of a block up to and including its `}`. If the
+    /// last one is an expression that is not followed by a semicolon, it
+    /// becomes the value of the block, as in `{ चर क = 2; क * क }`.
+    fn parse_block_body(&mut self) -> Result<TokenTree<'de>, Error> {
+        let mut statements = BumpVec::new_in(self.arena);
+        let mut value = None;
 
         loop {
             match self.lexer.peek() {
-                Some(Ok(Token { kind: TokenKind::RightBrace, .. })) => {
+                Some(Ok(Token {
+                    kind: TokenKind::RightBrace,
+                    ..
+                })) => {
                     self.lexer.next();
                     break;
                 }
-                Some(Ok(Token { kind: TokenKind::Semicolon, .. })) => {
+                Some(Ok(Token {
+                    kind: TokenKind::Semicolon,
+                    ..
+                })) => {
                     // skip empty statements inside block
                     self.lexer.next();
                 }
                 Some(Ok(_)) => {
-                    let stmt = self.parse_statement_within(0)?;
-                    statements.push(stmt);
-
-                    // consume any trailing semicolons after a statement
-                    loop {
-                        match self.lexer.peek() {
-                            Some(Ok(Token { kind: TokenKind::Semicolon, .. })) => {
-                                self.lexer.next();
-                            }
-                            _ => break,
-                        }
+                    let stmt = self.parse_located_statement()?;
+                    let ends_block = matches!(
+                        self.lexer.peek(),
+                        Some(Ok(Token {
+                            kind: TokenKind::RightBrace,
+                            ..
+                        }))
+                    );
+                    if ends_block && stmt.is_expression() {
+                        let TokenTree::Statement { node, .. } = stmt else {
+                            unreachable!("parse_located_statement always wraps")
+                        };
+                        value = Some(node);
+                    } else {
+                        statements.push(stmt);
+                        self.skip_semicolons();
                     }
                 }
                 Some(Err(_)) => {
-                    return Err(miette::miette!("lexer error while parsing block"));
+                    return Err(self
+                        .lexer
+                        .next()
+                        .expect("checked Some above")
+                        .expect_err("checked Err above"))
+                    .wrap_err_with(|| message!("in-block"));
+                }
+                None => {
+                    return Err(miette::miette!(
+                        code = "E0105",
+                        "{}",
+                        message!("E0105/block")
+                    ));
+                }
+            }
+        }
+
+        Ok(TokenTree::Block {
+            statements: statements.into_bump_slice(),
+            value,
+        })
+    }
+
+    /// Parses the rest of an `if` after the keyword. As an expression, its
+    /// value is that of the branch taken, or `nil` if no branch is.
+    fn parse_if(&mut self) -> Result<TokenTree<'de>, Error> {
+        self.lexer
+            .expect(TokenKind::LeftParen, "E0104/missing-left-paren")
+            .wrap_err_with(|| message!("in-if-condition"))?;
+
+        let cond = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-if-condition"))?;
+
+        self.lexer
+            .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+            .wrap_err_with(|| message!("in-if-condition"))?;
+
+        let block = self
+            .parse_block()
+            .wrap_err_with(|| message!("in-if-body"))?;
+
+        let mut otherwise = None;
+        if matches!(
+            self.lexer.peek(),
+            Some(Ok(Token {
+                kind: TokenKind::Else,
+                ..
+            }))
+        ) {
+            self.lexer.next();
+
+            otherwise = Some(
+                self.parse_block()
+                    .wrap_err_with(|| message!("in-else-body"))?,
+            );
+        }
+
+        Ok(TokenTree::If {
+            condition: self.alloc(cond),
+            yes: self.alloc(block),
+            no: otherwise.map(|no| self.alloc(no)),
+        })
+    }
+
+    /// Parses the rest of a `match` after the keyword: the value matched, in
+    /// parentheses, and then the arms in braces. Each arm is one or more
+    /// literals separated by commas, or `default`, followed by a colon and a
+    /// block. The `default` arm, if any, comes last. As an expression, its
+    /// value is that of the arm taken, or `nil` if none is.
+    fn parse_match(&mut self) -> Result<TokenTree<'de>, Error> {
+        self.lexer
+            .expect(TokenKind::LeftParen, "E0104/missing-left-paren")
+            .wrap_err_with(|| message!("in-match-value"))?;
+        let subject = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-match-value"))?;
+        self.lexer
+            .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+            .wrap_err_with(|| message!("in-match-value"))?;
+        self.lexer
+            .expect(TokenKind::LeftBrace, "E0104/missing-left-brace")
+            .wrap_err_with(|| message!("in-match-arms"))?;
+
+        let mut arms = BumpVec::new_in(self.arena);
+        let mut default = None;
+        loop {
+            let token = match self.lexer.peek() {
+                Some(Ok(token)) => *token,
+                Some(Err(_)) => {
+                    return Err(self
+                        .lexer
+                        .next()
+                        .expect("checked Some above")
+                        .expect_err("checked Err above"))
+                    .wrap_err_with(|| message!("in-match-arms"));
                 }
                 None => {
-                    return Err(miette::miette!("unterminated block"));
+                    return Err(miette::miette!(
+                        code = "E0105",
+                        "{}",
+                        message!("E0105/match")
+                    ));
+                }
+            };
+            if default.is_some() && token.kind != TokenKind::RightBrace {
+                return Err(miette::miette! {
+                    labels = vec![LabeledSpan::at(token_span(&token), "this arm")],
+                    code = "E0106",
+                    help = message!("E0106-help"),
+                    "{}",
+                    message!("E0106"),
                 }
+                .with_source_code(self.whole.to_string()));
             }
+            match token.kind {
+                TokenKind::RightBrace => {
+                    self.lexer.next();
+                    break;
+                }
+                TokenKind::Default => {
+                    self.lexer.next();
+                    self.lexer
+                        .expect(TokenKind::Colon, "E0104/missing-colon")
+                        .wrap_err_with(|| message!("in-match-default"))?;
+                    let body = self
+                        .parse_block()
+                        .wrap_err_with(|| message!("in-match-default"))?;
+                    default = Some(self.alloc(body));
+                }
+                _ => {
+                    let mut literals = BumpVec::new_in(self.arena);
+                    loop {
+                        literals.push(self.parse_match_literal()?);
+                        if self.lexer.peek().is_some_and(|token| {
+                            matches!(
+                                token,
+                                Ok(Token {
+                                    kind: TokenKind::Comma,
+                                    ..
+                                })
+                            )
+                        }) {
+                            self.lexer.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    let arm = arms.len() + 1;
+                    self.lexer
+                        .expect(TokenKind::Colon, "E0104/missing-colon")
+                        .wrap_err_with(|| message!("in-match-arm-number", number = arm))?;
+                    let body = self
+                        .parse_block()
+                        .wrap_err_with(|| message!("in-match-arm-number", number = arm))?;
+                    arms.push((literals.into_bump_slice(), body));
+                }
+            }
+        }
+
+        Ok(TokenTree::Match {
+            subject: self.alloc(subject),
+            arms: arms.into_bump_slice(),
+            default,
+        })
+    }
+
+    /// Parses a literal that a `match` arm compares against: a number
+    /// (possibly negative), a string without interpolation, a boolean, or
+    /// `nil`.
+    fn parse_match_literal(&mut self) -> Result<Atom<'de>, Error> {
+        let start = match self.lexer.peek() {
+            Some(Ok(token)) => token.offset,
+            _ => self.lexer.consumed_end(),
+        };
+        let mut literal = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-match-arm"))?;
+        if let TokenTree::Spanned { node, .. } = literal {
+            literal = node.clone();
+        }
+        match literal {
+            TokenTree::Atom(
+                atom @ (Atom::Number(_) | Atom::String(_) | Atom::Bool(_) | Atom::Nil),
+            ) => Ok(atom),
+            TokenTree::Cons(Op::Minus, operand)
+                if matches!(operand[..], [TokenTree::Atom(Atom::Number(_))]) =>
+            {
+                let [TokenTree::Atom(Atom::Number(n))] = operand[..] else {
+                    unreachable!("matched by the guard")
+                };
+                Ok(Atom::Number(-n))
+            }
+            other => Err(miette::miette! {
+                labels = vec![
+                    LabeledSpan::at(start..self.lexer.consumed_end(), "not a literal"),
+                ],
+                code = "E0107",
+                help = message!("E0107-help"),
+                "{}",
+                message!("E0107", arm = other),
+            }
+            .with_source_code(self.whole.to_string())),
         }
+    }
 
-        Ok(TokenTree::Cons(Op::Group, statements))
+    /// Parses the rest of a `try` after the keyword: a block, then `catch`
+    /// with the name of the variable that holds what was thrown, in
+    /// parentheses, and the block that handles it.
+    fn parse_try(&mut self) -> Result<TokenTree<'de>, Error> {
+        let body = self
+            .parse_block()
+            .wrap_err_with(|| message!("in-try-body"))?;
+        self.lexer
+            .expect(TokenKind::Catch, "E0104/missing-catch")
+            .wrap_err_with(|| message!("after-try-body"))?;
+        self.lexer
+            .expect(TokenKind::LeftParen, "E0104/missing-left-paren")
+            .wrap_err_with(|| message!("in-catch-variable"))?;
+        let variable = self
+            .lexer
+            .expect(TokenKind::Ident, "E0104/variable")
+            .wrap_err_with(|| message!("in-catch-variable"))?;
+        self.lexer
+            .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+            .wrap_err_with(|| message!("in-catch-variable"))?;
+        let handler = self
+            .parse_block()
+            .wrap_err_with(|| message!("in-catch-body"))?;
+
+        Ok(TokenTree::Try {
+            body: self.alloc(body),
+            variable: Atom::Ident(variable.origin, variable.offset),
+            handler: self.alloc(handler),
+        })
     }
 
-    pub fn parse_fun_call_arguments(&mut self) -> Result<Vec<TokenTree<'de>>, Error> {
-        let mut arguments = Vec::new();
+    /// Parses the rest of an interpolated string that starts with `head`, as
+    /// a left-leaning `+` chain: `"a ${x} b"` becomes `(+ (+ "a " (group x)) " b")`.
+    /// Since `+` with a string on the left stringifies its right-hand side, the
+    /// embedded expressions can be of any type.
+    fn parse_interpolation(&mut self, head: &'de str) -> Result<TokenTree<'de>, Error> {
+        let mut tree = TokenTree::Atom(Atom::String(self.string(Token::unescape(head))));
+
+        loop {
+            let expr = self
+                .parse_expression_within(0)
+                .wrap_err_with(|| message!("in-interpolated-expression"))?;
+            let group = TokenTree::Cons(Op::Group, self.slice([expr]));
+            tree = TokenTree::Cons(Op::Plus, self.slice([tree, group]));
+
+            let part = self.lexer.expect_where(
+                |token| matches!(token.kind, TokenKind::StringMiddle | TokenKind::StringTail),
+                "E0104/missing-interpolation-end",
+            )?;
+            let text = self.string(Token::unescape(part.origin));
+            if !text.is_empty() {
+                tree = TokenTree::Cons(
+                    Op::Plus,
+                    self.slice([tree, TokenTree::Atom(Atom::String(text))]),
+                );
+            }
+
+            if part.kind == TokenKind::StringTail {
+                return Ok(tree);
+            }
+        }
+    }
+
+    /// Parses the variable after a prefix `++` or `--`, whose token `kind` has
+    /// already been consumed.
+    fn parse_prefix_increment(&mut self, kind: TokenKind) -> Result<TokenTree<'de>, Error> {
+        let op = match kind {
+            TokenKind::PlusPlus => Op::PreIncrement,
+            TokenKind::MinusMinus => Op::PreDecrement,
+            _ => unreachable!("only called for ++ and --"),
+        };
+        let ident = self
+            .lexer
+            .expect(TokenKind::Ident, "E0104/variable")
+            .wrap_err_with(|| message!("in-prefix-operand", op = op))?;
+
+        Ok(TokenTree::Cons(
+            op,
+            self.slice([TokenTree::Atom(Atom::Ident(ident.origin, ident.offset))]),
+        ))
+    }
+
+    /// Parses what a `var` declares: a variable name, or a list of them like
+    /// `[x, y]` that the items of a list are unpacked into.
+    fn parse_declaration_target(&mut self) -> Result<TokenTree<'de>, Error> {
+        if matches!(
+            self.lexer.peek(),
+            Some(Ok(Token {
+                kind: TokenKind::LeftBracket,
+                ..
+            }))
+        ) {
+            self.lexer.next();
+            let names = self
+                .parse_list_items()
+                .wrap_err_with(|| message!("in-declaration"))?;
+            if let Some(name) = names
+                .iter()
+                .find(|t| !matches!(t, TokenTree::Atom(Atom::Ident(..))))
+            {
+                return Err(miette::miette!(
+                    code = "E0108",
+                    help = message!("E0108/declaration-help"),
+                    "{}",
+                    message!("E0108/declaration", target = name),
+                ));
+            }
+            return Ok(TokenTree::Cons(Op::List, names));
+        }
+
+        let token = self
+            .lexer
+            .expect(TokenKind::Ident, "E0104/identifier")
+            .wrap_err_with(|| message!("in-declaration"))?;
+        Ok(TokenTree::Atom(Atom::Ident(token.origin, token.offset)))
+    }
+
+    /// Parses the rest of `a, b = b, a` after its first target. Every value is
+    /// evaluated before any variable is assigned, so this swaps `a` and `b`.
+    fn parse_multiple_assignment(
+        &mut self,
+        first: TokenTree<'de>,
+    ) -> Result<TokenTree<'de>, Error> {
+        let mut targets = BumpVec::new_in(self.arena);
+        targets.push(first);
+        while matches!(
+            self.lexer.peek(),
+            Some(Ok(Token {
+                kind: TokenKind::Comma,
+                ..
+            }))
+        ) {
+            self.lexer.next();
+            let target = self
+                .lexer
+                .expect(TokenKind::Ident, "E0104/variable")
+                .wrap_err_with(|| message!("in-assignment-target", number = targets.len() + 1))?;
+            targets.push(TokenTree::Atom(Atom::Ident(target.origin, target.offset)));
+        }
+        if let Some(target) = targets
+            .iter()
+            .find(|t| !matches!(t, TokenTree::Atom(Atom::Ident(..))))
+        {
+            return Err(miette::miette!(
+                code = "E0108",
+                help = message!("E0108/assignment-help"),
+                "{}",
+                message!("E0108/assignment", target = target),
+            ));
+        }
+
+        let equal = self
+            .lexer
+            .expect(TokenKind::Equal, "E0104/missing-equal")
+            .wrap_err_with(|| message!("in-multiple-assignment"))?;
+
+        let mut values = BumpVec::new_in(self.arena);
+        loop {
+            let value = self
+                .parse_expression_within(0)
+                .wrap_err_with(|| message!("in-assignment-value", number = values.len() + 1))?;
+            values.push(value);
+            if !matches!(
+                self.lexer.peek(),
+                Some(Ok(Token {
+                    kind: TokenKind::Comma,
+                    ..
+                }))
+            ) {
+                break;
+            }
+            self.lexer.next();
+        }
+
+        if targets.len() != values.len() {
+            return Err(miette::miette! {
+                labels = vec![LabeledSpan::at(equal.offset..equal.offset + 1, "here")],
+                code = "E0109",
+                help = message!("E0109-help"),
+                "{}",
+                message!("E0109", values = values.len(), targets = targets.len()),
+            }
+            .with_source_code(self.whole.to_string()));
+        }
+
+        Ok(TokenTree::Assignments {
+            targets: targets.into_bump_slice(),
+            values: values.into_bump_slice(),
+        })
+    }
+
+    /// Parses the rest of `for (variable in iterable) { … }` once `variable`
+    /// has been parsed and `in` is next.
+    fn parse_for_in(&mut self, variable: TokenTree<'de>) -> Result<TokenTree<'de>, Error> {
+        let token = self
+            .lexer
+            .next()
+            .expect("peeked Some above")
+            .expect("peeked Ok above");
+        let TokenTree::Atom(variable @ Atom::Ident(..)) = variable else {
+            return Err(miette::miette! {
+                labels = vec![LabeledSpan::at(token_span(&token), "before this")],
+                code = "E0110",
+                help = message!("E0110-help"),
+                "{}",
+                message!("E0110", variable = variable),
+            }
+            .with_source_code(self.whole.to_string()));
+        };
+
+        let iterable = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-for-in-collection"))?;
+
+        self.lexer
+            .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+            .wrap_err_with(|| message!("in-for-in"))?;
+
+        let body = self
+            .parse_block()
+            .wrap_err_with(|| message!("in-for-in-body"))?;
+
+        Ok(TokenTree::ForIn {
+            variable,
+            iterable: self.alloc(iterable),
+            body: self.alloc(body),
+        })
+    }
+
+    /// Parses the rest of `for (var i = 0; i < n; i++) { … }` once `for (`
+    /// has been parsed and `var` is next. The variable is declared like any
+    /// other, in the scope the loop is in.
+    fn parse_for(&mut self) -> Result<TokenTree<'de>, Error> {
+        let declaration = self
+            .lexer
+            .next()
+            .expect("peeked Some above")
+            .expect("peeked Ok above");
+        if declaration.kind == TokenKind::Const {
+            return Err(miette::miette! {
+                labels = vec![LabeledSpan::at(token_span(&declaration), "here")],
+                code = "E0112",
+                help = message!("E0112/constant-help"),
+                "{}",
+                message!("E0112/constant"),
+            }
+            .with_source_code(self.whole.to_string()));
+        }
+
+        let target = self
+            .parse_declaration_target()
+            .wrap_err_with(|| message!("in-for-init"))?;
+        if let Some(Ok(Token {
+            kind: TokenKind::In,
+            ..
+        })) = self.lexer.peek()
+        {
+            return Err(miette::miette! {
+                labels = vec![LabeledSpan::at(token_span(&declaration), "here")],
+                code = "E0112",
+                help = message!("E0112/for-in-help"),
+                "{}",
+                message!("E0112/for-in"),
+            }
+            .with_source_code(self.whole.to_string()));
+        }
+        self.lexer
+            .expect(TokenKind::Equal, "E0104/missing-equal")
+            .wrap_err_with(|| message!("in-for-init"))?;
+        let value = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-for-init"))?;
+        let init = TokenTree::Cons(Op::Var, self.slice([target, value]));
+
+        self.parse_for_rest(init)
+    }
+
+    /// Parses the rest of `for (init; condition; increment) { … }` once
+    /// `init` has been parsed and `;` is next.
+    fn parse_for_rest(&mut self, init: TokenTree<'de>) -> Result<TokenTree<'de>, Error> {
+        self.lexer
+            .expect(TokenKind::Semicolon, "E0104/missing-semicolon")
+            .wrap_err_with(|| message!("in-for-header"))?;
+
+        let cond = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-for-condition"))?;
+
+        self.lexer
+            .expect(TokenKind::Semicolon, "E0104/missing-semicolon")
+            .wrap_err_with(|| message!("in-for-header"))?;
+
+        let inc = self
+            .parse_expression_within(0)
+            .wrap_err_with(|| message!("in-for-increment"))?;
+
+        self.lexer
+            .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+            .wrap_err_with(|| message!("in-for-header"))?;
+
+        let block = self
+            .parse_block()
+            .wrap_err_with(|| message!("in-for-body"))?;
+
+        Ok(TokenTree::Cons(
+            Op::For,
+            self.slice([init, cond, inc, block]),
+        ))
+    }
+
+    /// Parses the items of a list literal up to and including its `]`. A
+    /// trailing comma is allowed, as in `[1, 2, 3,]`.
+    fn parse_list_items(&mut self) -> Result<&'de [TokenTree<'de>], Error> {
+        let mut items = BumpVec::new_in(self.arena);
+
+        loop {
+            if matches!(
+                self.lexer.peek(),
+                Some(Ok(Token {
+                    kind: TokenKind::RightBracket,
+                    ..
+                }))
+            ) {
+                self.lexer.next();
+                return Ok(items.into_bump_slice());
+            }
+
+            let item = self
+                .parse_expression_within(0)
+                .wrap_err_with(|| message!("in-list-item", number = items.len() + 1))?;
+            items.push(item);
+
+            let token = self.lexer.expect_where(
+                |token| matches!(token.kind, TokenKind::RightBracket | TokenKind::Comma),
+                "E0104/missing-comma-or-bracket",
+            )?;
+            if token.kind == TokenKind::RightBracket {
+                return Ok(items.into_bump_slice());
+            }
+        }
+    }
+
+    pub fn parse_fun_call_arguments(&mut self) -> Result<&'de [TokenTree<'de>], Error> {
+        let mut arguments = BumpVec::new_in(self.arena);
 
         // parent has already eaten left paren as the operator
 
@@ -115,20 +893,21 @@ impl<'de> Parser<'de> {
             }))
         ) {
             // immediate argument list end
+            self.lexer.next();
         } else {
             loop {
-                let argument = self.parse_expression_within(0).wrap_err_with(|| {
-                    format!("in argument #{} of function call", arguments.len() + 1)
-                })?;
+                let argument = self
+                    .parse_expression_within(0)
+                    .wrap_err_with(|| message!("in-argument", number = arguments.len() + 1))?;
                 arguments.push(argument);
 
                 let token = self
                     .lexer
                     .expect_where(
                         |token| matches!(token.kind, TokenKind::RightParen | TokenKind::Comma),
-                        "continuing argument list",
+                        "E0104/missing-comma-or-paren",
                     )
-                    .wrap_err("in argument list of function call")?;
+                    .wrap_err_with(|| message!("in-arguments"))?;
 
                 if token.kind == TokenKind::RightParen {
                     break;
@@ -136,53 +915,59 @@ impl<'de> Parser<'de> {
             }
         }
 
-        Ok(arguments)
+        Ok(arguments.into_bump_slice())
     }
 
     pub fn parse_statement_within(&mut self, min_bp: u8) -> Result<TokenTree<'de>, Error> {
-        let lhs = match self.lexer.next() {
-            Some(Ok(token)) => token,
-            None => return Ok(TokenTree::Atom(Atom::Nil)),
-            Some(Err(e)) => {
-                return Err(e).wrap_err("on the left-hand side");
-            }
-        };
-
-        let mut lhs = match lhs {
-            Token {
-                kind: TokenKind::Ident,
-                origin,
-                ..
-            } => TokenTree::Atom(Atom::Ident(origin)),
-
-            Token {
-                kind: TokenKind::Super,
-                ..
-            } => TokenTree::Atom(Atom::Super),
+        self.enter()?;
+        let statement = self.parse_nested_statement(min_bp);
+        self.depth -= 1;
+        statement
+    }
 
-            Token {
-                kind: TokenKind::This,
+    fn parse_nested_statement(&mut self, min_bp: u8) -> Result<TokenTree<'de>, Error> {
+        match self.lexer.peek() {
+            Some(Ok(Token {
+                kind:
+                    TokenKind::Print
+                    | TokenKind::Return
+                    | TokenKind::Throw
+                    | TokenKind::For
+                    | TokenKind::While
+                    | TokenKind::Class
+                    | TokenKind::Var
+                    | TokenKind::Const
+                    | TokenKind::Fun
+                    | TokenKind::If
+                    | TokenKind::Match
+                    | TokenKind::Try,
                 ..
-            } => TokenTree::Atom(Atom::This),
-
-            Token {
-                kind: TokenKind::LeftParen,
+            })) => {}
+            // a block statement ends at its }, unlike a block in an expression
+            Some(Ok(Token {
+                kind: TokenKind::LeftBrace,
                 ..
-            } => {
-                let lhs = self
-                    .parse_expression_within(0)
-                    .wrap_err("in bracketed expression")?;
-
-                self.lexer
-                    .expect(
-                        TokenKind::RightParen,
-                        "Unexpected end to bracketed expression",
-                    )
-                    .wrap_err("after bracketed expression")?;
-
-                TokenTree::Cons(Op::Group, vec![lhs])
+            })) => return self.parse_block(),
+            // everything else is an expression statement
+            _ => {
+                let expr = self.parse_expression_within(min_bp)?;
+                return match self.lexer.peek() {
+                    Some(Ok(Token {
+                        kind: TokenKind::Comma,
+                        ..
+                    })) => self.parse_multiple_assignment(expr),
+                    _ => Ok(expr),
+                };
             }
+        }
 
+        let lhs = self
+            .lexer
+            .next()
+            .expect("peeked Some above")
+            .expect("peeked Ok above");
+
+        match lhs {
             // unary prefix expressions
             Token {
                 kind: TokenKind::Print | TokenKind::Return,
@@ -193,11 +978,27 @@ impl<'de> Parser<'de> {
                     TokenKind::Return => Op::Return,
                     _ => unreachable!("by the outer match arm pattern"),
                 };
+                // a `देयम` without a value returns `nil`
+                if op == Op::Return
+                    && matches!(
+                        self.lexer.peek(),
+                        None | Some(Ok(Token {
+                            kind: TokenKind::Semicolon | TokenKind::RightBrace,
+                            ..
+                        }))
+                    )
+                {
+                    return Ok(TokenTree::Cons(op, &[]));
+                }
+                let keyword = crate::locale::current().keyword(match op {
+                    Op::Print => "print",
+                    _ => "return",
+                });
                 let ((), r_bp) = prefix_binding_power(op);
                 let rhs = self
                     .parse_expression_within(r_bp)
-                    .wrap_err_with(|| format!("on the right-hand side of {op:?}"))?;
-                return Ok(TokenTree::Cons(op, vec![rhs]));
+                    .wrap_err_with(|| message!("on-right-hand-side-of", op = keyword))?;
+                Ok(TokenTree::Cons(op, self.slice([rhs])))
             }
 
             Token {
@@ -205,36 +1006,30 @@ impl<'de> Parser<'de> {
                 ..
             } => {
                 self.lexer
-                    .expect(TokenKind::LeftParen, "missing (")
-                    .wrap_err("in for loop condition")?;
+                    .expect(TokenKind::LeftParen, "E0104/missing-left-paren")
+                    .wrap_err_with(|| message!("in-for-header"))?;
 
-                let init = self
-                    .parse_expression_within(0)
-                    .wrap_err("in init condition of for loop")?;
-
-                self.lexer
-                    .expect(TokenKind::Semicolon, "missing ;")
-                    .wrap_err("in for loop condition")?;
-
-                let cond = self
-                    .parse_expression_within(0)
-                    .wrap_err("in loop condition of for loop")?;
-
-                self.lexer
-                    .expect(TokenKind::Semicolon, "missing ;")
-                    .wrap_err("in for loop condition")?;
+                if let Some(Ok(Token {
+                    kind: TokenKind::Var | TokenKind::Const,
+                    ..
+                })) = self.lexer.peek()
+                {
+                    return self.parse_for();
+                }
 
-                let inc = self
+                let init = self
                     .parse_expression_within(0)
-                    .wrap_err("in incremental condition of for loop")?;
-
-                self.lexer
-                    .expect(TokenKind::RightParen, "missing )")
-                    .wrap_err("in for loop condition")?;
+                    .wrap_err_with(|| message!("in-for-init"))?;
 
-                let block = self.parse_block().wrap_err("in body of for loop")?;
+                if let Some(Ok(Token {
+                    kind: TokenKind::In,
+                    ..
+                })) = self.lexer.peek()
+                {
+                    return self.parse_for_in(init);
+                }
 
-                return Ok(TokenTree::Cons(Op::For, vec![init, cond, inc, block]));
+                self.parse_for_rest(init)
             }
 
             Token {
@@ -242,20 +1037,22 @@ impl<'de> Parser<'de> {
                 ..
             } => {
                 self.lexer
-                    .expect(TokenKind::LeftParen, "missing (")
-                    .wrap_err("in while loop condition")?;
+                    .expect(TokenKind::LeftParen, "E0104/missing-left-paren")
+                    .wrap_err_with(|| message!("in-while-condition"))?;
 
                 let cond = self
                     .parse_expression_within(0)
-                    .wrap_err("in while loop condition")?;
+                    .wrap_err_with(|| message!("in-while-condition"))?;
 
                 self.lexer
-                    .expect(TokenKind::RightParen, "missing )")
-                    .wrap_err("in while loop condition")?;
+                    .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+                    .wrap_err_with(|| message!("in-while-condition"))?;
 
-                let block = self.parse_block().wrap_err("in body of while loop")?;
+                let block = self
+                    .parse_block()
+                    .wrap_err_with(|| message!("in-while-body"))?;
 
-                return Ok(TokenTree::Cons(Op::While, vec![cond, block]));
+                Ok(TokenTree::Cons(Op::While, self.slice([cond, block])))
             }
 
             Token {
@@ -264,247 +1061,208 @@ impl<'de> Parser<'de> {
             } => {
                 let token = self
                     .lexer
-                    .expect(TokenKind::Ident, "expected identifier")
-                    .wrap_err("in class name")?;
+                    .expect(TokenKind::Ident, "E0104/identifier")
+                    .wrap_err_with(|| message!("in-class-name"))?;
                 assert_eq!(token.kind, TokenKind::Ident);
-                let ident = TokenTree::Atom(Atom::Ident(token.origin));
+                let ident = TokenTree::Atom(Atom::Ident(token.origin, token.offset));
 
                 if lhs.kind == TokenKind::Var {
                     self.lexer
-                        .expect(TokenKind::Equal, "missing =")
-                        .wrap_err("in variable assignment")?;
+                        .expect(TokenKind::Equal, "E0104/missing-equal")
+                        .wrap_err_with(|| message!("in-declaration"))?;
                 }
 
-                let block = self.parse_block().wrap_err("in class definition")?;
+                let block = self
+                    .parse_block()
+                    .wrap_err_with(|| message!("in-class-body"))?;
 
-                return Ok(TokenTree::Cons(Op::Class, vec![ident, block]));
+                Ok(TokenTree::Cons(Op::Class, self.slice([ident, block])))
             }
 
             Token {
-                kind: TokenKind::Var,
+                kind: kind @ (TokenKind::Var | TokenKind::Const),
                 ..
             } => {
-                let token = self
-                    .lexer
-                    .expect(TokenKind::Ident, "expected identifier")
-                    .wrap_err("in variable assignment")?;
-                assert_eq!(token.kind, TokenKind::Ident);
-                let ident = TokenTree::Atom(Atom::Ident(token.origin));
+                let mut declarations = BumpVec::new_in(self.arena);
+                loop {
+                    let target = self.parse_declaration_target()?;
 
-                self.lexer
-                    .expect(TokenKind::Equal, "missing =")
-                    .wrap_err("in variable assignment")?;
+                    self.lexer
+                        .expect(TokenKind::Equal, "E0104/missing-equal")
+                        .wrap_err_with(|| message!("in-declaration"))?;
 
-                let second = self
-                    .parse_expression_within(0)
-                    .wrap_err("in variable assignment expression")?;
+                    let value = self
+                        .parse_expression_within(0)
+                        .wrap_err_with(|| message!("in-declaration-value"))?;
+                    declarations.push(target);
+                    declarations.push(value);
+
+                    if !matches!(
+                        self.lexer.peek(),
+                        Some(Ok(Token {
+                            kind: TokenKind::Comma,
+                            ..
+                        }))
+                    ) {
+                        break;
+                    }
+                    self.lexer.next();
+                }
 
-                return Ok(TokenTree::Cons(Op::Var, vec![ident, second]));
+                let op = if kind == TokenKind::Const {
+                    Op::Const
+                } else {
+                    Op::Var
+                };
+                Ok(TokenTree::Cons(op, declarations.into_bump_slice()))
             }
 
             Token {
                 kind: TokenKind::Fun,
                 ..
             } => {
+                // `विनियोग (…) { … }` without a name is a function expression
+                if let Some(Ok(Token {
+                    kind: TokenKind::LeftParen,
+                    ..
+                })) = self.lexer.peek()
+                {
+                    return self.parse_function(None);
+                }
                 let token = self
                     .lexer
-                    .expect(TokenKind::Ident, "expected identifier")
-                    .wrap_err("in function name declaration")?;
+                    .expect(TokenKind::Ident, "E0104/identifier")
+                    .wrap_err_with(|| message!("in-function-name"))?;
                 assert_eq!(token.kind, TokenKind::Ident);
-                let name = token.origin;
-                let ident = Atom::Ident(token.origin);
-
-                let mut parameters = Vec::new();
-
-                self.lexer
-                    .expect(TokenKind::LeftParen, "missing (")
-                    .wrap_err_with(|| format!("in parameter list of function {name}"))?;
-
-                if matches!(
-                    self.lexer.peek(),
-                    Some(Ok(Token {
-                        kind: TokenKind::RightParen,
-                        ..
-                    }))
-                ) {
-                    // immediate parameter list end
-                } else {
-                    loop {
-                        let parameter = self
-                            .lexer
-                            .expect(TokenKind::Ident, "unexpected token")
-                            .wrap_err_with(|| {
-                                format!("in parameter #{} of function {name}", parameters.len() + 1)
-                            })?;
-                        parameters.push(parameter);
-
-                        let token = self
-                            .lexer
-                            .expect_where(
-                                |token| {
-                                    matches!(token.kind, TokenKind::RightParen | TokenKind::Comma)
-                                },
-                                "continuing parameter list",
-                            )
-                            .wrap_err_with(|| format!("in parameter list of function {name}"))?;
-
-                        if token.kind == TokenKind::RightParen {
-                            break;
-                        }
-                    }
-                }
-
-                let block = self
-                    .parse_block()
-                    .wrap_err_with(|| format!("in body of function {name}"))?;
-
-                return Ok(TokenTree::Fun {
-                    name: ident,
-                    parameters,
-                    body: Box::new(block),
-                });
+                self.parse_function(Some(token))
             }
 
             Token {
                 kind: TokenKind::If,
                 ..
-            } => {
-                self.lexer
-                    .expect(TokenKind::LeftParen, "missing (")
-                    .wrap_err("in if condition")?;
-
-                let cond = self
-                    .parse_expression_within(0)
-                    .wrap_err("in if loop condition")?;
+            } => self.parse_if(),
 
-                self.lexer
-                    .expect(TokenKind::RightParen, "missing )")
-                    .wrap_err("in if loop condition")?;
-
-                let block = self.parse_block().wrap_err("in body of if")?;
-
-                let mut otherwise = None;
-                if matches!(
-                    self.lexer.peek(),
-                    Some(Ok(Token {
-                        kind: TokenKind::Else,
-                        ..
-                    }))
-                ) {
-                    self.lexer.next();
+            Token {
+                kind: TokenKind::Match,
+                ..
+            } => self.parse_match(),
 
-                    otherwise = Some(self.parse_block().wrap_err("in body of else")?);
-                }
+            Token {
+                kind: TokenKind::Try,
+                ..
+            } => self.parse_try(),
 
-                return Ok(TokenTree::If {
-                    condition: Box::new(cond),
-                    yes: Box::new(block),
-                    no: otherwise.map(Box::new),
-                });
+            Token {
+                kind: TokenKind::Throw,
+                ..
+            } => {
+                let ((), r_bp) = prefix_binding_power(Op::Throw);
+                let value = self
+                    .parse_expression_within(r_bp)
+                    .wrap_err_with(|| message!("in-throw-value"))?;
+                Ok(TokenTree::Spanned {
+                    span: token_span(&lhs),
+                    node: self.alloc(TokenTree::Cons(Op::Throw, self.slice([value]))),
+                })
             }
 
-            token => {
-                return Err(miette::miette! {
-                    labels = vec![
-                        LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here"),
-                    ],
-                    help = format!("Unexpected {token:?}"),
-                    "Expected a statement",
-                }
-                .with_source_code(self.whole.to_string()))
-            }
-        };
+            _ => unreachable!("only statement keywords get past the peek above"),
+        }
+    }
 
-        loop {
-            let op = self.lexer.peek();
-            if op.map_or(false, |op| op.is_err()) {
-                return Err(self
-                    .lexer
-                    .next()
-                    .expect("checked Some above")
-                    .expect_err("checked Err above"))
-                .wrap_err("in place of expected operator");
-            }
+    /// Parses the parameter list and body of a function, after its `विनियोग`
+    /// and its name, if it has one.
+    fn parse_function(&mut self, name: Option<Token<'de>>) -> Result<TokenTree<'de>, Error> {
+        let function = match name {
+            Some(name) => message!("function-named", name = name.origin),
+            None => message!("function-expression"),
+        };
+        let mut parameters = BumpVec::new_in(self.arena);
 
-            if let Some(Ok(Token { kind: TokenKind::Equal, .. })) = op {
-                self.lexer.next();
-                let rhs = self
-                    .parse_expression_within(0)
-                    .wrap_err_with(|| format!("on the right-hand side of {lhs} ="))?;
-                lhs = TokenTree::Cons(Op::Assign, vec![lhs, rhs]);
-                continue;
-            }
+        self.lexer
+            .expect(TokenKind::LeftParen, "E0104/missing-left-paren")
+            .wrap_err_with(|| message!("in-parameters", function = function))?;
 
-            let op = match op.map(|res| res.as_ref().expect("handled Err above")) {
-                None => break,
-                Some(Token {
-                    kind: TokenKind::Semicolon | TokenKind::RightBrace,
-                    ..
-                }) => break,
-                Some(Token {
-                    kind: TokenKind::LeftParen,
-                    ..
-                }) => Op::Call,
-                Some(Token {
-                    kind: TokenKind::Dot,
-                    ..
-                }) => Op::Field,
+        if matches!(
+            self.lexer.peek(),
+            Some(Ok(Token {
+                kind: TokenKind::RightParen,
+                ..
+            }))
+        ) {
+            // immediate parameter list end
+            self.lexer.next();
+        } else {
+            loop {
+                let parameter = self
+                    .lexer
+                    .expect(TokenKind::Ident, "E0104/parameter")
+                    .wrap_err_with(|| {
+                        message!(
+                            "in-parameter",
+                            number = parameters.len() + 1,
+                            function = function
+                        )
+                    })?;
+                parameters.push(parameter);
 
-                Some(token) => return Err(miette::miette! {
-                    labels = vec![
-                        LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here"),
-                    ],
-                    help = format!("Unexpected {token:?}"),
-                    "Expected an operator",
-                }
-                .with_source_code(self.whole.to_string())),
-            };
+                let token = self
+                    .lexer
+                    .expect_where(
+                        |token| matches!(token.kind, TokenKind::RightParen | TokenKind::Comma),
+                        "E0104/missing-comma-or-paren",
+                    )
+                    .wrap_err_with(|| message!("in-parameters", function = function))?;
 
-            if let Some((l_bp, ())) = postfix_binding_power(op) {
-                if l_bp < min_bp {
+                if token.kind == TokenKind::RightParen {
                     break;
                 }
-                self.lexer.next();
-
-                lhs = match op {
-                    Op::Call => TokenTree::Call {
-                        callee: Box::new(lhs),
-                        arguments: self
-                            .parse_fun_call_arguments()
-                            .wrap_err("in function call arguments")?,
-                    },
-                    _ => TokenTree::Cons(op, vec![lhs]),
-                };
-                continue;
             }
+        }
 
-            if let Some((l_bp, r_bp)) = infix_binding_power(op) {
-                if l_bp < min_bp {
-                    break;
-                }
-                self.lexer.next();
+        let block = self
+            .parse_block()
+            .wrap_err_with(|| message!("in-function-body", function = function))?;
 
-                let rhs = self
-                    .parse_expression_within(r_bp)
-                    .wrap_err_with(|| format!("on the right-hand side of {lhs} {op}"))?;
-                lhs = TokenTree::Cons(op, vec![lhs, rhs]);
-                continue;
-            }
+        Ok(TokenTree::Fun {
+            name: name.map(|name| Atom::Ident(name.origin, name.offset)),
+            parameters: parameters.into_bump_slice(),
+            body: self.alloc(block),
+        })
+    }
+    pub fn parse_expression_within(&mut self, min_bp: u8) -> Result<TokenTree<'de>, Error> {
+        self.enter()?;
+        let expression = self.parse_nested_expression(min_bp);
+        self.depth -= 1;
+        expression
+    }
 
-            break;
+    /// Counts one more level of nesting, failing if there are too many.
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth >= MAX_NESTING {
+            let at = self.lexer.consumed_end();
+            return Err(miette::miette! {
+                labels = vec![LabeledSpan::at_offset(at, "here")],
+                code = "E0111",
+                help = message!("E0111-help", max = MAX_NESTING),
+                "{}",
+                message!("E0111"),
+            }
+            .with_source_code(self.whole.to_string()));
         }
-
-        Ok(lhs)
+        self.depth += 1;
+        Ok(())
     }
 
-    pub fn parse_expression_within(&mut self, min_bp: u8) -> Result<TokenTree<'de>, Error> {
+    fn parse_nested_expression(&mut self, min_bp: u8) -> Result<TokenTree<'de>, Error> {
         let lhs = match self.lexer.next() {
             Some(Ok(token)) => token,
             None => return Ok(TokenTree::Atom(Atom::Nil)),
             Some(Err(e)) => {
-                return Err(e).wrap_err("on left-hand side");
+                return Err(e).wrap_err_with(|| message!("on-left-hand-side"));
             }
         };
+        let start = lhs.offset;
         let mut lhs =
             match lhs {
                 // atoms
@@ -512,7 +1270,14 @@ impl<'de> Parser<'de> {
                     kind: TokenKind::String,
                     origin,
                     ..
-                } => TokenTree::Atom(Atom::String(Token::unescape(origin))),
+                } => TokenTree::Atom(Atom::String(self.string(Token::unescape(origin)))),
+                Token {
+                    kind: TokenKind::StringHead,
+                    origin,
+                    ..
+                } => self
+                    .parse_interpolation(origin)
+                    .wrap_err_with(|| message!("in-interpolated-string"))?,
                 Token {
                     kind: TokenKind::Number(n),
                     ..
@@ -532,8 +1297,8 @@ impl<'de> Parser<'de> {
                 Token {
                     kind: TokenKind::Ident,
                     origin,
-                    ..
-                } => TokenTree::Atom(Atom::Ident(origin)),
+                    offset,
+                } => TokenTree::Atom(Atom::Ident(origin, offset)),
                 Token {
                     kind: TokenKind::Super,
                     ..
@@ -551,16 +1316,51 @@ impl<'de> Parser<'de> {
                 } => {
                     let lhs = self
                         .parse_expression_within(0)
-                        .wrap_err("in bracketed expression")?;
+                        .wrap_err_with(|| message!("in-group"))?;
                     self.lexer
-                        .expect(
-                            TokenKind::RightParen,
-                            "Unexpected end to bracketed expression",
-                        )
-                        .wrap_err("after bracketed expression")?;
-                    TokenTree::Cons(Op::Group, vec![lhs])
+                        .expect(TokenKind::RightParen, "E0104/missing-right-paren")
+                        .wrap_err_with(|| message!("after-group"))?;
+                    TokenTree::Cons(Op::Group, self.slice([lhs]))
                 }
 
+                Token {
+                    kind: TokenKind::LeftBracket,
+                    ..
+                } => TokenTree::Cons(
+                    Op::List,
+                    self.parse_list_items()
+                        .wrap_err_with(|| message!("in-list"))?,
+                ),
+
+                Token {
+                    kind: TokenKind::PlusPlus | TokenKind::MinusMinus,
+                    ..
+                } => self.parse_prefix_increment(lhs.kind)?,
+
+                // statement forms that have a value
+                Token {
+                    kind: TokenKind::If,
+                    ..
+                } => self
+                    .parse_if()
+                    .wrap_err_with(|| message!("in-if-expression"))?,
+                Token {
+                    kind: TokenKind::Match,
+                    ..
+                } => self
+                    .parse_match()
+                    .wrap_err_with(|| message!("in-match-expression"))?,
+                Token {
+                    kind: TokenKind::LeftBrace,
+                    ..
+                } => self
+                    .parse_block_body()
+                    .wrap_err_with(|| message!("in-block-expression"))?,
+                Token {
+                    kind: TokenKind::Fun,
+                    ..
+                } => self.parse_function(None)?,
+
                 // unary prefix expressions
                 Token {
                     kind: TokenKind::Bang | TokenKind::Minus,
@@ -574,30 +1374,57 @@ impl<'de> Parser<'de> {
                     let ((), r_bp) = prefix_binding_power(op);
                     let rhs = self
                         .parse_expression_within(r_bp)
-                        .wrap_err("in right-hand side")?;
-                    TokenTree::Cons(op, vec![rhs])
+                        .wrap_err_with(|| message!("on-right-hand-side"))?;
+                    let node = TokenTree::Cons(op, self.slice([rhs]));
+                    match op {
+                        Op::Minus => TokenTree::Spanned {
+                            span: lhs.offset..lhs.offset + lhs.origin.len(),
+                            node: self.alloc(node),
+                        },
+                        _ => node,
+                    }
                 }
 
                 token => return Err(miette::miette! {
                     labels = vec![
                         LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here"),
                     ],
-                    help = format!("Unexpected {token:?}"),
-                    "Expected an expression",
+                    code = "E0101",
+                    help = message!("unexpected-token", token = format!("{token:?}")),
+                    "{}", message!("E0101"),
                 }
                 .with_source_code(self.whole.to_string())),
             };
 
         loop {
             let op = self.lexer.peek();
-            if op.map_or(false, |op| op.is_err()) {
+            if op.is_some_and(|op| op.is_err()) {
                 return Err(self
                     .lexer
                     .next()
                     .expect("checked Some above")
                     .expect_err("checked Err above"))
-                .wrap_err("in place of expected operator");
+                .wrap_err_with(|| message!("instead-of-operator"));
+            }
+
+            if let Some(base) = compound_assignment(op) {
+                let (l_bp, r_bp) = infix_binding_power(Op::Assign).expect("assignment is infix");
+                if l_bp < min_bp {
+                    break;
+                }
+                let token = self
+                    .lexer
+                    .next()
+                    .expect("peeked Some above")
+                    .expect("handled Err above");
+
+                let rhs = self.parse_expression_within(r_bp).wrap_err_with(|| {
+                    message!("on-right-hand-side-of", op = format!("{lhs} {base}="))
+                })?;
+                lhs = desugar_compound_assignment(self.arena, base, token_span(&token), lhs, rhs);
+                continue;
             }
+
             let op = match op.map(|res| res.as_ref().expect("handled Err above")) {
                 None => break,
 
@@ -605,8 +1432,13 @@ impl<'de> Parser<'de> {
                     kind:
                         TokenKind::RightParen
                         | TokenKind::Comma
+                        | TokenKind::Colon
+                        | TokenKind::StringMiddle
+                        | TokenKind::StringTail
                         | TokenKind::Semicolon
-                        | TokenKind::RightBrace,
+                        | TokenKind::RightBrace
+                        | TokenKind::RightBracket
+                        | TokenKind::In,
                     ..
                 }) => break,
                 Some(Token {
@@ -617,6 +1449,14 @@ impl<'de> Parser<'de> {
                     kind: TokenKind::Dot,
                     ..
                 }) => Op::Field,
+                Some(Token {
+                    kind: TokenKind::PlusPlus,
+                    ..
+                }) => Op::PostIncrement,
+                Some(Token {
+                    kind: TokenKind::MinusMinus,
+                    ..
+                }) => Op::PostDecrement,
                 Some(Token {
                     kind: TokenKind::Minus,
                     ..
@@ -629,6 +1469,14 @@ impl<'de> Parser<'de> {
                     kind: TokenKind::Star,
                     ..
                 }) => Op::Star,
+                Some(Token {
+                    kind: TokenKind::StarStar,
+                    ..
+                }) => Op::StarStar,
+                Some(Token {
+                    kind: TokenKind::Percent,
+                    ..
+                }) => Op::Percent,
                 Some(Token {
                     kind: TokenKind::BangEqual,
                     ..
@@ -669,31 +1517,67 @@ impl<'de> Parser<'de> {
                     kind: TokenKind::Or,
                     ..
                 }) => Op::Or,
+                Some(Token {
+                    kind: TokenKind::Question,
+                    ..
+                }) => Op::Ternary,
+                Some(Token {
+                    kind: TokenKind::DotDot,
+                    ..
+                }) => Op::Range,
+                Some(Token {
+                    kind: TokenKind::DotDotEqual,
+                    ..
+                }) => Op::RangeInclusive,
 
-                Some(token) => return Err(miette::miette! {
-                    labels = vec![
-                        LabeledSpan::at(token.offset..token.offset + token.origin.len(), "here"),
-                    ],
-                    help = format!("Unexpected {token:?}"),
-                    "Expected an infix operator",
+                Some(token) => {
+                    let token = *token;
+                    return Err(self.missing_operator(token));
                 }
-                .with_source_code(self.whole.to_string())),
             };
 
             if let Some((l_bp, ())) = postfix_binding_power(op) {
                 if l_bp < min_bp {
                     break;
                 }
-                self.lexer.next();
+                let token = self
+                    .lexer
+                    .next()
+                    .expect("peeked Some above")
+                    .expect("handled Err above");
 
                 lhs = match op {
-                    Op::Call => TokenTree::Call {
-                        callee: Box::new(lhs),
-                        arguments: self
+                    // the span of a call is all of it, for errors the called
+                    // function raises to point at
+                    Op::Call => {
+                        let arguments = self
                             .parse_fun_call_arguments()
-                            .wrap_err("in function call arguments")?,
-                    },
-                    _ => TokenTree::Cons(op, vec![lhs]),
+                            .wrap_err_with(|| message!("in-arguments"))?;
+                        TokenTree::Spanned {
+                            span: start..self.lexer.consumed_end(),
+                            node: self.alloc(TokenTree::Call {
+                                callee: self.alloc(lhs),
+                                arguments,
+                            }),
+                        }
+                    }
+                    Op::PostIncrement | Op::PostDecrement => {
+                        if !matches!(lhs, TokenTree::Atom(Atom::Ident(..))) {
+                            return Err(miette::miette! {
+                                labels = vec![LabeledSpan::at(
+                                    token.offset..token.offset + token.origin.len(),
+                                    "here",
+                                )],
+                                code = "E0108",
+                                help = message!("E0108/increment-help", target = lhs),
+                                "{}",
+                                message!("E0108/increment", op = op),
+                            }
+                            .with_source_code(self.whole.to_string()));
+                        }
+                        TokenTree::Cons(op, self.slice([lhs]))
+                    }
+                    _ => TokenTree::Cons(op, self.slice([lhs])),
                 };
                 continue;
             }
@@ -702,19 +1586,42 @@ impl<'de> Parser<'de> {
                 if l_bp < min_bp {
                     break;
                 }
-                self.lexer.next();
+                let token = self
+                    .lexer
+                    .next()
+                    .expect("peeked Some above")
+                    .expect("handled Err above");
+                let span = token_span(&token);
 
                 lhs = match op {
-                    // TODO: ternary
-                    // let mhs = self.parse_within(0);
-                    // assert_eq!(lexer.next(), Token::Op(':'));
-                    // let rhs = self.parse_within(r_bp);
-                    // TokenTree::Cons(op, vec![lhs, mhs, rhs])
+                    Op::Ternary => {
+                        let mhs = self
+                            .parse_expression_within(0)
+                            .wrap_err_with(|| message!("in-true-branch", condition = lhs))?;
+                        self.lexer
+                            .expect(TokenKind::Colon, "E0104/missing-colon")
+                            .wrap_err_with(|| {
+                                message!("in-conditional", condition = lhs, yes = mhs)
+                            })?;
+                        let rhs = self.parse_expression_within(r_bp).wrap_err_with(|| {
+                            message!("in-false-branch", condition = lhs, yes = mhs)
+                        })?;
+                        TokenTree::Cons(op, self.slice([lhs, mhs, rhs]))
+                    }
                     _ => {
-                        let rhs = self
-                            .parse_expression_within(r_bp)
-                            .wrap_err_with(|| format!("on the right-hand side of {lhs} {op}"))?;
-                        TokenTree::Cons(op, vec![lhs, rhs])
+                        let rhs = self.parse_expression_within(r_bp).wrap_err_with(|| {
+                            message!("on-right-hand-side-of", op = format!("{lhs} {op}"))
+                        })?;
+                        if is_ordering(op) {
+                            chain_comparison(self.arena, lhs, op, span, rhs)
+                        } else if is_arithmetic(op) {
+                            TokenTree::Spanned {
+                                span,
+                                node: self.alloc(TokenTree::Cons(op, self.slice([lhs, rhs]))),
+                            }
+                        } else {
+                            TokenTree::Cons(op, self.slice([lhs, rhs]))
+                        }
                     }
                 };
                 continue;
@@ -727,13 +1634,14 @@ impl<'de> Parser<'de> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Atom<'de> {
-    String(Cow<'de, str>),
+    String(&'de str),
     Number(f64),
     Nil,
     Bool(bool),
-    Ident(&'de str),
+    /// A name, and the offset in the source it was written at.
+    Ident(&'de str, usize),
     Super,
     This,
 }
@@ -754,7 +1662,7 @@ impl fmt::Display for Atom<'_> {
             }
             Atom::Nil => write!(f, "nil"),
             Atom::Bool(b) => write!(f, "{b:?}"),
-            Atom::Ident(i) => write!(f, "{i}"),
+            Atom::Ident(i, _) => write!(f, "{i}"),
             Atom::Super => write!(f, "super"),
             Atom::This => write!(f, "this"),
         }
@@ -767,6 +1675,8 @@ pub enum Op {
     Minus,
     Plus,
     Star,
+    StarStar,
+    Percent,
     BangEqual,
     EqualEqual,
     LessEqual,
@@ -777,15 +1687,25 @@ pub enum Op {
     Bang,
     And,
     Or,
+    Ternary,
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
     Call,
     For,
     Class,
     Print,
     Return,
+    Throw,
     Field,
     Var,
+    Const,
     While,
     Group,
+    List,
+    Range,
+    RangeInclusive,
 }
 
 impl fmt::Display for Op {
@@ -798,6 +1718,8 @@ impl fmt::Display for Op {
                 Op::Minus => "-",
                 Op::Plus => "+",
                 Op::Star => "*",
+                Op::StarStar => "**",
+                Op::Percent => "%",
                 Op::BangEqual => "!=",
                 Op::EqualEqual => "==",
                 Op::LessEqual => "<=",
@@ -808,47 +1730,144 @@ impl fmt::Display for Op {
                 Op::Bang => "!",
                 Op::And => "and",
                 Op::Or => "or",
+                Op::Ternary => "?:",
+                Op::PreIncrement => "++",
+                Op::PreDecrement => "--",
+                Op::PostIncrement => "post++",
+                Op::PostDecrement => "post--",
                 Op::For => "for",
                 Op::Class => "class",
                 Op::Print => "print",
                 Op::Return => "return",
+                Op::Throw => "throw",
                 Op::Field => ".",
                 Op::Var => "var",
+                Op::Const => "const",
                 Op::While => "while",
                 Op::Call => "call",
                 Op::Group => "group",
+                Op::List => "list",
+                Op::Range => "..",
+                Op::RangeInclusive => "..=",
             }
         )
     }
 }
 
+/// A node of a syntax tree. The nodes it holds are in the [`Arena`] it was
+/// parsed into, so cloning one is cheap: the clone shares them.
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenTree<'de> {
     Atom(Atom<'de>),
-    Cons(Op, Vec<TokenTree<'de>>),
+    Cons(Op, &'de [TokenTree<'de>]),
+    /// `विनियोग name(parameters) body`, or without `name` a function
+    /// expression, which has the function as its value.
     Fun {
-        name: Atom<'de>,
-        parameters: Vec<Token<'de>>,
-        body: Box<TokenTree<'de>>,
+        name: Option<Atom<'de>>,
+        parameters: &'de [Token<'de>],
+        body: &'de TokenTree<'de>,
     },
     Call {
-        callee: Box<TokenTree<'de>>,
-        arguments: Vec<TokenTree<'de>>,
+        callee: &'de TokenTree<'de>,
+        arguments: &'de [TokenTree<'de>],
     },
     If {
-        condition: Box<TokenTree<'de>>,
-        yes: Box<TokenTree<'de>>,
-        no: Option<Box<TokenTree<'de>>>,
+        condition: &'de TokenTree<'de>,
+        yes: &'de TokenTree<'de>,
+        no: Option<&'de TokenTree<'de>>,
+    },
+    Block {
+        statements: &'de [TokenTree<'de>],
+        /// the trailing expression that gives the block its value, if any
+        value: Option<&'de TokenTree<'de>>,
+    },
+    /// A statement of a program or block, which starts at byte `offset` of
+    /// the parsed source.
+    Statement {
+        offset: usize,
+        node: &'de TokenTree<'de>,
+    },
+    /// `a, b = b, a`: assigns each value to the target at the same position,
+    /// after evaluating all of the values.
+    Assignments {
+        targets: &'de [TokenTree<'de>],
+        values: &'de [TokenTree<'de>],
+    },
+    /// A chain like `a < b <= c`, which holds when every adjacent pair does.
+    /// `operators[i]` compares `operands[i]` with `operands[i + 1]`, and
+    /// `spans[i]` is where it is in the parsed source.
+    Chain {
+        operands: &'de [TokenTree<'de>],
+        operators: &'de [Op],
+        spans: &'de [Range<usize>],
+    },
+    /// `match (subject) { literals: body … default: body }`, which runs the
+    /// body of the first arm with a literal equal to `subject`, or else the
+    /// default arm.
+    Match {
+        subject: &'de TokenTree<'de>,
+        arms: &'de [(&'de [Atom<'de>], TokenTree<'de>)],
+        default: Option<&'de TokenTree<'de>>,
+    },
+    /// `for (variable in iterable) body`, which runs `body` once for each
+    /// value of `iterable` with `variable` set to it.
+    ForIn {
+        variable: Atom<'de>,
+        iterable: &'de TokenTree<'de>,
+        body: &'de TokenTree<'de>,
+    },
+    /// `try body catch (variable) handler`, which runs `handler` with
+    /// `variable` set to what was thrown if `body` throws a value or fails
+    /// with a runtime error.
+    Try {
+        body: &'de TokenTree<'de>,
+        variable: Atom<'de>,
+        handler: &'de TokenTree<'de>,
+    },
+    /// An operator expression along with the byte range of its operator in
+    /// the parsed source, for errors about its operands to point at. For a
+    /// call, the range is the whole call.
+    Spanned {
+        span: Range<usize>,
+        node: &'de TokenTree<'de>,
     },
 }
 
+impl TokenTree<'_> {
+    /// Whether this node produces a value when evaluated, as opposed to
+    /// statements like `var` and `while` that only have effects.
+    pub fn is_expression(&self) -> bool {
+        if let TokenTree::Statement { node, .. } | TokenTree::Spanned { node, .. } = self {
+            return node.is_expression();
+        }
+        !matches!(
+            self,
+            TokenTree::Fun { name: Some(_), .. }
+                | TokenTree::Assignments { .. }
+                | TokenTree::ForIn { .. }
+                | TokenTree::Try { .. }
+                | TokenTree::Cons(
+                    Op::Var
+                        | Op::Const
+                        | Op::Print
+                        | Op::Return
+                        | Op::Throw
+                        | Op::While
+                        | Op::For
+                        | Op::Class,
+                    _
+                )
+        )
+    }
+}
+
 impl fmt::Display for TokenTree<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TokenTree::Atom(i) => write!(f, "{}", i),
             TokenTree::Cons(head, rest) => {
                 write!(f, "({}", head)?;
-                for s in rest {
+                for s in rest.iter() {
                     write!(f, " {s}")?
                 }
                 write!(f, ")")
@@ -858,15 +1877,18 @@ impl fmt::Display for TokenTree<'_> {
                 parameters,
                 body,
             } => {
-                write!(f, "(def {name}")?;
-                for p in parameters {
+                match name {
+                    Some(name) => write!(f, "(def {name}")?,
+                    None => write!(f, "(fun")?,
+                }
+                for p in parameters.iter() {
                     write!(f, " {p}")?
                 }
                 write!(f, " {body})")
             }
             TokenTree::Call { callee, arguments } => {
                 write!(f, "({callee}")?;
-                for a in arguments {
+                for a in arguments.iter() {
                     write!(f, " {a}")?
                 }
                 write!(f, ")")
@@ -878,41 +1900,210 @@ impl fmt::Display for TokenTree<'_> {
                 }
                 write!(f, ")")
             }
+            TokenTree::Block { statements, value } => {
+                write!(f, "(block")?;
+                for s in statements.iter() {
+                    write!(f, " {s}")?
+                }
+                if let Some(value) = value {
+                    write!(f, " => {value}")?
+                }
+                write!(f, ")")
+            }
+            TokenTree::Statement { node, .. } | TokenTree::Spanned { node, .. } => {
+                write!(f, "{node}")
+            }
+            TokenTree::Assignments { targets, values } => {
+                write!(f, "(=")?;
+                for t in targets.iter() {
+                    write!(f, " {t}")?
+                }
+                write!(f, " ;")?;
+                for v in values.iter() {
+                    write!(f, " {v}")?
+                }
+                write!(f, ")")
+            }
+            TokenTree::ForIn {
+                variable,
+                iterable,
+                body,
+            } => write!(f, "(for {variable} in {iterable} {body})"),
+            TokenTree::Try {
+                body,
+                variable,
+                handler,
+            } => write!(f, "(try {body} catch {variable} {handler})"),
+            TokenTree::Match {
+                subject,
+                arms,
+                default,
+            } => {
+                write!(f, "(match {subject}")?;
+                for (literals, body) in arms.iter() {
+                    write!(f, " (")?;
+                    for literal in literals.iter() {
+                        write!(f, "{literal} ")?
+                    }
+                    write!(f, "=> {body})")?
+                }
+                if let Some(default) = default {
+                    write!(f, " (default => {default})")?
+                }
+                write!(f, ")")
+            }
+            TokenTree::Chain {
+                operands,
+                operators,
+                ..
+            } => {
+                write!(f, "(chain {}", operands[0])?;
+                for (op, operand) in operators.iter().zip(&operands[1..]) {
+                    write!(f, " {op} {operand}")?
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
-fn prefix_binding_power(op: Op) -> ((), u8) {
+/// If `next` is a compound assignment operator such as `+=`, returns the
+/// binary operator it applies.
+fn compound_assignment(next: Option<&Result<Token<'_>, Error>>) -> Option<Op> {
+    match next?.as_ref().ok()?.kind {
+        TokenKind::PlusEqual => Some(Op::Plus),
+        TokenKind::MinusEqual => Some(Op::Minus),
+        TokenKind::StarEqual => Some(Op::Star),
+        TokenKind::SlashEqual => Some(Op::Slash),
+        _ => None,
+    }
+}
+
+/// Rewrites `target op= value` as `target = target op value`, where `span`
+/// is that of the `op=`.
+fn desugar_compound_assignment<'de>(
+    arena: &'de Arena,
+    op: Op,
+    span: Range<usize>,
+    target: TokenTree<'de>,
+    value: TokenTree<'de>,
+) -> TokenTree<'de> {
+    let combined = TokenTree::Spanned {
+        span,
+        node: arena.alloc(TokenTree::Cons(
+            op,
+            slice_in(arena, [target.clone(), value]),
+        )),
+    };
+    TokenTree::Cons(Op::Assign, slice_in(arena, [target, combined]))
+}
+
+/// Moves `items` into `arena`, one after another.
+fn slice_in<T>(arena: &Arena, items: impl IntoIterator<Item = T>) -> &[T] {
+    BumpVec::from_iter_in(items, arena).into_bump_slice()
+}
+
+fn token_span(token: &Token<'_>) -> Range<usize> {
+    token.offset..token.offset + token.origin.len()
+}
+
+/// Whether `op` orders its operands, and so chains like in mathematics.
+fn is_ordering(op: Op) -> bool {
+    matches!(
+        op,
+        Op::Less | Op::LessEqual | Op::Greater | Op::GreaterEqual
+    )
+}
+
+/// Whether `op` is a binary operator on numbers (or, for some, strings), whose
+/// operator is kept in a [`TokenTree::Spanned`] for type errors to point at.
+fn is_arithmetic(op: Op) -> bool {
+    matches!(
+        op,
+        Op::Plus
+            | Op::Minus
+            | Op::Star
+            | Op::StarStar
+            | Op::Slash
+            | Op::Percent
+            | Op::Range
+            | Op::RangeInclusive
+    )
+}
+
+/// Combines `lhs op rhs` into a single chain when `lhs` is itself an
+/// unparenthesized ordering comparison, so that `a < b < c` means
+/// `a < b और b < c` rather than comparing a boolean with `c`. The chain keeps
+/// each operand once so that `b` is only evaluated once. `span` is that of
+/// `op`.
+fn chain_comparison<'de>(
+    arena: &'de Arena,
+    lhs: TokenTree<'de>,
+    op: Op,
+    span: Range<usize>,
+    rhs: TokenTree<'de>,
+) -> TokenTree<'de> {
+    match lhs {
+        TokenTree::Spanned {
+            span: first_span,
+            node: &TokenTree::Cons(first, operands),
+        } if is_ordering(first) => TokenTree::Chain {
+            operands: slice_in(arena, operands.iter().cloned().chain([rhs])),
+            operators: slice_in(arena, [first, op]),
+            spans: slice_in(arena, [first_span, span]),
+        },
+        TokenTree::Chain {
+            operands,
+            operators,
+            spans,
+        } => TokenTree::Chain {
+            operands: slice_in(arena, operands.iter().cloned().chain([rhs])),
+            operators: slice_in(arena, operators.iter().copied().chain([op])),
+            spans: slice_in(arena, spans.iter().cloned().chain([span])),
+        },
+        lhs => TokenTree::Spanned {
+            span,
+            node: arena.alloc(TokenTree::Cons(op, slice_in(arena, [lhs, rhs]))),
+        },
+    }
+}
+
+pub(crate) fn prefix_binding_power(op: Op) -> ((), u8) {
     match op {
-        Op::Print | Op::Return => ((), 1),
-        Op::Bang | Op::Minus => ((), 11),
+        Op::Print | Op::Return | Op::Throw => ((), 1),
+        Op::Bang | Op::Minus => ((), 13),
         _ => panic!("bad op: {:?}", op),
     }
 }
 
-fn postfix_binding_power(op: Op) -> Option<(u8, ())> {
+pub(crate) fn postfix_binding_power(op: Op) -> Option<(u8, ())> {
     let res = match op {
-        Op::Call => (13, ()),
+        Op::Call | Op::PostIncrement | Op::PostDecrement => (15, ()),
         _ => return None,
     };
     Some(res)
 }
 
-fn infix_binding_power(op: Op) -> Option<(u8, u8)> {
+pub(crate) fn infix_binding_power(op: Op) -> Option<(u8, u8)> {
     let res = match op {
         Op::Assign => (2, 1),
-        // '?' => (4, 3),
-        Op::And | Op::Or => (3, 4),
+        Op::Ternary => (4, 3),
+        Op::And | Op::Or => (5, 6),
         Op::BangEqual
         | Op::EqualEqual
         | Op::Less
         | Op::LessEqual
         | Op::Greater
-        | Op::GreaterEqual => (5, 6),
-        Op::Plus | Op::Minus => (7, 8),
-        Op::Star | Op::Slash => (9, 10),
-        Op::Field => (16, 15),
+        | Op::GreaterEqual => (7, 8),
+        // looser than arithmetic, so 0..n + 1 ends at n + 1, and tighter
+        // than comparison, so a range can be compared
+        Op::Range | Op::RangeInclusive => (8, 9),
+        Op::Plus | Op::Minus => (9, 10),
+        Op::Star | Op::Slash | Op::Percent => (11, 12),
+        // binds tighter than prefix minus on its left, so -2 ** 2 is -(2 ** 2)
+        Op::StarStar => (14, 13),
+        Op::Field => (18, 17),
         _ => return None,
     };
     Some(res)
-}
\ No newline at end of file
+}
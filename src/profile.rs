@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// What `run --profile` measures while a program runs: how many statements
+/// run on each line and how long they take, and how often each function is
+/// called and how long it takes, by the chain of calls that led to it.
+///
+/// Time is charged to whatever was running when it passed, the innermost
+/// statement and the innermost function, so a line's or a function's own
+/// time doesn't include that of the statements or calls inside it.
+#[derive(Debug)]
+pub struct Profile {
+    /// The program, then each function called, under the one that called
+    /// it; a function called from two places has a node for each.
+    nodes: Vec<Node>,
+    /// The node of the function running now.
+    current: usize,
+    /// The offsets of the statements running now, innermost last.
+    statements: Vec<usize>,
+    /// How often the statement at each offset ran, and its own time.
+    tallies: HashMap<usize, (u64, Duration)>,
+    /// When time was last charged.
+    last: Instant,
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    parent: usize,
+    children: Vec<usize>,
+    calls: u64,
+    /// The time spent in the function itself, on the calls of this node.
+    own: Duration,
+}
+
+/// A function's line of the profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    /// The time from each call until it returned, counting a recursive call
+    /// only once.
+    pub total: Duration,
+    /// The time spent in the function's own statements, not in the
+    /// functions it called.
+    pub own: Duration,
+}
+
+/// A line's part of the profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineProfile {
+    /// 1-based.
+    pub line: usize,
+    /// How many statements starting on the line ran, counting each time.
+    pub runs: u64,
+    /// The time spent in those statements, not in the ones nested in them.
+    pub own: Duration,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Profile {
+            nodes: vec![Node {
+                name: String::new(),
+                parent: 0,
+                children: Vec::new(),
+                calls: 1,
+                own: Duration::ZERO,
+            }],
+            current: 0,
+            statements: Vec::new(),
+            tallies: HashMap::new(),
+            last: Instant::now(),
+        }
+    }
+
+    /// Charges the time since it was last charged to what is running.
+    fn charge(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        self.nodes[self.current].own += elapsed;
+        if let Some(offset) = self.statements.last() {
+            self.tallies.entry(*offset).or_default().1 += elapsed;
+        }
+    }
+
+    pub(crate) fn enter_statement(&mut self, offset: usize) {
+        self.charge();
+        self.statements.push(offset);
+        self.tallies.entry(offset).or_default().0 += 1;
+    }
+
+    pub(crate) fn leave_statement(&mut self) {
+        self.charge();
+        self.statements.pop();
+    }
+
+    pub(crate) fn enter_function(&mut self, name: &str) {
+        self.charge();
+        let parent = self.current;
+        let child = self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].name == name);
+        let child = child.unwrap_or_else(|| {
+            self.nodes.push(Node {
+                name: name.to_string(),
+                parent,
+                children: Vec::new(),
+                calls: 0,
+                own: Duration::ZERO,
+            });
+            let child = self.nodes.len() - 1;
+            self.nodes[parent].children.push(child);
+            child
+        });
+        self.nodes[child].calls += 1;
+        self.current = child;
+    }
+
+    pub(crate) fn leave_function(&mut self) {
+        self.charge();
+        self.current = self.nodes[self.current].parent;
+    }
+
+    /// Charges the time up to now, once the program has finished.
+    pub(crate) fn finish(&mut self) {
+        self.charge();
+    }
+
+    /// How long the program ran for.
+    pub fn elapsed(&self) -> Duration {
+        self.nodes.iter().map(|node| node.own).sum()
+    }
+
+    /// Every function called, builtins included, from the one that took the
+    /// most time of its own to the one that took the least.
+    pub fn functions(&self) -> Vec<FunctionProfile> {
+        // children come after their parents, so going backwards adds up
+        // each node's time before its parent's
+        let mut totals: Vec<Duration> = self.nodes.iter().map(|node| node.own).collect();
+        for (index, node) in self.nodes.iter().enumerate().skip(1).rev() {
+            let total = totals[index];
+            totals[node.parent] += total;
+        }
+
+        let mut functions: Vec<FunctionProfile> = Vec::new();
+        for (index, node) in self.nodes.iter().enumerate().skip(1) {
+            let position = match functions.iter().position(|f| f.name == node.name) {
+                Some(position) => position,
+                None => {
+                    functions.push(FunctionProfile {
+                        name: node.name.clone(),
+                        calls: 0,
+                        total: Duration::ZERO,
+                        own: Duration::ZERO,
+                    });
+                    functions.len() - 1
+                }
+            };
+            let function = &mut functions[position];
+            function.calls += node.calls;
+            function.own += node.own;
+            // the time of a call inside a call of the same function is
+            // already part of the outer one's
+            if !self
+                .ancestors(index)
+                .any(|ancestor| self.nodes[ancestor].name == node.name)
+            {
+                function.total += totals[index];
+            }
+        }
+        functions.sort_by_key(|function| std::cmp::Reverse(function.own));
+        functions
+    }
+
+    /// Each line of `source`, the source the program was parsed from, on
+    /// which statements ran, from the one that took the most time to the one
+    /// that took the least.
+    pub fn lines(&self, source: &str) -> Vec<LineProfile> {
+        let starts: Vec<usize> = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(at, _)| at + 1))
+            .collect();
+        let mut lines: HashMap<usize, LineProfile> = HashMap::new();
+        for (offset, (runs, own)) in &self.tallies {
+            let line = starts.partition_point(|start| start <= offset);
+            let entry = lines.entry(line).or_insert(LineProfile {
+                line,
+                runs: 0,
+                own: Duration::ZERO,
+            });
+            entry.runs += runs;
+            entry.own += *own;
+        }
+        let mut lines: Vec<LineProfile> = lines.into_values().collect();
+        lines.sort_by(|a, b| b.own.cmp(&a.own).then(a.line.cmp(&b.line)));
+        lines
+    }
+
+    /// The profile in the folded format that flamegraph tools read: a line
+    /// for each chain of calls, `program;outer;inner 1234`, with the time
+    /// spent in the innermost function in microseconds. `program` names the
+    /// bottom of every chain.
+    pub fn folded(&self, program: &str) -> String {
+        let mut out = String::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let micros = node.own.as_micros();
+            if micros == 0 {
+                continue;
+            }
+            let mut chain: Vec<&str> = self
+                .ancestors(index)
+                .map(|at| &*self.nodes[at].name)
+                .collect();
+            chain.reverse();
+            chain.push(&node.name);
+            chain[0] = program;
+            let _ = writeln!(out, "{} {micros}", chain.join(";"));
+        }
+        out
+    }
+
+    /// The nodes that `index` was called under, innermost first, down to the
+    /// program.
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut at = index;
+        std::iter::from_fn(move || {
+            if at == 0 {
+                return None;
+            }
+            at = self.nodes[at].parent;
+            Some(at)
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::Interpreter;
+
+/// How a run that `run --report` describes ended, named as in `run-all`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Ok,
+    SyntaxError,
+    RuntimeError,
+}
+
+impl RunStatus {
+    fn name(self) -> &'static str {
+        match self {
+            RunStatus::Ok => "ok",
+            RunStatus::SyntaxError => "syntax error",
+            RunStatus::RuntimeError => "runtime error",
+        }
+    }
+}
+
+/// Where the report for `program` goes: next to it, with `.report.json` in
+/// place of its extension.
+pub fn path_for(program: &Path) -> PathBuf {
+    program.with_extension("report.json")
+}
+
+/// Writes the JSON report of a run of `program` next to it, returning where.
+/// `interpreter` is the one that ran the program, or `None` if it never
+/// started. Nothing about the run leaves the machine.
+pub fn write(
+    program: &Path,
+    status: RunStatus,
+    error: Option<&str>,
+    elapsed: Duration,
+    interpreter: Option<&Interpreter>,
+) -> io::Result<PathBuf> {
+    let native_calls: serde_json::Map<String, serde_json::Value> = interpreter
+        .into_iter()
+        .flat_map(Interpreter::native_calls)
+        .map(|(name, calls)| (name.to_string(), calls.into()))
+        .collect();
+    let report = json!({
+        "program": program.display().to_string(),
+        "status": status.name(),
+        "error": error,
+        "duration_ms": elapsed.as_secs_f64() * 1000.0,
+        "statements_executed": interpreter.map_or(0, Interpreter::statements_executed),
+        "native_calls": native_calls,
+        // the source files the run loaded, which is only the program itself
+        // until programs can import others
+        "files": [program.display().to_string()],
+    });
+
+    let path = path_for(program);
+    let mut text = serde_json::to_string_pretty(&report).expect("reports serialize");
+    text.push('\n');
+    fs::write(&path, text)?;
+    Ok(path)
+}
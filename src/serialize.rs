@@ -0,0 +1,252 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use thiserror::Error;
+
+use crate::gc::Gc;
+use crate::interpreter::{runtime_error, NativeFn, Value};
+use crate::locale;
+
+/// Every blob starts with this, so that foreign data is rejected up front and
+/// the format can be revised without misreading old blobs.
+const MAGIC: &[u8; 4] = b"SKV1";
+
+const TAG_NIL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_NUMBER: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_BYTES: u8 = 5;
+const TAG_LIST: u8 = 6;
+
+/// How deeply lists may nest in a blob, so that encoding and decoding, which
+/// recurse once per level, can't run out of stack on any thread.
+pub const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Error)]
+pub enum EncodeError {
+    #[error("{0} values cannot be serialized")]
+    Unserializable(&'static str),
+    #[error("lists nest more than {MAX_DEPTH} deep")]
+    TooDeep,
+}
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("data does not start with a serialized value header")]
+    BadHeader,
+    #[error("data ends in the middle of a value")]
+    Truncated,
+    #[error("unknown value tag {0}")]
+    UnknownTag(u8),
+    #[error("serialized string is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("{0} byte(s) left over after the value")]
+    TrailingBytes(usize),
+    #[error("lists nest more than {MAX_DEPTH} deep")]
+    TooDeep,
+}
+
+/// Encodes `value` into the portable binary format: the header, then a tag
+/// byte per value followed by its payload. Numbers are little-endian IEEE 754
+/// doubles, and strings and byte blobs are prefixed with their length as an
+/// unsigned LEB128 varint. Lists are their item count as a varint followed by
+/// each item.
+///
+/// Lists are immutable, so values are trees and there is nothing cyclic to
+/// encode yet. Mutable reference types will be written once and referred
+/// back to by index. A tree is bounded by [`MAX_DEPTH`] instead: lists
+/// nested deeper than that are an error both ways.
+pub fn encode(value: &Value) -> Result<Bytes, EncodeError> {
+    let mut out = BytesMut::new();
+    out.put_slice(MAGIC);
+    encode_into(value, &mut out, 0)?;
+    Ok(out.freeze())
+}
+
+/// Encodes `value`, which is nested in `depth` lists.
+fn encode_into(value: &Value, out: &mut BytesMut, depth: usize) -> Result<(), EncodeError> {
+    match value {
+        Value::Nil => out.put_u8(TAG_NIL),
+        Value::Bool(false) => out.put_u8(TAG_FALSE),
+        Value::Bool(true) => out.put_u8(TAG_TRUE),
+        Value::Number(n) => {
+            out.put_u8(TAG_NUMBER);
+            out.put_f64_le(*n);
+        }
+        Value::String(s) => {
+            out.put_u8(TAG_STRING);
+            put_varint(out, s.len() as u64);
+            out.put_slice(s.as_bytes());
+        }
+        Value::Bytes(b) => {
+            out.put_u8(TAG_BYTES);
+            put_varint(out, b.len() as u64);
+            out.put_slice(b);
+        }
+        Value::List(items) => {
+            if depth == MAX_DEPTH {
+                return Err(EncodeError::TooDeep);
+            }
+            out.put_u8(TAG_LIST);
+            put_varint(out, items.len() as u64);
+            for item in items.iter() {
+                encode_into(item, out, depth + 1)?;
+            }
+        }
+        Value::Range(_) => return Err(EncodeError::Unserializable("range")),
+        Value::Native(_) | Value::Host(_) | Value::Function(_) => {
+            return Err(EncodeError::Unserializable("function"))
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a blob produced by [`encode`], which must contain exactly one value.
+pub fn decode(mut data: &[u8]) -> Result<Value, DecodeError> {
+    if !data.starts_with(MAGIC) {
+        return Err(DecodeError::BadHeader);
+    }
+    data.advance(MAGIC.len());
+
+    let value = decode_from(&mut data, 0)?;
+    if data.has_remaining() {
+        return Err(DecodeError::TrailingBytes(data.remaining()));
+    }
+    Ok(value)
+}
+
+/// Decodes a value nested in `depth` lists.
+fn decode_from(data: &mut &[u8], depth: usize) -> Result<Value, DecodeError> {
+    if !data.has_remaining() {
+        return Err(DecodeError::Truncated);
+    }
+
+    Ok(match data.get_u8() {
+        TAG_NIL => Value::Nil,
+        TAG_FALSE => Value::Bool(false),
+        TAG_TRUE => Value::Bool(true),
+        TAG_NUMBER => {
+            if data.remaining() < 8 {
+                return Err(DecodeError::Truncated);
+            }
+            Value::Number(data.get_f64_le())
+        }
+        TAG_STRING => {
+            let raw = take_sized(data)?;
+            let s = String::from_utf8(raw.to_vec()).map_err(|_| DecodeError::InvalidUtf8)?;
+            Value::String(s.into())
+        }
+        TAG_BYTES => Value::Bytes(Bytes::copy_from_slice(take_sized(data)?)),
+        TAG_LIST => {
+            if depth == MAX_DEPTH {
+                return Err(DecodeError::TooDeep);
+            }
+            let len = get_varint(data)?;
+            // every item takes at least a byte, which bounds what a corrupt
+            // length can make us allocate
+            if len > data.remaining() as u64 {
+                return Err(DecodeError::Truncated);
+            }
+            let items = (0..len)
+                .map(|_| decode_from(data, depth + 1))
+                .collect::<Result<_, _>>()?;
+            Value::List(Gc::new(items))
+        }
+        tag => return Err(DecodeError::UnknownTag(tag)),
+    })
+}
+
+fn put_varint(out: &mut BytesMut, mut n: u64) {
+    while n >= 0x80 {
+        out.put_u8((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.put_u8(n as u8);
+}
+
+fn get_varint(data: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        if !data.has_remaining() {
+            return Err(DecodeError::Truncated);
+        }
+        let byte = data.get_u8();
+        n |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(DecodeError::Truncated)
+}
+
+/// Reads a varint length and then that many bytes.
+fn take_sized<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let len = get_varint(data)?;
+    let len = usize::try_from(len).map_err(|_| DecodeError::Truncated)?;
+    if data.len() < len {
+        return Err(DecodeError::Truncated);
+    }
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Ok(taken)
+}
+
+/// `serialize(value)` returns `value` encoded as a byte blob. Values that
+/// cannot be serialized, such as functions, are a runtime error.
+pub(crate) const SERIALIZE: NativeFn = NativeFn {
+    name: "serialize",
+    arity: 1..=1,
+    fun: |_, args| {
+        encode(&args[0])
+            .map(Value::Bytes)
+            .map_err(|e| runtime_error!("E1020/serialize", error = e))
+    },
+};
+
+/// `deserialize(blob)` returns the value encoded in `blob`. Anything that is
+/// not a blob produced by `serialize` is a runtime error.
+pub(crate) const DESERIALIZE: NativeFn = NativeFn {
+    name: "deserialize",
+    arity: 1..=1,
+    fun: |_, args| match &args[0] {
+        Value::Bytes(blob) => {
+            decode(blob).map_err(|e| runtime_error!("E1020/deserialize", error = e))
+        }
+        other => Err(runtime_error!(
+            "E1017/bytes",
+            kind = locale::current().type_name(other.type_name())
+        )),
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A list holding a list, and so on `depth` deep, around nil.
+    fn nested(depth: usize) -> Value {
+        (0..depth).fold(Value::Nil, |inner, _| Value::List(Gc::new(vec![inner])))
+    }
+
+    #[test]
+    fn nested_lists_round_trip() {
+        let blob = encode(&nested(MAX_DEPTH)).expect("the value encodes");
+        let value = decode(&blob).expect("the blob decodes");
+        assert_eq!(encode(&value).expect("the value encodes again"), blob);
+    }
+
+    #[test]
+    fn lists_nested_too_deep_are_rejected() {
+        assert!(matches!(
+            encode(&nested(MAX_DEPTH + 1)),
+            Err(EncodeError::TooDeep)
+        ));
+
+        // a blob from a less careful encoder
+        let mut blob = MAGIC.to_vec();
+        for _ in 0..200_000 {
+            blob.extend([TAG_LIST, 1]);
+        }
+        blob.push(TAG_NIL);
+        assert!(matches!(decode(&blob), Err(DecodeError::TooDeep)));
+    }
+}
@@ -0,0 +1,78 @@
+use miette::{Diagnostic, IntoDiagnostic, SourceSpan, WrapErr};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Diagnostic, Debug, Error)]
+#[error(
+    "'{path}' is not valid UTF-8: invalid byte 0x{byte:02X} at offset {offset} (line {line}, column {column})"
+)]
+pub struct InvalidUtf8Error {
+    pub path: String,
+    pub byte: u8,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+
+    #[source_code]
+    src: String,
+
+    #[label = "invalid UTF-8 starts here"]
+    err_span: SourceSpan,
+
+    #[help]
+    hint: String,
+}
+
+/// Reads a source file, requiring it to be UTF-8.
+///
+/// With `lossy`, invalid byte sequences are replaced with U+FFFD instead of
+/// failing; otherwise the error points at the first invalid byte.
+pub fn read_source(path: &Path, lossy: bool) -> miette::Result<String> {
+    let bytes = fs::read(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("reading '{}' failed", path.display()))?;
+
+    match String::from_utf8(bytes) {
+        Ok(contents) => Ok(contents),
+        Err(e) if lossy => {
+            let contents = String::from_utf8_lossy(e.as_bytes()).into_owned();
+            crate::note!(
+                "replaced {} invalid UTF-8 sequence(s) in '{}'",
+                contents.matches(char::REPLACEMENT_CHARACTER).count(),
+                path.display()
+            );
+            Ok(contents)
+        }
+        Err(e) => {
+            let bytes = e.as_bytes();
+            let offset = e.utf8_error().valid_up_to();
+            let valid = std::str::from_utf8(&bytes[..offset]).expect("valid up to offset");
+            let line_start = valid.rfind('\n').map_or(0, |nl| nl + 1);
+
+            Err(InvalidUtf8Error {
+                path: path.display().to_string(),
+                byte: bytes[offset],
+                offset,
+                line: valid.matches('\n').count() + 1,
+                column: valid[line_start..].chars().count() + 1,
+                src: String::from_utf8_lossy(bytes).into_owned(),
+                err_span: SourceSpan::from(offset..offset + char::REPLACEMENT_CHARACTER.len_utf8()),
+                hint: encoding_hint(bytes),
+            }
+            .into())
+        }
+    }
+}
+
+fn encoding_hint(bytes: &[u8]) -> String {
+    let likely = if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        "the file starts with a UTF-16 byte order mark; "
+    } else if bytes.iter().any(|&b| (0xA1..=0xFA).contains(&b)) && !bytes.contains(&0) {
+        "the file may be in a legacy 8-bit encoding such as ISCII or Windows-1252; "
+    } else {
+        ""
+    };
+
+    format!("{likely}re-save it as UTF-8, or pass --lossy to replace invalid bytes with U+FFFD")
+}
@@ -0,0 +1,210 @@
+use crate::gc::Gc;
+use crate::interpreter::{runtime_error, NativeFn, RuntimeError, Value};
+use crate::locale;
+
+/// `length(x)` returns the number of characters (Unicode code points) in a
+/// string, the number of items in a list, or the numbers in a range.
+/// `length("नमस्ते")` is 6: न, म, स, the virama, त, and the vowel sign.
+pub(crate) const LENGTH: NativeFn = NativeFn {
+    name: "length",
+    arity: 1..=1,
+    fun: |_, args| match &args[0] {
+        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        Value::List(items) => Ok(Value::Number(items.len() as f64)),
+        Value::Range(range) => Ok(Value::Number(range.len() as f64)),
+        other => Err(runtime_error!(
+            "E1017/items",
+            name = "length",
+            kind = locale::current().type_name(other.type_name())
+        )),
+    },
+};
+
+/// `len(x)`, a shorter name for [`LENGTH`].
+pub(crate) const LEN: NativeFn = NativeFn {
+    name: "len",
+    ..LENGTH
+};
+
+/// `substring(s, start, end)` returns the characters of `s` from index
+/// `start` up to but not including `end`, counting code points from 0.
+/// Without `end` it runs to the end of `s`, so `substring(s, i, i + 1)` is
+/// the character at `i`.
+pub(crate) const SUBSTRING: NativeFn = NativeFn {
+    name: "substring",
+    arity: 2..=3,
+    fun: |_, args| {
+        let s = string_argument("substring", &args[0])?;
+        let length = s.chars().count();
+        let start = index_argument("substring", &args[1], length)?;
+        let end = match args.get(2) {
+            Some(end) => index_argument("substring", end, length)?,
+            None => length,
+        };
+        if start > end {
+            return Err(runtime_error!("E1018/substring", start = start, end = end));
+        }
+        let substring: String = s.chars().skip(start).take(end - start).collect();
+        Ok(Value::String(substring.into()))
+    },
+};
+
+/// `upper(s)` returns `s` with its letters in upper case. Scripts without
+/// case, such as Devanagari, are left as they are.
+pub(crate) const UPPER: NativeFn = NativeFn {
+    name: "upper",
+    arity: 1..=1,
+    fun: |_, args| {
+        let s = string_argument("upper", &args[0])?;
+        Ok(Value::String(s.to_uppercase().into()))
+    },
+};
+
+/// `lower(s)` returns `s` with its letters in lower case.
+pub(crate) const LOWER: NativeFn = NativeFn {
+    name: "lower",
+    arity: 1..=1,
+    fun: |_, args| {
+        let s = string_argument("lower", &args[0])?;
+        Ok(Value::String(s.to_lowercase().into()))
+    },
+};
+
+/// `split(s, separator)` returns the list of the parts of `s` between
+/// occurrences of `separator`. An empty separator splits `s` into its
+/// characters.
+pub(crate) const SPLIT: NativeFn = NativeFn {
+    name: "split",
+    arity: 2..=2,
+    fun: |_, args| {
+        let s = string_argument("split", &args[0])?;
+        let separator = string_argument("split", &args[1])?;
+        let parts = if separator.is_empty() {
+            s.chars()
+                .map(|c| Value::String(c.to_string().into()))
+                .collect()
+        } else {
+            s.split(separator)
+                .map(|part| Value::String(part.into()))
+                .collect()
+        };
+        Ok(Value::List(Gc::new(parts)))
+    },
+};
+
+/// `contains(s, part)` returns whether `part` occurs in `s`.
+pub(crate) const CONTAINS: NativeFn = NativeFn {
+    name: "contains",
+    arity: 2..=2,
+    fun: |_, args| {
+        let s = string_argument("contains", &args[0])?;
+        let part = string_argument("contains", &args[1])?;
+        Ok(Value::Bool(s.contains(part)))
+    },
+};
+
+/// `replace(s, from, to)` returns `s` with every occurrence of `from`
+/// replaced by `to`.
+pub(crate) const REPLACE: NativeFn = NativeFn {
+    name: "replace",
+    arity: 3..=3,
+    fun: |_, args| {
+        let s = string_argument("replace", &args[0])?;
+        let from = string_argument("replace", &args[1])?;
+        let to = string_argument("replace", &args[2])?;
+        if from.is_empty() {
+            return Err(runtime_error!("E1018/replace"));
+        }
+        Ok(Value::String(s.replace(from, to).into()))
+    },
+};
+
+/// `collate(a, b)` returns -1, 0, or 1 as `a` sorts before, with, or after
+/// `b` in dictionary order, rather than the code point order of `<`: case is
+/// ignored, Devanagari digits sort as the ASCII ones, and a Devanagari
+/// consonant without a vowel sign is read as having the vowel अ, so that
+/// `"वाक्"` sorts before `"वाक"`, as in a Sanskrit dictionary. Strings that
+/// only differ in what is ignored are ordered by their code points, so only
+/// equal strings give 0.
+pub(crate) const COLLATE: NativeFn = NativeFn {
+    name: "collate",
+    arity: 2..=2,
+    fun: |_, args| {
+        let a = string_argument("collate", &args[0])?;
+        let b = string_argument("collate", &args[1])?;
+        let ordering = collation_key(a)
+            .cmp(&collation_key(b))
+            .then_with(|| a.cmp(b));
+        Ok(Value::Number(ordering as i8 as f64))
+    },
+};
+
+/// The string builtins, which every interpreter has.
+pub(crate) const STRINGS: [NativeFn; 9] = [
+    LENGTH, LEN, SUBSTRING, UPPER, LOWER, SPLIT, CONTAINS, REPLACE, COLLATE,
+];
+
+/// The weights [`COLLATE`] compares `s` by. Each weight is a code point
+/// times 4, which leaves room to put the anusvara and visarga after every
+/// vowel and before the consonants.
+fn collation_key(s: &str) -> Vec<u32> {
+    const A: u32 = 0x0905 * 4;
+    let mut key = Vec::with_capacity(s.len());
+    let mut chars = s.chars().flat_map(char::to_lowercase).peekable();
+    while let Some(c) = chars.next() {
+        let weight = match c {
+            // a vowel sign sorts as its vowel: ा as आ, and so on
+            '\u{093E}'..='\u{094C}' => (c as u32 - 0x093E + 0x0906) * 4,
+            '\u{0901}' | '\u{0902}' => 0x0914 * 4 + 1,
+            '\u{0903}' => 0x0914 * 4 + 2,
+            '\u{0966}'..='\u{096F}' => (c as u32 - 0x0966 + '0' as u32) * 4,
+            // the virama only takes the vowel from the consonant before it
+            '\u{094D}' => continue,
+            '\u{0915}'..='\u{0939}' | '\u{0958}'..='\u{095F}' => {
+                key.push(c as u32 * 4);
+                if chars.peek() == Some(&'\u{093C}') {
+                    key.push(0x093C * 4);
+                    chars.next();
+                }
+                let vowel = matches!(chars.peek(), Some('\u{093E}'..='\u{094D}'));
+                if !vowel {
+                    key.push(A);
+                }
+                continue;
+            }
+            _ => c as u32 * 4,
+        };
+        key.push(weight);
+    }
+    key
+}
+
+fn string_argument<'a>(name: &str, value: &'a Value) -> Result<&'a str, RuntimeError> {
+    match value {
+        Value::String(s) => Ok(s),
+        other => Err(runtime_error!(
+            "E1017/string",
+            name = name,
+            kind = locale::current().type_name(other.type_name())
+        )),
+    }
+}
+
+/// A code point index into a string of `length` code points, which may be
+/// `length` itself, for the end.
+fn index_argument(name: &str, value: &Value, length: usize) -> Result<usize, RuntimeError> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 && *n >= 0.0 && *n <= length as f64 => Ok(*n as usize),
+        Value::Number(n) => Err(runtime_error!(
+            "E1018/index",
+            name = name,
+            index = n,
+            length = length
+        )),
+        other => Err(runtime_error!(
+            "E1017/index",
+            name = name,
+            kind = locale::current().type_name(other.type_name())
+        )),
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use crate::batch::Captured;
+use crate::diagnostics::Original;
+use crate::interpreter::{RuntimeError, Value};
+use crate::parse::{Atom, TokenTree};
+use crate::Interpreter;
+
+/// What the name of a function has to start with for `test` to run it.
+pub const TEST_PREFIXES: &[&str] = &["test", "परीक्षा"];
+
+/// The result of running one test function.
+#[derive(Debug)]
+pub struct TestOutcome {
+    pub name: String,
+    /// Everything the test printed, including what the rest of the program
+    /// printed before it.
+    pub output: String,
+    /// Why the test failed, or `None` if it passed.
+    pub error: Option<miette::Report>,
+    pub elapsed: Duration,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Runs each test function declared at the top level of `program`, which was
+/// parsed from the translation of `original`, and returns their outcomes in the order they are
+/// declared. A test function is one whose name starts with one of
+/// [`TEST_PREFIXES`]; it takes no parameters, and passes if its body runs
+/// without a runtime error, such as a failed `assert`.
+///
+/// Each test gets its own interpreter, built by `configure` from a fresh one, which
+/// first runs every other top-level statement of the program, so tests can
+/// share setup but can't observe each other.
+pub fn run_tests(
+    program: &[TokenTree<'_>],
+    original: Original<'_>,
+    configure: impl Fn(Interpreter) -> Interpreter,
+) -> Vec<TestOutcome> {
+    let (tests, setup): (Vec<_>, Vec<_>) = program
+        .iter()
+        .partition(|statement| test_function(statement).is_some());
+    let setup: Vec<TokenTree<'_>> = setup.into_iter().cloned().collect();
+
+    let report = |e: RuntimeError| original.report(&e);
+    tests
+        .into_iter()
+        .filter_map(test_function)
+        .map(|(name, has_parameters, statement)| {
+            let started = Instant::now();
+            let output = Captured::default();
+            let error = if has_parameters {
+                Some(miette::miette!(
+                    "test function {name} takes parameters, but tests are run without arguments"
+                ))
+            } else {
+                let mut interpreter = configure(Interpreter::new())
+                    .output(output.clone())
+                    .input(std::io::empty());
+                interpreter
+                    .eval_program(&setup)
+                    .and_then(|_| interpreter.eval_program(std::slice::from_ref(statement)))
+                    .and_then(|_| {
+                        let test = interpreter.get(name).unwrap_or(Value::Nil);
+                        interpreter.call(test, &[])
+                    })
+                    .err()
+                    .map(report)
+            };
+            TestOutcome {
+                name: name.to_string(),
+                output: output.contents(),
+                error,
+                elapsed: started.elapsed(),
+            }
+        })
+        .collect()
+}
+
+/// The name of the test function `statement` declares, whether it has
+/// parameters, and `statement` itself, or `None` if it doesn't declare one.
+fn test_function<'a, 'de>(
+    statement: &'a TokenTree<'de>,
+) -> Option<(&'de str, bool, &'a TokenTree<'de>)> {
+    let mut node = statement;
+    if let TokenTree::Statement { node: inner, .. } = node {
+        node = inner;
+    }
+    match node {
+        TokenTree::Fun {
+            name: Some(Atom::Ident(name, _)),
+            parameters,
+            ..
+        } if TEST_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) => {
+            Some((*name, !parameters.is_empty(), statement))
+        }
+        _ => None,
+    }
+}
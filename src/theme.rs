@@ -0,0 +1,89 @@
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// When to emit ANSI colors and styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color output going to a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// The output stream a piece of styled text is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Semantic roles that CLI output is styled by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Version,
+    Author,
+    Note,
+    Success,
+    Failure,
+    /// Syntax highlighting, by what a token is
+    Keyword,
+    String,
+    Number,
+    Operator,
+    Comment,
+}
+
+impl Style {
+    fn ansi(self) -> &'static str {
+        match self {
+            Style::Version => "32",
+            Style::Author => "1;31",
+            Style::Note => "36",
+            Style::Success => "32",
+            Style::Failure => "31",
+            Style::Keyword => "1;35",
+            Style::String => "32",
+            Style::Number => "36",
+            Style::Operator => "33",
+            Style::Comment => "2",
+        }
+    }
+}
+
+static CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Sets the color policy for the rest of the process. Only the first call has
+/// any effect.
+pub fn set_color_choice(choice: ColorChoice) {
+    let _ = CHOICE.set(choice);
+}
+
+pub fn color_choice() -> ColorChoice {
+    CHOICE.get().copied().unwrap_or_default()
+}
+
+/// Whether styled output should be emitted on `stream`.
+pub fn colors_enabled(stream: Stream) -> bool {
+    match color_choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            let terminal = match stream {
+                Stream::Stdout => std::io::stdout().is_terminal(),
+                Stream::Stderr => std::io::stderr().is_terminal(),
+            };
+            terminal && !no_color
+        }
+    }
+}
+
+/// Wraps `text` in the escape codes for `style`, if colors are enabled on `stream`.
+pub fn paint(stream: Stream, style: Style, text: &str) -> String {
+    if colors_enabled(stream) {
+        format!("\x1b[{}m{text}\x1b[0m", style.ansi())
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use miette::{LabeledSpan, NamedSource};
+use serde::{Deserialize, Serialize};
+
+use crate::lex::{Token, TokenKind};
+
+/// How `tokenize` writes tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TokenFormat {
+    /// one `KIND lexeme literal` line per token, followed by `EOF  null`
+    #[default]
+    Text,
+    /// one JSON object per line, which `parse --from-tokens` reads back
+    Json,
+}
+
+/// A token as one line of JSON:
+/// `{"kind":"NUMBER","lexeme":"४२","literal":42.0,"offset":8,"line":1}`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonToken<'a> {
+    #[serde(borrow)]
+    kind: Cow<'a, str>,
+    #[serde(borrow)]
+    lexeme: Cow<'a, str>,
+    #[serde(default)]
+    literal: serde_json::Value,
+    /// Byte offset of the token in the source.
+    #[serde(default)]
+    offset: Option<usize>,
+    /// Line of the token in the source, counting from 1.
+    #[serde(default)]
+    line: Option<usize>,
+}
+
+/// Writes `token`, scanned from `source`, as a line of JSON.
+pub fn to_json(token: &Token<'_>, source: &str) -> String {
+    let literal = match token.kind {
+        TokenKind::Number(n) => n.into(),
+        TokenKind::String
+        | TokenKind::StringHead
+        | TokenKind::StringMiddle
+        | TokenKind::StringTail => Token::unescape(token.origin).into(),
+        _ => serde_json::Value::Null,
+    };
+    let token = JsonToken {
+        kind: token.kind.name().into(),
+        lexeme: token.origin.into(),
+        literal,
+        offset: Some(token.offset),
+        line: Some(source[..token.offset].matches('\n').count() + 1),
+    };
+    serde_json::to_string(&token).expect("tokens serialize")
+}
+
+/// Tokens read back from JSON, along with a source for them to point into.
+///
+/// The source is rebuilt from the lexemes: each one goes on its `line` and at
+/// its `offset` where those leave room, so an unedited stream gets the same
+/// offsets, and line breaks for statement terminators, as the program it was
+/// scanned from. Tokens added or moved by hand go after the one before them.
+pub struct TokenStream {
+    source: String,
+    tokens: Vec<(TokenKind, Range<usize>)>,
+}
+
+impl TokenStream {
+    /// Reads tokens written by [`to_json`], one per line. Blank lines are
+    /// skipped.
+    pub fn from_json(input: &str) -> miette::Result<Self> {
+        let mut stream = Self {
+            source: String::new(),
+            tokens: Vec::new(),
+        };
+        let mut line = 1;
+
+        let mut at = 0;
+        for json in input.split_inclusive('\n') {
+            let span = at..at + json.trim_end().len();
+            at += json.len();
+            if json.trim().is_empty() {
+                continue;
+            }
+            let invalid = |message: String| {
+                miette::miette!(
+                    labels = vec![LabeledSpan::at(span.clone(), "this token")],
+                    "invalid token stream: {message}"
+                )
+                .with_source_code(NamedSource::new("<tokens>", input.to_string()))
+            };
+
+            let token: JsonToken<'_> =
+                serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+            let kind = match &*token.kind {
+                "NUMBER" => {
+                    let n = token
+                        .literal
+                        .as_f64()
+                        .or_else(|| token.lexeme.parse().ok())
+                        .ok_or_else(|| invalid("a NUMBER needs a numeric literal".into()))?;
+                    TokenKind::Number(n)
+                }
+                name => TokenKind::from_name(name)
+                    .ok_or_else(|| invalid(format!("unknown token kind '{name}'")))?,
+            };
+
+            let source = &mut stream.source;
+            if let Some(to) = token.line.filter(|&to| to > line) {
+                source.extend(std::iter::repeat_n('\n', to - line));
+                line = to;
+            }
+            match token.offset {
+                Some(offset) if offset >= source.len() => {
+                    let pad = offset - source.len();
+                    source.extend(std::iter::repeat_n(' ', pad));
+                }
+                _ if !source.is_empty() && !source.ends_with(char::is_whitespace) => {
+                    source.push(' ');
+                }
+                _ => {}
+            }
+            let start = source.len();
+            source.push_str(&token.lexeme);
+            line += token.lexeme.matches('\n').count();
+            stream.tokens.push((kind, start..source.len()));
+        }
+
+        Ok(stream)
+    }
+
+    /// The rebuilt source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The tokens, pointing into [`TokenStream::source`].
+    pub fn tokens(&self) -> Vec<Token<'_>> {
+        self.tokens
+            .iter()
+            .map(|(kind, range)| Token {
+                origin: &self.source[range.clone()],
+                offset: range.start,
+                kind: *kind,
+            })
+            .collect()
+    }
+}
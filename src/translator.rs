@@ -1,30 +1,542 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::ops::Range;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::lex::{is_ident_continue, is_word, RAW_QUOTES};
+
+/// A single keyword of a language pack along with its Lox equivalent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyword {
+    /// The canonical spelling, which [`normalize_keywords`] rewrites the
+    /// synonyms to.
+    pub native: &'static str,
+    /// Other accepted spellings, such as archaic or regional ones.
+    pub synonyms: &'static [&'static str],
+    pub lox: &'static str,
+    /// The keyword in IAST. Programs can spell the keyword this way too, or
+    /// without the diacritics; see [`romanizations`].
+    pub transliteration: &'static str,
+    /// A one-line program that uses the keyword and runs as it is, or empty
+    /// for a keyword whose feature doesn't run yet, like `class`.
+    pub example: &'static str,
+}
+
+impl Keyword {
+    /// The canonical spelling followed by the synonyms.
+    pub fn spellings(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.native).chain(self.synonyms.iter().copied())
+    }
+
+    /// Every spelling a program can write the keyword with: the canonical
+    /// one, the synonyms, and the [`romanizations`] of the transliteration.
+    pub fn all_spellings(&self) -> impl Iterator<Item = Cow<'static, str>> {
+        self.spellings().map(Cow::Borrowed).chain(
+            romanizations(self.transliteration)
+                .into_iter()
+                .map(Cow::Owned),
+        )
+    }
+}
+
+/// The spellings in Latin letters of a keyword transliterated as
+/// `transliteration`: the transliteration itself, and the same with its
+/// diacritics dropped (`yāvad` and `yavad`), for keyboards without them. A
+/// transliteration that isn't a single word has none.
+pub fn romanizations(transliteration: &str) -> Vec<String> {
+    if !is_word(transliteration) {
+        return Vec::new();
+    }
+    let plain: String = transliteration
+        .nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .collect();
+    if plain == transliteration || !is_word(&plain) {
+        vec![transliteration.to_string()]
+    } else {
+        vec![transliteration.to_string(), plain]
+    }
+}
+
+/// The keyword table for one source language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LanguagePack {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub keywords: &'static [Keyword],
+}
+
+impl LanguagePack {
+    /// The keyword spelled `word`, in its canonical form, as a synonym, or
+    /// romanized.
+    pub fn keyword(&self, word: &str) -> Option<&'static Keyword> {
+        self.keywords
+            .iter()
+            .find(|keyword| keyword.all_spellings().any(|spelling| spelling == word))
+    }
+
+    /// Translates the keywords of this pack in `contents` into their Lox
+    /// equivalents, as [`translate_file_contents`] does for Sanskrit.
+    pub fn translate(&self, contents: &str) -> String {
+        self.translate_with_map(contents).0
+    }
+
+    /// Like [`LanguagePack::translate`], but also returns where each
+    /// translated keyword came from in `contents`.
+    pub fn translate_with_map(&self, contents: &str) -> (String, SourceMap) {
+        rewrite_keywords(self, contents, |keyword| keyword.lox)
+    }
+
+    /// Rewrites every keyword of this pack spelled as one of its synonyms to
+    /// its canonical spelling, as [`normalize_keywords`] does for Sanskrit.
+    pub fn normalize(&self, contents: &str) -> String {
+        rewrite_keywords(self, contents, |keyword| keyword.native).0
+    }
+}
+
+pub const SANSKRIT: LanguagePack = LanguagePack {
+    code: "sa",
+    name: "संस्कृतम्",
+    keywords: &[
+        Keyword {
+            native: "च",
+            synonyms: &[],
+            lox: "and",
+            transliteration: "ca",
+            example: "कथय सत्य च असत्य;",
+        },
+        Keyword {
+            native: "पकड़",
+            synonyms: &[],
+            lox: "catch",
+            transliteration: "pakaṛa",
+            example: "प्रयत्न { फेंक 1; } पकड़ (त्रुटि) { कथय त्रुटि; }",
+        },
+        Keyword {
+            native: "श्रेणी",
+            synonyms: &[],
+            lox: "class",
+            transliteration: "śreṇī",
+            example: "",
+        },
+        Keyword {
+            native: "स्थिर",
+            synonyms: &[],
+            lox: "const",
+            transliteration: "sthira",
+            example: "स्थिर पाई = 3.14;",
+        },
+        Keyword {
+            native: "अन्यथा",
+            synonyms: &[],
+            lox: "default",
+            transliteration: "anyathā",
+            example: "चर क = 5; मेल (क) { 1: { कथय 1; } अन्यथा: { कथय 0; } }",
+        },
+        Keyword {
+            native: "अथ्वा",
+            synonyms: &["अथवा"],
+            lox: "else",
+            transliteration: "athvā",
+            example: "चर क = असत्य; यदि (क) { कथय 1; } अथ्वा { कथय 2; }",
+        },
+        Keyword {
+            native: "असत्य",
+            synonyms: &[],
+            lox: "false",
+            transliteration: "asatya",
+            example: "चर ध्वज = असत्य;",
+        },
+        Keyword {
+            native: "पुरा",
+            synonyms: &[],
+            lox: "for",
+            transliteration: "purā",
+            example: "पुरा (चर क = 0; क < 3; क = क + 1) { कथय क; }",
+        },
+        Keyword {
+            native: "विनियोग",
+            synonyms: &[],
+            lox: "fun",
+            transliteration: "viniyoga",
+            example: "विनियोग योग(क, ख) { देयम क + ख; }",
+        },
+        Keyword {
+            native: "यदि",
+            synonyms: &["यदी"],
+            lox: "if",
+            transliteration: "yadi",
+            example: "चर क = 1; यदि (क > 0) { कथय क; }",
+        },
+        Keyword {
+            native: "मध्ये",
+            synonyms: &[],
+            lox: "in",
+            transliteration: "madhye",
+            example: "पुरा (फल मध्ये [1, 2, 3]) { कथय फल; }",
+        },
+        Keyword {
+            native: "मेल",
+            synonyms: &[],
+            lox: "match",
+            transliteration: "mela",
+            example: "चर क = 2; मेल (क) { 1, 2: { कथय \"कम\"; } अन्यथा: { कथय \"अधिक\"; } }",
+        },
+        Keyword {
+            native: "नेति",
+            synonyms: &[],
+            lox: "nil",
+            transliteration: "neti",
+            example: "चर रिक्त = नेति;",
+        },
+        Keyword {
+            native: "विकल्प",
+            synonyms: &[],
+            lox: "or",
+            transliteration: "vikalpa",
+            example: "कथय असत्य विकल्प सत्य;",
+        },
+        Keyword {
+            native: "कथय",
+            synonyms: &[],
+            lox: "print",
+            transliteration: "kathaya",
+            example: "कथय \"नमस्ते\";",
+        },
+        Keyword {
+            native: "देयम",
+            synonyms: &["प्रतिददाति"],
+            lox: "return",
+            transliteration: "deyam",
+            example: "विनियोग द्विगुण(क) { देयम क * 2; }",
+        },
+        Keyword {
+            native: "महा",
+            synonyms: &[],
+            lox: "super",
+            transliteration: "mahā",
+            example: "",
+        },
+        Keyword {
+            native: "यह",
+            synonyms: &[],
+            lox: "this",
+            transliteration: "yaha",
+            example: "",
+        },
+        Keyword {
+            native: "फेंक",
+            synonyms: &[],
+            lox: "throw",
+            transliteration: "pheṃka",
+            example: "प्रयत्न { फेंक \"अमान्य संख्या\"; } पकड़ (त्रुटि) { कथय त्रुटि; }",
+        },
+        Keyword {
+            native: "सत्य",
+            synonyms: &[],
+            lox: "true",
+            transliteration: "satya",
+            example: "चर ध्वज = सत्य;",
+        },
+        Keyword {
+            native: "प्रयत्न",
+            synonyms: &[],
+            lox: "try",
+            transliteration: "prayatna",
+            example: "चर क = 1; प्रयत्न { क = क / 0; } पकड़ (त्रुटि) { क = 0; }",
+        },
+        Keyword {
+            native: "चर",
+            synonyms: &[],
+            lox: "var",
+            transliteration: "cara",
+            example: "चर क = 1;",
+        },
+        Keyword {
+            native: "यावद",
+            synonyms: &[],
+            lox: "while",
+            transliteration: "yāvad",
+            example: "चर क = 0; यावद (क < 3) { क = क + 1; }",
+        },
+    ],
+};
+
+pub const LANGUAGE_PACKS: &[LanguagePack] = &[SANSKRIT];
+
+/// The Lox keywords, every one of which a language pack must spell.
+pub const LOX_KEYWORDS: &[&str] = &[
+    "and", "catch", "class", "const", "default", "else", "false", "for", "fun", "if", "in",
+    "match", "nil", "or", "print", "return", "super", "this", "throw", "true", "try", "var",
+    "while",
+];
+
+/// Looks up a built-in language pack by its language code (e.g. `sa`).
+pub fn language_pack(code: &str) -> Option<&'static LanguagePack> {
+    LANGUAGE_PACKS.iter().find(|pack| pack.code == code)
+}
 
 /// Translates Sanskrit keywords in the given source contents into
 /// their Lox equivalents so the rest of the pipeline can operate
 /// on standard Lox syntax.
+///
+/// Only whole words are translated, so a keyword inside a longer identifier
+/// (like the `च` in `वचन`) is left alone, and so is text inside string
+/// literals and comments. Code inside a string's `${…}` is translated. Line
+/// breaks are kept, so lines in the output match lines in `contents`.
 pub fn translate_file_contents(contents: &str) -> miette::Result<String> {
-    let mut replacements = HashMap::new();
-    replacements.insert("श्रेणी", "class");
-    replacements.insert("अथ्वा", "else");
-    replacements.insert("असत्य", "false");
-    replacements.insert("पुरा", "for");
-    replacements.insert("विनियोग", "fun");
-    replacements.insert("यदि", "if");
-    replacements.insert("नेति", "nil");
-    replacements.insert("विकल्प", "or");
-    replacements.insert("कथय", "print");
-    replacements.insert("देयम", "return");
-    replacements.insert("महा", "super");
-    replacements.insert("यह", "this");
-    replacements.insert("सत्य", "true");
-    replacements.insert("चर", "var");
-    replacements.insert("यावद", "while");
-
-    let mut output = contents.to_string();
-    for (from, to) in replacements {
-        output = output.replace(from, to);
-    }
-
-    Ok(output)
-}
\ No newline at end of file
+    Ok(SANSKRIT.translate(contents))
+}
+
+/// Like [`translate_file_contents`], but also returns a [`SourceMap`] from
+/// the translation back to `contents`.
+pub fn translate_file_contents_with_map(contents: &str) -> miette::Result<(String, SourceMap)> {
+    Ok(SANSKRIT.translate_with_map(contents))
+}
+
+/// Where each keyword a translation rewrote came from, so that tools that
+/// work on the translated text can point at the original. Text between the
+/// keywords is copied unchanged, so only its offset moves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct SourceMap {
+    /// The rewritten keywords, in order.
+    pub mappings: Vec<Mapping>,
+}
+
+/// A keyword at byte range `original` of the source that was written at
+/// byte range `translated` of the translation.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Mapping {
+    pub original: Range<usize>,
+    pub translated: Range<usize>,
+}
+
+impl SourceMap {
+    /// The offset in the source of `offset` in the translation. An offset
+    /// inside a translated keyword maps to the start of the original one.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let before = self
+            .mappings
+            .partition_point(|m| m.translated.start <= offset);
+        match before.checked_sub(1).map(|i| &self.mappings[i]) {
+            None => offset,
+            Some(m) if offset < m.translated.end => m.original.start,
+            Some(m) => offset - m.translated.end + m.original.end,
+        }
+    }
+
+    /// The map as the JSON that `translate --source-map` writes, naming the
+    /// file the offsets of `original` are into.
+    pub fn to_json(&self, source: &str) -> String {
+        #[derive(serde::Serialize)]
+        struct Json<'a> {
+            version: u32,
+            source: &'a str,
+            mappings: &'a [Mapping],
+        }
+        let json = Json {
+            version: 1,
+            source,
+            mappings: &self.mappings,
+        };
+        let mut text = serde_json::to_string_pretty(&json).expect("source maps serialize");
+        text.push('\n');
+        text
+    }
+}
+
+/// Rewrites every keyword spelled as one of its synonyms to its canonical
+/// spelling, leaving the rest of `contents` as it is. Like translation, this
+/// only touches whole words outside strings and comments.
+pub fn normalize_keywords(contents: &str) -> String {
+    SANSKRIT.normalize(contents)
+}
+
+/// Replaces each keyword of `pack` in `contents`, in any of its spellings,
+/// with `replacement(keyword)`, and maps where each replacement came from.
+fn rewrite_keywords(
+    pack: &LanguagePack,
+    contents: &str,
+    replacement: impl Fn(&Keyword) -> &'static str,
+) -> (String, SourceMap) {
+    let spellings: HashMap<Cow<str>, &Keyword> = pack
+        .keywords
+        .iter()
+        .flat_map(|keyword| {
+            keyword
+                .all_spellings()
+                .map(move |spelling| (spelling, keyword))
+        })
+        .collect();
+
+    let mut output = String::with_capacity(contents.len());
+    let mut map = SourceMap::default();
+    // the brace depth inside each `${` we are in, as in the lexer
+    let mut interpolations: Vec<usize> = Vec::new();
+    let mut in_string = false;
+    let mut previous = None;
+    let mut rest = contents;
+
+    while let Some(c) = rest.chars().next() {
+        let mut taken = c.len_utf8();
+        if in_string {
+            if c == '"' {
+                in_string = false;
+            } else if rest.starts_with("${") {
+                in_string = false;
+                interpolations.push(0);
+                taken = 2;
+            }
+        } else if rest.starts_with("//") {
+            taken = rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(raw) = rest.strip_prefix(RAW_QUOTES) {
+            // a raw string has no interpolation, so it is skipped whole
+            taken = match raw.find(RAW_QUOTES) {
+                Some(close) => {
+                    let after = &raw[close..];
+                    RAW_QUOTES.len() + close + after.len() - after.trim_start_matches('"').len()
+                }
+                None => rest.len(),
+            };
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '{' {
+            if let Some(depth) = interpolations.last_mut() {
+                *depth += 1;
+            }
+        } else if c == '}' {
+            match interpolations.last_mut() {
+                Some(0) => {
+                    interpolations.pop();
+                    in_string = true;
+                }
+                Some(depth) => *depth -= 1,
+                None => {}
+            }
+        } else if !previous.is_some_and(is_ident_continue) {
+            // only whole words are keywords, so that e.g. असत्य isn't read as
+            // अ + सत्य
+            let word = &rest[..rest.find(|c| !is_ident_continue(c)).unwrap_or(rest.len())];
+            if let Some(keyword) = spellings.get(word) {
+                let at = contents.len() - rest.len();
+                let start = output.len();
+                output.push_str(replacement(keyword));
+                map.mappings.push(Mapping {
+                    original: at..at + word.len(),
+                    translated: start..output.len(),
+                });
+                rest = &rest[word.len()..];
+                previous = word.chars().next_back();
+                continue;
+            }
+        }
+
+        output.push_str(&rest[..taken]);
+        previous = rest[..taken].chars().next_back();
+        rest = &rest[taken..];
+    }
+
+    (output, map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `चर` (6 bytes) becomes `var` (3) and `कथय` (9) becomes `print` (5).
+    const PROGRAM: &str = "चर क = 1; कथय क;";
+
+    /// `contents` with Lox keywords, and the map back to it.
+    fn translated(contents: &str) -> (String, SourceMap) {
+        SANSKRIT.translate_with_map(contents)
+    }
+
+    #[test]
+    fn each_rewritten_keyword_is_mapped() {
+        let (text, map) = translated(PROGRAM);
+        assert_eq!(text, "var क = 1; print क;");
+        assert_eq!(
+            map.mappings,
+            [
+                Mapping {
+                    original: 0..6,
+                    translated: 0..3,
+                },
+                Mapping {
+                    original: 16..25,
+                    translated: 13..18,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_between_keywords_move_with_the_keyword_before_them() {
+        let (text, map) = translated(PROGRAM);
+        for (word, original) in [("var", 0), ("क", 7), ("print", 16)] {
+            assert_eq!(map.original_offset(text.find(word).unwrap()), original);
+        }
+        assert_eq!(map.original_offset(text.rfind('क').unwrap()), 26);
+        assert_eq!(&PROGRAM[26..29], "क");
+    }
+
+    #[test]
+    fn an_offset_inside_a_rewritten_keyword_maps_to_its_start() {
+        let (_, map) = translated(PROGRAM);
+        assert_eq!(map.original_offset(2), 0);
+        assert_eq!(map.original_offset(15), 16);
+        assert_eq!(map.original_offset(17), 16);
+    }
+
+    #[test]
+    fn an_offset_past_the_last_keyword_moves_by_its_end() {
+        let (text, map) = translated(PROGRAM);
+        assert_eq!(map.original_offset(text.len() - 1), PROGRAM.len() - 1);
+        assert_eq!(map.original_offset(text.len()), PROGRAM.len());
+    }
+
+    #[test]
+    fn keywords_that_grow_in_translation_map_back() {
+        let (text, map) = translated("x ca y;");
+        assert_eq!(text, "x and y;");
+        assert_eq!(map.original_offset(3), 2);
+        assert_eq!(map.original_offset(4), 2);
+        assert_eq!(map.original_offset(6), 5);
+        assert_eq!(map.original_offset(text.len()), 7);
+    }
+
+    #[test]
+    fn without_keywords_offsets_are_kept() {
+        let (text, map) = translated("x = \"चर\"; // कथय");
+        assert_eq!(map, SourceMap::default());
+        assert_eq!(map.original_offset(5), 5);
+        assert_eq!(map.original_offset(text.len()), text.len());
+    }
+
+    #[test]
+    fn the_json_names_the_source_and_lists_the_mappings() {
+        let (_, map) = translated("चर क;");
+        assert_eq!(
+            map.to_json("क.sk"),
+            r#"{
+  "version": 1,
+  "source": "क.sk",
+  "mappings": [
+    {
+      "original": {
+        "start": 0,
+        "end": 6
+      },
+      "translated": {
+        "start": 0,
+        "end": 3
+      }
+    }
+  ]
+}
+"#
+        );
+    }
+}
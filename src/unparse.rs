@@ -0,0 +1,587 @@
+use crate::lex::{TokenKind, RAW_QUOTES};
+use crate::parse::{
+    infix_binding_power, postfix_binding_power, prefix_binding_power, Atom, Op, TokenTree,
+};
+use crate::translator::LanguagePack;
+use crate::Lexer;
+
+/// How unparsed source spells its keywords.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keywords<'a> {
+    /// In Lox, as the parser reads them.
+    Lox,
+    /// In the canonical spellings of a language pack, as people write them.
+    Pack(&'a LanguagePack),
+}
+
+impl Keywords<'_> {
+    fn spell(self, lox: &'static str) -> &'static str {
+        match self {
+            Keywords::Lox => lox,
+            Keywords::Pack(pack) => pack
+                .keywords
+                .iter()
+                .find(|keyword| keyword.lox == lox)
+                .map_or(lox, |keyword| keyword.native),
+        }
+    }
+}
+
+/// Writes `program` back out as source text, one statement per line, with
+/// blocks indented by two spaces. Parsing the result gives the same syntax
+/// trees, apart from source offsets, so this is the way back from an edited
+/// or generated tree to a program.
+///
+/// Comments, blank lines, and the original layout are not in the tree and
+/// so are lost, and every statement ends with `terminator`: `;`, or `।` or
+/// `॥`, which end statements too.
+pub fn unparse(program: &[TokenTree<'_>], keywords: Keywords<'_>, terminator: &str) -> String {
+    let mut writer = Writer {
+        out: String::new(),
+        indent: 0,
+        keywords,
+        terminator,
+    };
+    for statement in program {
+        writer.statement(statement);
+    }
+    writer.out
+}
+
+impl TokenTree<'_> {
+    /// This node as source text, as [`unparse`] writes it. An expression is
+    /// written on one line, except for any blocks in it; parentheses are
+    /// added where the tree groups operators differently from how their
+    /// precedence would.
+    pub fn to_source(&self, keywords: Keywords<'_>) -> String {
+        let mut writer = Writer {
+            out: String::new(),
+            indent: 0,
+            keywords,
+            terminator: ";",
+        };
+        if self.is_expression() {
+            writer.expression(self);
+        } else {
+            writer.statement(self);
+            writer.out.truncate(writer.out.trim_end().len());
+        }
+        writer.out
+    }
+}
+
+/// The spelling of `;` that `source` ends its statements with most often,
+/// so that [`unparse`] can keep it: `;`, `।`, or `॥`.
+pub fn terminator(source: &str) -> &'static str {
+    let mut counts = [(";", 0), ("।", 0), ("॥", 0)];
+    for token in Lexer::new(source).map_while(Result::ok) {
+        if token.kind != TokenKind::Semicolon {
+            continue;
+        }
+        if let Some((_, count)) = counts
+            .iter_mut()
+            .find(|(spelling, _)| *spelling == token.origin)
+        {
+            *count += 1;
+        }
+    }
+    // the first of the most used, so `;` when there are none
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map_or(";", |(spelling, _)| spelling)
+}
+
+struct Writer<'k> {
+    out: String,
+    indent: usize,
+    keywords: Keywords<'k>,
+    terminator: &'k str,
+}
+
+/// How tightly an operand that nothing can come between, like a name or a
+/// call, binds on either side.
+const TIGHTEST: u8 = u8::MAX;
+
+/// How tightly a prefix `-` or `!` binds its operand.
+fn prefix() -> u8 {
+    prefix_binding_power(Op::Minus).1
+}
+
+/// How tightly a comparison binds on its left and right.
+fn comparison() -> (u8, u8) {
+    infix_binding_power(Op::Less).expect("comparisons are infix")
+}
+
+impl Writer<'_> {
+    fn keyword(&mut self, lox: &'static str) {
+        let word = self.keywords.spell(lox);
+        self.out.push_str(word);
+    }
+
+    fn line(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+    }
+
+    /// Writes a statement and the line break after it.
+    fn statement(&mut self, node: &TokenTree<'_>) {
+        let node = unwrap_statement(node);
+        self.expression(node);
+        if needs_semicolon(node) {
+            self.out.push_str(self.terminator);
+        }
+        self.line();
+    }
+
+    fn block(&mut self, statements: &[TokenTree<'_>], value: Option<&TokenTree<'_>>) {
+        if statements.is_empty() && value.is_none() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push('{');
+        self.indent += 1;
+        self.line();
+        for statement in statements {
+            self.statement(statement);
+        }
+        if let Some(value) = value {
+            self.expression(value);
+            self.line();
+        }
+        self.indent -= 1;
+        // the line break after the last statement indented for the block
+        self.out.truncate(self.out.trim_end_matches(' ').len());
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+        self.out.push('}');
+    }
+
+    /// Writes `node` wherever anything may stand, such as a statement or an
+    /// argument.
+    fn expression(&mut self, node: &TokenTree<'_>) {
+        match node {
+            TokenTree::Atom(atom) => self.atom(atom),
+            TokenTree::Statement { node, .. } | TokenTree::Spanned { node, .. } => {
+                self.expression(node)
+            }
+            TokenTree::Cons(Op::Plus, _) if self.interpolation(node) => {}
+            TokenTree::Cons(op, children) => self.cons(*op, children),
+            TokenTree::Fun {
+                name,
+                parameters,
+                body,
+            } => {
+                self.keyword("fun");
+                self.out.push(' ');
+                if let Some(name) = name {
+                    self.atom(name);
+                }
+                self.out.push('(');
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(parameter.origin);
+                }
+                self.out.push_str(") ");
+                self.expression(body);
+            }
+            TokenTree::Call { callee, arguments } => {
+                let (call, ()) = postfix_binding_power(Op::Call).expect("calls are postfix");
+                self.left_operand(callee, call);
+                self.out.push('(');
+                self.list(arguments);
+                self.out.push(')');
+            }
+            TokenTree::If { condition, yes, no } => {
+                self.keyword("if");
+                self.out.push_str(" (");
+                self.expression(condition);
+                self.out.push_str(") ");
+                self.expression(yes);
+                if let Some(no) = no {
+                    self.out.push(' ');
+                    self.keyword("else");
+                    self.out.push(' ');
+                    self.expression(no);
+                }
+            }
+            TokenTree::Block { statements, value } => self.block(statements, value.as_deref()),
+            TokenTree::Assignments { targets, values } => {
+                self.list(targets);
+                self.out.push_str(" = ");
+                self.list(values);
+            }
+            TokenTree::Chain {
+                operands,
+                operators,
+                ..
+            } => {
+                let (left, right) = comparison();
+                self.left_operand(&operands[0], left);
+                for (op, operand) in operators.iter().zip(&operands[1..]) {
+                    self.out.push_str(&format!(" {op} "));
+                    self.right_operand(operand, right);
+                }
+            }
+            TokenTree::Match {
+                subject,
+                arms,
+                default,
+            } => {
+                self.keyword("match");
+                self.out.push_str(" (");
+                self.expression(subject);
+                self.out.push_str(") {");
+                self.indent += 1;
+                for (literals, body) in arms.iter() {
+                    self.line();
+                    for (i, literal) in literals.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str(", ");
+                        }
+                        self.atom(literal);
+                    }
+                    self.out.push_str(": ");
+                    self.expression(body);
+                }
+                if let Some(default) = default {
+                    self.line();
+                    self.keyword("default");
+                    self.out.push_str(": ");
+                    self.expression(default);
+                }
+                self.indent -= 1;
+                self.line();
+                self.out.push('}');
+            }
+            TokenTree::ForIn {
+                variable,
+                iterable,
+                body,
+            } => {
+                self.keyword("for");
+                self.out.push_str(" (");
+                self.atom(variable);
+                self.out.push(' ');
+                self.keyword("in");
+                self.out.push(' ');
+                self.expression(iterable);
+                self.out.push_str(") ");
+                self.expression(body);
+            }
+            TokenTree::Try {
+                body,
+                variable,
+                handler,
+            } => {
+                self.keyword("try");
+                self.out.push(' ');
+                self.expression(body);
+                self.out.push(' ');
+                self.keyword("catch");
+                self.out.push_str(" (");
+                self.atom(variable);
+                self.out.push_str(") ");
+                self.expression(handler);
+            }
+        }
+    }
+
+    fn cons(&mut self, op: Op, children: &[TokenTree<'_>]) {
+        match (op, children) {
+            (Op::Group, [inner]) => {
+                self.out.push('(');
+                self.expression(inner);
+                self.out.push(')');
+            }
+            (Op::List, items) => {
+                self.out.push('[');
+                self.list(items);
+                self.out.push(']');
+            }
+            (Op::Var | Op::Const, declarations) => {
+                self.keyword(if op == Op::Const { "const" } else { "var" });
+                self.out.push(' ');
+                for (i, declaration) in declarations.chunks(2).enumerate() {
+                    if let [target, value] = declaration {
+                        if i > 0 {
+                            self.out.push_str(", ");
+                        }
+                        self.expression(target);
+                        self.out.push_str(" = ");
+                        self.expression(value);
+                    }
+                }
+            }
+            (Op::Return, []) => self.keyword("return"),
+            (Op::Print | Op::Return | Op::Throw, [value]) => {
+                self.keyword(match op {
+                    Op::Print => "print",
+                    Op::Return => "return",
+                    _ => "throw",
+                });
+                self.out.push(' ');
+                self.right_operand(value, prefix_binding_power(op).1);
+            }
+            (Op::While, [condition, body]) => {
+                self.keyword("while");
+                self.out.push_str(" (");
+                self.expression(condition);
+                self.out.push_str(") ");
+                self.expression(body);
+            }
+            (Op::For, [init, condition, increment, body]) => {
+                self.keyword("for");
+                self.out.push_str(" (");
+                self.expression(init);
+                self.out.push_str("; ");
+                self.expression(condition);
+                self.out.push_str("; ");
+                self.expression(increment);
+                self.out.push_str(") ");
+                self.expression(body);
+            }
+            (Op::Class, [name, body]) => {
+                self.keyword("class");
+                self.out.push(' ');
+                self.expression(name);
+                self.out.push(' ');
+                self.expression(body);
+            }
+            (Op::Ternary, [condition, yes, no]) => {
+                let (left, right) = infix_binding_power(op).expect("the ternary is infix");
+                self.left_operand(condition, left);
+                self.out.push_str(" ? ");
+                self.expression(yes);
+                self.out.push_str(" : ");
+                self.right_operand(no, right);
+            }
+            (Op::PreIncrement | Op::PreDecrement, [name]) => {
+                self.out
+                    .push_str(if op == Op::PreIncrement { "++" } else { "--" });
+                self.expression(name);
+            }
+            (Op::PostIncrement | Op::PostDecrement, [name]) => {
+                self.expression(name);
+                self.out
+                    .push_str(if op == Op::PostIncrement { "++" } else { "--" });
+            }
+            (Op::Minus | Op::Bang, [operand]) => {
+                self.out.push_str(if op == Op::Minus { "-" } else { "!" });
+                // `- -x` written as `--x` would be a decrement
+                if op == Op::Minus && starts_with_minus(operand) {
+                    self.out.push('(');
+                    self.expression(operand);
+                    self.out.push(')');
+                } else {
+                    self.right_operand(operand, prefix());
+                }
+            }
+            (Op::Field, [object, field]) => {
+                let (left, _) = infix_binding_power(Op::Field).expect("fields are infix");
+                self.left_operand(object, left);
+                self.out.push('.');
+                self.expression(field);
+            }
+            (op, [lhs, rhs]) => {
+                let (left, right) = infix_binding_power(op).unwrap_or((0, 0));
+                self.left_operand(lhs, left);
+                match op {
+                    Op::Range | Op::RangeInclusive => self.out.push_str(&op.to_string()),
+                    Op::And => {
+                        self.out.push(' ');
+                        self.keyword("and");
+                        self.out.push(' ');
+                    }
+                    Op::Or => {
+                        self.out.push(' ');
+                        self.keyword("or");
+                        self.out.push(' ');
+                    }
+                    _ => self.out.push_str(&format!(" {op} ")),
+                }
+                self.right_operand(rhs, right);
+            }
+            (op, children) => {
+                // not a shape the parser makes; write it as the parser
+                // would read a call
+                self.out.push_str(&format!("{op}("));
+                self.list(children);
+                self.out.push(')');
+            }
+        }
+    }
+
+    /// Writes the interpolated string that `"a ${x} b"` parses to, if `node`
+    /// is one: a left-leaning chain of `+` without a span, starting with a
+    /// string, in which each expression is grouped.
+    fn interpolation(&mut self, node: &TokenTree<'_>) -> bool {
+        let mut parts = Vec::new();
+        let mut rest = node;
+        while let TokenTree::Cons(Op::Plus, pair) = rest {
+            let [lhs, rhs] = &pair[..] else {
+                return false;
+            };
+            parts.push(rhs);
+            rest = lhs;
+        }
+        let TokenTree::Atom(Atom::String(head)) = rest else {
+            return false;
+        };
+        let interpolated = |part: &&TokenTree<'_>| matches!(part, TokenTree::Cons(Op::Group, _));
+        if !parts.iter().any(interpolated) {
+            return false;
+        }
+        // text that only a raw string can hold can't be interpolated into
+        let plain = |text: &str| !text.contains('"') && !text.contains("${");
+        if !plain(head)
+            || !parts.iter().all(|part| {
+                interpolated(part) || matches!(part, TokenTree::Atom(Atom::String(s)) if plain(s))
+            })
+        {
+            return false;
+        }
+
+        self.out.push('"');
+        self.out.push_str(head);
+        for part in parts.into_iter().rev() {
+            match part {
+                TokenTree::Cons(Op::Group, inner) => {
+                    self.out.push_str("${");
+                    if let [inner] = &inner[..] {
+                        self.expression(inner);
+                    }
+                    self.out.push('}');
+                }
+                TokenTree::Atom(Atom::String(text)) => self.out.push_str(text),
+                _ => unreachable!("checked above"),
+            }
+        }
+        self.out.push('"');
+        true
+    }
+
+    fn list(&mut self, items: &[TokenTree<'_>]) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expression(item);
+        }
+    }
+
+    /// Writes the operand on the left of an operator that binds `left`
+    /// tightly on that side, in parentheses if it would otherwise take the
+    /// operator into itself.
+    fn left_operand(&mut self, operand: &TokenTree<'_>, left: u8) {
+        let (_, operand_right) = binding_powers(operand);
+        self.operand(operand, operand_right <= left);
+    }
+
+    /// Writes the operand on the right of an operator that binds `right`
+    /// tightly on that side.
+    fn right_operand(&mut self, operand: &TokenTree<'_>, right: u8) {
+        let (operand_left, _) = binding_powers(operand);
+        self.operand(operand, operand_left < right);
+    }
+
+    fn operand(&mut self, operand: &TokenTree<'_>, parenthesize: bool) {
+        if parenthesize {
+            self.out.push('(');
+            self.expression(operand);
+            self.out.push(')');
+        } else {
+            self.expression(operand);
+        }
+    }
+
+    fn atom(&mut self, atom: &Atom<'_>) {
+        match atom {
+            Atom::String(s) if s.contains('"') || s.contains("${") => {
+                self.out.push_str(RAW_QUOTES);
+                // the first line break of a raw string is dropped
+                if s.starts_with('\n') || s.starts_with("\r\n") {
+                    self.out.push('\n');
+                }
+                self.out.push_str(s);
+                self.out.push_str(RAW_QUOTES);
+            }
+            Atom::String(s) => {
+                self.out.push('"');
+                self.out.push_str(s);
+                self.out.push('"');
+            }
+            // unlike Display, whole numbers are written without `.0`
+            Atom::Number(n) => self.out.push_str(&n.to_string()),
+            Atom::Nil => self.keyword("nil"),
+            Atom::Bool(true) => self.keyword("true"),
+            Atom::Bool(false) => self.keyword("false"),
+            Atom::Ident(name, _) => self.out.push_str(name),
+            Atom::Super => self.keyword("super"),
+            Atom::This => self.keyword("this"),
+        }
+    }
+}
+
+fn unwrap_statement<'a, 'de>(node: &'a TokenTree<'de>) -> &'a TokenTree<'de> {
+    match node {
+        TokenTree::Statement { node, .. } => unwrap_statement(node),
+        node => node,
+    }
+}
+
+/// Whether a statement ends with `;`, as opposed to with the `}` of a block.
+fn needs_semicolon(node: &TokenTree<'_>) -> bool {
+    !matches!(
+        node,
+        TokenTree::Fun { name: Some(_), .. }
+            | TokenTree::If { .. }
+            | TokenTree::Block { .. }
+            | TokenTree::Match { .. }
+            | TokenTree::ForIn { .. }
+            | TokenTree::Try { .. }
+            | TokenTree::Cons(Op::While | Op::For | Op::Class, _)
+    )
+}
+
+/// Whether `node` is written starting with a `-`.
+fn starts_with_minus(node: &TokenTree<'_>) -> bool {
+    match node {
+        TokenTree::Atom(Atom::Number(n)) => n.is_sign_negative(),
+        TokenTree::Cons(Op::Minus, children) => children.len() == 1,
+        TokenTree::Cons(Op::PreDecrement, _) => true,
+        TokenTree::Spanned { node, .. } => starts_with_minus(node),
+        _ => false,
+    }
+}
+
+/// How tightly the outermost operator of `node` binds on its left and right,
+/// as in the parser. Statement forms used as values, like `if`, get 0, so
+/// that they are always parenthesized as operands.
+fn binding_powers(node: &TokenTree<'_>) -> (u8, u8) {
+    match node {
+        TokenTree::Spanned { node, .. } | TokenTree::Statement { node, .. } => binding_powers(node),
+        TokenTree::Atom(Atom::Number(n)) if n.is_sign_negative() => (TIGHTEST, prefix()),
+        TokenTree::Atom(_) | TokenTree::Call { .. } => (TIGHTEST, TIGHTEST),
+        TokenTree::Chain { .. } => comparison(),
+        TokenTree::Cons(
+            Op::Group
+            | Op::List
+            | Op::PreIncrement
+            | Op::PreDecrement
+            | Op::PostIncrement
+            | Op::PostDecrement,
+            _,
+        ) => (TIGHTEST, TIGHTEST),
+        TokenTree::Cons(Op::Minus, children) if children.len() == 1 => (TIGHTEST, prefix()),
+        TokenTree::Cons(Op::Bang, _) => (TIGHTEST, prefix()),
+        TokenTree::Cons(op, _) => infix_binding_power(*op).unwrap_or((0, 0)),
+        _ => (0, 0),
+    }
+}
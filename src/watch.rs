@@ -0,0 +1,89 @@
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use miette::{IntoDiagnostic, WrapErr};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::note;
+
+/// How long to wait after a change before running again, so that an editor
+/// that saves a file in several steps has finished.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Calls `run`, and calls it again each time one of the files at `paths` is
+/// changed, created, or removed, until the process is interrupted. `run`
+/// returns the code the process would exit with, which is reported after
+/// each run. The terminal is cleared before each run, when stdout is one.
+pub fn watch(paths: &[PathBuf], mut run: impl FnMut() -> i32) -> miette::Result<()> {
+    let files: Vec<PathBuf> = paths
+        .iter()
+        .map(std::path::absolute)
+        .collect::<Result<_, _>>()
+        .into_diagnostic()
+        .wrap_err("finding the files to watch failed")?;
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // the receiver only goes away when watching ends
+        let _ = sender.send(event);
+    })
+    .into_diagnostic()
+    .wrap_err("starting to watch files failed")?;
+    // editors often save by replacing a file, which would end a watch on the
+    // file itself, so it is the directories that are watched
+    let mut dirs: Vec<&Path> = files
+        .iter()
+        .map(|file| file.parent().unwrap_or(Path::new("/")))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("watching '{}' failed", dir.display()))?;
+    }
+
+    let watched = match paths {
+        [path] => format!("'{}'", path.display()),
+        _ => format!("{} files", paths.len()),
+    };
+    loop {
+        clear_terminal();
+        match run() {
+            0 => note!("watching {watched} for changes; press Ctrl-C to stop"),
+            code => note!("exited with code {code}; watching {watched} for changes"),
+        }
+
+        loop {
+            let event = events
+                .recv()
+                .into_diagnostic()
+                .wrap_err("watching files failed")?;
+            if event.is_ok_and(|event| changes(&event, &files)) {
+                break;
+            }
+        }
+        std::thread::sleep(SETTLE);
+        while events.try_recv().is_ok() {}
+    }
+}
+
+/// Whether `event` changes one of `files`, rather than only reading it or
+/// being about another file in the same directory.
+fn changes(event: &Event, files: &[PathBuf]) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event.paths.iter().any(|path| files.contains(path))
+}
+
+fn clear_terminal() {
+    let mut stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        let _ = write!(stdout, "\x1b[2J\x1b[H");
+        let _ = stdout.flush();
+    }
+}